@@ -0,0 +1,16 @@
+create table refresh_tokens (
+  id uuid default uuid_generate_v4(),
+  user_id uuid not null references users(id) on delete cascade,
+  family_id uuid not null,
+  token_hash text unique not null,
+
+  expires_at timestamptz not null,
+  used_at timestamptz,
+  revoked_at timestamptz,
+
+  created_at timestamptz not null default now(),
+  primary key (id)
+);
+
+create index refresh_tokens_family_id_idx on refresh_tokens (family_id);
+create index refresh_tokens_user_id_idx on refresh_tokens (user_id);
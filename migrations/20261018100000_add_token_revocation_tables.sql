@@ -0,0 +1,20 @@
+-- Access tokens revoked one by one, kept until they would have expired anyway
+create table revoked_tokens (
+  jti uuid not null,
+  user_id uuid not null,
+  expires_at timestamptz not null,
+
+  created_at timestamptz not null default now(),
+  primary key (jti)
+);
+
+-- Every access token issued to the user before `revoked_before` is revoked.
+-- There is no foreign key so the revocation outlives a deleted user
+create table revoked_sessions (
+  user_id uuid not null,
+  revoked_before timestamptz not null,
+  expires_at timestamptz not null,
+
+  created_at timestamptz not null default now(),
+  primary key (user_id)
+);
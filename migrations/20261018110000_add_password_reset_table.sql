@@ -0,0 +1,13 @@
+create table password_reset_tokens (
+  id uuid default uuid_generate_v4(),
+  user_id uuid not null references users(id) on delete cascade,
+  token_hash text unique not null,
+
+  expires_at timestamptz not null,
+  used_at timestamptz,
+
+  created_at timestamptz not null default now(),
+  primary key (id)
+);
+
+create index password_reset_tokens_user_id_idx on password_reset_tokens (user_id);
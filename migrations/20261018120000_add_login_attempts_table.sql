@@ -0,0 +1,11 @@
+create table login_attempts (
+  -- 'account' for a username, 'ip' for a client address
+  kind text not null,
+  identifier text not null,
+  failures integer not null default 0,
+
+  last_failure_at timestamptz not null,
+  locked_until timestamptz,
+
+  primary key (kind, identifier)
+);
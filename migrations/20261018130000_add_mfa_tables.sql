@@ -0,0 +1,37 @@
+create table user_mfa (
+  user_id uuid references users(id) on delete cascade,
+  -- base32 encoded TOTP secret
+  secret text not null,
+  -- null until the user confirmed the enrollment with a valid code
+  enabled_at timestamptz,
+  -- last TOTP time step accepted, a code cannot be used twice
+  last_used_step bigint,
+
+  created_at timestamptz not null default now(),
+  primary key (user_id)
+);
+
+create table mfa_recovery_codes (
+  id uuid default uuid_generate_v4(),
+  user_id uuid not null references users(id) on delete cascade,
+  code_hash text not null,
+  used_at timestamptz,
+  primary key (id)
+);
+
+create index mfa_recovery_codes_user_id_idx on mfa_recovery_codes (user_id);
+
+create table mfa_challenges (
+  id uuid default uuid_generate_v4(),
+  user_id uuid not null references users(id) on delete cascade,
+  token_hash text unique not null,
+  failed_attempts integer not null default 0,
+
+  expires_at timestamptz not null,
+  used_at timestamptz,
+
+  created_at timestamptz not null default now(),
+  primary key (id)
+);
+
+alter table refresh_tokens add column mfa_verified boolean not null default false;
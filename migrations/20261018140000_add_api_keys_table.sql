@@ -0,0 +1,19 @@
+create table api_keys (
+  id uuid default uuid_generate_v4(),
+  user_id uuid not null references users(id) on delete cascade,
+  name text not null,
+  key_hash text unique not null,
+  -- first characters of the key, so the owner can tell their keys apart
+  hint text not null,
+  -- null when the key can do everything its owner can
+  scopes text[],
+
+  expires_at timestamptz,
+  last_used_at timestamptz,
+  revoked_at timestamptz,
+
+  created_at timestamptz not null default now(),
+  primary key (id)
+);
+
+create index api_keys_user_id_idx on api_keys (user_id);
@@ -0,0 +1,20 @@
+-- Nullable, the accounts created before cannot be given an address
+alter table users
+  add column email citext unique,
+  add column email_verified_at timestamptz;
+
+create table email_verification_tokens (
+  id uuid default uuid_generate_v4(),
+  user_id uuid not null references users(id) on delete cascade,
+  -- The address being verified, the user may have changed it since
+  email citext not null,
+  token_hash text unique not null,
+
+  expires_at timestamptz not null,
+  used_at timestamptz,
+
+  created_at timestamptz not null default now(),
+  primary key (id)
+);
+
+create index email_verification_tokens_user_id_idx on email_verification_tokens (user_id);
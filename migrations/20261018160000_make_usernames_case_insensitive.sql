@@ -0,0 +1,21 @@
+-- Usernames are NFKC normalized and unique without case. The accounts only differing
+-- by their case or their Unicode form must be renamed before this migration can run
+do $$
+declare
+  collisions text;
+begin
+  select string_agg(names, '; ') into collisions from (
+    select string_agg(username, ', ' order by username) as names
+    from users
+    group by lower(normalize(trim(username), NFKC))
+    having count(*) > 1
+  ) as duplicates;
+  if collisions is not null then
+    raise exception 'colliding usernames, rename all but one of each group: %', collisions;
+  end if;
+end $$;
+
+update users set username = normalize(trim(username), NFKC)
+  where username <> normalize(trim(username), NFKC);
+
+alter table users alter column username type citext;
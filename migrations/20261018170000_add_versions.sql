@@ -0,0 +1,3 @@
+-- Bumped on every change, the ETag of the resource
+alter table users add column version integer not null default 1;
+alter table tickets add column version integer not null default 1;
@@ -0,0 +1,15 @@
+create table idempotency_keys (
+  user_id uuid not null references users(id) on delete cascade,
+  key text not null,
+  -- hash of the request, a key cannot be reused for a different one
+  request_hash text not null,
+  -- both null while the first request is still running
+  status_code smallint,
+  response_body jsonb,
+
+  created_at timestamptz not null default now(),
+  expires_at timestamptz not null,
+  primary key (user_id, key)
+);
+
+create index idempotency_keys_expires_at_idx on idempotency_keys (expires_at);
@@ -0,0 +1,7 @@
+-- The listings are paginated on (sort column, id), see `pg_pagination::push_page`.
+-- The username is already covered by its unique index
+create index tickets_created_at_id_idx on tickets (created_at, id);
+create index tickets_concert_date_id_idx on tickets (concert_date, id);
+create index tickets_price_id_idx on tickets (price, id);
+create index tickets_owner_id_idx on tickets (owner_id);
+create index users_created_at_id_idx on users (created_at, id);
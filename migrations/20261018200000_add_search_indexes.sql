@@ -0,0 +1,6 @@
+-- Fuzzy matching on trigrams and prefix matching on words, see `GET /search`
+create extension if not exists pg_trgm;
+
+create index tickets_concert_name_trgm_idx on tickets using gin (concert_name gin_trgm_ops);
+create index tickets_concert_name_tsv_idx on tickets using gin (to_tsvector('simple', concert_name));
+create index users_username_trgm_idx on users using gin ((username::text) gin_trgm_ops);
@@ -0,0 +1,35 @@
+create table concerts (
+  id uuid default uuid_generate_v4(),
+
+  name text not null,
+  date timestamptz not null,
+  venue text,
+  capacity integer check (capacity > 0),
+  description text,
+  -- bumped on every change, the ETag of the resource
+  version integer not null default 1,
+
+  created_at timestamptz not null default now(),
+  updated_at timestamptz not null default now(),
+  primary key (id)
+);
+
+-- One concert for every name and date the tickets were sold for
+insert into concerts (name, date, created_at, updated_at)
+  select concert_name, concert_date, min(created_at), min(created_at)
+  from tickets group by concert_name, concert_date;
+
+alter table tickets add column concert_id uuid references concerts(id);
+update tickets set concert_id = concerts.id from concerts
+  where concerts.name = tickets.concert_name and concerts.date = tickets.concert_date;
+alter table tickets alter column concert_id set not null;
+
+-- Their indexes go along, the search and the listings now go through the concerts
+alter table tickets drop column concert_name, drop column concert_date;
+
+create index tickets_concert_id_idx on tickets (concert_id);
+create index concerts_date_id_idx on concerts (date, id);
+create index concerts_name_id_idx on concerts (name, id);
+create index concerts_created_at_id_idx on concerts (created_at, id);
+create index concerts_name_trgm_idx on concerts using gin (name gin_trgm_ops);
+create index concerts_name_tsv_idx on concerts using gin (to_tsvector('simple', name));
@@ -0,0 +1,40 @@
+create table venues (
+  id uuid default uuid_generate_v4(),
+
+  name text not null,
+  address text,
+  -- bumped on every change, the ETag of the resource
+  version integer not null default 1,
+
+  created_at timestamptz not null default now(),
+  updated_at timestamptz not null default now(),
+  primary key (id)
+);
+
+create table seats (
+  id uuid default uuid_generate_v4(),
+  venue_id uuid not null references venues(id) on delete cascade,
+
+  section text not null,
+  row text not null,
+  number text not null,
+  -- order of the seat in the seating chart it was imported from
+  position integer not null,
+  primary key (id),
+  unique (venue_id, section, row, number)
+);
+
+create index seats_venue_id_position_idx on seats (venue_id, position);
+create index venues_name_id_idx on venues (name, id);
+create index venues_created_at_id_idx on venues (created_at, id);
+
+-- The free-text venues of the concerts become venues, without a seating chart yet
+insert into venues (name) select distinct venue from concerts where venue is not null;
+alter table concerts add column venue_id uuid references venues(id);
+update concerts set venue_id = venues.id from venues where venues.name = concerts.venue;
+alter table concerts drop column venue;
+create index concerts_venue_id_idx on concerts (venue_id);
+
+-- A seat is sold once per concert, the tickets without a seat are not restricted
+alter table tickets add column seat_id uuid references seats(id);
+create unique index tickets_concert_id_seat_id_key on tickets (concert_id, seat_id);
@@ -0,0 +1,23 @@
+create table ticket_categories (
+  id uuid default uuid_generate_v4(),
+  concert_id uuid not null references concerts(id) on delete cascade,
+
+  name text not null,
+  price float8 not null check (price >= 0),
+  capacity integer not null check (capacity > 0),
+  -- tickets issued in the category, kept up to date along with the tickets so checking
+  -- the capacity only has to lock this row
+  sold integer not null default 0,
+  -- bumped on every change, the ETag of the resource
+  version integer not null default 1,
+
+  created_at timestamptz not null default now(),
+  updated_at timestamptz not null default now(),
+  primary key (id),
+  constraint ticket_categories_concert_id_name_key unique (concert_id, name),
+  constraint ticket_categories_sold_check check (sold between 0 and capacity)
+);
+
+-- The tickets issued before the categories keep none
+alter table tickets add column category_id uuid references ticket_categories(id);
+create index tickets_category_id_idx on tickets (category_id);
@@ -0,0 +1,30 @@
+-- Tickets set aside for a buyer until they check out or the hold expires
+create table holds (
+  id uuid default uuid_generate_v4(),
+  user_id uuid not null references users(id) on delete cascade,
+  concert_id uuid not null references concerts(id) on delete cascade,
+  category_id uuid not null references ticket_categories(id) on delete cascade,
+
+  quantity integer not null check (quantity > 0),
+  expires_at timestamptz not null,
+
+  created_at timestamptz not null default now(),
+  primary key (id)
+);
+create index holds_expires_at_idx on holds (expires_at);
+
+-- The seats of a hold taken on specific seats. The seats are locked while they are
+-- checked against the tickets and the other holds, an expired hold no longer counts
+create table hold_seats (
+  hold_id uuid not null references holds(id) on delete cascade,
+  concert_id uuid not null,
+  seat_id uuid not null references seats(id),
+  primary key (hold_id, seat_id)
+);
+create index hold_seats_concert_id_seat_id_idx on hold_seats (concert_id, seat_id);
+
+-- Tickets held in the category, they count against its capacity until released
+alter table ticket_categories add column held integer not null default 0;
+alter table ticket_categories drop constraint ticket_categories_sold_check;
+alter table ticket_categories add constraint ticket_categories_sold_check
+  check (sold >= 0 and held >= 0 and sold + held <= capacity);
@@ -0,0 +1,36 @@
+create table orders (
+  id uuid default uuid_generate_v4(),
+  user_id uuid not null references users(id) on delete cascade,
+
+  status text not null default 'pending',
+  -- sum of the items, priced by the server when the order is placed
+  total float8 not null check (total >= 0),
+  -- the hold checked out by the order, cleared once the hold is converted or released
+  hold_id uuid references holds(id) on delete set null,
+  -- bumped on every change, the ETag of the resource
+  version integer not null default 1,
+
+  created_at timestamptz not null default now(),
+  updated_at timestamptz not null default now(),
+  paid_at timestamptz,
+  primary key (id),
+  constraint orders_status_check check (status in ('pending', 'paid', 'cancelled', 'refunded'))
+);
+create index orders_user_id_created_at_idx on orders (user_id, created_at, id);
+
+create table order_items (
+  order_id uuid not null references orders(id) on delete cascade,
+  position integer not null,
+
+  concert_id uuid not null references concerts(id),
+  category_id uuid not null references ticket_categories(id),
+  quantity integer not null check (quantity > 0),
+  -- the seats of the tickets, empty for unnumbered tickets
+  seat_ids uuid[] not null default '{}',
+  unit_price float8 not null check (unit_price >= 0),
+  primary key (order_id, position)
+);
+
+-- The tickets issued when an order is paid, the others have none
+alter table tickets add column order_id uuid references orders(id);
+create index tickets_order_id_idx on tickets (order_id);
@@ -0,0 +1,39 @@
+use std::sync::RwLock;
+
+use chrono::{DateTime, Duration, Utc};
+
+/// Source of the current time, so the time-based checks can be tested
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when told to
+pub struct FixedClock(RwLock<DateTime<Utc>>);
+
+impl FixedClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self(RwLock::new(now))
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.0.write().expect("clock poisoned") = now;
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.0.write().expect("clock poisoned") += duration;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.0.read().expect("clock poisoned")
+    }
+}
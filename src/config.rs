@@ -1,3 +1,4 @@
+use chrono::Duration;
 use config::{builder::DefaultState, ConfigBuilder, Environment};
 use serde::Deserialize;
 
@@ -8,9 +9,256 @@ pub struct Cfg {
     pub db_port: String,
     pub db_name: String,
     pub jwt_secret: String,
+    /// Directory of the RSA keys signing the tokens, see `JwtHandler::from_config`
+    pub jwt_keys_dir: Option<String>,
+    /// Id of the key signing the new tokens, the `JWT_SECRET` is used when unset
+    pub jwt_signing_key_id: Option<String>,
+    /// Issuer (`iss`) of the tokens, defaults to `jwt_handler::DEFAULT_ISSUER`
+    pub jwt_issuer: Option<String>,
+    /// Audience (`aud`) of the tokens, defaults to `jwt_handler::DEFAULT_AUDIENCE`
+    pub jwt_audience: Option<String>,
+    /// Clock skew tolerated when checking the token dates, in seconds
+    pub jwt_leeway: Option<i64>,
+    /// Unix timestamp before which the tokens without `iss` must be issued, defaults to the startup
+    pub jwt_legacy_cutoff: Option<i64>,
+    /// Accept the tokens issued before the claims were typed, see `JwtHandler::from_config`
+    #[serde(default = "default_true")]
+    pub jwt_accept_legacy_tokens: bool,
+    /// Argon2 variant of the password hashes: `argon2id`, `argon2i` or `argon2d`
+    #[serde(default = "default_password_hash_algorithm")]
+    pub password_hash_algorithm: String,
+    /// Memory used to hash a password, in KiB
+    #[serde(default = "default_password_hash_m_cost")]
+    pub password_hash_m_cost: u32,
+    /// Number of passes over the memory
+    #[serde(default = "default_password_hash_t_cost")]
+    pub password_hash_t_cost: u32,
+    /// Degree of parallelism
+    #[serde(default = "default_password_hash_p_cost")]
+    pub password_hash_p_cost: u32,
+    /// Secret mixed in the password hashes, every password must be reset if it changes
+    pub password_pepper: Option<String>,
+    /// Shortest password accepted, in characters
+    #[serde(default = "default_password_min_length")]
+    pub password_min_length: usize,
+    /// Longest password accepted, in characters
+    #[serde(default = "default_password_max_length")]
+    pub password_max_length: usize,
+    #[serde(default)]
+    pub password_require_lowercase: bool,
+    #[serde(default)]
+    pub password_require_uppercase: bool,
+    #[serde(default)]
+    pub password_require_digit: bool,
+    #[serde(default)]
+    pub password_require_symbol: bool,
+    /// Refuse the passwords containing the username
+    #[serde(default = "default_true")]
+    pub password_reject_username: bool,
+    /// File of breached or common passwords to refuse, one per line
+    pub password_blocklist_file: Option<String>,
+    /// Lifetime of the access tokens, in seconds
+    #[serde(default = "default_access_token_ttl")]
+    pub access_token_ttl: i64,
+    /// Lifetime of the refresh tokens, in seconds
+    #[serde(default = "default_refresh_token_ttl")]
+    pub refresh_token_ttl: i64,
+    /// Lifetime of the password reset tokens, in seconds
+    #[serde(default = "default_password_reset_ttl")]
+    pub password_reset_ttl: i64,
+    /// Lifetime of the email verification tokens, in seconds
+    #[serde(default = "default_email_verification_ttl")]
+    pub email_verification_ttl: i64,
+    /// How the notifications are delivered: `log`, `file` or `smtp`
+    #[serde(default = "default_notifier")]
+    pub notifier: String,
+    /// Directory where the `file` notifier writes the notifications
+    pub notifier_spool_dir: Option<String>,
+    /// Relay used by the `smtp` notifier
+    pub smtp_host: Option<String>,
+    /// Defaults to the standard port of `smtp_tls`
+    pub smtp_port: Option<u16>,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    /// Sender of the emails, such as `Tickets <noreply@example.com>`
+    pub smtp_from: Option<String>,
+    /// Encryption of the connection to the relay: `tls`, `starttls` or `none`
+    #[serde(default = "default_smtp_tls")]
+    pub smtp_tls: String,
+    /// Failed logins allowed for a username before it is locked out
+    #[serde(default = "default_login_account_free_attempts")]
+    pub login_account_free_attempts: i32,
+    /// Failed logins allowed for a client address before it is locked out
+    #[serde(default = "default_login_ip_free_attempts")]
+    pub login_ip_free_attempts: i32,
+    /// First lockout delay, doubled on every new failure, in seconds
+    #[serde(default = "default_login_backoff_base")]
+    pub login_backoff_base: i64,
+    /// Longest lockout, in seconds
+    #[serde(default = "default_login_max_lockout")]
+    pub login_max_lockout: i64,
+    /// Failed logins older than this are forgotten, in seconds
+    #[serde(default = "default_login_attempts_window")]
+    pub login_attempts_window: i64,
+    /// Use the `X-Forwarded-For` header as the client address, only behind a trusted proxy
+    #[serde(default)]
+    pub trust_forwarded_for: bool,
+    /// Issuer shown by the authenticator apps next to the TOTP codes
+    #[serde(default = "default_totp_issuer")]
+    pub totp_issuer: String,
+    /// Refuse the admin tokens obtained without two-factor authentication
+    #[serde(default)]
+    pub require_admin_mfa: bool,
+    /// Lifetime of the challenge tokens returned by `/login` when 2FA is enabled, in seconds
+    #[serde(default = "default_mfa_challenge_ttl")]
+    pub mfa_challenge_ttl: i64,
+    /// How long the response to a request sent with an `Idempotency-Key` is kept, in seconds
+    #[serde(default = "default_idempotency_key_ttl")]
+    pub idempotency_key_ttl: i64,
+    /// Delay between two purges of the expired revoked tokens, in seconds, at least 1
+    #[serde(default = "default_revocation_sweep_interval")]
+    pub revocation_sweep_interval: u64,
+    /// How long held tickets are set aside for a buyer, in seconds
+    #[serde(default = "default_hold_ttl")]
+    pub hold_ttl: i64,
+    /// Delay between two releases of the expired holds, in seconds, at least 1
+    #[serde(default = "default_hold_sweep_interval")]
+    pub hold_sweep_interval: u64,
+}
+
+fn default_password_hash_algorithm() -> String {
+    "argon2id".to_string()
+}
+
+fn default_password_hash_m_cost() -> u32 {
+    argon2::Params::DEFAULT_M_COST
+}
+
+fn default_password_hash_t_cost() -> u32 {
+    argon2::Params::DEFAULT_T_COST
+}
+
+fn default_password_hash_p_cost() -> u32 {
+    argon2::Params::DEFAULT_P_COST
+}
+
+fn default_password_min_length() -> usize {
+    8
+}
+
+fn default_password_max_length() -> usize {
+    128
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_access_token_ttl() -> i64 {
+    15 * 60
+}
+
+fn default_refresh_token_ttl() -> i64 {
+    30 * 24 * 60 * 60
+}
+
+fn default_password_reset_ttl() -> i64 {
+    60 * 60
+}
+
+fn default_email_verification_ttl() -> i64 {
+    24 * 60 * 60
+}
+
+fn default_notifier() -> String {
+    "log".to_string()
+}
+
+fn default_smtp_tls() -> String {
+    "starttls".to_string()
+}
+
+fn default_login_account_free_attempts() -> i32 {
+    5
+}
+
+fn default_login_ip_free_attempts() -> i32 {
+    20
+}
+
+fn default_login_backoff_base() -> i64 {
+    1
+}
+
+fn default_login_max_lockout() -> i64 {
+    15 * 60
+}
+
+fn default_login_attempts_window() -> i64 {
+    60 * 60
+}
+
+fn default_totp_issuer() -> String {
+    "iomentum-backend".to_string()
+}
+
+fn default_mfa_challenge_ttl() -> i64 {
+    5 * 60
+}
+
+fn default_idempotency_key_ttl() -> i64 {
+    24 * 60 * 60
+}
+
+fn default_revocation_sweep_interval() -> u64 {
+    60
+}
+
+fn default_hold_ttl() -> i64 {
+    10 * 60
+}
+
+fn default_hold_sweep_interval() -> u64 {
+    30
 }
 
 impl Cfg {
+    pub fn access_token_ttl(&self) -> Duration {
+        Duration::seconds(self.access_token_ttl)
+    }
+
+    pub fn refresh_token_ttl(&self) -> Duration {
+        Duration::seconds(self.refresh_token_ttl)
+    }
+
+    pub fn password_reset_ttl(&self) -> Duration {
+        Duration::seconds(self.password_reset_ttl)
+    }
+
+    pub fn email_verification_ttl(&self) -> Duration {
+        Duration::seconds(self.email_verification_ttl)
+    }
+
+    pub fn mfa_challenge_ttl(&self) -> Duration {
+        Duration::seconds(self.mfa_challenge_ttl)
+    }
+
+    pub fn idempotency_key_ttl(&self) -> Duration {
+        Duration::seconds(self.idempotency_key_ttl)
+    }
+
+    pub fn revocation_sweep_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.revocation_sweep_interval)
+    }
+
+    pub fn hold_ttl(&self) -> Duration {
+        Duration::seconds(self.hold_ttl)
+    }
+
+    pub fn hold_sweep_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.hold_sweep_interval)
+    }
+
     pub fn db_url(&self) -> String {
         format!("{}/{}", self.without_db_name(), self.db_name)
     }
@@ -26,9 +274,20 @@ impl Cfg {
         dotenv::dotenv().ok();
         let cfg = ConfigBuilder::<DefaultState>::default().add_source(Environment::default());
 
-        cfg.build()
+        let cfg: Self = cfg
+            .build()
             .expect("cannot build config")
             .try_deserialize()
-            .expect("cannot convert config")
+            .expect("cannot convert config");
+        // A periodic task cannot run every 0 seconds
+        assert!(
+            cfg.revocation_sweep_interval > 0,
+            "REVOCATION_SWEEP_INTERVAL must be at least 1 second"
+        );
+        assert!(
+            cfg.hold_sweep_interval > 0,
+            "HOLD_SWEEP_INTERVAL must be at least 1 second"
+        );
+        cfg
     }
 }
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::types::api_key_types::{ApiKey, Scope};
+
+#[derive(Deserialize, Debug)]
+pub struct NewApiKeyDto {
+    pub name: String,
+    /// `tickets:read`, `tickets:write`, `users:read` or `users:write`, read-only when omitted
+    pub scopes: Option<Vec<String>>,
+    /// Never expires when omitted
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ApiKeyDto {
+    pub id: Uuid,
+    pub name: String,
+    /// First characters of the key
+    pub hint: String,
+    pub scopes: Option<Vec<String>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ApiKey> for ApiKeyDto {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            id: key.id,
+            name: key.name,
+            hint: key.hint,
+            scopes: key
+                .scopes
+                .map(|scopes| scopes.iter().map(Scope::to_string).collect()),
+            expires_at: key.expires_at,
+            last_used_at: key.last_used_at,
+            created_at: key.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct CreatedApiKeyDto {
+    /// Shown only once, only its hash is stored
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKeyDto,
+}
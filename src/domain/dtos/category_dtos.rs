@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Error,
+    types::category_types::{NewTicketCategory, TicketCategory, TicketCategoryChanges},
+};
+
+use super::present;
+
+#[derive(Debug, Serialize)]
+pub struct TicketCategoryDto {
+    pub id: Uuid,
+    pub concert_id: Uuid,
+    pub name: String,
+    pub price: f64,
+    pub capacity: i32,
+    pub sold: i32,
+    pub held: i32,
+    pub remaining: i32,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<TicketCategory> for TicketCategoryDto {
+    fn from(category: TicketCategory) -> Self {
+        TicketCategoryDto {
+            remaining: category.remaining(),
+            id: category.id,
+            concert_id: category.concert_id,
+            name: category.name,
+            price: category.price,
+            capacity: category.capacity,
+            sold: category.sold,
+            held: category.held,
+            created_at: category.created_at,
+            updated_at: category.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TicketCategoryInputDto {
+    pub name: String,
+    pub price: f64,
+    pub capacity: i32,
+}
+
+impl TryFrom<TicketCategoryInputDto> for NewTicketCategory {
+    type Error = Error;
+
+    fn try_from(category: TicketCategoryInputDto) -> Result<Self, Self::Error> {
+        NewTicketCategory::new(&category.name, category.price, category.capacity)
+    }
+}
+
+/// A JSON merge patch of a ticket category, the missing fields are left untouched
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TicketCategoryPatchDto {
+    #[serde(default, deserialize_with = "present")]
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub price: Option<f64>,
+    #[serde(default, deserialize_with = "present")]
+    pub capacity: Option<i32>,
+}
+
+impl TryFrom<TicketCategoryPatchDto> for TicketCategoryChanges {
+    type Error = Error;
+
+    fn try_from(patch: TicketCategoryPatchDto) -> Result<Self, Self::Error> {
+        TicketCategoryChanges::new(patch.name.as_deref(), patch.price, patch.capacity)
+    }
+}
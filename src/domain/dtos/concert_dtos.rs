@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Error,
+    types::{
+        concert_types::{Concert, ConcertChanges, ConcertFilter, ConcertQuery, NewConcert},
+        pagination::PageRequest,
+    },
+};
+
+use super::{nullable, present};
+
+#[derive(Debug, Serialize)]
+pub struct ConcertDto {
+    pub id: Uuid,
+    pub name: String,
+    pub date: DateTime<Utc>,
+    pub venue_id: Option<Uuid>,
+    pub capacity: Option<i32>,
+    pub description: Option<String>,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Concert> for ConcertDto {
+    fn from(concert: Concert) -> Self {
+        ConcertDto {
+            id: concert.id,
+            name: concert.name,
+            date: concert.date,
+            venue_id: concert.venue_id,
+            capacity: concert.capacity,
+            description: concert.description,
+            created_at: concert.created_at,
+            updated_at: concert.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConcertInputDto {
+    pub name: String,
+    pub date: DateTime<Utc>,
+    pub venue_id: Option<Uuid>,
+    pub capacity: Option<i32>,
+    pub description: Option<String>,
+}
+
+impl TryFrom<ConcertInputDto> for NewConcert {
+    type Error = Error;
+
+    fn try_from(concert: ConcertInputDto) -> Result<Self, Self::Error> {
+        NewConcert::new(
+            &concert.name,
+            concert.date,
+            concert.venue_id,
+            concert.capacity,
+            concert.description,
+        )
+    }
+}
+
+/// A JSON merge patch of a concert, the missing fields are left untouched.
+/// Only the optional fields can be removed with `null`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConcertPatchDto {
+    #[serde(default, deserialize_with = "present")]
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub date: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub venue_id: Option<Option<Uuid>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub capacity: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub description: Option<Option<String>>,
+}
+
+impl TryFrom<ConcertPatchDto> for ConcertChanges {
+    type Error = Error;
+
+    fn try_from(patch: ConcertPatchDto) -> Result<Self, Self::Error> {
+        ConcertChanges::new(
+            patch.name.as_deref(),
+            patch.date,
+            patch.venue_id,
+            patch.capacity,
+            patch.description,
+        )
+    }
+}
+
+/// The query string of `GET /concerts`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConcertListQueryDto {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    /// `date`, `name` or `created_at`, prefixed with `-` for the descending order
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub include_total: bool,
+
+    pub name: Option<String>,
+    pub date_from: Option<DateTime<Utc>>,
+    pub date_to: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<ConcertListQueryDto> for ConcertQuery {
+    type Error = Error;
+
+    fn try_from(query: ConcertListQueryDto) -> Result<Self, Self::Error> {
+        let page = PageRequest::new(
+            query.sort.as_deref(),
+            query.limit,
+            query.cursor.as_deref(),
+            query.include_total,
+        )?;
+        Ok(ConcertQuery {
+            filter: ConcertFilter {
+                name: query.name,
+                date_from: query.date_from,
+                date_to: query.date_to,
+            },
+            page,
+        })
+    }
+}
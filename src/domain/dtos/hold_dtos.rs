@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::types::hold_types::Hold;
+
+#[derive(Debug, Serialize)]
+pub struct HoldDto {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub concert_id: Uuid,
+    pub category_id: Uuid,
+    pub quantity: i32,
+    pub seat_ids: Vec<Uuid>,
+    pub expires_at: DateTime<Utc>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Hold> for HoldDto {
+    fn from(hold: Hold) -> Self {
+        HoldDto {
+            id: hold.id,
+            user_id: hold.user_id,
+            concert_id: hold.concert_id,
+            category_id: hold.category_id,
+            quantity: hold.quantity,
+            seat_ids: hold.seat_ids,
+            expires_at: hold.expires_at,
+            created_at: hold.created_at,
+        }
+    }
+}
+
+/// The tickets to hold for the caller, a number of them or specific seats
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HoldInputDto {
+    pub concert_id: Uuid,
+    pub category_id: Uuid,
+    #[serde(default)]
+    pub quantity: Option<i32>,
+    #[serde(default)]
+    pub seat_ids: Vec<Uuid>,
+}
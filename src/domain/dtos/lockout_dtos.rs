@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::domain::types::login_attempt_types::LoginAttempts;
+
+#[derive(Serialize, Debug)]
+pub struct LockoutDto {
+    /// `account` or `ip`
+    pub kind: String,
+    pub identifier: String,
+    pub failures: i32,
+    pub last_failure_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+impl From<LoginAttempts> for LockoutDto {
+    fn from(attempts: LoginAttempts) -> Self {
+        Self {
+            kind: attempts.kind.to_string(),
+            identifier: attempts.identifier,
+            failures: attempts.failures,
+            last_failure_at: attempts.last_failure_at,
+            locked_until: attempts.locked_until,
+        }
+    }
+}
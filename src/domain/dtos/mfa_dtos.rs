@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+use super::token_dtos::TokenPairDto;
+
+#[derive(Serialize, Debug)]
+pub struct MfaEnrollmentDto {
+    /// Base32 encoded secret, for the apps that cannot scan the URI
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MfaConfirmDto {
+    pub code: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RecoveryCodesDto {
+    /// Shown only once, each code can be used a single time instead of a TOTP code
+    pub recovery_codes: Vec<String>,
+}
+
+/// Either a TOTP code or a recovery code
+#[derive(Deserialize, Debug)]
+pub struct MfaCodeDto {
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MfaLoginDto {
+    pub challenge_token: String,
+    #[serde(flatten)]
+    pub code: MfaCodeDto,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MfaChallengeDto {
+    pub mfa_required: bool,
+    pub challenge_token: String,
+    /// Lifetime of the challenge token, in seconds
+    pub expires_in: i64,
+}
+
+/// The reply of `/login`: the tokens, or a challenge when the account has 2FA enabled
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum LoginDto {
+    Tokens(TokenPairDto),
+    MfaChallenge(MfaChallengeDto),
+}
@@ -1,2 +1,36 @@
+pub mod api_key_dtos;
+pub mod category_dtos;
+pub mod concert_dtos;
+pub mod hold_dtos;
+pub mod lockout_dtos;
+pub mod mfa_dtos;
+pub mod order_dtos;
+pub mod page_dtos;
+pub mod search_dtos;
 pub mod ticket_dtos;
+pub mod token_dtos;
 pub mod user_dtos;
+pub mod venue_dtos;
+
+use serde::{Deserialize, Deserializer};
+
+/// Deserialize a field of a JSON merge patch, along with `#[serde(default)]`.
+/// A missing field is `None` and left untouched, `null` is refused as none of
+/// the fields can be removed
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Deserialize an optional field of a JSON merge patch, along with `#[serde(default)]`.
+/// A missing field is `None` and left untouched, `null` is `Some(None)` and removes it
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
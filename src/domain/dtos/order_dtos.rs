@@ -0,0 +1,122 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Error,
+    types::{
+        order_types::{Order, OrderItem, OrderQuery, OrderStatus},
+        pagination::PageRequest,
+    },
+};
+
+#[derive(Debug, Serialize)]
+pub struct OrderDto {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub status: String,
+    pub total: f64,
+    pub hold_id: Option<Uuid>,
+    pub items: Vec<OrderItemDto>,
+    pub ticket_ids: Vec<Uuid>,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub paid_at: Option<DateTime<Utc>>,
+}
+
+impl From<Order> for OrderDto {
+    fn from(order: Order) -> Self {
+        OrderDto {
+            id: order.id,
+            user_id: order.user_id,
+            status: order.status.to_string(),
+            total: order.total,
+            hold_id: order.hold_id,
+            items: order.items.into_iter().map(OrderItemDto::from).collect(),
+            ticket_ids: order.ticket_ids,
+            created_at: order.created_at,
+            updated_at: order.updated_at,
+            paid_at: order.paid_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrderItemDto {
+    pub concert_id: Uuid,
+    pub category_id: Uuid,
+    pub quantity: i32,
+    pub seat_ids: Vec<Uuid>,
+    pub unit_price: f64,
+    pub subtotal: f64,
+}
+
+impl From<OrderItem> for OrderItemDto {
+    fn from(item: OrderItem) -> Self {
+        OrderItemDto {
+            subtotal: item.subtotal(),
+            concert_id: item.concert_id,
+            category_id: item.category_id,
+            quantity: item.quantity,
+            seat_ids: item.seat_ids,
+            unit_price: item.unit_price,
+        }
+    }
+}
+
+/// What to order: some items, or the tickets of a hold of the caller.
+/// The prices are never sent, they are the ones of the categories
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OrderInputDto {
+    #[serde(default)]
+    pub items: Vec<OrderItemInputDto>,
+    #[serde(default)]
+    pub hold_id: Option<Uuid>,
+}
+
+/// Tickets of a category, a number of them or specific seats
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OrderItemInputDto {
+    pub category_id: Uuid,
+    #[serde(default)]
+    pub quantity: Option<i32>,
+    #[serde(default)]
+    pub seat_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrderListQueryDto {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    /// `created_at` or `total`, prefixed with `-` for the descending order
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub include_total: bool,
+    /// Only the orders with this status
+    pub status: Option<String>,
+}
+
+impl OrderListQueryDto {
+    /// The orders of `user_id` the query asks for
+    pub fn into_query(self, user_id: Uuid) -> Result<OrderQuery, Error> {
+        let page = PageRequest::new(
+            self.sort.as_deref(),
+            self.limit,
+            self.cursor.as_deref(),
+            self.include_total,
+        )?;
+        let status = self
+            .status
+            .as_deref()
+            .map(OrderStatus::try_from)
+            .transpose()?;
+        Ok(OrderQuery {
+            user_id,
+            status,
+            page,
+        })
+    }
+}
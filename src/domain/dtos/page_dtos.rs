@@ -0,0 +1,27 @@
+use serde::Serialize;
+
+use crate::domain::types::pagination::Page;
+
+/// A page of a listing, `next_cursor` is sent back as `cursor` to get the next one
+#[derive(Debug, Serialize)]
+pub struct PageDto<T> {
+    pub items: Vec<T>,
+    /// Missing on the last page
+    pub next_cursor: Option<String>,
+    /// Only counted when the client asks for it with `include_total`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+}
+
+impl<T, U> From<Page<U>> for PageDto<T>
+where
+    U: Into<T>,
+{
+    fn from(page: Page<U>) -> Self {
+        PageDto {
+            next_cursor: page.next_cursor.as_ref().map(|c| c.encode()),
+            total: page.total,
+            items: page.items.into_iter().map(|item| item.into()).collect(),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::types::{
+    search_types::{Highlight, SearchHit, SearchQuery},
+    ticket_types::Ticket,
+    user_types::User,
+};
+
+use super::{ticket_dtos::TicketDto, user_dtos::UserDto};
+
+/// The query string of `GET /search`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchQueryDto {
+    pub q: String,
+    /// Most results of each kind
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TicketHitDto {
+    #[serde(flatten)]
+    pub ticket: TicketDto,
+    pub rank: f32,
+    /// The matching parts of the concert name
+    pub highlights: Vec<Highlight>,
+}
+
+impl TicketHitDto {
+    pub fn new(hit: SearchHit<Ticket>, query: &SearchQuery) -> Self {
+        TicketHitDto {
+            highlights: query.highlight(&hit.item.concert_name),
+            rank: hit.rank,
+            ticket: hit.item.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserHitDto {
+    #[serde(flatten)]
+    pub user: UserDto,
+    pub rank: f32,
+    /// The matching parts of the username
+    pub highlights: Vec<Highlight>,
+}
+
+impl UserHitDto {
+    pub fn new(hit: SearchHit<User>, query: &SearchQuery) -> Self {
+        UserHitDto {
+            highlights: query.highlight(hit.item.username.as_ref()),
+            rank: hit.rank,
+            user: hit.item.into(),
+        }
+    }
+}
+
+/// The results of each kind, the best first
+#[derive(Debug, Serialize)]
+pub struct SearchResultsDto {
+    pub tickets: Vec<TicketHitDto>,
+    pub users: Vec<UserHitDto>,
+}
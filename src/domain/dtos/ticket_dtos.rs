@@ -2,15 +2,30 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::domain::types::ticket_types::{NewTicket, Ticket};
+use crate::domain::{
+    errors::Error,
+    types::{
+        pagination::PageRequest,
+        ticket_types::{NewTicket, Ticket, TicketChanges, TicketFilter, TicketQuery},
+    },
+};
+
+use super::{nullable, present};
 
 #[derive(Debug, Serialize)]
 pub struct TicketDto {
     pub id: Uuid,
     pub owner_id: Uuid,
 
+    pub concert_id: Uuid,
     pub concert_name: String,
     pub concert_date: DateTime<Utc>,
+    pub seat_id: Option<Uuid>,
+    pub seat_section: Option<String>,
+    pub seat_row: Option<String>,
+    pub seat_number: Option<String>,
+    pub category_id: Option<Uuid>,
+    pub category_name: Option<String>,
     pub barcode_data: String,
     pub price: f64,
 
@@ -23,8 +38,15 @@ impl From<Ticket> for TicketDto {
         TicketDto {
             id: ticket.id,
             owner_id: ticket.owner_id,
+            concert_id: ticket.concert_id,
             concert_name: ticket.concert_name,
             concert_date: ticket.concert_date,
+            seat_id: ticket.seat_id,
+            seat_section: ticket.seat_section,
+            seat_row: ticket.seat_row,
+            seat_number: ticket.seat_number,
+            category_id: ticket.category_id,
+            category_name: ticket.category_name,
             barcode_data: ticket.barcode_data,
             price: ticket.price,
             created_at: ticket.created_at,
@@ -33,23 +55,108 @@ impl From<Ticket> for TicketDto {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TicketInputDto {
     pub owner_id: Uuid,
-    pub concert_name: String,
-    pub concert_date: DateTime<Utc>,
+    pub concert_id: Uuid,
+    #[serde(default)]
+    pub seat_id: Option<Uuid>,
+    #[serde(default)]
+    pub category_id: Option<Uuid>,
     pub barcode_data: String,
-    pub price: f64,
+    /// Left out for a ticket of a category
+    #[serde(default)]
+    pub price: Option<f64>,
 }
 
-impl From<TicketInputDto> for NewTicket {
-    fn from(ticket: TicketInputDto) -> Self {
-        NewTicket {
-            owner_id: ticket.owner_id,
-            concert_name: ticket.concert_name,
-            concert_date: ticket.concert_date,
-            barcode_data: ticket.barcode_data,
-            price: ticket.price,
+impl TryFrom<TicketInputDto> for NewTicket {
+    type Error = Error;
+
+    fn try_from(ticket: TicketInputDto) -> Result<Self, Self::Error> {
+        NewTicket::new(
+            ticket.owner_id,
+            ticket.concert_id,
+            ticket.seat_id,
+            ticket.category_id,
+            ticket.barcode_data,
+            ticket.price,
+        )
+    }
+}
+
+/// A JSON merge patch of a ticket, the missing fields are left untouched.
+/// Only the seat and the category can be removed with `null`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TicketPatchDto {
+    #[serde(default, deserialize_with = "present")]
+    pub owner_id: Option<Uuid>,
+    #[serde(default, deserialize_with = "present")]
+    pub concert_id: Option<Uuid>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub seat_id: Option<Option<Uuid>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub category_id: Option<Option<Uuid>>,
+    #[serde(default, deserialize_with = "present")]
+    pub barcode_data: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub price: Option<f64>,
+}
+
+impl From<TicketPatchDto> for TicketChanges {
+    fn from(patch: TicketPatchDto) -> Self {
+        TicketChanges {
+            owner_id: patch.owner_id,
+            concert_id: patch.concert_id,
+            seat_id: patch.seat_id,
+            category_id: patch.category_id,
+            barcode_data: patch.barcode_data,
+            price: patch.price,
         }
     }
 }
+
+/// The query string of `GET /tickets`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TicketListQueryDto {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    /// `created_at`, `concert_date` or `price`, prefixed with `-` for the descending order
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub include_total: bool,
+
+    pub owner_id: Option<Uuid>,
+    pub concert_id: Option<Uuid>,
+    pub concert_name: Option<String>,
+    pub concert_date_from: Option<DateTime<Utc>>,
+    pub concert_date_to: Option<DateTime<Utc>>,
+    pub price_min: Option<f64>,
+    pub price_max: Option<f64>,
+}
+
+impl TryFrom<TicketListQueryDto> for TicketQuery {
+    type Error = Error;
+
+    fn try_from(query: TicketListQueryDto) -> Result<Self, Self::Error> {
+        let page = PageRequest::new(
+            query.sort.as_deref(),
+            query.limit,
+            query.cursor.as_deref(),
+            query.include_total,
+        )?;
+        Ok(TicketQuery {
+            filter: TicketFilter {
+                owner_id: query.owner_id,
+                concert_id: query.concert_id,
+                concert_name: query.concert_name,
+                concert_date_from: query.concert_date_from,
+                concert_date_to: query.concert_date_to,
+                price_min: query.price_min,
+                price_max: query.price_max,
+            },
+            page,
+        })
+    }
+}
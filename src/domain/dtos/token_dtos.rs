@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Debug)]
+pub struct TokenPairDto {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    /// Lifetime of the access token, in seconds
+    pub expires_in: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RefreshTokenInputDto {
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct LogoutInputDto {
+    /// Also revoke the refresh token family of the session when given
+    pub refresh_token: Option<String>,
+}
@@ -1,27 +1,129 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::domain::{
-    errors::Error,
-    types::{
-        user_types::{NewUser, User},
-        PasswordHash, Role, Username,
+use crate::{
+    domain::{
+        errors::Error,
+        password_policy::PasswordPolicy,
+        types::{
+            pagination::PageRequest,
+            user_types::{NewUser, User, UserChanges, UserFilter, UserQuery},
+            Email, PasswordHash, Role, Username,
+        },
     },
+    handlers::password_hasher::PasswordHasher,
 };
 
+use super::present;
+
 #[derive(Deserialize, Debug)]
 pub struct NewUserDto {
     pub username: String,
+    pub email: String,
     pub password: String,
     pub role: String,
 }
 
-impl TryFrom<NewUserDto> for NewUser {
+impl NewUserDto {
+    /// Validate the input and hash the password
+    pub async fn into_new_user(
+        self,
+        policy: &PasswordPolicy,
+        hasher: &PasswordHasher,
+    ) -> Result<NewUser, Error> {
+        let username = Username::new(&self.username)?;
+        let email = Email::new(&self.email)?;
+        let password_hash =
+            PasswordHash::new(&self.password, username.as_ref(), policy, hasher).await?;
+        Ok(NewUser {
+            username,
+            email,
+            password_hash,
+            role: Role::new(self.role)?,
+        })
+    }
+}
+
+/// The replacement of a user. The password is not part of it, it is changed through
+/// `POST /users/me/password` which asks for the current one
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct UserInputDto {
+    pub username: String,
+    pub email: String,
+    pub role: String,
+}
+
+impl TryFrom<UserInputDto> for UserChanges {
+    type Error = Error;
+
+    fn try_from(user: UserInputDto) -> Result<Self, Self::Error> {
+        Ok(UserChanges {
+            username: Some(Username::new(&user.username)?),
+            email: Some(Email::new(&user.email)?),
+            role: Some(Role::new(user.role)?),
+        })
+    }
+}
+
+/// A JSON merge patch of a user, the missing fields are left untouched.
+/// Like a replacement, it cannot change the password
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct UserPatchDto {
+    #[serde(default, deserialize_with = "present")]
+    pub username: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub email: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub role: Option<String>,
+}
+
+impl TryFrom<UserPatchDto> for UserChanges {
     type Error = Error;
-    fn try_from(value: NewUserDto) -> Result<Self, Self::Error> {
-        Ok(Self {
-            username: Username::new(&value.username)?,
-            password_hash: PasswordHash::new(&value.password)?,
-            role: Role::new(value.role)?,
+
+    fn try_from(patch: UserPatchDto) -> Result<Self, Self::Error> {
+        Ok(UserChanges {
+            username: patch.username.as_deref().map(Username::new).transpose()?,
+            email: patch.email.as_deref().map(Email::new).transpose()?,
+            role: patch.role.map(Role::new).transpose()?,
+        })
+    }
+}
+
+/// The query string of `GET /users`
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct UserListQueryDto {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    /// `created_at` or `username`, prefixed with `-` for the descending order
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub include_total: bool,
+
+    pub role: Option<String>,
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<UserListQueryDto> for UserQuery {
+    type Error = Error;
+
+    fn try_from(query: UserListQueryDto) -> Result<Self, Self::Error> {
+        let page = PageRequest::new(
+            query.sort.as_deref(),
+            query.limit,
+            query.cursor.as_deref(),
+            query.include_total,
+        )?;
+        Ok(UserQuery {
+            filter: UserFilter {
+                role: query.role.map(Role::new).transpose()?,
+                created_from: query.created_from,
+                created_to: query.created_to,
+            },
+            page,
         })
     }
 }
@@ -32,18 +134,62 @@ pub struct UserLoginInputDto {
     pub password: String,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct ChangePasswordDto {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ResetPasswordDto {
+    /// The token delivered by the notifier
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct VerifyEmailDto {
+    /// The token delivered by the notifier
+    pub token: String,
+}
+
 #[derive(Serialize, Debug)]
 pub struct UserDto {
     pub id: uuid::Uuid,
     pub username: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
     pub role: String,
 }
 
+/// What `/users/me` returns, more than the other users may see
+#[derive(Serialize, Debug)]
+pub struct ProfileDto {
+    #[serde(flatten)]
+    pub user: UserDto,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub ticket_count: i64,
+}
+
+impl ProfileDto {
+    pub fn new(user: User, ticket_count: i64) -> Self {
+        Self {
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            user: user.into(),
+            ticket_count,
+        }
+    }
+}
+
 impl From<User> for UserDto {
     fn from(user: User) -> Self {
         Self {
             id: user.id,
             username: user.username.as_ref().to_string(),
+            email: user.email.map(|e| e.as_ref().to_string()),
+            email_verified: user.email_verified,
             role: user.role.to_string(),
         }
     }
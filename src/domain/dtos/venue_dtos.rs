@@ -0,0 +1,240 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Error,
+    types::{
+        pagination::PageRequest,
+        venue_types::{
+            group_seats, NewVenue, Row, Seat, SeatingChart, Section, Venue, VenueChanges,
+            VenueQuery,
+        },
+    },
+};
+
+use super::{nullable, present};
+
+#[derive(Debug, Serialize)]
+pub struct VenueDto {
+    pub id: Uuid,
+    pub name: String,
+    pub address: Option<String>,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Venue> for VenueDto {
+    fn from(venue: Venue) -> Self {
+        VenueDto {
+            id: venue.id,
+            name: venue.name,
+            address: venue.address,
+            created_at: venue.created_at,
+            updated_at: venue.updated_at,
+        }
+    }
+}
+
+/// A venue along with its seating chart
+#[derive(Debug, Serialize)]
+pub struct VenueDetailsDto {
+    #[serde(flatten)]
+    pub venue: VenueDto,
+    pub sections: Vec<SectionDto<SeatDto>>,
+}
+
+impl From<(Venue, Vec<Seat>)> for VenueDetailsDto {
+    fn from((venue, seats): (Venue, Vec<Seat>)) -> Self {
+        VenueDetailsDto {
+            venue: venue.into(),
+            sections: SectionDto::from_seats(
+                seats.into_iter().map(|s| (s, ())).collect(),
+                |s, ()| SeatDto {
+                    id: s.id,
+                    number: s.number,
+                },
+            ),
+        }
+    }
+}
+
+/// The seats of the venue of a concert, for the frontend to draw its map
+#[derive(Debug, Serialize)]
+pub struct ConcertSeatsDto {
+    pub concert_id: Uuid,
+    pub venue_id: Uuid,
+    pub available: usize,
+    pub total: usize,
+    pub sections: Vec<SectionDto<SeatAvailabilityDto>>,
+}
+
+impl ConcertSeatsDto {
+    pub fn new(concert_id: Uuid, venue_id: Uuid, seats: Vec<(Seat, bool)>) -> Self {
+        ConcertSeatsDto {
+            concert_id,
+            venue_id,
+            available: seats.iter().filter(|(_, available)| *available).count(),
+            total: seats.len(),
+            sections: SectionDto::from_seats(seats, |s, available| SeatAvailabilityDto {
+                id: s.id,
+                number: s.number,
+                available,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SectionDto<S> {
+    pub name: String,
+    pub rows: Vec<RowDto<S>>,
+}
+
+impl<S> SectionDto<S> {
+    fn from_seats<T>(seats: Vec<(Seat, T)>, to_dto: impl Fn(Seat, T) -> S) -> Vec<Self> {
+        group_seats(seats)
+            .into_iter()
+            .map(|(name, rows)| SectionDto {
+                name,
+                rows: rows
+                    .into_iter()
+                    .map(|(name, seats)| RowDto {
+                        name,
+                        seats: seats.into_iter().map(|(s, t)| to_dto(s, t)).collect(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RowDto<S> {
+    pub name: String,
+    pub seats: Vec<S>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeatDto {
+    pub id: Uuid,
+    pub number: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeatAvailabilityDto {
+    pub id: Uuid,
+    pub number: String,
+    pub available: bool,
+}
+
+/// A seating chart file: the sections of the venue, their rows and the seat numbers
+/// of each row, in the order they are displayed
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeatingChartDto {
+    pub sections: Vec<SectionInputDto>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SectionInputDto {
+    pub name: String,
+    pub rows: Vec<RowInputDto>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RowInputDto {
+    pub name: String,
+    pub seats: Vec<String>,
+}
+
+impl TryFrom<SeatingChartDto> for SeatingChart {
+    type Error = Error;
+
+    fn try_from(chart: SeatingChartDto) -> Result<Self, Self::Error> {
+        SeatingChart::new(
+            chart
+                .sections
+                .into_iter()
+                .map(|section| Section {
+                    name: section.name,
+                    rows: section
+                        .rows
+                        .into_iter()
+                        .map(|row| Row {
+                            name: row.name,
+                            seats: row.seats,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VenueInputDto {
+    pub name: String,
+    pub address: Option<String>,
+    /// A venue created without a chart has no seat, the tickets of its concerts are
+    /// unnumbered
+    #[serde(default)]
+    pub seating_chart: SeatingChartDto,
+}
+
+impl TryFrom<VenueInputDto> for NewVenue {
+    type Error = Error;
+
+    fn try_from(venue: VenueInputDto) -> Result<Self, Self::Error> {
+        NewVenue::new(&venue.name, venue.address, venue.seating_chart.try_into()?)
+    }
+}
+
+/// A JSON merge patch of a venue, the missing fields are left untouched.
+/// Only the address can be removed with `null`, the seating chart is replaced on its own
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VenuePatchDto {
+    #[serde(default, deserialize_with = "present")]
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub address: Option<Option<String>>,
+}
+
+impl TryFrom<VenuePatchDto> for VenueChanges {
+    type Error = Error;
+
+    fn try_from(patch: VenuePatchDto) -> Result<Self, Self::Error> {
+        VenueChanges::new(patch.name.as_deref(), patch.address)
+    }
+}
+
+/// The query string of `GET /venues`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VenueListQueryDto {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    /// `name` or `created_at`, prefixed with `-` for the descending order
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub include_total: bool,
+}
+
+impl TryFrom<VenueListQueryDto> for VenueQuery {
+    type Error = Error;
+
+    fn try_from(query: VenueListQueryDto) -> Result<Self, Self::Error> {
+        let page = PageRequest::new(
+            query.sort.as_deref(),
+            query.limit,
+            query.cursor.as_deref(),
+            query.include_total,
+        )?;
+        Ok(VenueQuery { page })
+    }
+}
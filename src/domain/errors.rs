@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use super::password_policy::PasswordViolation;
+
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 #[derive(Error, Debug)]
@@ -8,6 +10,8 @@ pub enum Error {
     InternalError(String),
     #[error("username already exists")]
     UsernameAlreadyExists,
+    #[error("email already used by another account")]
+    EmailAlreadyExists,
     #[error("user not found")]
     UserNotFound,
     #[error("user creation failed: {0}")]
@@ -28,12 +32,224 @@ pub enum Error {
     TicketUpdateFailed(sqlx::Error),
     #[error("could not delete ticket: {0}")]
     TicketDeletionFailed(sqlx::Error),
-    #[error("invalid password: {0}")]
-    InvalidPassword(String),
+    #[error("concert fetch failed: {0}")]
+    ConcertFetchFailed(sqlx::Error),
+    #[error("concert not found")]
+    ConcertNotFound,
+    #[error("concert creation failed: {0}")]
+    ConcertCreationFailed(sqlx::Error),
+    #[error("concert update failed: {0}")]
+    ConcertUpdateFailed(sqlx::Error),
+    #[error("could not delete concert: {0}")]
+    ConcertDeletionFailed(sqlx::Error),
+    #[error("the concert still has tickets or orders")]
+    ConcertHasTickets,
+    #[error("invalid concert: {0}")]
+    InvalidConcert(String),
+    #[error("venue fetch failed: {0}")]
+    VenueFetchFailed(sqlx::Error),
+    #[error("venue not found")]
+    VenueNotFound,
+    #[error("venue creation failed: {0}")]
+    VenueCreationFailed(sqlx::Error),
+    #[error("venue update failed: {0}")]
+    VenueUpdateFailed(sqlx::Error),
+    #[error("could not delete venue: {0}")]
+    VenueDeletionFailed(sqlx::Error),
+    #[error("the venue still has concerts")]
+    VenueHasConcerts,
+    #[error("the seats of the venue are still held by tickets")]
+    SeatsInUse,
+    #[error("invalid venue: {0}")]
+    InvalidVenue(String),
+    #[error("invalid seating chart: {0}")]
+    InvalidSeatingChart(String),
+    #[error("the concert has no venue")]
+    ConcertHasNoVenue,
+    #[error("seat not found")]
+    SeatNotFound,
+    #[error("the seat is not in the venue of the concert")]
+    SeatNotInVenue,
+    #[error("the seat is already taken for this concert")]
+    SeatTaken,
+    #[error("invalid ticket: {0}")]
+    InvalidTicket(String),
+    #[error("ticket category fetch failed: {0}")]
+    CategoryFetchFailed(sqlx::Error),
+    #[error("ticket category not found")]
+    CategoryNotFound,
+    #[error("ticket category creation failed: {0}")]
+    CategoryCreationFailed(sqlx::Error),
+    #[error("ticket category update failed: {0}")]
+    CategoryUpdateFailed(sqlx::Error),
+    #[error("could not delete ticket category: {0}")]
+    CategoryDeletionFailed(sqlx::Error),
+    #[error("the concert already has a category with this name")]
+    CategoryAlreadyExists,
+    #[error("the ticket category still has tickets or orders")]
+    CategoryHasTickets,
+    #[error("the capacity cannot go below the tickets already sold or held")]
+    CapacityBelowSold,
+    #[error("the category is not one of the concert")]
+    CategoryNotInConcert,
+    #[error("a category must be chosen for the tickets of this concert")]
+    CategoryRequired,
+    #[error("invalid ticket category: {0}")]
+    InvalidCategory(String),
+    #[error("the category is sold out")]
+    SoldOut,
+    #[error("hold fetch failed: {0}")]
+    HoldFetchFailed(sqlx::Error),
+    #[error("hold not found")]
+    HoldNotFound,
+    #[error("hold creation failed: {0}")]
+    HoldCreationFailed(sqlx::Error),
+    #[error("could not release hold: {0}")]
+    HoldDeletionFailed(sqlx::Error),
+    #[error("the hold has expired")]
+    HoldExpired,
+    #[error("invalid hold: {0}")]
+    InvalidHold(String),
+    #[error("the seat is held by another buyer for this concert")]
+    SeatHeld,
+    #[error("order fetch failed: {0}")]
+    OrderFetchFailed(sqlx::Error),
+    #[error("order not found")]
+    OrderNotFound,
+    #[error("order creation failed: {0}")]
+    OrderCreationFailed(sqlx::Error),
+    #[error("order update failed: {0}")]
+    OrderUpdateFailed(sqlx::Error),
+    #[error("invalid order: {0}")]
+    InvalidOrder(String),
+    #[error("invalid order status: {0}")]
+    InvalidOrderStatus(String),
+    #[error("a {0} order cannot be {1}")]
+    InvalidOrderTransition(String, String),
+    #[error("refresh token creation failed: {0}")]
+    RefreshTokenCreationFailed(sqlx::Error),
+    #[error("refresh token update failed: {0}")]
+    RefreshTokenUpdateFailed(sqlx::Error),
+    #[error("invalid refresh token")]
+    InvalidRefreshToken,
+    #[error("refresh token reuse detected, please log in again")]
+    RefreshTokenReused,
+    #[error("token revocation failed: {0}")]
+    RevocationFailed(sqlx::Error),
+    #[error("revoked tokens fetch failed: {0}")]
+    RevocationFetchFailed(sqlx::Error),
+    #[error("password reset failed: {0}")]
+    PasswordResetFailed(sqlx::Error),
+    #[error("invalid or expired password reset token")]
+    InvalidResetToken,
+    #[error("email verification failed: {0}")]
+    EmailVerificationFailed(sqlx::Error),
+    #[error("invalid or expired email verification token")]
+    InvalidVerificationToken,
+    #[error("the email address is already verified")]
+    EmailAlreadyVerified,
+    #[error("the user has no email address")]
+    MissingEmail,
+    #[error("the email address of the user must be verified first")]
+    EmailNotVerified,
+    #[error("notification failed: {0}")]
+    NotificationFailed(String),
+    #[error("password hashing failed: {0}")]
+    PasswordHashingFailed(String),
+    #[error("invalid password: {}", join_violations(.0))]
+    InvalidPassword(Vec<PasswordViolation>),
     #[error("invalid username: {0}")]
     InvalidUsername(String),
+    #[error("invalid email: {0}")]
+    InvalidEmail(String),
+    #[error("the resource was modified since it was fetched")]
+    PreconditionFailed,
+    #[error("the If-Match header is required, fetch the resource for its ETag")]
+    PreconditionRequired,
+    #[error("idempotency key tracking failed: {0}")]
+    IdempotencyKeyFailed(sqlx::Error),
+    #[error("invalid idempotency key: {0}")]
+    InvalidIdempotencyKey(String),
+    #[error("a request with this idempotency key is still being processed, retry later")]
+    IdempotencyKeyInUse,
+    #[error("the idempotency key was already used for a different request")]
+    IdempotencyKeyReused,
+    #[error("invalid cursor: {0}")]
+    InvalidCursor(String),
+    #[error("invalid sort: {0} is not a sortable field")]
+    InvalidSort(String),
+    #[error("invalid page request: {0}")]
+    InvalidPageRequest(String),
+    #[error("invalid search: {0}")]
+    InvalidSearch(String),
+    #[error("unsupported media type: {0}")]
+    UnsupportedMediaType(String),
+    #[error("invalid patch: {0}")]
+    InvalidPatch(String),
     #[error("invalid role: {0} does not exist")]
     InvalidRole(String),
+    #[error("malformed token: {0}")]
+    MalformedToken(String),
+    #[error("invalid token signature")]
+    InvalidTokenSignature,
+    #[error("unknown signing key {0}")]
+    UnknownSigningKey(String),
+    #[error("token expired")]
+    TokenExpired,
+    #[error("token not yet valid")]
+    TokenNotYetValid,
+    #[error("invalid token issuer")]
+    InvalidTokenIssuer,
+    #[error("invalid token audience")]
+    InvalidTokenAudience,
+    #[error("invalid credentials")]
+    InvalidCredentials,
+    #[error("too many failed login attempts, retry in {0} seconds")]
+    TooManyLoginAttempts(i64),
+    #[error("login attempts tracking failed: {0}")]
+    LoginAttemptsFailed(sqlx::Error),
+    #[error("invalid attempt kind: {0}, expected account or ip")]
+    InvalidAttemptKind(String),
+    #[error("mfa fetch failed: {0}")]
+    MfaFetchFailed(sqlx::Error),
+    #[error("mfa update failed: {0}")]
+    MfaUpdateFailed(sqlx::Error),
+    #[error("two-factor authentication is already enabled")]
+    MfaAlreadyEnabled,
+    #[error("two-factor authentication is not enabled")]
+    MfaNotEnabled,
+    #[error("invalid two-factor authentication code")]
+    InvalidMfaCode,
+    #[error("invalid or expired two-factor authentication challenge")]
+    InvalidMfaChallenge,
+    #[error("two-factor authentication is required for this account, enroll at /users/me/mfa")]
+    MfaRequired,
+    #[error("api key creation failed: {0}")]
+    ApiKeyCreationFailed(sqlx::Error),
+    #[error("api key fetch failed: {0}")]
+    ApiKeyFetchFailed(sqlx::Error),
+    #[error("api key update failed: {0}")]
+    ApiKeyUpdateFailed(sqlx::Error),
+    #[error("api key not found")]
+    ApiKeyNotFound,
+    #[error("invalid, expired or revoked API key")]
+    InvalidApiKey,
+    #[error("invalid API key: {0}")]
+    InvalidApiKeyInput(String),
+    #[error("invalid scope: {0}, expected tickets:read, tickets:write, users:read or users:write")]
+    InvalidScope(String),
     #[error("login failed: {0}")]
     LoginFailed(String),
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+}
+
+fn join_violations(violations: &[PasswordViolation]) -> String {
+    violations
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
@@ -1,3 +1,6 @@
 pub mod dtos;
 pub mod errors;
+pub mod password_policy;
+pub mod policy;
+pub mod preconditions;
 pub mod types;
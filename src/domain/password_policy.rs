@@ -0,0 +1,248 @@
+use std::{collections::HashSet, fmt::Display, fs};
+
+use serde::Serialize;
+
+use crate::{
+    domain::errors::{Error, Result},
+    Cfg,
+};
+
+/// A rule of the `PasswordPolicy` a password does not follow
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum PasswordViolation {
+    TooShort { min_length: usize },
+    TooLong { max_length: usize },
+    MissingLowercase,
+    MissingUppercase,
+    MissingDigit,
+    MissingSymbol,
+    ContainsUsername,
+    Common,
+}
+
+impl Display for PasswordViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PasswordViolation::TooShort { min_length } => {
+                write!(f, "must be at least {min_length} characters long")
+            }
+            PasswordViolation::TooLong { max_length } => {
+                write!(f, "must be at most {max_length} characters long")
+            }
+            PasswordViolation::MissingLowercase => write!(f, "must contain a lowercase letter"),
+            PasswordViolation::MissingUppercase => write!(f, "must contain an uppercase letter"),
+            PasswordViolation::MissingDigit => write!(f, "must contain a digit"),
+            PasswordViolation::MissingSymbol => write!(f, "must contain a symbol"),
+            PasswordViolation::ContainsUsername => write!(f, "must not contain the username"),
+            PasswordViolation::Common => write!(f, "is too common"),
+        }
+    }
+}
+
+/// The rules a new password must follow. Lengths are counted in characters, not bytes
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub reject_username: bool,
+    /// Lowercased passwords known from breaches or too common to be used
+    pub common_passwords: HashSet<String>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+            reject_username: true,
+            common_passwords: HashSet::new(),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn from_config(config: &Cfg) -> Result<Self> {
+        let mut policy = Self {
+            min_length: config.password_min_length,
+            max_length: config.password_max_length,
+            require_lowercase: config.password_require_lowercase,
+            require_uppercase: config.password_require_uppercase,
+            require_digit: config.password_require_digit,
+            require_symbol: config.password_require_symbol,
+            reject_username: config.password_reject_username,
+            common_passwords: HashSet::new(),
+        };
+        if let Some(path) = &config.password_blocklist_file {
+            let list = fs::read_to_string(path).map_err(|e| {
+                Error::InternalError(format!("Cannot read the password blocklist {path}: {e}"))
+            })?;
+            policy.common_passwords = parse_blocklist(&list);
+        }
+        Ok(policy)
+    }
+
+    /// Check a password against every rule, all the failing ones are reported
+    pub fn check(&self, password: &str, username: &str) -> Result<()> {
+        let mut violations = Vec::new();
+        let length = password.chars().count();
+        if length < self.min_length {
+            violations.push(PasswordViolation::TooShort {
+                min_length: self.min_length,
+            });
+        }
+        if length > self.max_length {
+            violations.push(PasswordViolation::TooLong {
+                max_length: self.max_length,
+            });
+        }
+        let rules = [
+            (
+                self.require_lowercase,
+                password.chars().any(char::is_lowercase),
+                PasswordViolation::MissingLowercase,
+            ),
+            (
+                self.require_uppercase,
+                password.chars().any(char::is_uppercase),
+                PasswordViolation::MissingUppercase,
+            ),
+            (
+                self.require_digit,
+                password.chars().any(|c| c.is_ascii_digit()),
+                PasswordViolation::MissingDigit,
+            ),
+            (
+                self.require_symbol,
+                password.chars().any(|c| !c.is_alphanumeric()),
+                PasswordViolation::MissingSymbol,
+            ),
+        ];
+        for (required, present, violation) in rules {
+            if required && !present {
+                violations.push(violation);
+            }
+        }
+        let lowercase = password.to_lowercase();
+        if self.reject_username
+            && !username.is_empty()
+            && lowercase.contains(&username.to_lowercase())
+        {
+            violations.push(PasswordViolation::ContainsUsername);
+        }
+        if self.common_passwords.contains(&lowercase) {
+            violations.push(PasswordViolation::Common);
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidPassword(violations))
+        }
+    }
+}
+
+/// One password per line, blank lines and `#` comments are ignored
+fn parse_blocklist(list: &str) -> HashSet<String> {
+    list.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn violations(
+        policy: &PasswordPolicy,
+        password: &str,
+        username: &str,
+    ) -> Vec<PasswordViolation> {
+        match policy.check(password, username) {
+            Ok(()) => Vec::new(),
+            Err(Error::InvalidPassword(violations)) => violations,
+            Err(e) => panic!("unexpected error {e}"),
+        }
+    }
+
+    #[test]
+    fn test_length_is_counted_in_characters() {
+        let policy = PasswordPolicy::default();
+        // 8 bytes but 4 characters
+        assert_eq!(
+            vec![PasswordViolation::TooShort { min_length: 8 }],
+            violations(&policy, "éééé", "username")
+        );
+        assert!(policy.check("éééééééé", "username").is_ok());
+        assert_eq!(
+            vec![PasswordViolation::TooLong { max_length: 128 }],
+            violations(&policy, &"a".repeat(129), "username")
+        );
+    }
+
+    #[test]
+    fn test_every_failing_rule_is_reported() {
+        let policy = PasswordPolicy {
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            vec![
+                PasswordViolation::TooShort { min_length: 8 },
+                PasswordViolation::MissingUppercase,
+                PasswordViolation::MissingDigit,
+                PasswordViolation::MissingSymbol,
+            ],
+            violations(&policy, "abc", "username")
+        );
+        assert!(policy.check("Abcdefg1!", "username").is_ok());
+    }
+
+    #[test]
+    fn test_username_is_rejected() {
+        let policy = PasswordPolicy::default();
+        assert_eq!(
+            vec![PasswordViolation::ContainsUsername],
+            violations(&policy, "my-JohnDoe-password", "johndoe")
+        );
+        let policy = PasswordPolicy {
+            reject_username: false,
+            ..Default::default()
+        };
+        assert!(policy.check("my-JohnDoe-password", "johndoe").is_ok());
+    }
+
+    #[test]
+    fn test_common_passwords_are_rejected() {
+        let policy = PasswordPolicy {
+            common_passwords: parse_blocklist("# comment\npassword1\n\n  Qwertyuiop \n"),
+            ..Default::default()
+        };
+        assert_eq!(2, policy.common_passwords.len());
+        assert_eq!(
+            vec![PasswordViolation::Common],
+            violations(&policy, "QWERTYUIOP", "username")
+        );
+        assert!(policy.check("not so common", "username").is_ok());
+    }
+
+    #[test]
+    fn test_violations_are_serialized_with_their_rule() {
+        let violation =
+            serde_json::to_value(PasswordViolation::TooShort { min_length: 8 }).unwrap();
+        assert_eq!("too_short", violation["rule"]);
+        assert_eq!(8, violation["min_length"]);
+    }
+}
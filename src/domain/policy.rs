@@ -0,0 +1,618 @@
+use std::fmt::Display;
+
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::{
+        api_key_types::ScopeResource,
+        hold_types::Hold,
+        order_types::Order,
+        ticket_types::{Ticket, TicketChanges},
+        user_types::User,
+        JwtClaims, Role, Username,
+    },
+};
+
+/// What the caller is trying to do with a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Read,
+    Update,
+    Delete,
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Read => write!(f, "read"),
+            Action::Update => write!(f, "update"),
+            Action::Delete => write!(f, "delete"),
+        }
+    }
+}
+
+/// Owners may manage their own tickets, admins may manage every ticket
+pub fn authorize_ticket(claims: &JwtClaims, action: Action, ticket: &Ticket) -> Result<()> {
+    if claims.is_admin() || claims.user_id == ticket.owner_id {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(format!("you cannot {action} this ticket")))
+    }
+}
+
+/// Only admins may issue tickets directly, the others get theirs by paying an order
+pub fn authorize_ticket_issuance(claims: &JwtClaims) -> Result<()> {
+    if claims.is_admin() {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(
+            "tickets are bought through orders".to_string(),
+        ))
+    }
+}
+
+/// Only admins may hand out tickets to someone else
+pub fn authorize_ticket_owner(claims: &JwtClaims, owner_id: Uuid) -> Result<()> {
+    if claims.is_admin() || claims.user_id == owner_id {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(
+            "you cannot give a ticket to another user".to_string(),
+        ))
+    }
+}
+
+/// Only admins may reprice a ticket or move it to another concert or category,
+/// the owners may send the current values back unchanged
+pub fn authorize_ticket_changes(
+    claims: &JwtClaims,
+    ticket: &Ticket,
+    changes: &TicketChanges,
+) -> Result<()> {
+    let repriced = changes.concert_id.is_some_and(|c| c != ticket.concert_id)
+        || changes.category_id.is_some_and(|c| c != ticket.category_id)
+        || changes.price.is_some_and(|p| p != ticket.price);
+    if claims.is_admin() || !repriced {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(
+            "you cannot change the price, concert or category of a ticket".to_string(),
+        ))
+    }
+}
+
+/// Tickets can only be bought by or given to users with a verified email, so they can be reached
+pub fn authorize_ticket_recipient(owner: &User) -> Result<()> {
+    if owner.email_verified {
+        Ok(())
+    } else {
+        Err(Error::EmailNotVerified)
+    }
+}
+
+/// Holders may manage their own holds, admins may manage every hold
+pub fn authorize_hold(claims: &JwtClaims, action: Action, hold: &Hold) -> Result<()> {
+    if claims.is_admin() || claims.user_id == hold.user_id {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(format!("you cannot {action} this hold")))
+    }
+}
+
+/// Only holders may check out their holds, the tickets go to whoever orders them
+pub fn authorize_checkout(claims: &JwtClaims, hold: &Hold) -> Result<()> {
+    if claims.user_id == hold.user_id {
+        Ok(())
+    } else {
+        Err(Error::Forbidden("you cannot order this hold".to_string()))
+    }
+}
+
+/// Buyers may manage their own orders, admins may manage every order
+pub fn authorize_order(claims: &JwtClaims, action: Action, order: &Order) -> Result<()> {
+    if claims.is_admin() || claims.user_id == order.user_id {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(format!("you cannot {action} this order")))
+    }
+}
+
+/// Only admins may confirm that an order is paid, its tickets are issued then.
+/// The buyers pay through the payment provider, whose confirmation comes with an admin key
+pub fn authorize_payment(claims: &JwtClaims) -> Result<()> {
+    if claims.is_admin() {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(
+            "only admins can confirm a payment".to_string(),
+        ))
+    }
+}
+
+/// Only admins may refund an order
+pub fn authorize_refund(claims: &JwtClaims) -> Result<()> {
+    if claims.is_admin() {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(
+            "only admins can refund orders".to_string(),
+        ))
+    }
+}
+
+/// Users may manage their own account, admins may manage every account
+pub fn authorize_user(claims: &JwtClaims, action: Action, user_id: Uuid) -> Result<()> {
+    if claims.is_admin() || claims.user_id == user_id {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(format!("you cannot {action} this user")))
+    }
+}
+
+/// Users may look themselves up by their username, admins may look up everyone.
+/// Decided from the token alone, so the lookup cannot tell who has an account
+pub fn authorize_username_lookup(claims: &JwtClaims, username: &str) -> Result<()> {
+    if claims.is_admin()
+        || Username::comparison_key(&claims.sub) == Username::comparison_key(username)
+    {
+        Ok(())
+    } else {
+        Err(Error::Forbidden("you cannot read this user".to_string()))
+    }
+}
+
+/// Only admins may change a role, including their own
+pub fn authorize_role_change(claims: &JwtClaims, current: &Role, new: &Role) -> Result<()> {
+    if claims.is_admin() || current.as_ref() == new.as_ref() {
+        Ok(())
+    } else {
+        Err(Error::Forbidden("you cannot change your role".to_string()))
+    }
+}
+
+/// Anyone may register, but only admins may create other admins
+pub fn authorize_user_creation(claims: Option<&JwtClaims>, role: &Role) -> Result<()> {
+    if !role.is_admin() || claims.is_some_and(|c| c.is_admin()) {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(
+            "only an admin can create an admin account".to_string(),
+        ))
+    }
+}
+
+/// Listing every user or ticket is reserved to admins
+pub fn authorize_listing(claims: &JwtClaims) -> Result<()> {
+    if claims.is_admin() {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(
+            "only an admin can list everything".to_string(),
+        ))
+    }
+}
+
+/// The concert catalog, ticket categories included, is managed by the admins,
+/// anyone logged in may browse it
+pub fn authorize_concert_management(claims: &JwtClaims) -> Result<()> {
+    if claims.is_admin() {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(
+            "only an admin can manage the concerts".to_string(),
+        ))
+    }
+}
+
+/// The venues and their seating charts are managed by the admins, like the concerts
+pub fn authorize_venue_management(claims: &JwtClaims) -> Result<()> {
+    if claims.is_admin() {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(
+            "only an admin can manage the venues".to_string(),
+        ))
+    }
+}
+
+/// Admins search everything, the other users only find their own tickets and account.
+/// Returns the only owner to search in, if any
+pub fn search_owner(claims: &JwtClaims) -> Option<Uuid> {
+    if claims.is_admin() {
+        None
+    } else {
+        Some(claims.user_id)
+    }
+}
+
+/// Revoking the sessions of a user is reserved to admins
+pub fn authorize_session_revocation(claims: &JwtClaims) -> Result<()> {
+    if claims.is_admin() {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(
+            "only an admin can revoke the sessions of a user".to_string(),
+        ))
+    }
+}
+
+/// Issuing a password reset token is reserved to admins
+pub fn authorize_password_reset(claims: &JwtClaims) -> Result<()> {
+    if claims.is_admin() {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(
+            "only an admin can reset the password of a user".to_string(),
+        ))
+    }
+}
+
+/// Viewing and clearing the login lockouts is reserved to admins
+pub fn authorize_lockout_management(claims: &JwtClaims) -> Result<()> {
+    if claims.is_admin() {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(
+            "only an admin can manage the login lockouts".to_string(),
+        ))
+    }
+}
+
+/// When `required`, admins must have logged in with a second factor
+pub fn authorize_mfa(claims: &JwtClaims, required: bool) -> Result<()> {
+    if !required || !claims.is_admin() || claims.has_mfa() {
+        Ok(())
+    } else {
+        Err(Error::MfaRequired)
+    }
+}
+
+/// API keys are restricted to their scopes, a key without scopes can only read.
+/// `resource` is None for the routes no scope gives access to, only sessions reach them
+pub fn authorize_api_key_scope(
+    claims: &JwtClaims,
+    resource: Option<ScopeResource>,
+    write: bool,
+) -> Result<()> {
+    if claims.api_key_id.is_none() {
+        return Ok(());
+    }
+    let allowed = match (&claims.scopes, resource) {
+        (_, None) => false,
+        (None, Some(_)) => !write,
+        (Some(scopes), Some(resource)) => scopes.iter().any(|s| s.allows(resource, write)),
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(
+            "this API key is not allowed to do this".to_string(),
+        ))
+    }
+}
+
+/// Credentials (password, 2FA, API keys) can only be managed with a login session,
+/// a leaked API key must not be enough to take over the account
+pub fn authorize_credential_management(claims: &JwtClaims) -> Result<()> {
+    if claims.api_key_id.is_none() {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(
+            "an API key cannot manage credentials".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::types::{api_key_types::Scope, order_types::OrderStatus, Email};
+    use chrono::{Duration, Utc};
+
+    fn claims(role: &str) -> JwtClaims {
+        JwtClaims::new(
+            "username".to_string(),
+            Uuid::new_v4(),
+            role.to_string(),
+            Duration::days(1),
+        )
+    }
+
+    /// A ticket, a hold and an order of the same user
+    struct Owned {
+        ticket: Ticket,
+        hold: Hold,
+        order: Order,
+    }
+
+    fn owned_by(owner_id: Uuid) -> Owned {
+        let now = Utc::now();
+        Owned {
+            ticket: Ticket {
+                id: Uuid::new_v4(),
+                owner_id,
+                concert_id: Uuid::new_v4(),
+                concert_name: "Trivium".to_string(),
+                concert_date: now,
+                seat_id: None,
+                seat_section: None,
+                seat_row: None,
+                seat_number: None,
+                category_id: None,
+                category_name: None,
+                barcode_data: "12345-abcde-67890".to_string(),
+                price: 50.0,
+                version: 1,
+                created_at: now,
+                updated_at: now,
+            },
+            hold: Hold {
+                id: Uuid::new_v4(),
+                user_id: owner_id,
+                concert_id: Uuid::new_v4(),
+                category_id: Uuid::new_v4(),
+                quantity: 2,
+                seat_ids: vec![],
+                expires_at: now,
+                created_at: now,
+            },
+            order: Order {
+                id: Uuid::new_v4(),
+                user_id: owner_id,
+                status: OrderStatus::Pending,
+                total: 100.0,
+                hold_id: None,
+                items: vec![],
+                ticket_ids: vec![],
+                version: 1,
+                created_at: now,
+                updated_at: now,
+                paid_at: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_owner_can_manage_own_ticket() {
+        let user = claims("user");
+        let ticket = owned_by(user.user_id).ticket;
+        assert!(authorize_ticket(&user, Action::Read, &ticket).is_ok());
+        assert!(authorize_ticket(&user, Action::Update, &ticket).is_ok());
+        assert!(authorize_ticket(&user, Action::Delete, &ticket).is_ok());
+    }
+
+    #[test]
+    fn test_user_cannot_manage_other_ticket() {
+        let user = claims("user");
+        let ticket = owned_by(Uuid::new_v4()).ticket;
+        assert!(matches!(
+            authorize_ticket(&user, Action::Read, &ticket),
+            Err(Error::Forbidden(_))
+        ));
+        assert!(authorize_ticket(&user, Action::Delete, &ticket).is_err());
+        assert!(authorize_ticket_owner(&user, ticket.owner_id).is_err());
+    }
+
+    #[test]
+    fn test_admin_can_manage_everything() {
+        let admin = claims("admin");
+        let ticket = owned_by(Uuid::new_v4()).ticket;
+        assert!(authorize_ticket(&admin, Action::Delete, &ticket).is_ok());
+        assert!(authorize_ticket_owner(&admin, ticket.owner_id).is_ok());
+        assert!(authorize_user(&admin, Action::Delete, Uuid::new_v4()).is_ok());
+        assert!(authorize_listing(&admin).is_ok());
+    }
+
+    #[test]
+    fn test_search_is_limited_to_own_tickets() {
+        let user = claims("user");
+        assert_eq!(Some(user.user_id), search_owner(&user));
+        assert!(search_owner(&claims("admin")).is_none());
+    }
+
+    #[test]
+    fn test_holder_can_manage_own_hold() {
+        let user = claims("user");
+        assert!(authorize_hold(&user, Action::Update, &owned_by(user.user_id).hold).is_ok());
+        let other = owned_by(Uuid::new_v4()).hold;
+        assert!(matches!(
+            authorize_hold(&user, Action::Read, &other),
+            Err(Error::Forbidden(_))
+        ));
+        assert!(authorize_hold(&claims("admin"), Action::Delete, &other).is_ok());
+    }
+
+    #[test]
+    fn test_only_holder_checks_out() {
+        let user = claims("user");
+        assert!(authorize_checkout(&user, &owned_by(user.user_id).hold).is_ok());
+        let other = owned_by(Uuid::new_v4()).hold;
+        assert!(authorize_checkout(&user, &other).is_err());
+        // The tickets of a hold go to whoever orders them, so only the holder checks it out
+        assert!(authorize_checkout(&claims("admin"), &other).is_err());
+    }
+
+    #[test]
+    fn test_buyer_can_manage_own_order() {
+        let user = claims("user");
+        assert!(authorize_order(&user, Action::Update, &owned_by(user.user_id).order).is_ok());
+        let other = owned_by(Uuid::new_v4()).order;
+        assert!(matches!(
+            authorize_order(&user, Action::Read, &other),
+            Err(Error::Forbidden(_))
+        ));
+        assert!(authorize_order(&claims("admin"), Action::Read, &other).is_ok());
+    }
+
+    #[test]
+    fn test_only_admins_confirm_payments() {
+        let user = claims("user");
+        assert!(matches!(authorize_payment(&user), Err(Error::Forbidden(_))));
+        assert!(authorize_payment(&claims("admin")).is_ok());
+    }
+
+    #[test]
+    fn test_only_admins_refund() {
+        assert!(authorize_refund(&claims("user")).is_err());
+        assert!(authorize_refund(&claims("admin")).is_ok());
+    }
+
+    #[test]
+    fn test_only_admins_manage_concerts_and_venues() {
+        let user = claims("user");
+        assert!(authorize_concert_management(&user).is_err());
+        assert!(authorize_venue_management(&user).is_err());
+        let admin = claims("admin");
+        assert!(authorize_concert_management(&admin).is_ok());
+        assert!(authorize_venue_management(&admin).is_ok());
+    }
+
+    #[test]
+    fn test_only_admins_revoke_sessions() {
+        assert!(authorize_session_revocation(&claims("user")).is_err());
+        assert!(authorize_session_revocation(&claims("admin")).is_ok());
+    }
+
+    #[test]
+    fn test_only_admins_reset_passwords() {
+        assert!(authorize_password_reset(&claims("user")).is_err());
+        assert!(authorize_password_reset(&claims("admin")).is_ok());
+    }
+
+    #[test]
+    fn test_only_admins_manage_lockouts() {
+        assert!(authorize_lockout_management(&claims("user")).is_err());
+        assert!(authorize_lockout_management(&claims("admin")).is_ok());
+    }
+
+    #[test]
+    fn test_only_verified_users_receive_tickets() {
+        let mut owner = User {
+            id: Uuid::new_v4(),
+            username: Username::new("username").unwrap(),
+            email: Some(Email::new("username@example.com").unwrap()),
+            email_verified: false,
+            role: Role::new("user".to_string()).unwrap(),
+            version: 1,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        assert!(matches!(
+            authorize_ticket_recipient(&owner),
+            Err(Error::EmailNotVerified)
+        ));
+        owner.email_verified = true;
+        assert!(authorize_ticket_recipient(&owner).is_ok());
+    }
+
+    #[test]
+    fn test_only_admins_issue_tickets() {
+        assert!(matches!(
+            authorize_ticket_issuance(&claims("user")),
+            Err(Error::Forbidden(_))
+        ));
+        assert!(authorize_ticket_issuance(&claims("admin")).is_ok());
+    }
+
+    #[test]
+    fn test_only_admins_reprice_tickets() {
+        let user = claims("user");
+        let ticket = owned_by(user.user_id).ticket;
+        let changes = |concert_id, category_id, price| TicketChanges {
+            owner_id: None,
+            concert_id,
+            seat_id: None,
+            category_id,
+            barcode_data: Some("67890-abcde-12345".to_string()),
+            price,
+        };
+        let unchanged = changes(Some(ticket.concert_id), Some(None), Some(ticket.price));
+        assert!(authorize_ticket_changes(&user, &ticket, &unchanged).is_ok());
+        for repriced in [
+            changes(Some(Uuid::new_v4()), None, None),
+            changes(None, Some(Some(Uuid::new_v4())), None),
+            changes(None, None, Some(10.0)),
+        ] {
+            assert!(matches!(
+                authorize_ticket_changes(&user, &ticket, &repriced),
+                Err(Error::Forbidden(_))
+            ));
+            assert!(authorize_ticket_changes(&claims("admin"), &ticket, &repriced).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_user_can_only_manage_own_account() {
+        let user = claims("user");
+        assert!(authorize_user(&user, Action::Update, user.user_id).is_ok());
+        assert!(authorize_user(&user, Action::Update, Uuid::new_v4()).is_err());
+        assert!(authorize_listing(&user).is_err());
+    }
+
+    #[test]
+    fn test_users_only_look_themselves_up() {
+        // The claims are issued to "username"
+        let user = claims("user");
+        assert!(authorize_username_lookup(&user, " UserName ").is_ok());
+        assert!(matches!(
+            authorize_username_lookup(&user, "other"),
+            Err(Error::Forbidden(_))
+        ));
+        assert!(authorize_username_lookup(&claims("admin"), "other").is_ok());
+    }
+
+    #[test]
+    fn test_user_cannot_change_own_role() {
+        let user_role = Role::new("user".to_string()).unwrap();
+        let admin_role = Role::new("admin".to_string()).unwrap();
+        assert!(authorize_role_change(&claims("user"), &user_role, &user_role).is_ok());
+        assert!(authorize_role_change(&claims("user"), &user_role, &admin_role).is_err());
+        assert!(authorize_role_change(&claims("admin"), &admin_role, &user_role).is_ok());
+    }
+
+    #[test]
+    fn test_only_admins_create_admins() {
+        let user_role = Role::new("user".to_string()).unwrap();
+        let admin_role = Role::new("admin".to_string()).unwrap();
+        assert!(authorize_user_creation(None, &user_role).is_ok());
+        assert!(authorize_user_creation(None, &admin_role).is_err());
+        assert!(authorize_user_creation(Some(&claims("user")), &admin_role).is_err());
+        assert!(authorize_user_creation(Some(&claims("admin")), &admin_role).is_ok());
+    }
+
+    #[test]
+    fn test_admin_mfa_requirement() {
+        assert!(authorize_mfa(&claims("admin"), false).is_ok());
+        assert!(matches!(
+            authorize_mfa(&claims("admin"), true),
+            Err(Error::MfaRequired)
+        ));
+        assert!(authorize_mfa(&claims("admin").with_mfa(), true).is_ok());
+        assert!(authorize_mfa(&claims("user"), true).is_ok());
+    }
+
+    #[test]
+    fn test_api_key_scopes() {
+        let tickets = Some(ScopeResource::Tickets);
+        let session = claims("user");
+        assert!(authorize_api_key_scope(&session, None, true).is_ok());
+        assert!(authorize_credential_management(&session).is_ok());
+
+        // Without scopes, a key can only read
+        let mut key = claims("user");
+        key.api_key_id = Some(Uuid::new_v4());
+        assert!(authorize_api_key_scope(&key, tickets, false).is_ok());
+        assert!(authorize_api_key_scope(&key, tickets, true).is_err());
+        assert!(authorize_api_key_scope(&key, None, false).is_err());
+        assert!(authorize_credential_management(&key).is_err());
+
+        key.scopes = Some(vec![Scope::TicketsWrite]);
+        assert!(authorize_api_key_scope(&key, tickets, true).is_ok());
+        assert!(authorize_api_key_scope(&key, None, false).is_err());
+        key.scopes = Some(vec![Scope::TicketsRead]);
+        assert!(authorize_api_key_scope(&key, tickets, false).is_ok());
+        assert!(authorize_api_key_scope(&key, tickets, true).is_err());
+        assert!(authorize_api_key_scope(&key, Some(ScopeResource::Users), false).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use crate::domain::errors::{Error, Result};
+
+/// The ETag of a ticket or a user, made from their version
+pub fn etag(version: i32) -> String {
+    format!("\"{version}\"")
+}
+
+/// Writes must send back the ETag of the version they were made from, a change
+/// made in the meantime would be lost otherwise. `*` matches any version
+pub fn check_if_match(if_match: Option<&str>, version: i32) -> Result<()> {
+    let Some(if_match) = if_match else {
+        return Err(Error::PreconditionRequired);
+    };
+    let etag = etag(version);
+    // Weak ETags never match, If-Match uses the strong comparison
+    let matches = if_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag == etag);
+    if matches {
+        Ok(())
+    } else {
+        Err(Error::PreconditionFailed)
+    }
+}
+
+/// Whether the client already has this version, a read then gets a 304
+pub fn is_not_modified(if_none_match: Option<&str>, version: i32) -> bool {
+    let Some(if_none_match) = if_none_match else {
+        return false;
+    };
+    let etag = etag(version);
+    if_none_match
+        .split(',')
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_match() {
+        assert!(check_if_match(Some("\"3\""), 3).is_ok());
+        assert!(check_if_match(Some("\"1\", \"3\""), 3).is_ok());
+        assert!(check_if_match(Some("*"), 3).is_ok());
+        assert!(matches!(
+            check_if_match(Some("\"2\""), 3),
+            Err(Error::PreconditionFailed)
+        ));
+        assert!(matches!(
+            check_if_match(Some("W/\"3\""), 3),
+            Err(Error::PreconditionFailed)
+        ));
+        assert!(matches!(
+            check_if_match(None, 3),
+            Err(Error::PreconditionRequired)
+        ));
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        assert!(is_not_modified(Some("\"3\""), 3));
+        assert!(is_not_modified(Some("W/\"3\""), 3));
+        assert!(is_not_modified(Some("\"1\", \"3\""), 3));
+        assert!(is_not_modified(Some("*"), 3));
+        assert!(!is_not_modified(Some("\"2\""), 3));
+        assert!(!is_not_modified(None, 3));
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::errors::{Error, Result};
+
+/// What an API key restricted to scopes may access, a write scope implies the read one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    TicketsRead,
+    TicketsWrite,
+    UsersRead,
+    UsersWrite,
+}
+
+impl Scope {
+    pub fn new(scope: &str) -> Result<Self> {
+        match scope {
+            "tickets:read" => Ok(Scope::TicketsRead),
+            "tickets:write" => Ok(Scope::TicketsWrite),
+            "users:read" => Ok(Scope::UsersRead),
+            "users:write" => Ok(Scope::UsersWrite),
+            other => Err(Error::InvalidScope(other.to_string())),
+        }
+    }
+
+    /// Whether the scope gives access to `resource`.
+    /// Any scope can search, only the resources it can read are searched
+    pub fn allows(&self, resource: ScopeResource, write: bool) -> bool {
+        if resource == ScopeResource::Search {
+            return !write;
+        }
+        let (scope_resource, scope_write) = match self {
+            Scope::TicketsRead => (ScopeResource::Tickets, false),
+            Scope::TicketsWrite => (ScopeResource::Tickets, true),
+            Scope::UsersRead => (ScopeResource::Users, false),
+            Scope::UsersWrite => (ScopeResource::Users, true),
+        };
+        scope_resource == resource && (scope_write || !write)
+    }
+}
+
+/// What the scopes of an API key give access to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeResource {
+    Tickets,
+    Users,
+    Search,
+}
+
+impl ScopeResource {
+    /// The resource of the route at `path`, None for the routes no scope gives access to,
+    /// like the lockouts or the sessions and credentials of the users.
+    /// The concerts, their venues and ticket categories go along with the tickets sold for them,
+    /// as do the holds and orders the tickets are bought through, whoever they belong to
+    pub fn of_path(path: &str) -> Option<Self> {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match segments.as_slice() {
+            ["search"] => Some(ScopeResource::Search),
+            ["tickets" | "concerts" | "venues" | "categories" | "holds" | "orders", ..] => {
+                Some(ScopeResource::Tickets)
+            }
+            ["users", "me", "tickets" | "orders"] | ["users", _, "orders"] => {
+                Some(ScopeResource::Tickets)
+            }
+            ["users"] | ["users", _] | ["users", "by-username", _] => Some(ScopeResource::Users),
+            ["users", "me", "email", "verification"] => Some(ScopeResource::Users),
+            _ => None,
+        }
+    }
+}
+
+impl AsRef<str> for Scope {
+    fn as_ref(&self) -> &str {
+        match self {
+            Scope::TicketsRead => "tickets:read",
+            Scope::TicketsWrite => "tickets:write",
+            Scope::UsersRead => "users:read",
+            Scope::UsersWrite => "users:write",
+        }
+    }
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
+pub struct ApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub hint: String,
+    /// None when the key is not restricted to scopes, it can then read what the scopes cover
+    pub scopes: Option<Vec<Scope>>,
+
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct NewApiKey {
+    pub user_id: Uuid,
+    pub name: String,
+    pub key_hash: String,
+    pub hint: String,
+    pub scopes: Option<Vec<Scope>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_parsing() {
+        for scope in ["tickets:read", "tickets:write", "users:read", "users:write"] {
+            assert_eq!(scope, Scope::new(scope).unwrap().as_ref());
+        }
+        assert!(matches!(Scope::new("tickets"), Err(Error::InvalidScope(_))));
+    }
+
+    #[test]
+    fn test_write_scope_implies_read() {
+        use ScopeResource::*;
+        assert!(Scope::TicketsWrite.allows(Tickets, false));
+        assert!(Scope::TicketsWrite.allows(Tickets, true));
+        assert!(Scope::TicketsRead.allows(Tickets, false));
+        assert!(!Scope::TicketsRead.allows(Tickets, true));
+        assert!(!Scope::TicketsWrite.allows(Users, false));
+        assert!(Scope::UsersRead.allows(Search, false));
+        assert!(!Scope::UsersWrite.allows(Search, true));
+    }
+
+    #[test]
+    fn test_routes_map_to_their_resource() {
+        let id = Uuid::new_v4();
+        let resource = |path: String| ScopeResource::of_path(&path);
+        assert_eq!(Some(ScopeResource::Search), resource("/search".to_string()));
+        for path in [
+            format!("/tickets/by-user/{id}"),
+            format!("/concerts/{id}/categories"),
+            format!("/venues/{id}/seating-chart"),
+            format!("/orders/{id}/pay"),
+            "/users/me/tickets".to_string(),
+            "/users/me/orders".to_string(),
+            format!("/users/{id}/orders"),
+        ] {
+            assert_eq!(Some(ScopeResource::Tickets), resource(path));
+        }
+        for path in [
+            "/users".to_string(),
+            "/users/me".to_string(),
+            format!("/users/{id}"),
+            "/users/by-username/test1".to_string(),
+        ] {
+            assert_eq!(Some(ScopeResource::Users), resource(path));
+        }
+        for path in [
+            "/lockouts".to_string(),
+            format!("/users/{id}/sessions"),
+            format!("/users/{id}/password-reset"),
+            "/users/me/api-keys".to_string(),
+            "/users/me/mfa".to_string(),
+            "/logout".to_string(),
+        ] {
+            assert_eq!(None, resource(path));
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::errors::{Error, Result};
+
+/// Longest category name accepted, in characters
+pub const MAX_NAME_LENGTH: usize = 100;
+
+/// A kind of ticket of a concert, such as the pit or the balcony, sold at one price
+/// up to its capacity
+pub struct TicketCategory {
+    pub id: Uuid,
+    pub concert_id: Uuid,
+    pub name: String,
+    pub price: f64,
+    pub capacity: i32,
+    /// Tickets issued in the category
+    pub sold: i32,
+    /// Tickets set aside by the unreleased holds, see `hold_types`
+    pub held: i32,
+    /// Bumped on every change, see `domain::preconditions`
+    pub version: i32,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TicketCategory {
+    /// Tickets still available in the category, neither sold nor held
+    pub fn remaining(&self) -> i32 {
+        self.capacity - self.sold - self.held
+    }
+}
+
+pub struct NewTicketCategory {
+    pub name: String,
+    pub price: f64,
+    pub capacity: i32,
+}
+
+impl NewTicketCategory {
+    pub fn new(name: &str, price: f64, capacity: i32) -> Result<Self> {
+        Ok(NewTicketCategory {
+            name: validate_name(name)?,
+            price: validate_price(price)?,
+            capacity: validate_capacity(capacity)?,
+        })
+    }
+}
+
+/// The columns to change, the others are left untouched.
+/// A new price only applies to the tickets issued afterwards
+pub struct TicketCategoryChanges {
+    pub name: Option<String>,
+    pub price: Option<f64>,
+    pub capacity: Option<i32>,
+}
+
+impl TicketCategoryChanges {
+    pub fn new(name: Option<&str>, price: Option<f64>, capacity: Option<i32>) -> Result<Self> {
+        Ok(TicketCategoryChanges {
+            name: name.map(validate_name).transpose()?,
+            price: price.map(validate_price).transpose()?,
+            capacity: capacity.map(validate_capacity).transpose()?,
+        })
+    }
+}
+
+impl From<NewTicketCategory> for TicketCategoryChanges {
+    fn from(category: NewTicketCategory) -> Self {
+        TicketCategoryChanges {
+            name: Some(category.name),
+            price: Some(category.price),
+            capacity: Some(category.capacity),
+        }
+    }
+}
+
+fn validate_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::InvalidCategory(
+            "the name cannot be empty".to_string(),
+        ));
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(Error::InvalidCategory(format!(
+            "the name must be at most {MAX_NAME_LENGTH} characters long"
+        )));
+    }
+    Ok(name.to_string())
+}
+
+fn validate_price(price: f64) -> Result<f64> {
+    if price.is_finite() && price >= 0.0 {
+        Ok(price)
+    } else {
+        Err(Error::InvalidCategory(
+            "the price cannot be negative".to_string(),
+        ))
+    }
+}
+
+fn validate_capacity(capacity: i32) -> Result<i32> {
+    if capacity > 0 {
+        Ok(capacity)
+    } else {
+        Err(Error::InvalidCategory(
+            "the capacity must be positive".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_category() {
+        let category = NewTicketCategory::new(" Pit ", 80.0, 500).unwrap();
+        assert_eq!("Pit", category.name);
+        for (name, price, capacity) in [(" ", 80.0, 500), ("Pit", -1.0, 500), ("Pit", 80.0, 0)] {
+            assert!(matches!(
+                NewTicketCategory::new(name, price, capacity),
+                Err(Error::InvalidCategory(_))
+            ));
+        }
+        assert!(TicketCategoryChanges::new(None, Some(f64::NAN), None).is_err());
+        assert!(TicketCategoryChanges::new(None, None, Some(10)).is_ok());
+    }
+}
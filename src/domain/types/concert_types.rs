@@ -0,0 +1,206 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::pagination::{PageRequest, SortField},
+};
+
+/// Longest concert name accepted, in characters
+pub const MAX_NAME_LENGTH: usize = 200;
+
+pub struct Concert {
+    pub id: Uuid,
+    pub name: String,
+    pub date: DateTime<Utc>,
+    pub venue_id: Option<Uuid>,
+    /// Most people the venue can take in, unknown when missing
+    pub capacity: Option<i32>,
+    pub description: Option<String>,
+    /// Bumped on every change, see `domain::preconditions`
+    pub version: i32,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub struct NewConcert {
+    pub name: String,
+    pub date: DateTime<Utc>,
+    pub venue_id: Option<Uuid>,
+    pub capacity: Option<i32>,
+    pub description: Option<String>,
+}
+
+impl NewConcert {
+    pub fn new(
+        name: &str,
+        date: DateTime<Utc>,
+        venue_id: Option<Uuid>,
+        capacity: Option<i32>,
+        description: Option<String>,
+    ) -> Result<Self> {
+        Ok(NewConcert {
+            name: validate_name(name)?,
+            date,
+            venue_id,
+            capacity: capacity.map(validate_capacity).transpose()?,
+            description,
+        })
+    }
+}
+
+/// The columns to change, the others are left untouched.
+/// The optional columns are cleared with `Some(None)`
+pub struct ConcertChanges {
+    pub name: Option<String>,
+    pub date: Option<DateTime<Utc>>,
+    pub venue_id: Option<Option<Uuid>>,
+    pub capacity: Option<Option<i32>>,
+    pub description: Option<Option<String>>,
+}
+
+impl ConcertChanges {
+    pub fn new(
+        name: Option<&str>,
+        date: Option<DateTime<Utc>>,
+        venue_id: Option<Option<Uuid>>,
+        capacity: Option<Option<i32>>,
+        description: Option<Option<String>>,
+    ) -> Result<Self> {
+        Ok(ConcertChanges {
+            name: name.map(validate_name).transpose()?,
+            date,
+            venue_id,
+            capacity: capacity
+                .map(|c| c.map(validate_capacity).transpose())
+                .transpose()?,
+            description,
+        })
+    }
+}
+
+impl From<NewConcert> for ConcertChanges {
+    fn from(concert: NewConcert) -> Self {
+        ConcertChanges {
+            name: Some(concert.name),
+            date: Some(concert.date),
+            venue_id: Some(concert.venue_id),
+            capacity: Some(concert.capacity),
+            description: Some(concert.description),
+        }
+    }
+}
+
+fn validate_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::InvalidConcert(
+            "the name cannot be empty".to_string(),
+        ));
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(Error::InvalidConcert(format!(
+            "the name must be at most {MAX_NAME_LENGTH} characters long"
+        )));
+    }
+    Ok(name.to_string())
+}
+
+fn validate_capacity(capacity: i32) -> Result<i32> {
+    if capacity > 0 {
+        Ok(capacity)
+    } else {
+        Err(Error::InvalidConcert(
+            "the capacity must be positive".to_string(),
+        ))
+    }
+}
+
+/// The columns the concerts can be sorted on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConcertSortField {
+    Date,
+    Name,
+    CreatedAt,
+}
+
+impl ConcertSortField {
+    /// The value of the column for `concert`, as stored in the cursors
+    pub fn value(&self, concert: &Concert) -> String {
+        match self {
+            ConcertSortField::Date => concert.date.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            ConcertSortField::Name => concert.name.clone(),
+            ConcertSortField::CreatedAt => concert
+                .created_at
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        }
+    }
+}
+
+impl SortField for ConcertSortField {
+    const DEFAULT: Self = ConcertSortField::Date;
+
+    fn new(field: &str) -> Result<Self> {
+        match field {
+            "date" => Ok(ConcertSortField::Date),
+            "name" => Ok(ConcertSortField::Name),
+            "created_at" => Ok(ConcertSortField::CreatedAt),
+            other => Err(Error::InvalidSort(other.to_string())),
+        }
+    }
+
+    fn is_valid_value(&self, value: &str) -> bool {
+        match self {
+            ConcertSortField::Date | ConcertSortField::CreatedAt => {
+                DateTime::parse_from_rfc3339(value).is_ok()
+            }
+            ConcertSortField::Name => true,
+        }
+    }
+}
+
+impl AsRef<str> for ConcertSortField {
+    fn as_ref(&self) -> &str {
+        match self {
+            ConcertSortField::Date => "date",
+            ConcertSortField::Name => "name",
+            ConcertSortField::CreatedAt => "created_at",
+        }
+    }
+}
+
+/// Only the concerts matching every filter set are listed.
+/// The range includes its start and excludes its end
+#[derive(Debug, Default)]
+pub struct ConcertFilter {
+    /// Compared without case
+    pub name: Option<String>,
+    pub date_from: Option<DateTime<Utc>>,
+    pub date_to: Option<DateTime<Utc>>,
+}
+
+pub struct ConcertQuery {
+    pub filter: ConcertFilter,
+    pub page: PageRequest<ConcertSortField>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_concert() {
+        let concert = NewConcert::new("  Trivium ", Utc::now(), None, Some(500), None).unwrap();
+        assert_eq!("Trivium", concert.name);
+        for (name, capacity) in [(" ", None), ("Trivium", Some(0))] {
+            assert!(matches!(
+                NewConcert::new(name, Utc::now(), None, capacity, None),
+                Err(Error::InvalidConcert(_))
+            ));
+        }
+        let changes = ConcertChanges::new(None, None, None, Some(None), None).unwrap();
+        assert_eq!(Some(None), changes.capacity);
+        assert!(ConcertChanges::new(Some(""), None, None, None, None).is_err());
+    }
+}
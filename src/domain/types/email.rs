@@ -0,0 +1,84 @@
+use serde::Serialize;
+
+use crate::domain::errors::{Error, Result};
+
+/// Longest address allowed by RFC 5321
+const MAX_LENGTH: usize = 254;
+
+/// An email address, only checked for an obvious shape: the verification flow proves it works
+#[derive(Debug, Clone, Serialize)]
+pub struct Email(String);
+
+impl Email {
+    pub fn new(e: &str) -> Result<Self> {
+        let s = e.trim().to_string();
+        if s.is_empty() {
+            return Err(Error::InvalidEmail("Email cannot be empty".to_string()));
+        }
+        if s.len() > MAX_LENGTH {
+            return Err(Error::InvalidEmail(format!(
+                "Email cannot be longer than {MAX_LENGTH} characters"
+            )));
+        }
+        if s.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(Error::InvalidEmail(
+                "Email cannot contain spaces".to_string(),
+            ));
+        }
+        match s.rsplit_once('@') {
+            Some((local, domain))
+                if !local.is_empty()
+                    && !local.contains('@')
+                    && domain.contains('.')
+                    && !domain.starts_with('.')
+                    && !domain.ends_with('.') =>
+            {
+                Ok(Self(s))
+            }
+            _ => Err(Error::InvalidEmail(format!("{s} is not an email address"))),
+        }
+    }
+
+    /// This must **only** be used when loading an email from the database,
+    /// the addresses saved before the current rules are kept as they are
+    ///
+    /// # Safety
+    /// This function is unsafe because it skips the email validation
+    pub unsafe fn new_unchecked(email: String) -> Self {
+        Self(email)
+    }
+}
+
+impl AsRef<str> for Email {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_email_new() {
+        let email = Email::new(" john.doe+tickets@example.com ").unwrap();
+        assert_eq!(email.as_ref(), "john.doe+tickets@example.com");
+    }
+
+    #[test]
+    fn test_email_new_invalid_email() {
+        for email in [
+            "",
+            "john.doe",
+            "@example.com",
+            "john@doe@example.com",
+            "john.doe@localhost",
+            "john.doe@example.",
+            "john doe@example.com",
+        ] {
+            assert!(Email::new(email).is_err(), "{email} should be refused");
+        }
+        let long = format!("{}@example.com", "a".repeat(250));
+        assert!(Email::new(&long).is_err());
+    }
+}
@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Email;
+
+pub struct EmailVerificationToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub email: Email,
+
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct NewEmailVerificationToken {
+    pub user_id: Uuid,
+    pub email: Email,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+}
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::errors::{Error, Result};
+
+/// Most tickets one hold can set aside
+pub const MAX_QUANTITY: i32 = 10;
+
+/// Tickets of a category set aside for a user until `expires_at`, either unnumbered
+/// or on specific seats. They are turned into tickets or given back to the category
+pub struct Hold {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub concert_id: Uuid,
+    pub category_id: Uuid,
+    pub quantity: i32,
+    /// The seats held, empty for unnumbered tickets
+    pub seat_ids: Vec<Uuid>,
+    pub expires_at: DateTime<Utc>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl Hold {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
+
+pub struct NewHold {
+    pub user_id: Uuid,
+    pub concert_id: Uuid,
+    pub category_id: Uuid,
+    /// One ticket per seat when seats are held
+    pub quantity: i32,
+    pub seat_ids: Vec<Uuid>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl NewHold {
+    /// Either a quantity or the seats to hold are given, the quantity of a hold on
+    /// seats is their number
+    pub fn new(
+        user_id: Uuid,
+        concert_id: Uuid,
+        category_id: Uuid,
+        quantity: Option<i32>,
+        seat_ids: Vec<Uuid>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Self> {
+        let quantity = ticket_count(quantity, &seat_ids).map_err(Error::InvalidHold)?;
+        Ok(NewHold {
+            user_id,
+            concert_id,
+            category_id,
+            quantity,
+            seat_ids,
+            expires_at,
+        })
+    }
+}
+
+/// Number of tickets asked for with `quantity` or `seat_ids`, one of them is required.
+/// Fails with the reason the request is invalid
+pub fn ticket_count(quantity: Option<i32>, seat_ids: &[Uuid]) -> std::result::Result<i32, String> {
+    let quantity = match (quantity, seat_ids.len()) {
+        (None, 0) => return Err("a quantity or some seats are required".to_string()),
+        (None, seats) => seats as i32,
+        (Some(quantity), 0) => quantity,
+        (Some(quantity), seats) if quantity as usize == seats => quantity,
+        (Some(_), _) => return Err("the quantity must match the number of seats".to_string()),
+    };
+    if !(1..=MAX_QUANTITY).contains(&quantity) {
+        return Err(format!(
+            "between 1 and {MAX_QUANTITY} tickets can be taken at once"
+        ));
+    }
+    let mut unique = seat_ids.to_vec();
+    unique.sort();
+    unique.dedup();
+    if unique.len() != seat_ids.len() {
+        return Err("a seat is taken twice".to_string());
+    }
+    Ok(quantity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_hold() {
+        let new = |quantity, seat_ids| {
+            NewHold::new(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                quantity,
+                seat_ids,
+                Utc::now(),
+            )
+        };
+        let seat = Uuid::new_v4();
+        assert_eq!(4, new(Some(4), vec![]).unwrap().quantity);
+        assert_eq!(2, new(None, vec![seat, Uuid::new_v4()]).unwrap().quantity);
+        assert_eq!(1, new(Some(1), vec![seat]).unwrap().quantity);
+        for (quantity, seat_ids) in [
+            (None, vec![]),
+            (Some(0), vec![]),
+            (Some(MAX_QUANTITY + 1), vec![]),
+            (Some(2), vec![seat]),
+            (None, vec![seat, seat]),
+        ] {
+            assert!(matches!(
+                new(quantity, seat_ids),
+                Err(Error::InvalidHold(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::errors::{Error, Result};
+
+/// Longest `Idempotency-Key` accepted, a UUID is the usual choice
+pub const MAX_KEY_LENGTH: usize = 255;
+
+/// The `Idempotency-Key` header a client sends so a retried request is only run once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    pub fn new(key: &str) -> Result<Self> {
+        if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+            return Err(Error::InvalidIdempotencyKey(format!(
+                "must be between 1 and {MAX_KEY_LENGTH} characters"
+            )));
+        }
+        if !key.chars().all(|c| c.is_ascii_graphic()) {
+            return Err(Error::InvalidIdempotencyKey(
+                "must only contain visible ASCII characters".to_string(),
+            ));
+        }
+        Ok(IdempotencyKey(key.to_string()))
+    }
+}
+
+impl AsRef<str> for IdempotencyKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The response given to the first request sent with a key
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredResponse {
+    pub status_code: u16,
+    pub body: serde_json::Value,
+}
+
+pub struct NewIdempotencyRecord {
+    pub user_id: Uuid,
+    pub key: IdempotencyKey,
+    pub request_hash: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// What to do with a request sent with an idempotency key
+#[derive(Debug, PartialEq)]
+pub enum IdempotencyClaim {
+    /// First time the key is seen, the request is run and its response stored
+    Started,
+    /// The request already ran, its response is sent again
+    Replay(StoredResponse),
+    /// The first request with the key has not finished yet
+    InProgress,
+    /// The key was used for a different request
+    Mismatch,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idempotency_key() {
+        let key = "5f0c6e52-1b7e-4a57-9d0e-3f3c3b1e8a77";
+        assert_eq!(key, IdempotencyKey::new(key).unwrap().as_ref());
+        assert!(IdempotencyKey::new(&"a".repeat(MAX_KEY_LENGTH)).is_ok());
+        for key in ["", "with space", "é", &"a".repeat(MAX_KEY_LENGTH + 1)] {
+            assert!(matches!(
+                IdempotencyKey::new(key),
+                Err(Error::InvalidIdempotencyKey(_))
+            ));
+        }
+    }
+}
@@ -1,78 +1,177 @@
-use std::collections::BTreeMap;
-
-use chrono::{Duration, Utc};
-use serde::Deserialize;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use uuid::Uuid;
 
-use crate::domain::errors::{Error, Result};
+use super::api_key_types::{ApiKey, Scope};
 
-#[derive(Debug, Deserialize)]
+/// Claims of the access tokens, the registered ones follow RFC 7519.
+///
+/// `iss` and `aud` are filled by the `JwtHandler` when the token is signed.
+#[derive(Debug, Serialize, Deserialize)]
 pub struct JwtClaims {
+    #[serde(default)]
+    pub iss: String,
+    #[serde(
+        default,
+        serialize_with = "serialize_audience",
+        deserialize_with = "deserialize_audience"
+    )]
+    pub aud: Vec<String>,
     pub sub: String,
     pub user_id: Uuid,
     pub role: String,
     pub exp: i64,
+    pub nbf: i64,
     pub iat: i64,
+    /// Unique identifier of the token, used to revoke it
+    pub jti: Uuid,
+    /// Authentication methods used to obtain the token (RFC 8176): `pwd`, then `otp`
+    #[serde(default)]
+    pub amr: Vec<String>,
+    /// Set when the request was authenticated with an API key instead of a token
+    #[serde(skip)]
+    pub api_key_id: Option<Uuid>,
+    /// Restrictions of the API key, None when unrestricted
+    #[serde(skip)]
+    pub scopes: Option<Vec<Scope>>,
 }
 
 impl JwtClaims {
-    pub fn new(username: String, user_id: Uuid, role: String) -> Self {
+    pub fn new(username: String, user_id: Uuid, role: String, ttl: Duration) -> Self {
+        let now = Utc::now();
         Self {
+            iss: String::new(),
+            aud: Vec::new(),
             sub: username,
             user_id,
             role,
-            exp: (Utc::now() + Duration::days(1)).timestamp(),
-            iat: Utc::now().timestamp(),
+            exp: (now + ttl).timestamp(),
+            nbf: now.timestamp(),
+            iat: now.timestamp(),
+            jti: Uuid::new_v4(),
+            amr: vec!["pwd".to_string()],
+            api_key_id: None,
+            scopes: None,
+        }
+    }
+
+    /// The claims of a request authenticated with an API key of the user
+    pub fn for_api_key(username: String, role: String, key: ApiKey) -> Self {
+        let now = Utc::now();
+        Self {
+            iss: String::new(),
+            aud: Vec::new(),
+            sub: username,
+            user_id: key.user_id,
+            role,
+            exp: key
+                .expires_at
+                .unwrap_or(DateTime::<Utc>::MAX_UTC)
+                .timestamp(),
+            nbf: now.timestamp(),
+            iat: now.timestamp(),
+            jti: key.id,
+            amr: Vec::new(),
+            api_key_id: Some(key.id),
+            scopes: key.scopes,
         }
     }
 
-    pub fn to_tree_map(&self) -> BTreeMap<&'static str, String> {
-        let mut claims = BTreeMap::new();
-        claims.insert("sub", self.sub.clone());
-        claims.insert("user_id", self.user_id.to_string());
-        claims.insert("role", self.role.clone());
-        claims.insert("exp", self.exp.to_string());
-        claims.insert("iat", self.iat.to_string());
-        claims
+    /// Mark the token as obtained with a second factor
+    pub fn with_mfa(mut self) -> Self {
+        self.amr.push("otp".to_string());
+        self
+    }
+
+    pub fn has_mfa(&self) -> bool {
+        self.amr.iter().any(|method| method == "otp")
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.role == "admin"
+    }
+
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.exp, 0).unwrap_or_default()
+    }
+}
+
+/// A single audience is written as a plain string, as most libraries expect
+fn serialize_audience<S: Serializer>(aud: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+    match aud {
+        [aud] => serializer.serialize_str(aud),
+        _ => aud.serialize(serializer),
     }
+}
 
-    pub fn is_valid(&self) -> bool {
-        let now = Utc::now().timestamp();
-        self.exp > now && self.iat <= now
+/// RFC 7519 allows the audience to be either a string or an array of strings
+fn deserialize_audience<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Audience {
+        One(String),
+        Many(Vec<String>),
     }
+
+    Ok(match Audience::deserialize(deserializer)? {
+        Audience::One(aud) => vec![aud],
+        Audience::Many(aud) => aud,
+    })
 }
 
-impl TryFrom<BTreeMap<String, String>> for JwtClaims {
-    type Error = Error;
-
-    fn try_from(claims: BTreeMap<String, String>) -> Result<Self> {
-        let sub = claims
-            .get("sub")
-            .ok_or_else(|| Error::LoginFailed("Error in JWT formatting".to_string()))?;
-        let user_id = claims
-            .get("user_id")
-            .ok_or_else(|| Error::LoginFailed("Error in JWT formatting".to_string()))?;
-        let role = claims
-            .get("role")
-            .ok_or_else(|| Error::LoginFailed("Error in JWT formatting".to_string()))?;
-        let exp = claims
-            .get("exp")
-            .ok_or_else(|| Error::LoginFailed("Error in JWT formatting".to_string()))?;
-        let iat = claims
-            .get("iat")
-            .ok_or_else(|| Error::LoginFailed("Error in JWT formatting".to_string()))?;
-
-        Ok(Self {
-            sub: sub.clone(),
-            user_id: Uuid::parse_str(user_id)
-                .map_err(|_| Error::LoginFailed("Error in JWT formatting".to_string()))?,
-            role: role.clone(),
-            exp: exp
-                .parse()
-                .map_err(|_| Error::LoginFailed("Error in JWT formatting".to_string()))?,
-            iat: iat
-                .parse()
-                .map_err(|_| Error::LoginFailed("Error in JWT formatting".to_string()))?,
-        })
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_claims_are_encoded_as_json_types() {
+        let mut claims = JwtClaims::new(
+            "username".to_string(),
+            Uuid::new_v4(),
+            "user".to_string(),
+            Duration::minutes(15),
+        );
+        claims.aud = vec!["api".to_string()];
+        let encoded = serde_json::to_value(&claims).unwrap();
+        assert!(encoded["exp"].is_i64());
+        assert!(encoded["nbf"].is_i64());
+        assert!(encoded["iat"].is_i64());
+        assert_eq!("api", encoded["aud"]);
+        assert_eq!(claims.jti.to_string(), encoded["jti"]);
+    }
+
+    #[test]
+    fn test_audience_can_be_an_array() {
+        let claims: JwtClaims = serde_json::from_value(json!({
+            "iss": "issuer",
+            "aud": ["api", "other"],
+            "sub": "username",
+            "user_id": Uuid::new_v4(),
+            "role": "user",
+            "exp": 2,
+            "nbf": 1,
+            "iat": 1,
+            "jti": Uuid::new_v4(),
+        }))
+        .unwrap();
+        assert_eq!(vec!["api", "other"], claims.aud);
+    }
+
+    #[test]
+    fn test_string_timestamps_are_rejected() {
+        let claims = serde_json::from_value::<JwtClaims>(json!({
+            "sub": "username",
+            "user_id": Uuid::new_v4(),
+            "role": "user",
+            "exp": "2",
+            "nbf": "1",
+            "iat": "1",
+            "jti": Uuid::new_v4(),
+        }));
+        assert!(claims.is_err());
     }
 }
@@ -0,0 +1,50 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+
+use crate::domain::errors::{Error, Result};
+
+/// What the failed login attempts are counted against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttemptKind {
+    /// The username sent by the client, whether the account exists or not
+    Account,
+    /// The address of the client
+    Ip,
+}
+
+impl TryFrom<&str> for AttemptKind {
+    type Error = Error;
+
+    fn try_from(kind: &str) -> Result<Self> {
+        match kind {
+            "account" => Ok(AttemptKind::Account),
+            "ip" => Ok(AttemptKind::Ip),
+            other => Err(Error::InvalidAttemptKind(other.to_string())),
+        }
+    }
+}
+
+impl AsRef<str> for AttemptKind {
+    fn as_ref(&self) -> &str {
+        match self {
+            AttemptKind::Account => "account",
+            AttemptKind::Ip => "ip",
+        }
+    }
+}
+
+impl Display for AttemptKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
+pub struct LoginAttempts {
+    pub kind: AttemptKind,
+    pub identifier: String,
+    /// Consecutive failures, reset when the attempts window is over
+    pub failures: i32,
+    pub last_failure_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
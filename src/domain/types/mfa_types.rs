@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+pub struct UserMfa {
+    pub user_id: Uuid,
+    /// Base32 encoded TOTP secret
+    pub secret: String,
+    /// None until the enrollment is confirmed
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub last_used_step: Option<i64>,
+}
+
+impl UserMfa {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled_at.is_some()
+    }
+}
+
+/// Issued by the login once the password is verified, redeemed with a TOTP or recovery code
+pub struct MfaChallenge {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub failed_attempts: i32,
+
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl MfaChallenge {
+    pub fn is_usable(&self, now: DateTime<Utc>, max_attempts: i32) -> bool {
+        self.used_at.is_none() && self.expires_at > now && self.failed_attempts < max_attempts
+    }
+}
+
+pub struct NewMfaChallenge {
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+}
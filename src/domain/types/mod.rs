@@ -1,10 +1,27 @@
+pub mod api_key_types;
+pub mod category_types;
+pub mod concert_types;
+pub mod email;
+pub mod email_verification_types;
+pub mod hold_types;
+pub mod idempotency_types;
 pub mod jwt_claims;
+pub mod login_attempt_types;
+pub mod mfa_types;
+pub mod order_types;
+pub mod pagination;
 pub mod password;
+pub mod password_reset_types;
+pub mod refresh_token_types;
+pub mod revocation_types;
 pub mod role;
+pub mod search_types;
 pub mod ticket_types;
 pub mod user_types;
 pub mod username;
+pub mod venue_types;
 
+pub use email::Email;
 pub use jwt_claims::JwtClaims;
 pub use password::PasswordHash;
 pub use role::Role;
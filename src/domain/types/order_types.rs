@@ -0,0 +1,272 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::{
+        hold_types::ticket_count,
+        pagination::{PageRequest, SortField},
+    },
+};
+
+/// Where an order stands: it is placed `pending`, its tickets are issued once `paid`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStatus {
+    Pending,
+    Paid,
+    Cancelled,
+    Refunded,
+}
+
+impl TryFrom<&str> for OrderStatus {
+    type Error = Error;
+
+    fn try_from(status: &str) -> Result<Self> {
+        match status {
+            "pending" => Ok(OrderStatus::Pending),
+            "paid" => Ok(OrderStatus::Paid),
+            "cancelled" => Ok(OrderStatus::Cancelled),
+            "refunded" => Ok(OrderStatus::Refunded),
+            other => Err(Error::InvalidOrderStatus(other.to_string())),
+        }
+    }
+}
+
+impl OrderStatus {
+    /// A pending order is paid or cancelled, a paid order can then be refunded.
+    /// The cancelled and refunded orders are final
+    pub fn check_transition(self, next: OrderStatus) -> Result<()> {
+        match (self, next) {
+            (OrderStatus::Pending, OrderStatus::Paid | OrderStatus::Cancelled)
+            | (OrderStatus::Paid, OrderStatus::Refunded) => Ok(()),
+            _ => Err(Error::InvalidOrderTransition(
+                self.to_string(),
+                next.to_string(),
+            )),
+        }
+    }
+}
+
+impl AsRef<str> for OrderStatus {
+    fn as_ref(&self) -> &str {
+        match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Paid => "paid",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Refunded => "refunded",
+        }
+    }
+}
+
+impl Display for OrderStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
+pub struct Order {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub status: OrderStatus,
+    /// Sum of the items, in the currency of the ticket prices
+    pub total: f64,
+    /// The hold the order checks out, until it is paid or the hold is released
+    pub hold_id: Option<Uuid>,
+    pub items: Vec<OrderItem>,
+    /// The tickets issued when the order was paid
+    pub ticket_ids: Vec<Uuid>,
+    /// Bumped on every change, see `domain::preconditions`
+    pub version: i32,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub paid_at: Option<DateTime<Utc>>,
+}
+
+/// Tickets of one category of a concert, priced when the order is placed
+pub struct OrderItem {
+    pub concert_id: Uuid,
+    pub category_id: Uuid,
+    pub quantity: i32,
+    /// The seats of the tickets, empty for unnumbered tickets
+    pub seat_ids: Vec<Uuid>,
+    /// Price of the category when the order was placed
+    pub unit_price: f64,
+}
+
+impl OrderItem {
+    /// Either a quantity or the seats are given, like for a hold. The price is the
+    /// one of the category, never one chosen by the buyer
+    pub fn new(
+        concert_id: Uuid,
+        category_id: Uuid,
+        quantity: Option<i32>,
+        seat_ids: Vec<Uuid>,
+        unit_price: f64,
+    ) -> Result<Self> {
+        let quantity = ticket_count(quantity, &seat_ids).map_err(Error::InvalidOrder)?;
+        Ok(OrderItem {
+            concert_id,
+            category_id,
+            quantity,
+            seat_ids,
+            unit_price,
+        })
+    }
+
+    pub fn subtotal(&self) -> f64 {
+        self.unit_price * self.quantity as f64
+    }
+}
+
+pub struct NewOrder {
+    pub user_id: Uuid,
+    pub hold_id: Option<Uuid>,
+    pub items: Vec<OrderItem>,
+    pub total: f64,
+}
+
+impl NewOrder {
+    /// The total is computed from the items, which cannot be empty nor sell a seat twice
+    pub fn new(user_id: Uuid, hold_id: Option<Uuid>, items: Vec<OrderItem>) -> Result<Self> {
+        if items.is_empty() {
+            return Err(Error::InvalidOrder("an order needs items".to_string()));
+        }
+        let mut seats: Vec<(Uuid, Uuid)> = items
+            .iter()
+            .flat_map(|item| item.seat_ids.iter().map(|s| (item.concert_id, *s)))
+            .collect();
+        let count = seats.len();
+        seats.sort();
+        seats.dedup();
+        if seats.len() != count {
+            return Err(Error::InvalidOrder("a seat is ordered twice".to_string()));
+        }
+        let total = items.iter().map(OrderItem::subtotal).sum();
+        Ok(NewOrder {
+            user_id,
+            hold_id,
+            items,
+            total,
+        })
+    }
+
+    /// Number of tickets issued when the order is paid
+    pub fn ticket_count(&self) -> i32 {
+        self.items.iter().map(|item| item.quantity).sum()
+    }
+}
+
+/// The columns the orders can be sorted on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderSortField {
+    CreatedAt,
+    Total,
+}
+
+impl OrderSortField {
+    /// The value of the column for `order`, as stored in the cursors
+    pub fn value(&self, order: &Order) -> String {
+        match self {
+            OrderSortField::CreatedAt => order
+                .created_at
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            OrderSortField::Total => order.total.to_string(),
+        }
+    }
+}
+
+impl SortField for OrderSortField {
+    const DEFAULT: Self = OrderSortField::CreatedAt;
+
+    fn new(field: &str) -> Result<Self> {
+        match field {
+            "created_at" => Ok(OrderSortField::CreatedAt),
+            "total" => Ok(OrderSortField::Total),
+            other => Err(Error::InvalidSort(other.to_string())),
+        }
+    }
+
+    fn is_valid_value(&self, value: &str) -> bool {
+        match self {
+            OrderSortField::CreatedAt => DateTime::parse_from_rfc3339(value).is_ok(),
+            OrderSortField::Total => value.parse::<f64>().is_ok_and(f64::is_finite),
+        }
+    }
+}
+
+impl AsRef<str> for OrderSortField {
+    fn as_ref(&self) -> &str {
+        match self {
+            OrderSortField::CreatedAt => "created_at",
+            OrderSortField::Total => "total",
+        }
+    }
+}
+
+/// The orders of a user, optionally only the ones with a status
+pub struct OrderQuery {
+    pub user_id: Uuid,
+    pub status: Option<OrderStatus>,
+    pub page: PageRequest<OrderSortField>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::types::hold_types::MAX_QUANTITY;
+
+    fn item(quantity: Option<i32>, seat_ids: Vec<Uuid>, unit_price: f64) -> Result<OrderItem> {
+        OrderItem::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            quantity,
+            seat_ids,
+            unit_price,
+        )
+    }
+
+    #[test]
+    fn test_order_total() {
+        let seat = Uuid::new_v4();
+        let items = vec![
+            item(Some(3), vec![], 40.0).unwrap(),
+            item(None, vec![seat, Uuid::new_v4()], 80.5).unwrap(),
+        ];
+        let order = NewOrder::new(Uuid::new_v4(), None, items).unwrap();
+        assert_eq!(281.0, order.total);
+        assert_eq!(5, order.ticket_count());
+
+        assert!(item(None, vec![], 40.0).is_err());
+        assert!(item(Some(2), vec![seat], 40.0).is_err());
+        assert!(item(Some(MAX_QUANTITY + 1), vec![], 40.0).is_err());
+        assert!(matches!(
+            NewOrder::new(Uuid::new_v4(), None, vec![]),
+            Err(Error::InvalidOrder(_))
+        ));
+    }
+
+    #[test]
+    fn test_order_transitions() {
+        use OrderStatus::*;
+        assert!(Pending.check_transition(Paid).is_ok());
+        assert!(Pending.check_transition(Cancelled).is_ok());
+        assert!(Paid.check_transition(Refunded).is_ok());
+        for (from, to) in [
+            (Paid, Paid),
+            (Paid, Cancelled),
+            (Cancelled, Paid),
+            (Refunded, Paid),
+            (Pending, Refunded),
+        ] {
+            assert!(matches!(
+                from.check_transition(to),
+                Err(Error::InvalidOrderTransition(_, _))
+            ));
+        }
+        assert_eq!(Refunded, OrderStatus::try_from("refunded").unwrap());
+        assert!(OrderStatus::try_from("shipped").is_err());
+    }
+}
@@ -0,0 +1,298 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::errors::{Error, Result};
+
+/// Number of items in a page when the client does not ask for a limit
+pub const DEFAULT_LIMIT: i64 = 50;
+/// Largest page a client can ask for
+pub const MAX_LIMIT: i64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// The order of a listing, written `field` or `-field` for the descending order.
+/// The ties are broken by the id so every item has a single place
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort<F> {
+    pub field: F,
+    pub direction: SortDirection,
+}
+
+/// The columns a listing can be sorted on
+pub trait SortField: Sized + Copy + AsRef<str> {
+    const DEFAULT: Self;
+
+    fn new(field: &str) -> Result<Self>;
+
+    /// Whether a cursor value can be compared with the column
+    fn is_valid_value(&self, value: &str) -> bool;
+}
+
+impl<F: SortField> Sort<F> {
+    pub fn new(sort: Option<&str>) -> Result<Self> {
+        let Some(sort) = sort else {
+            return Ok(Sort {
+                field: F::DEFAULT,
+                direction: SortDirection::Asc,
+            });
+        };
+        let (field, direction) = match sort.strip_prefix('-') {
+            Some(field) => (field, SortDirection::Desc),
+            None => (sort, SortDirection::Asc),
+        };
+        Ok(Sort {
+            field: F::new(field)?,
+            direction,
+        })
+    }
+
+    /// How the sort is written in the query string
+    pub fn key(&self) -> String {
+        match self.direction {
+            SortDirection::Asc => self.field.as_ref().to_string(),
+            SortDirection::Desc => format!("-{}", self.field.as_ref()),
+        }
+    }
+}
+
+/// Where a page ends: the sort value and the id of its last item.
+/// It is opaque to the clients, who send it back to get the next page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    /// The sort the cursor was made for, it cannot be used with another one
+    pub sort: String,
+    pub value: String,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("a cursor is serializable");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    /// Decode a cursor sent by a client, it must have been made for `sort`
+    pub fn decode<F: SortField>(cursor: &str, sort: &Sort<F>) -> Result<Self> {
+        let invalid = || Error::InvalidCursor("malformed cursor".to_string());
+        let json = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let cursor: Cursor = serde_json::from_slice(&json).map_err(|_| invalid())?;
+        if cursor.sort != sort.key() {
+            return Err(Error::InvalidCursor(format!(
+                "the cursor was made for the sort {}",
+                cursor.sort
+            )));
+        }
+        if !sort.field.is_valid_value(&cursor.value) {
+            return Err(invalid());
+        }
+        Ok(cursor)
+    }
+}
+
+/// Which page of a listing to fetch
+#[derive(Debug, Clone)]
+pub struct PageRequest<F> {
+    pub sort: Sort<F>,
+    pub limit: i64,
+    /// The page starts after this item, at the start of the listing when missing
+    pub after: Option<Cursor>,
+    /// Also count the items matching the filters, which costs a second query
+    pub with_total: bool,
+}
+
+impl<F: SortField> PageRequest<F> {
+    pub fn new(
+        sort: Option<&str>,
+        limit: Option<i64>,
+        cursor: Option<&str>,
+        with_total: bool,
+    ) -> Result<Self> {
+        let sort = Sort::new(sort)?;
+        let limit = limit.unwrap_or(DEFAULT_LIMIT);
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(Error::InvalidPageRequest(format!(
+                "the limit must be between 1 and {MAX_LIMIT}"
+            )));
+        }
+        let after = cursor.map(|c| Cursor::decode(c, &sort)).transpose()?;
+        Ok(PageRequest {
+            sort,
+            limit,
+            after,
+            with_total,
+        })
+    }
+}
+
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Missing on the last page
+    pub next_cursor: Option<Cursor>,
+    pub total: Option<i64>,
+}
+
+impl<T> Page<T> {
+    /// Build a page from up to `limit + 1` items, the extra one telling there is a next page.
+    /// `sort_value` gives the value of the sort column and the id of an item
+    pub fn from_items<F: SortField>(
+        mut items: Vec<T>,
+        request: &PageRequest<F>,
+        total: Option<i64>,
+        sort_value: impl Fn(&T) -> (String, Uuid),
+    ) -> Self {
+        let has_next = items.len() as i64 > request.limit;
+        items.truncate(request.limit as usize);
+        let next_cursor = match items.last() {
+            Some(last) if has_next => {
+                let (value, id) = sort_value(last);
+                Some(Cursor {
+                    sort: request.sort.key(),
+                    value,
+                    id,
+                })
+            }
+            _ => None,
+        };
+        Page {
+            items,
+            next_cursor,
+            total,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+            total: self.total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Field {
+        Name,
+        Rank,
+    }
+
+    impl SortField for Field {
+        const DEFAULT: Self = Field::Name;
+
+        fn new(field: &str) -> Result<Self> {
+            match field {
+                "name" => Ok(Field::Name),
+                "rank" => Ok(Field::Rank),
+                other => Err(Error::InvalidSort(other.to_string())),
+            }
+        }
+
+        fn is_valid_value(&self, value: &str) -> bool {
+            match self {
+                Field::Name => true,
+                Field::Rank => value.parse::<i64>().is_ok(),
+            }
+        }
+    }
+
+    impl AsRef<str> for Field {
+        fn as_ref(&self) -> &str {
+            match self {
+                Field::Name => "name",
+                Field::Rank => "rank",
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort_parsing() {
+        let sort = Sort::<Field>::new(None).unwrap();
+        assert_eq!(
+            (Field::Name, SortDirection::Asc),
+            (sort.field, sort.direction)
+        );
+        let sort = Sort::<Field>::new(Some("-rank")).unwrap();
+        assert_eq!(
+            (Field::Rank, SortDirection::Desc),
+            (sort.field, sort.direction)
+        );
+        assert_eq!("-rank", sort.key());
+        assert!(matches!(
+            Sort::<Field>::new(Some("id")),
+            Err(Error::InvalidSort(_))
+        ));
+    }
+
+    #[test]
+    fn test_cursor_is_tied_to_the_sort() {
+        let sort = Sort::<Field>::new(Some("-rank")).unwrap();
+        let cursor = Cursor {
+            sort: sort.key(),
+            value: "3".to_string(),
+            id: Uuid::new_v4(),
+        };
+        assert_eq!(cursor, Cursor::decode(&cursor.encode(), &sort).unwrap());
+
+        let other_sort = Sort::<Field>::new(Some("rank")).unwrap();
+        assert!(matches!(
+            Cursor::decode(&cursor.encode(), &other_sort),
+            Err(Error::InvalidCursor(_))
+        ));
+        let tampered = Cursor {
+            value: "three".to_string(),
+            ..cursor
+        };
+        assert!(matches!(
+            Cursor::decode(&tampered.encode(), &sort),
+            Err(Error::InvalidCursor(_))
+        ));
+        assert!(matches!(
+            Cursor::decode("not a cursor", &sort),
+            Err(Error::InvalidCursor(_))
+        ));
+    }
+
+    #[test]
+    fn test_limit_bounds() {
+        let request = PageRequest::<Field>::new(None, None, None, false).unwrap();
+        assert_eq!(DEFAULT_LIMIT, request.limit);
+        for limit in [0, MAX_LIMIT + 1] {
+            assert!(matches!(
+                PageRequest::<Field>::new(None, Some(limit), None, false),
+                Err(Error::InvalidPageRequest(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_page_from_items() {
+        let request = PageRequest::<Field>::new(Some("rank"), Some(2), None, false).unwrap();
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let items: Vec<(i64, Uuid)> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (i as i64, *id))
+            .collect();
+
+        let page = Page::from_items(items.clone(), &request, None, |(rank, id)| {
+            (rank.to_string(), *id)
+        });
+        assert_eq!(2, page.items.len());
+        let cursor = page.next_cursor.unwrap();
+        assert_eq!(("1".to_string(), ids[1]), (cursor.value, cursor.id));
+
+        let page = Page::from_items(items[..2].to_vec(), &request, Some(2), |(rank, id)| {
+            (rank.to_string(), *id)
+        });
+        assert!(page.next_cursor.is_none());
+        assert_eq!(Some(2), page.total);
+    }
+}
@@ -2,8 +2,8 @@ use secrecy::{ExposeSecret, SecretString};
 use serde::Serialize;
 
 use crate::{
-    domain::errors::{Error, Result},
-    handlers::password_hasher::hash_password,
+    domain::{errors::Result, password_policy::PasswordPolicy},
+    handlers::password_hasher::PasswordHasher,
 };
 
 #[derive(Debug, Clone)]
@@ -12,16 +12,21 @@ pub struct PasswordHash(SecretString);
 impl PasswordHash {
     /// Create a new PasswordHash from a cleartext password.
     ///
-    /// The function will validate and hash the password.
-    pub fn new(password: &str) -> Result<Self> {
-        if password.len() < 8 {
-            return Err(Error::InvalidPassword(
-                "Password must be at least 8 characters long".to_string(),
-            ));
-        }
+    /// The function will validate the password of `username` against the policy and hash it.
+    pub async fn new(
+        password: &str,
+        username: &str,
+        policy: &PasswordPolicy,
+        hasher: &PasswordHasher,
+    ) -> Result<Self> {
+        policy.check(password, username)?;
+        Self::rehash(password, hasher).await
+    }
 
-        let hash = hash_password(password)
-            .map_err(|_| Error::InvalidPassword("Failed to hash password".to_string()))?;
+    /// Hash again a password that was just verified, when its hash is outdated.
+    /// It is not validated, it was accepted when the user chose it
+    pub async fn rehash(password: &str, hasher: &PasswordHasher) -> Result<Self> {
+        let hash = hasher.hash_blocking(password.to_string()).await?;
         Ok(Self(hash.into()))
     }
 
@@ -52,24 +57,34 @@ impl Serialize for PasswordHash {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_password_hash_new() {
+    async fn new_password_hash(password: &str) -> Result<PasswordHash> {
+        PasswordHash::new(
+            password,
+            "username",
+            &PasswordPolicy::default(),
+            &PasswordHasher::default(),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_password_hash_new() {
         let password = "password";
-        let password_hash = PasswordHash::new(password).unwrap();
+        let password_hash = new_password_hash(password).await.unwrap();
         assert_ne!(password, password_hash.expose_secret());
     }
 
-    #[test]
-    fn test_password_hash_new_invalid_password() {
+    #[tokio::test]
+    async fn test_password_hash_new_invalid_password() {
         let password = "pass";
-        let password_hash = PasswordHash::new(password);
+        let password_hash = new_password_hash(password).await;
         assert!(password_hash.is_err());
     }
 
-    #[test]
-    fn test_password_serialization_displays_nothing() {
+    #[tokio::test]
+    async fn test_password_serialization_displays_nothing() {
         let password = "password";
-        let password_hash = PasswordHash::new(password).unwrap();
+        let password_hash = new_password_hash(password).await.unwrap();
 
         let serialized = serde_json::to_string(&password_hash).unwrap();
         assert_eq!(serialized, "\"[secret]\"");
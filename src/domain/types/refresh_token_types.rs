@@ -0,0 +1,25 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Every token obtained by rotating a refresh token shares the family of the original one
+    pub family_id: Uuid,
+    /// The login of the family went through two-factor authentication
+    pub mfa_verified: bool,
+
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct NewRefreshToken {
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    pub token_hash: String,
+    pub mfa_verified: bool,
+    pub expires_at: DateTime<Utc>,
+}
@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// A single access token revoked before its expiry
+pub struct RevokedToken {
+    pub jti: Uuid,
+    pub user_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Every access token issued to a user before `revoked_before` is revoked
+pub struct RevokedSession {
+    pub user_id: Uuid,
+    pub revoked_before: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
@@ -17,6 +17,10 @@ impl Role {
         }
         Ok(Self(role))
     }
+
+    pub fn is_admin(&self) -> bool {
+        self.0 == "admin"
+    }
 }
 
 impl AsRef<str> for Role {
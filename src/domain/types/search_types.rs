@@ -0,0 +1,161 @@
+use serde::Serialize;
+
+use crate::domain::errors::{Error, Result};
+
+/// Longest search accepted, in characters
+pub const MAX_QUERY_LENGTH: usize = 100;
+/// Results of each kind when the client does not ask for a limit
+pub const DEFAULT_LIMIT: i64 = 20;
+/// Most results of each kind a client can ask for
+pub const MAX_LIMIT: i64 = 50;
+
+/// What a client searches for, split in words
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    text: String,
+    terms: Vec<String>,
+}
+
+impl SearchQuery {
+    pub fn new(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if text.chars().count() > MAX_QUERY_LENGTH {
+            return Err(Error::InvalidSearch(format!(
+                "must be at most {MAX_QUERY_LENGTH} characters long"
+            )));
+        }
+        let terms: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|term| !term.is_empty())
+            .map(|term| term.to_lowercase())
+            .collect();
+        if terms.is_empty() {
+            return Err(Error::InvalidSearch(
+                "must contain at least a letter or a digit".to_string(),
+            ));
+        }
+        Ok(SearchQuery {
+            text: text.to_string(),
+            terms,
+        })
+    }
+
+    /// The search as the client typed it, for the fuzzy matching
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// A full-text query matching the words starting with any of the terms.
+    /// The terms are only made of letters and digits, so they cannot inject operators
+    pub fn prefix_tsquery(&self) -> String {
+        self.terms
+            .iter()
+            .map(|term| format!("{term}:*"))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+
+    /// Where the words of `text` matching the search are, so a client can highlight them.
+    /// A word matches a term it starts with, or that it is at most a typo or two away from
+    pub fn highlight(&self, text: &str) -> Vec<Highlight> {
+        let mut highlights = vec![];
+        let mut word_start = None;
+        let chars: Vec<char> = text.chars().collect();
+        for (i, c) in chars.iter().chain(std::iter::once(&' ')).enumerate() {
+            match (c.is_alphanumeric(), word_start) {
+                (true, None) => word_start = Some(i),
+                (false, Some(start)) => {
+                    let word: String = chars[start..i].iter().collect::<String>().to_lowercase();
+                    if self.terms.iter().any(|term| is_match(&word, term)) {
+                        highlights.push(Highlight { start, end: i });
+                    }
+                    word_start = None;
+                }
+                _ => {}
+            }
+        }
+        highlights
+    }
+}
+
+/// A matching part of a text, in characters from its start
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Highlight {
+    pub start: usize,
+    /// Excluded
+    pub end: usize,
+}
+
+/// A search result, the higher the rank the better it matches
+pub struct SearchHit<T> {
+    pub item: T,
+    pub rank: f32,
+}
+
+fn is_match(word: &str, term: &str) -> bool {
+    if word.starts_with(term) {
+        return true;
+    }
+    // One typo allowed every four characters
+    let allowed = term.chars().count() / 4;
+    allowed > 0 && edit_distance(word, term) <= allowed
+}
+
+/// Levenshtein distance between two words
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_query() {
+        let query = SearchQuery::new("  Trivium & Gojira:* ").unwrap();
+        assert_eq!("Trivium & Gojira:*", query.text());
+        assert_eq!("trivium:* | gojira:*", query.prefix_tsquery());
+        for text in ["", " & | ", &"a".repeat(MAX_QUERY_LENGTH + 1)] {
+            assert!(matches!(
+                SearchQuery::new(text),
+                Err(Error::InvalidSearch(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_highlight() {
+        let query = SearchQuery::new("trivum live").unwrap();
+        assert_eq!(
+            vec![
+                Highlight { start: 0, end: 7 },
+                Highlight { start: 8, end: 12 }
+            ],
+            query.highlight("Trivium Live in Paris")
+        );
+        let query = SearchQuery::new("goj").unwrap();
+        assert_eq!(
+            vec![Highlight { start: 4, end: 10 }],
+            query.highlight("Été Gojira")
+        );
+        assert!(query.highlight("Metallica").is_empty());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(0, edit_distance("gojira", "gojira"));
+        assert_eq!(1, edit_distance("trivium", "trivum"));
+        assert_eq!(3, edit_distance("kitten", "sitting"));
+        assert_eq!(3, edit_distance("", "abc"));
+    }
+}
@@ -1,14 +1,36 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
 use uuid::Uuid;
 
+use crate::domain::{
+    errors::{Error, Result},
+    types::pagination::{PageRequest, SortField},
+};
+
 pub struct Ticket {
     pub id: Uuid,
     pub owner_id: Uuid,
 
+    pub concert_id: Uuid,
+    /// Read from the concert
     pub concert_name: String,
+    /// Read from the concert
     pub concert_date: DateTime<Utc>,
+    /// The seat the ticket is bound to, unnumbered when missing
+    pub seat_id: Option<Uuid>,
+    /// Read from the seat
+    pub seat_section: Option<String>,
+    /// Read from the seat
+    pub seat_row: Option<String>,
+    /// Read from the seat
+    pub seat_number: Option<String>,
+    /// The category the ticket was issued in, see `category_types`
+    pub category_id: Option<Uuid>,
+    /// Read from the category
+    pub category_name: Option<String>,
     pub barcode_data: String,
     pub price: f64,
+    /// Bumped on every change, see `domain::preconditions`
+    pub version: i32,
 
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -16,8 +38,166 @@ pub struct Ticket {
 
 pub struct NewTicket {
     pub owner_id: Uuid,
-    pub concert_name: String,
-    pub concert_date: DateTime<Utc>,
+    pub concert_id: Uuid,
+    /// A seat of the venue of the concert
+    pub seat_id: Option<Uuid>,
+    /// A category of the concert, required once the concert has some
+    pub category_id: Option<Uuid>,
     pub barcode_data: String,
-    pub price: f64,
+    /// Missing for a ticket of a category, it costs the price of its category
+    pub price: Option<f64>,
+}
+
+impl NewTicket {
+    pub fn new(
+        owner_id: Uuid,
+        concert_id: Uuid,
+        seat_id: Option<Uuid>,
+        category_id: Option<Uuid>,
+        barcode_data: String,
+        price: Option<f64>,
+    ) -> Result<Self> {
+        match (category_id, price) {
+            (Some(_), Some(_)) => Err(Error::InvalidTicket(
+                "a ticket of a category costs the price of its category".to_string(),
+            )),
+            (None, None) => Err(Error::InvalidTicket(
+                "the price is required without a category".to_string(),
+            )),
+            _ => Ok(NewTicket {
+                owner_id,
+                concert_id,
+                seat_id,
+                category_id,
+                barcode_data,
+                price,
+            }),
+        }
+    }
+}
+
+/// The columns to change, the others are left untouched.
+/// The seat and the category are cleared with `Some(None)`.
+/// The price follows the category when it changes
+pub struct TicketChanges {
+    pub owner_id: Option<Uuid>,
+    pub concert_id: Option<Uuid>,
+    pub seat_id: Option<Option<Uuid>>,
+    pub category_id: Option<Option<Uuid>>,
+    pub barcode_data: Option<String>,
+    pub price: Option<f64>,
+}
+
+impl From<NewTicket> for TicketChanges {
+    fn from(ticket: NewTicket) -> Self {
+        TicketChanges {
+            owner_id: Some(ticket.owner_id),
+            concert_id: Some(ticket.concert_id),
+            seat_id: Some(ticket.seat_id),
+            category_id: Some(ticket.category_id),
+            barcode_data: Some(ticket.barcode_data),
+            price: ticket.price,
+        }
+    }
+}
+
+/// The columns the tickets can be sorted on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TicketSortField {
+    CreatedAt,
+    ConcertDate,
+    Price,
+}
+
+impl TicketSortField {
+    /// The value of the column for `ticket`, as stored in the cursors
+    pub fn value(&self, ticket: &Ticket) -> String {
+        match self {
+            TicketSortField::CreatedAt => ticket
+                .created_at
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            TicketSortField::ConcertDate => ticket
+                .concert_date
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            TicketSortField::Price => ticket.price.to_string(),
+        }
+    }
+}
+
+impl SortField for TicketSortField {
+    const DEFAULT: Self = TicketSortField::CreatedAt;
+
+    fn new(field: &str) -> Result<Self> {
+        match field {
+            "created_at" => Ok(TicketSortField::CreatedAt),
+            "concert_date" => Ok(TicketSortField::ConcertDate),
+            "price" => Ok(TicketSortField::Price),
+            other => Err(Error::InvalidSort(other.to_string())),
+        }
+    }
+
+    fn is_valid_value(&self, value: &str) -> bool {
+        match self {
+            TicketSortField::CreatedAt | TicketSortField::ConcertDate => {
+                DateTime::parse_from_rfc3339(value).is_ok()
+            }
+            TicketSortField::Price => value.parse::<f64>().is_ok_and(f64::is_finite),
+        }
+    }
+}
+
+impl AsRef<str> for TicketSortField {
+    fn as_ref(&self) -> &str {
+        match self {
+            TicketSortField::CreatedAt => "created_at",
+            TicketSortField::ConcertDate => "concert_date",
+            TicketSortField::Price => "price",
+        }
+    }
+}
+
+/// Only the tickets matching every filter set are listed.
+/// The ranges include their start and exclude their end
+#[derive(Debug, Default)]
+pub struct TicketFilter {
+    pub owner_id: Option<Uuid>,
+    pub concert_id: Option<Uuid>,
+    /// Compared without case
+    pub concert_name: Option<String>,
+    pub concert_date_from: Option<DateTime<Utc>>,
+    pub concert_date_to: Option<DateTime<Utc>>,
+    pub price_min: Option<f64>,
+    pub price_max: Option<f64>,
+}
+
+pub struct TicketQuery {
+    pub filter: TicketFilter,
+    pub page: PageRequest<TicketSortField>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_ticket_price() {
+        let ticket = |category_id, price| {
+            NewTicket::new(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                None,
+                category_id,
+                "12345-abcde-67890".to_string(),
+                price,
+            )
+        };
+        assert!(ticket(None, Some(50.0)).is_ok());
+        assert!(ticket(Some(Uuid::new_v4()), None).is_ok());
+        for (category_id, price) in [(None, None), (Some(Uuid::new_v4()), Some(50.0))] {
+            assert!(matches!(
+                ticket(category_id, price),
+                Err(Error::InvalidTicket(_))
+            ));
+        }
+    }
 }
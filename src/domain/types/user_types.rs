@@ -1,11 +1,27 @@
+use chrono::{DateTime, SecondsFormat, Utc};
 use uuid::Uuid;
 
-use crate::domain::types::{PasswordHash, Role, Username};
+use crate::domain::{
+    errors::{Error, Result},
+    types::{
+        pagination::{PageRequest, SortField},
+        Email, PasswordHash, Role, Username,
+    },
+};
 
 pub struct User {
     pub id: Uuid,
     pub username: Username,
+    /// Missing on the accounts created before the addresses were collected
+    pub email: Option<Email>,
+    /// Whether the user proved they own `email`, reset when it changes
+    pub email_verified: bool,
     pub role: Role,
+    /// Bumped on every change, see `domain::preconditions`
+    pub version: i32,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 pub struct InternalUseUser {
@@ -17,6 +33,75 @@ pub struct InternalUseUser {
 
 pub struct NewUser {
     pub username: Username,
+    pub email: Email,
     pub password_hash: PasswordHash,
     pub role: Role,
 }
+
+/// The columns to change, the others are left untouched
+pub struct UserChanges {
+    pub username: Option<Username>,
+    pub email: Option<Email>,
+    pub role: Option<Role>,
+}
+
+/// The columns the users can be sorted on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserSortField {
+    CreatedAt,
+    Username,
+}
+
+impl UserSortField {
+    /// The value of the column for `user`, as stored in the cursors
+    pub fn value(&self, user: &User) -> String {
+        match self {
+            UserSortField::CreatedAt => {
+                user.created_at.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+            }
+            UserSortField::Username => user.username.as_ref().to_string(),
+        }
+    }
+}
+
+impl SortField for UserSortField {
+    const DEFAULT: Self = UserSortField::CreatedAt;
+
+    fn new(field: &str) -> Result<Self> {
+        match field {
+            "created_at" => Ok(UserSortField::CreatedAt),
+            "username" => Ok(UserSortField::Username),
+            other => Err(Error::InvalidSort(other.to_string())),
+        }
+    }
+
+    fn is_valid_value(&self, value: &str) -> bool {
+        match self {
+            UserSortField::CreatedAt => DateTime::parse_from_rfc3339(value).is_ok(),
+            UserSortField::Username => true,
+        }
+    }
+}
+
+impl AsRef<str> for UserSortField {
+    fn as_ref(&self) -> &str {
+        match self {
+            UserSortField::CreatedAt => "created_at",
+            UserSortField::Username => "username",
+        }
+    }
+}
+
+/// Only the users matching every filter set are listed.
+/// The range includes its start and excludes its end
+#[derive(Debug, Default)]
+pub struct UserFilter {
+    pub role: Option<Role>,
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+}
+
+pub struct UserQuery {
+    pub filter: UserFilter,
+    pub page: PageRequest<UserSortField>,
+}
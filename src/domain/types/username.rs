@@ -1,28 +1,97 @@
 use serde::Serialize;
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::{
+    skeleton, GeneralSecurityProfile, RestrictionLevel, RestrictionLevelDetection,
+};
 
 use crate::domain::errors::{Error, Result};
 
+const MIN_LENGTH: usize = 3;
+const MAX_LENGTH: usize = 32;
+
+/// Allowed between the letters and digits
+const SEPARATORS: [char; 3] = ['-', '_', '.'];
+
+/// Names a user could use to pass for the staff, compared by their look
+const RESERVED_NAMES: [&str; 14] = [
+    "admin",
+    "administrator",
+    "root",
+    "superuser",
+    "system",
+    "support",
+    "security",
+    "moderator",
+    "staff",
+    "official",
+    "anonymous",
+    "null",
+    "undefined",
+    "everyone",
+];
+
+/// A username, NFKC normalized. The case is kept but two usernames only differing
+/// by their case are the same account, the database compares them without case
 #[derive(Debug, Serialize)]
 pub struct Username(String);
 
 impl Username {
     pub fn new(u: &str) -> Result<Self> {
-        let s = u.trim().to_string();
-        let forbidden_characters = ['/', '(', ')', '"', '<', '>', '\\', '{', '}'];
-        if s.is_empty() {
-            Err(Error::InvalidUsername(
-                "Username cannot be empty".to_string(),
-            ))
-        } else if s.chars().any(|g| forbidden_characters.contains(&g)) {
-            Err(Error::InvalidUsername(
-                "Username cannot contain special characters".to_string(),
-            ))
-        } else {
-            Ok(Self(s))
+        let s = Self::normalize(u);
+        let length = s.chars().count();
+        if !(MIN_LENGTH..=MAX_LENGTH).contains(&length) {
+            return Err(Error::InvalidUsername(format!(
+                "Username must be between {MIN_LENGTH} and {MAX_LENGTH} characters long"
+            )));
+        }
+        if let Some(c) = s
+            .chars()
+            .find(|c| !c.identifier_allowed() && !SEPARATORS.contains(c))
+        {
+            return Err(Error::InvalidUsername(format!(
+                "Username cannot contain {c:?}"
+            )));
+        }
+        // Latin mixed with Cyrillic, for instance, is how look-alikes are made
+        let letters: String = s.chars().filter(|c| !SEPARATORS.contains(c)).collect();
+        if !letters.check_restriction_level(RestrictionLevel::HighlyRestrictive) {
+            return Err(Error::InvalidUsername(
+                "Username cannot mix scripts".to_string(),
+            ));
+        }
+        if is_reserved(&s) {
+            return Err(Error::InvalidUsername(format!("{s} is reserved")));
         }
+        Ok(Self(s))
+    }
+
+    /// This must **only** be used when loading a username from the database,
+    /// the accounts created before the current rules keep their name
+    ///
+    /// # Safety
+    /// This function is unsafe because it skips the username validation
+    pub unsafe fn new_unchecked(username: String) -> Self {
+        Self(username)
+    }
+
+    /// The form usernames are saved in, used to look up a username typed by a user
+    pub fn normalize(u: &str) -> String {
+        u.trim().nfkc().collect()
+    }
+
+    /// What two usernames are compared by, "Alice" and "alice" are the same account
+    pub fn comparison_key(u: &str) -> String {
+        Self::normalize(u).to_lowercase()
     }
 }
 
+fn is_reserved(username: &str) -> bool {
+    let username: String = skeleton(&username.to_lowercase()).collect();
+    RESERVED_NAMES
+        .iter()
+        .any(|reserved| skeleton(reserved).eq(username.chars()))
+}
+
 impl AsRef<str> for Username {
     fn as_ref(&self) -> &str {
         &self.0
@@ -39,19 +108,53 @@ mod tests {
         let username = Username::new(username).unwrap();
         assert_eq!(username.as_ref(), "username");
 
-        let username = " us$rname ";
+        let username = " John.Doe_42 ";
         let username = Username::new(username).unwrap();
-        assert_eq!(username.as_ref(), "us$rname");
+        assert_eq!(username.as_ref(), "John.Doe_42");
+
+        let username = Username::new("Ζωή").unwrap();
+        assert_eq!(username.as_ref(), "Ζωή");
+    }
+
+    #[test]
+    fn test_username_is_nfkc_normalized() {
+        // Fullwidth letters and a decomposed accent
+        let username = Username::new("ｊｏｈｎ").unwrap();
+        assert_eq!(username.as_ref(), "john");
+        let username = Username::new("jose\u{301}").unwrap();
+        assert_eq!(username.as_ref(), "jos\u{e9}");
     }
 
     #[test]
     fn test_username_new_invalid_username() {
-        let username = "";
-        let username = Username::new(username);
-        assert!(username.is_err());
+        for username in [
+            "",
+            "ab",
+            &"a".repeat(33),
+            "us/ername",
+            "us$rname",
+            "user name",
+            "user\u{200b}name",
+            // A Cyrillic "а" among Latin letters
+            "p\u{430}ypal",
+        ] {
+            assert!(
+                Username::new(username).is_err(),
+                "{username:?} should be refused"
+            );
+        }
+    }
 
-        let username = "us/ername";
-        let username = Username::new(username);
-        assert!(username.is_err());
+    #[test]
+    fn test_reserved_usernames() {
+        // "rn" looks like "m"
+        for username in ["admin", "Admin", "ROOT", "adrnin"] {
+            let res = Username::new(username);
+            assert!(
+                matches!(res, Err(Error::InvalidUsername(_))),
+                "{username:?} should be reserved"
+            );
+        }
+        assert!(Username::new("admins_fan").is_ok());
     }
 }
@@ -0,0 +1,360 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::pagination::{PageRequest, SortField},
+};
+
+/// Longest name of a venue, a section, a row or a seat, in characters
+pub const MAX_LABEL_LENGTH: usize = 200;
+/// Most seats a seating chart can hold
+pub const MAX_SEATS: usize = 100_000;
+
+pub struct Venue {
+    pub id: Uuid,
+    pub name: String,
+    pub address: Option<String>,
+    /// Bumped on every change, see `domain::preconditions`
+    pub version: i32,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub struct NewVenue {
+    pub name: String,
+    pub address: Option<String>,
+    pub seating_chart: SeatingChart,
+}
+
+impl NewVenue {
+    pub fn new(name: &str, address: Option<String>, seating_chart: SeatingChart) -> Result<Self> {
+        Ok(NewVenue {
+            name: validate_label("name", name)?,
+            address,
+            seating_chart,
+        })
+    }
+}
+
+/// The columns to change, the others are left untouched.
+/// The address is cleared with `Some(None)`
+pub struct VenueChanges {
+    pub name: Option<String>,
+    pub address: Option<Option<String>>,
+}
+
+impl VenueChanges {
+    pub fn new(name: Option<&str>, address: Option<Option<String>>) -> Result<Self> {
+        Ok(VenueChanges {
+            name: name.map(|n| validate_label("name", n)).transpose()?,
+            address,
+        })
+    }
+}
+
+/// A seat of a venue, `section`, `row` and `number` are the labels printed on the ticket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seat {
+    pub id: Uuid,
+    pub venue_id: Uuid,
+    pub section: String,
+    pub row: String,
+    pub number: String,
+}
+
+/// A seat to create, `position` keeps the order of the seating chart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewSeat {
+    pub section: String,
+    pub row: String,
+    pub number: String,
+    pub position: i32,
+}
+
+/// How the seats of a venue are laid out: sections made of rows made of seats,
+/// in the order they are displayed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeatingChart {
+    pub sections: Vec<Section>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub rows: Vec<Row>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub name: String,
+    pub seats: Vec<String>,
+}
+
+impl SeatingChart {
+    /// Check that every label is set and unique where it has to be: the sections in the
+    /// venue, the rows in their section and the seats in their row
+    pub fn new(sections: Vec<Section>) -> Result<Self> {
+        let mut section_names = HashSet::new();
+        let mut seat_count = 0;
+        for section in &sections {
+            validate_label("section name", &section.name)?;
+            if !section_names.insert(section.name.trim()) {
+                return Err(invalid_chart(format!(
+                    "the section {} is listed twice",
+                    section.name
+                )));
+            }
+            let mut row_names = HashSet::new();
+            for row in &section.rows {
+                validate_label("row name", &row.name)?;
+                if !row_names.insert(row.name.trim()) {
+                    return Err(invalid_chart(format!(
+                        "the row {} of the section {} is listed twice",
+                        row.name, section.name
+                    )));
+                }
+                let mut numbers = HashSet::new();
+                for number in &row.seats {
+                    validate_label("seat number", number)?;
+                    if !numbers.insert(number.trim()) {
+                        return Err(invalid_chart(format!(
+                            "the seat {number} of the row {} of the section {} is listed twice",
+                            row.name, section.name
+                        )));
+                    }
+                }
+                seat_count += row.seats.len();
+            }
+        }
+        if seat_count > MAX_SEATS {
+            return Err(invalid_chart(format!(
+                "a venue can have at most {MAX_SEATS} seats"
+            )));
+        }
+        let sections = sections
+            .into_iter()
+            .map(|section| Section {
+                name: section.name.trim().to_string(),
+                rows: section
+                    .rows
+                    .into_iter()
+                    .map(|row| Row {
+                        name: row.name.trim().to_string(),
+                        seats: row.seats.iter().map(|s| s.trim().to_string()).collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Ok(SeatingChart { sections })
+    }
+
+    /// Every seat of the chart, in order
+    pub fn seats(&self) -> Vec<NewSeat> {
+        let mut seats = vec![];
+        for section in &self.sections {
+            for row in &section.rows {
+                for number in &row.seats {
+                    seats.push(NewSeat {
+                        section: section.name.clone(),
+                        row: row.name.clone(),
+                        number: number.clone(),
+                        position: seats.len() as i32,
+                    });
+                }
+            }
+        }
+        seats
+    }
+}
+
+/// The seats of a row along with a value each, after the name of the row
+pub type SeatRow<T> = (String, Vec<(Seat, T)>);
+/// The rows of a section, after the name of the section
+pub type SeatSection<T> = (String, Vec<SeatRow<T>>);
+
+/// Group seats listed in chart order into their sections and rows, the seats of a row
+/// being listed one after the other
+pub fn group_seats<T>(seats: Vec<(Seat, T)>) -> Vec<SeatSection<T>> {
+    let mut sections: Vec<SeatSection<T>> = vec![];
+    for (seat, value) in seats {
+        if sections.last().map(|(name, _)| name) != Some(&seat.section) {
+            sections.push((seat.section.clone(), vec![]));
+        }
+        let (_, rows) = sections.last_mut().expect("a section was just pushed");
+        if rows.last().map(|(name, _)| name) != Some(&seat.row) {
+            rows.push((seat.row.clone(), vec![]));
+        }
+        let (_, row) = rows.last_mut().expect("a row was just pushed");
+        row.push((seat, value));
+    }
+    sections
+}
+
+fn validate_label(what: &str, label: &str) -> Result<String> {
+    let label = label.trim();
+    let error = |reason: String| match what {
+        "name" => Error::InvalidVenue(reason),
+        _ => invalid_chart(reason),
+    };
+    if label.is_empty() {
+        return Err(error(format!("the {what} cannot be empty")));
+    }
+    if label.chars().count() > MAX_LABEL_LENGTH {
+        return Err(error(format!(
+            "the {what} must be at most {MAX_LABEL_LENGTH} characters long"
+        )));
+    }
+    Ok(label.to_string())
+}
+
+fn invalid_chart(reason: String) -> Error {
+    Error::InvalidSeatingChart(reason)
+}
+
+/// The columns the venues can be sorted on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VenueSortField {
+    Name,
+    CreatedAt,
+}
+
+impl VenueSortField {
+    /// The value of the column for `venue`, as stored in the cursors
+    pub fn value(&self, venue: &Venue) -> String {
+        match self {
+            VenueSortField::Name => venue.name.clone(),
+            VenueSortField::CreatedAt => venue
+                .created_at
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        }
+    }
+}
+
+impl SortField for VenueSortField {
+    const DEFAULT: Self = VenueSortField::Name;
+
+    fn new(field: &str) -> Result<Self> {
+        match field {
+            "name" => Ok(VenueSortField::Name),
+            "created_at" => Ok(VenueSortField::CreatedAt),
+            other => Err(Error::InvalidSort(other.to_string())),
+        }
+    }
+
+    fn is_valid_value(&self, value: &str) -> bool {
+        match self {
+            VenueSortField::Name => true,
+            VenueSortField::CreatedAt => DateTime::parse_from_rfc3339(value).is_ok(),
+        }
+    }
+}
+
+impl AsRef<str> for VenueSortField {
+    fn as_ref(&self) -> &str {
+        match self {
+            VenueSortField::Name => "name",
+            VenueSortField::CreatedAt => "created_at",
+        }
+    }
+}
+
+pub struct VenueQuery {
+    pub page: PageRequest<VenueSortField>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(name: &str, seats: &[&str]) -> Row {
+        Row {
+            name: name.to_string(),
+            seats: seats.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn section(name: &str, rows: Vec<Row>) -> Section {
+        Section {
+            name: name.to_string(),
+            rows,
+        }
+    }
+
+    #[test]
+    fn test_seating_chart() {
+        let chart = SeatingChart::new(vec![
+            section(" Pit ", vec![row("1", &["1 ", "2"])]),
+            section("Balcony", vec![row("A", &["1"]), row("B", &["1"])]),
+        ])
+        .unwrap();
+        let seats = chart.seats();
+        assert_eq!(4, seats.len());
+        assert_eq!(
+            NewSeat {
+                section: "Pit".to_string(),
+                row: "1".to_string(),
+                number: "1".to_string(),
+                position: 0,
+            },
+            seats[0]
+        );
+        assert_eq!(("Balcony", "B", 3), {
+            let s = &seats[3];
+            (s.section.as_str(), s.row.as_str(), s.position)
+        });
+
+        for sections in [
+            vec![section("Pit", vec![]), section("Pit ", vec![])],
+            vec![section("Pit", vec![row("1", &[]), row("1", &[])])],
+            vec![section("Pit", vec![row("1", &["1", "1"])])],
+            vec![section("Pit", vec![row("1", &[""])])],
+            vec![section("", vec![])],
+        ] {
+            assert!(matches!(
+                SeatingChart::new(sections),
+                Err(Error::InvalidSeatingChart(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_group_seats() {
+        let venue_id = Uuid::new_v4();
+        let seat = |section: &str, row: &str, number: &str| Seat {
+            id: Uuid::new_v4(),
+            venue_id,
+            section: section.to_string(),
+            row: row.to_string(),
+            number: number.to_string(),
+        };
+        let grouped = group_seats(vec![
+            (seat("Pit", "1", "1"), true),
+            (seat("Pit", "1", "2"), false),
+            (seat("Pit", "2", "1"), true),
+            (seat("Balcony", "1", "1"), true),
+        ]);
+        assert_eq!(2, grouped.len());
+        assert_eq!("Pit", grouped[0].0);
+        assert_eq!(2, grouped[0].1.len());
+        assert_eq!(2, grouped[0].1[0].1.len());
+        assert!(!grouped[0].1[0].1[1].1);
+        assert_eq!("Balcony", grouped[1].0);
+    }
+
+    #[test]
+    fn test_new_venue() {
+        let chart = SeatingChart::new(vec![]).unwrap();
+        assert_eq!(
+            "Zénith",
+            NewVenue::new(" Zénith", None, chart.clone()).unwrap().name
+        );
+        assert!(matches!(
+            NewVenue::new(" ", None, chart),
+            Err(Error::InvalidVenue(_))
+        ));
+    }
+}
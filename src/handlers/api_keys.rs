@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        dtos::api_key_dtos::{ApiKeyDto, CreatedApiKeyDto, NewApiKeyDto},
+        errors::{Error, Result},
+        policy,
+        types::{
+            api_key_types::{NewApiKey, Scope},
+            JwtClaims,
+        },
+    },
+    handlers::{errors::result_to_warp_reply, opaque_token},
+    AppState,
+};
+
+type ReplyRes<T> = std::result::Result<T, Rejection>;
+
+/// Tells the API keys apart from the access tokens in the `Authorization` header
+pub const API_KEY_PREFIX: &str = "iom_";
+/// Length of the hint stored with a key, the prefix included
+const HINT_LENGTH: usize = 12;
+const MAX_NAME_LENGTH: usize = 100;
+
+pub async fn create_api_key(
+    claims: JwtClaims,
+    input: NewApiKeyDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let created = async {
+        policy::authorize_credential_management(&claims)?;
+        let name = input.name.trim().to_string();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            return Err(Error::InvalidApiKeyInput(format!(
+                "the name must be between 1 and {MAX_NAME_LENGTH} characters"
+            )));
+        }
+        if input.expires_at.is_some_and(|e| e <= Utc::now()) {
+            return Err(Error::InvalidApiKeyInput(
+                "the expiration date is in the past".to_string(),
+            ));
+        }
+        let scopes = input
+            .scopes
+            .map(|scopes| scopes.iter().map(|s| Scope::new(s)).collect::<Result<_>>())
+            .transpose()?;
+
+        let key = format!("{API_KEY_PREFIX}{}", opaque_token::generate());
+        let api_key = app_state
+            .api_key_model
+            .create_api_key(NewApiKey {
+                user_id: claims.user_id,
+                name,
+                key_hash: opaque_token::hash(&key),
+                hint: key[..HINT_LENGTH].to_string(),
+                scopes,
+                expires_at: input.expires_at,
+            })
+            .await?;
+        Ok(CreatedApiKeyDto {
+            key,
+            api_key: api_key.into(),
+        })
+    }
+    .await;
+    result_to_warp_reply(created)
+}
+
+pub async fn get_api_keys(claims: JwtClaims, app_state: Arc<AppState>) -> ReplyRes<impl Reply> {
+    let keys = async {
+        policy::authorize_credential_management(&claims)?;
+        let keys = app_state
+            .api_key_model
+            .get_user_api_keys(claims.user_id)
+            .await?;
+        Ok(keys
+            .into_iter()
+            .map(|k| k.into())
+            .collect::<Vec<ApiKeyDto>>())
+    }
+    .await;
+    result_to_warp_reply(keys)
+}
+
+pub async fn revoke_api_key(
+    id: uuid::Uuid,
+    claims: JwtClaims,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = async {
+        policy::authorize_credential_management(&claims)?;
+        app_state
+            .api_key_model
+            .revoke_api_key(claims.user_id, id)
+            .await
+    }
+    .await;
+    result_to_warp_reply(res)
+}
+
+/// Build the claims of a request authenticated with an API key.
+/// The owner is loaded so the key always acts with their current role
+pub async fn authenticate_api_key(app_state: &AppState, key: &str) -> Result<JwtClaims> {
+    let api_key = app_state
+        .api_key_model
+        .use_api_key(opaque_token::hash(key))
+        .await?
+        .ok_or(Error::InvalidApiKey)?;
+    let user = match app_state.user_model.get_user(api_key.user_id).await {
+        Ok(user) => user,
+        Err(Error::UserNotFound) => return Err(Error::InvalidApiKey),
+        Err(e) => return Err(e),
+    };
+    Ok(JwtClaims::for_api_key(
+        user.username.as_ref().to_string(),
+        user.role.as_ref().to_string(),
+        api_key,
+    ))
+}
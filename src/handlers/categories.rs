@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        dtos::category_dtos::{TicketCategoryDto, TicketCategoryInputDto, TicketCategoryPatchDto},
+        errors::Error,
+        policy, preconditions,
+        types::{
+            category_types::{NewTicketCategory, TicketCategoryChanges},
+            JwtClaims,
+        },
+    },
+    AppState,
+};
+
+use super::errors::{result_to_warp_reply, tagged_result_to_warp_reply};
+
+type ReplyRes<T> = Result<T, Rejection>;
+
+/// The categories of the concert along with their remaining capacity
+pub async fn get_concert_categories(
+    concert_id: Uuid,
+    _claims: JwtClaims,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let categories = async {
+        app_state.concert_model.get_concert(concert_id).await?;
+        let categories = app_state.category_model.get_categories(concert_id).await?;
+        Ok(categories
+            .into_iter()
+            .map(TicketCategoryDto::from)
+            .collect::<Vec<_>>())
+    }
+    .await;
+    result_to_warp_reply(categories)
+}
+
+pub async fn get_category_by_id(
+    id: Uuid,
+    _claims: JwtClaims,
+    if_none_match: Option<String>,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let category = async {
+        let category = app_state.category_model.get_category(id).await?;
+        let version = category.version;
+        Ok((TicketCategoryDto::from(category), version))
+    }
+    .await;
+    tagged_result_to_warp_reply(category, if_none_match)
+}
+
+pub async fn create_category(
+    concert_id: Uuid,
+    claims: JwtClaims,
+    category: TicketCategoryInputDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let category_id = async {
+        policy::authorize_concert_management(&claims)?;
+        let category: NewTicketCategory = category.try_into()?;
+        app_state
+            .category_model
+            .create_category(concert_id, category)
+            .await
+    }
+    .await;
+    result_to_warp_reply(category_id)
+}
+
+pub async fn update_category(
+    id: Uuid,
+    claims: JwtClaims,
+    if_match: Option<String>,
+    patch: TicketCategoryPatchDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = match patch.try_into() {
+        Ok(changes) => modify_category(id, &claims, if_match, changes, &app_state).await,
+        Err(e) => Err(e),
+    };
+    tagged_result_to_warp_reply(res, None)
+}
+
+pub async fn replace_category(
+    id: Uuid,
+    claims: JwtClaims,
+    if_match: Option<String>,
+    category_input: TicketCategoryInputDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = match NewTicketCategory::try_from(category_input) {
+        Ok(category) => modify_category(id, &claims, if_match, category.into(), &app_state).await,
+        Err(e) => Err(e),
+    };
+    tagged_result_to_warp_reply(res, None)
+}
+
+/// Returns the id and the new version of the category
+async fn modify_category(
+    id: Uuid,
+    claims: &JwtClaims,
+    if_match: Option<String>,
+    changes: TicketCategoryChanges,
+    app_state: &AppState,
+) -> Result<(Uuid, i32), Error> {
+    policy::authorize_concert_management(claims)?;
+    let category = app_state.category_model.get_category(id).await?;
+    preconditions::check_if_match(if_match.as_deref(), category.version)?;
+    let version = app_state
+        .category_model
+        .update_category(id, changes, category.version)
+        .await?;
+    Ok((id, version))
+}
+
+/// A category can only be deleted before any of its tickets is sold
+pub async fn delete_category(
+    id: Uuid,
+    claims: JwtClaims,
+    if_match: Option<String>,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let category_id = async {
+        policy::authorize_concert_management(&claims)?;
+        let category = app_state.category_model.get_category(id).await?;
+        preconditions::check_if_match(if_match.as_deref(), category.version)?;
+        app_state
+            .category_model
+            .delete_category(id, category.version)
+            .await
+    }
+    .await;
+    result_to_warp_reply(category_id)
+}
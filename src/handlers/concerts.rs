@@ -0,0 +1,162 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        dtos::{
+            concert_dtos::{ConcertDto, ConcertInputDto, ConcertListQueryDto, ConcertPatchDto},
+            page_dtos::PageDto,
+            venue_dtos::ConcertSeatsDto,
+        },
+        errors::Error,
+        policy, preconditions,
+        types::{
+            concert_types::{ConcertChanges, NewConcert},
+            JwtClaims,
+        },
+    },
+    AppState,
+};
+
+use super::errors::{result_to_warp_reply, tagged_result_to_warp_reply};
+
+type ReplyRes<T> = Result<T, Rejection>;
+
+pub async fn get_all_concerts(
+    _claims: JwtClaims,
+    query: ConcertListQueryDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let concerts = async {
+        let page = app_state
+            .concert_model
+            .query_concerts(query.try_into()?)
+            .await?;
+        Ok(PageDto::<ConcertDto>::from(page))
+    }
+    .await;
+    result_to_warp_reply(concerts)
+}
+
+pub async fn get_concert_by_id(
+    id: Uuid,
+    _claims: JwtClaims,
+    if_none_match: Option<String>,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let concert = async {
+        let concert = app_state.concert_model.get_concert(id).await?;
+        let version = concert.version;
+        Ok((ConcertDto::from(concert), version))
+    }
+    .await;
+    tagged_result_to_warp_reply(concert, if_none_match)
+}
+
+/// Every seat of the venue of the concert and whether it is still available,
+/// laid out as in the seating chart
+pub async fn get_concert_seats(
+    id: Uuid,
+    _claims: JwtClaims,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let seats = async {
+        let concert = app_state.concert_model.get_concert(id).await?;
+        let venue_id = concert.venue_id.ok_or(Error::ConcertHasNoVenue)?;
+        let seats = app_state
+            .venue_model
+            .get_concert_seats(venue_id, id, app_state.clock.now())
+            .await?;
+        Ok(ConcertSeatsDto::new(id, venue_id, seats))
+    }
+    .await;
+    result_to_warp_reply(seats)
+}
+
+pub async fn create_concert(
+    claims: JwtClaims,
+    concert: ConcertInputDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let concert_id = async {
+        policy::authorize_concert_management(&claims)?;
+        let concert: NewConcert = concert.try_into()?;
+        app_state.concert_model.create_concert(concert).await
+    }
+    .await;
+    result_to_warp_reply(concert_id)
+}
+
+pub async fn update_concert(
+    id: Uuid,
+    claims: JwtClaims,
+    if_match: Option<String>,
+    patch: ConcertPatchDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = match patch.try_into() {
+        Ok(changes) => modify_concert(id, &claims, if_match, changes, &app_state).await,
+        Err(e) => Err(e),
+    };
+    tagged_result_to_warp_reply(res, None)
+}
+
+pub async fn replace_concert(
+    id: Uuid,
+    claims: JwtClaims,
+    if_match: Option<String>,
+    concert_input: ConcertInputDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = match NewConcert::try_from(concert_input) {
+        Ok(concert) => modify_concert(id, &claims, if_match, concert.into(), &app_state).await,
+        Err(e) => Err(e),
+    };
+    tagged_result_to_warp_reply(res, None)
+}
+
+/// Returns the id and the new version of the concert
+async fn modify_concert(
+    id: Uuid,
+    claims: &JwtClaims,
+    if_match: Option<String>,
+    changes: ConcertChanges,
+    app_state: &AppState,
+) -> Result<(Uuid, i32), Error> {
+    policy::authorize_concert_management(claims)?;
+    let concert = app_state.concert_model.get_concert(id).await?;
+    preconditions::check_if_match(if_match.as_deref(), concert.version)?;
+    // The seats sold for the concert belong to its venue
+    if changes.venue_id.is_some_and(|v| v != concert.venue_id)
+        && app_state.venue_model.count_seated_tickets(id).await? > 0
+    {
+        return Err(Error::SeatsInUse);
+    }
+    let version = app_state
+        .concert_model
+        .update_concert(id, changes, concert.version)
+        .await?;
+    Ok((id, version))
+}
+
+/// A concert can only be deleted once no ticket refers to it
+pub async fn delete_concert(
+    id: Uuid,
+    claims: JwtClaims,
+    if_match: Option<String>,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let concert_id = async {
+        policy::authorize_concert_management(&claims)?;
+        let concert = app_state.concert_model.get_concert(id).await?;
+        preconditions::check_if_match(if_match.as_deref(), concert.version)?;
+        app_state
+            .concert_model
+            .delete_concert(id, concert.version)
+            .await
+    }
+    .await;
+    result_to_warp_reply(concert_id)
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        dtos::user_dtos::VerifyEmailDto,
+        errors::{Error, Result},
+        policy,
+        types::{email_verification_types::NewEmailVerificationToken, Email, JwtClaims},
+    },
+    handlers::{errors::result_to_warp_reply, opaque_token},
+    notifiers::notifier::Notification,
+    AppState,
+};
+
+type ReplyRes<T> = std::result::Result<T, Rejection>;
+
+/// Issue a verification token for `email` and deliver it to that address
+pub async fn send_verification(app_state: &AppState, user_id: Uuid, email: &Email) -> Result<()> {
+    let token = opaque_token::generate();
+    let expires_at = Utc::now() + app_state.email_verification_ttl;
+    app_state
+        .email_verification_model
+        .create_verification_token(NewEmailVerificationToken {
+            user_id,
+            email: email.clone(),
+            token_hash: opaque_token::hash(&token),
+            expires_at,
+        })
+        .await?;
+    app_state
+        .notifier
+        .send(Notification {
+            recipient: email.as_ref().to_string(),
+            subject: "Verify your email address".to_string(),
+            body: format!(
+                "Use this token to verify your email address: {token}\nIt expires at {expires_at}."
+            ),
+        })
+        .await
+}
+
+/// Send a new verification token to the caller, the previous ones can no longer be used
+pub async fn request_verification(
+    claims: JwtClaims,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = async {
+        policy::authorize_credential_management(&claims)?;
+        let user = app_state.user_model.get_user(claims.user_id).await?;
+        let email = user.email.ok_or(Error::MissingEmail)?;
+        if user.email_verified {
+            return Err(Error::EmailAlreadyVerified);
+        }
+        send_verification(&app_state, user.id, &email).await
+    }
+    .await;
+    result_to_warp_reply(res)
+}
+
+/// Redeem a verification token, it only counts if the user still has the same address
+pub async fn verify_email(input: VerifyEmailDto, app_state: Arc<AppState>) -> ReplyRes<impl Reply> {
+    let res = async {
+        let token = app_state
+            .email_verification_model
+            .consume_verification_token(opaque_token::hash(&input.token))
+            .await?;
+        let verified = app_state
+            .user_model
+            .verify_email(token.user_id, token.email)
+            .await?;
+        if !verified {
+            return Err(Error::InvalidVerificationToken);
+        }
+        Ok(())
+    }
+    .await;
+    result_to_warp_reply(res)
+}
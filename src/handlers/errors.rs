@@ -1,12 +1,20 @@
+use std::convert::Infallible;
+
 use serde::Serialize;
 use serde_json::json;
 use warp::{
-    http::StatusCode,
-    reject::Rejection,
-    reply::{self, Reply},
+    body::BodyDeserializeError,
+    http::{header::ETAG, StatusCode},
+    reject::{InvalidQuery, MethodNotAllowed, Reject, Rejection, UnsupportedMediaType},
+    reply::{self, Reply, Response},
+};
+
+use crate::domain::{
+    errors::{Error, Result},
+    preconditions,
 };
 
-use crate::domain::errors::{Error, Result};
+impl Reject for Error {}
 
 pub fn result_to_warp_reply<T>(result: Result<T>) -> Result<impl Reply, Rejection>
 where
@@ -14,24 +22,98 @@ where
 {
     match result {
         Ok(data) => Ok(reply::with_status(reply::json(&data), StatusCode::ACCEPTED)),
+        Err(e) => Ok(error_reply(&e, to_http_status_code(&e))),
+    }
+}
 
-        Err(e) => {
-            let message = e.to_string();
-            Ok(reply::with_status(
-                reply::json(&json!({
-                    "status": "fail",
-                    "message": message,
-                })),
-                to_http_status_code(e),
-            ))
+/// Like `result_to_warp_reply`, with the `ETag` of the version of the resource.
+/// A client already holding that version gets a 304 without a body
+pub fn tagged_result_to_warp_reply<T>(
+    result: Result<(T, i32)>,
+    if_none_match: Option<String>,
+) -> Result<Response, Rejection>
+where
+    T: Serialize,
+{
+    match result {
+        Ok((data, version)) => {
+            let etag = preconditions::etag(version);
+            if preconditions::is_not_modified(if_none_match.as_deref(), version) {
+                return Ok(reply::with_header(StatusCode::NOT_MODIFIED, ETAG, etag).into_response());
+            }
+            let reply = reply::with_status(reply::json(&data), StatusCode::ACCEPTED);
+            Ok(reply::with_header(reply, ETAG, etag).into_response())
         }
+        Err(e) => Ok(error_reply(&e, to_http_status_code(&e)).into_response()),
+    }
+}
+
+/// Turns the rejections emitted by the filters into the same JSON replies as the handlers
+pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
+    if let Some(e) = err.find::<Error>() {
+        return Ok(error_reply(e, to_http_status_code(e)));
     }
+    let (message, status) = if err.is_not_found() {
+        ("not found".to_string(), StatusCode::NOT_FOUND)
+    } else if let Some(e) = err.find::<BodyDeserializeError>() {
+        (e.to_string(), StatusCode::BAD_REQUEST)
+    } else if let Some(e) = err.find::<InvalidQuery>() {
+        (e.to_string(), StatusCode::BAD_REQUEST)
+    } else if let Some(e) = err.find::<UnsupportedMediaType>() {
+        (e.to_string(), StatusCode::UNSUPPORTED_MEDIA_TYPE)
+    } else if err.find::<MethodNotAllowed>().is_some() {
+        (
+            "method not allowed".to_string(),
+            StatusCode::METHOD_NOT_ALLOWED,
+        )
+    } else {
+        (format!("{err:?}"), StatusCode::INTERNAL_SERVER_ERROR)
+    };
+    Ok(fail_reply(
+        json!({ "status": "fail", "message": message }),
+        status,
+    ))
+}
+
+/// The status and the body `result_to_warp_reply` replies with, so the response can be stored
+pub fn result_to_json<T>(result: Result<T>) -> (StatusCode, serde_json::Value)
+where
+    T: Serialize,
+{
+    let data = result.and_then(|data| {
+        serde_json::to_value(data).map_err(|e| Error::InternalError(e.to_string()))
+    });
+    match data {
+        Ok(data) => (StatusCode::ACCEPTED, data),
+        Err(e) => (to_http_status_code(&e), error_body(&e)),
+    }
+}
+
+fn error_reply(e: &Error, status: StatusCode) -> reply::WithStatus<reply::Json> {
+    fail_reply(error_body(e), status)
+}
+
+/// The failing rules of a rejected password are listed so a client can display them
+fn error_body(e: &Error) -> serde_json::Value {
+    let mut body = json!({
+        "status": "fail",
+        "message": e.to_string(),
+    });
+    if let Error::InvalidPassword(violations) = e {
+        body["violations"] = json!(violations);
+    }
+    body
+}
+
+fn fail_reply(body: serde_json::Value, status: StatusCode) -> reply::WithStatus<reply::Json> {
+    reply::with_status(reply::json(&body), status)
 }
 
-fn to_http_status_code(e: Error) -> StatusCode {
+fn to_http_status_code(e: &Error) -> StatusCode {
     match e {
         Error::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         Error::UsernameAlreadyExists => StatusCode::CONFLICT,
+        Error::EmailAlreadyExists => StatusCode::CONFLICT,
         Error::UserNotFound => StatusCode::NO_CONTENT,
         Error::UserCreationFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
         Error::UserFetchFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
@@ -42,9 +124,111 @@ fn to_http_status_code(e: Error) -> StatusCode {
         Error::TicketCreationFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
         Error::TicketUpdateFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
         Error::TicketDeletionFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::ConcertFetchFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::ConcertNotFound => StatusCode::NOT_FOUND,
+        Error::ConcertCreationFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::ConcertUpdateFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::ConcertDeletionFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::ConcertHasTickets => StatusCode::CONFLICT,
+        Error::InvalidConcert(_) => StatusCode::BAD_REQUEST,
+        Error::VenueFetchFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::VenueNotFound => StatusCode::NOT_FOUND,
+        Error::VenueCreationFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::VenueUpdateFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::VenueDeletionFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::VenueHasConcerts => StatusCode::CONFLICT,
+        Error::SeatsInUse => StatusCode::CONFLICT,
+        Error::InvalidVenue(_) => StatusCode::BAD_REQUEST,
+        Error::InvalidSeatingChart(_) => StatusCode::BAD_REQUEST,
+        Error::ConcertHasNoVenue => StatusCode::NOT_FOUND,
+        Error::SeatNotFound => StatusCode::NOT_FOUND,
+        Error::SeatNotInVenue => StatusCode::BAD_REQUEST,
+        Error::SeatTaken => StatusCode::CONFLICT,
+        Error::InvalidTicket(_) => StatusCode::BAD_REQUEST,
+        Error::CategoryFetchFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::CategoryNotFound => StatusCode::NOT_FOUND,
+        Error::CategoryCreationFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::CategoryUpdateFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::CategoryDeletionFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::CategoryAlreadyExists => StatusCode::CONFLICT,
+        Error::CategoryHasTickets => StatusCode::CONFLICT,
+        Error::CapacityBelowSold => StatusCode::CONFLICT,
+        Error::CategoryNotInConcert => StatusCode::BAD_REQUEST,
+        Error::CategoryRequired => StatusCode::BAD_REQUEST,
+        Error::InvalidCategory(_) => StatusCode::BAD_REQUEST,
+        Error::SoldOut => StatusCode::CONFLICT,
+        Error::HoldFetchFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::HoldNotFound => StatusCode::NOT_FOUND,
+        Error::HoldCreationFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::HoldDeletionFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::HoldExpired => StatusCode::GONE,
+        Error::InvalidHold(_) => StatusCode::BAD_REQUEST,
+        Error::SeatHeld => StatusCode::CONFLICT,
+        Error::OrderFetchFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::OrderNotFound => StatusCode::NOT_FOUND,
+        Error::OrderCreationFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::OrderUpdateFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::InvalidOrder(_) => StatusCode::BAD_REQUEST,
+        Error::InvalidOrderStatus(_) => StatusCode::BAD_REQUEST,
+        Error::InvalidOrderTransition(_, _) => StatusCode::CONFLICT,
+        Error::RefreshTokenCreationFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::RefreshTokenUpdateFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::InvalidRefreshToken => StatusCode::UNAUTHORIZED,
+        Error::RefreshTokenReused => StatusCode::UNAUTHORIZED,
+        Error::RevocationFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::RevocationFetchFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::PasswordResetFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::InvalidResetToken => StatusCode::BAD_REQUEST,
+        Error::EmailVerificationFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::InvalidVerificationToken => StatusCode::BAD_REQUEST,
+        Error::EmailAlreadyVerified => StatusCode::CONFLICT,
+        Error::MissingEmail => StatusCode::CONFLICT,
+        Error::EmailNotVerified => StatusCode::FORBIDDEN,
+        Error::NotificationFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::PasswordHashingFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
         Error::InvalidPassword(_) => StatusCode::BAD_REQUEST,
         Error::InvalidUsername(_) => StatusCode::BAD_REQUEST,
+        Error::InvalidEmail(_) => StatusCode::BAD_REQUEST,
+        Error::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+        Error::PreconditionRequired => StatusCode::PRECONDITION_REQUIRED,
+        Error::IdempotencyKeyFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::InvalidIdempotencyKey(_) => StatusCode::BAD_REQUEST,
+        Error::IdempotencyKeyInUse => StatusCode::CONFLICT,
+        Error::IdempotencyKeyReused => StatusCode::UNPROCESSABLE_ENTITY,
+        Error::InvalidCursor(_) => StatusCode::BAD_REQUEST,
+        Error::InvalidSort(_) => StatusCode::BAD_REQUEST,
+        Error::InvalidPageRequest(_) => StatusCode::BAD_REQUEST,
+        Error::InvalidSearch(_) => StatusCode::BAD_REQUEST,
+        Error::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        Error::InvalidPatch(_) => StatusCode::BAD_REQUEST,
         Error::InvalidRole(_) => StatusCode::BAD_REQUEST,
+        Error::MalformedToken(_) => StatusCode::UNAUTHORIZED,
+        Error::InvalidTokenSignature => StatusCode::UNAUTHORIZED,
+        Error::UnknownSigningKey(_) => StatusCode::UNAUTHORIZED,
+        Error::TokenExpired => StatusCode::UNAUTHORIZED,
+        Error::TokenNotYetValid => StatusCode::UNAUTHORIZED,
+        Error::InvalidTokenIssuer => StatusCode::UNAUTHORIZED,
+        Error::InvalidTokenAudience => StatusCode::UNAUTHORIZED,
+        Error::InvalidCredentials => StatusCode::UNAUTHORIZED,
+        Error::TooManyLoginAttempts(_) => StatusCode::TOO_MANY_REQUESTS,
+        Error::LoginAttemptsFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::InvalidAttemptKind(_) => StatusCode::BAD_REQUEST,
+        Error::MfaFetchFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::MfaUpdateFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::MfaAlreadyEnabled => StatusCode::CONFLICT,
+        Error::MfaNotEnabled => StatusCode::BAD_REQUEST,
+        Error::InvalidMfaCode => StatusCode::UNAUTHORIZED,
+        Error::InvalidMfaChallenge => StatusCode::UNAUTHORIZED,
+        Error::MfaRequired => StatusCode::FORBIDDEN,
+        Error::ApiKeyCreationFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::ApiKeyFetchFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::ApiKeyUpdateFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::ApiKeyNotFound => StatusCode::NOT_FOUND,
+        Error::InvalidApiKey => StatusCode::UNAUTHORIZED,
+        Error::InvalidApiKeyInput(_) => StatusCode::BAD_REQUEST,
+        Error::InvalidScope(_) => StatusCode::BAD_REQUEST,
         Error::LoginFailed(_) => StatusCode::UNAUTHORIZED,
+        Error::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+        Error::Forbidden(_) => StatusCode::FORBIDDEN,
     }
 }
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        dtos::hold_dtos::{HoldDto, HoldInputDto},
+        policy::{self, Action},
+        types::{hold_types::NewHold, JwtClaims},
+    },
+    AppState,
+};
+
+use super::{errors::result_to_warp_reply, tickets::check_seat};
+
+type ReplyRes<T> = Result<T, Rejection>;
+
+pub async fn get_hold_by_id(
+    id: Uuid,
+    claims: JwtClaims,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let hold = async {
+        let hold = app_state.hold_model.get_hold(id).await?;
+        policy::authorize_hold(&claims, Action::Read, &hold)?;
+        Ok(HoldDto::from(hold))
+    }
+    .await;
+    result_to_warp_reply(hold)
+}
+
+/// Hold tickets for the caller until the hold TTL runs out
+pub async fn create_hold(
+    claims: JwtClaims,
+    hold: HoldInputDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let hold_id = async {
+        let user = app_state.user_model.get_user(claims.user_id).await?;
+        policy::authorize_ticket_recipient(&user)?;
+        app_state.concert_model.get_concert(hold.concert_id).await?;
+        for seat_id in &hold.seat_ids {
+            check_seat(&app_state, hold.concert_id, *seat_id).await?;
+        }
+        let now = app_state.clock.now();
+        let hold = NewHold::new(
+            claims.user_id,
+            hold.concert_id,
+            hold.category_id,
+            hold.quantity,
+            hold.seat_ids,
+            now + app_state.hold_ttl,
+        )?;
+        app_state.hold_model.create_hold(hold, now).await
+    }
+    .await;
+    result_to_warp_reply(hold_id)
+}
+
+/// Give the held tickets back before the hold expires
+pub async fn release_hold(
+    id: Uuid,
+    claims: JwtClaims,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let hold_id = async {
+        let hold = app_state.hold_model.get_hold(id).await?;
+        policy::authorize_hold(&claims, Action::Delete, &hold)?;
+        app_state.hold_model.release_hold(id).await?;
+        Ok(id)
+    }
+    .await;
+    result_to_warp_reply(hold_id)
+}
@@ -0,0 +1,128 @@
+use std::future::Future;
+
+use chrono::Utc;
+use serde::Serialize;
+use uuid::Uuid;
+use warp::{
+    http::StatusCode,
+    reject::Rejection,
+    reply::{self, Reply, Response},
+};
+
+use crate::{
+    domain::{
+        errors::{Error, Result},
+        types::idempotency_types::{
+            IdempotencyClaim, IdempotencyKey, NewIdempotencyRecord, StoredResponse,
+        },
+    },
+    handlers::{errors::result_to_json, opaque_token},
+    AppState,
+};
+
+/// Header set on the responses sent again for a retried request
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Identify a request by its route and body, a key can only be used for one of them
+pub fn fingerprint<T>(route: &str, input: &T) -> String
+where
+    T: Serialize,
+{
+    let body = serde_json::to_string(input).expect("the request body is serializable");
+    opaque_token::hash(&format!("{route}\n{body}"))
+}
+
+/// Run `request` at most once per `Idempotency-Key` of the caller, the retries get the
+/// stored response back. Without a key the request is simply run.
+/// The server errors are not stored, the request can be retried with the same key
+pub async fn idempotent<T, F>(
+    app_state: &AppState,
+    user_id: Uuid,
+    key: Option<String>,
+    request_hash: String,
+    request: F,
+) -> Result<Response, Rejection>
+where
+    T: Serialize,
+    F: Future<Output = Result<T>>,
+{
+    let Some(key) = key else {
+        let (status, body) = result_to_json(request.await);
+        return Ok(json_reply(status, &body));
+    };
+    let res = async {
+        let key = IdempotencyKey::new(&key)?;
+        let record = NewIdempotencyRecord {
+            user_id,
+            key: key.clone(),
+            request_hash,
+            expires_at: Utc::now() + app_state.idempotency_key_ttl,
+        };
+        match app_state.idempotency_key_model.begin(record).await? {
+            IdempotencyClaim::Started => {}
+            IdempotencyClaim::Replay(response) => return Ok(replay(response)),
+            IdempotencyClaim::InProgress => return Err(Error::IdempotencyKeyInUse),
+            IdempotencyClaim::Mismatch => return Err(Error::IdempotencyKeyReused),
+        }
+
+        let (status, body) = result_to_json(request.await);
+        let model = &app_state.idempotency_key_model;
+        // The request already ran, its response is sent even if it cannot be stored
+        let stored = if status.is_server_error() {
+            model.release(user_id, &key).await
+        } else {
+            let response = StoredResponse {
+                status_code: status.as_u16(),
+                body: body.clone(),
+            };
+            model.complete(user_id, &key, response).await
+        };
+        if let Err(e) = stored {
+            eprintln!("Failed to store the response of idempotency key {key:?}: {e}");
+        }
+        Ok(json_reply(status, &body))
+    }
+    .await;
+    match res {
+        Ok(response) => Ok(response),
+        Err(e) => {
+            let (status, body) = result_to_json::<()>(Err(e));
+            Ok(json_reply(status, &body))
+        }
+    }
+}
+
+fn replay(response: StoredResponse) -> Response {
+    let status =
+        StatusCode::from_u16(response.status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let reply = json_reply(status, &response.body);
+    reply::with_header(reply, REPLAYED_HEADER, "true").into_response()
+}
+
+fn json_reply(status: StatusCode, body: &serde_json::Value) -> Response {
+    reply::with_status(reply::json(body), status).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        let body = json!({ "price": 50.0 });
+        assert_eq!(
+            fingerprint("POST /tickets", &body),
+            fingerprint("POST /tickets", &body)
+        );
+        assert_ne!(
+            fingerprint("POST /tickets", &body),
+            fingerprint("POST /tickets", &json!({ "price": 60.0 }))
+        );
+        assert_ne!(
+            fingerprint("POST /tickets", &body),
+            fingerprint("POST /orders", &body)
+        );
+    }
+}
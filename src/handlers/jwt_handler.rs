@@ -1,63 +1,527 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, fs, path::Path};
 
-use hmac::{Hmac, Mac};
-use jwt::{SignWithKey, VerifyWithKey};
-use sha2::Sha256;
+use chrono::Utc;
+use jwt::{
+    header::HeaderType, Header, SignWithKey, SigningAlgorithm, Token, Unverified, VerifyWithKey,
+};
+use serde::{Deserialize, Deserializer};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
 
-use crate::domain::{
-    errors::{Error, Result},
-    types::JwtClaims,
+use crate::{
+    domain::{
+        errors::{Error, Result},
+        types::JwtClaims,
+    },
+    handlers::jwt_keys::{Jwks, JwtKey},
+    Cfg,
 };
 
+/// Signs and verifies the access tokens.
+///
+/// Tokens are signed by the key selected with `set_signing_key` and carry its id in the
+/// `kid` header. Every key known to the handler verifies tokens, so rotating keys is done by:
+/// 1. adding the new key, which is published in the JWKS but not used yet,
+/// 2. signing with the new key once the other services picked it up,
+/// 3. retiring the old key once the tokens it signed expired.
+///
+/// Tokens without a `kid` are verified with the HS256 `JWT_SECRET`, which keeps the tokens
+/// issued before the keys were introduced valid. Those without an `iss` predate the typed
+/// claims, see `LegacyClaims`: they are accepted until they expire, as long as they were
+/// issued before the legacy cutoff and for no longer than `LEGACY_TOKEN_TTL`.
 pub struct JwtHandler {
-    key: Hmac<Sha256>,
+    legacy_key: JwtKey,
+    keys: BTreeMap<String, JwtKey>,
+    signing_kid: Option<String>,
+    issuer: String,
+    audience: String,
+    /// Clock skew tolerated when checking `exp` and `nbf`, in seconds
+    leeway: i64,
+    /// The legacy tokens must be issued before this timestamp, None when they are refused
+    legacy_cutoff: Option<i64>,
+}
+
+pub const DEFAULT_ISSUER: &str = "iomentum-backend";
+pub const DEFAULT_AUDIENCE: &str = "iomentum-backend";
+pub const DEFAULT_LEEWAY: i64 = 30;
+/// Lifetime of the tokens issued before the claims were typed, in seconds
+pub const LEGACY_TOKEN_TTL: i64 = 24 * 60 * 60;
+
+/// Claims of the HS256 tokens issued before the claims were typed. Every value was written
+/// as a string, and they carry no `iss`, `aud` nor `nbf`
+#[derive(Deserialize)]
+struct LegacyClaims {
+    sub: String,
+    user_id: Uuid,
+    role: String,
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    exp: i64,
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    iat: i64,
+    jti: Option<Uuid>,
+}
+
+/// The legacy timestamps are strings, parsed as numbers as well to be lenient
+fn deserialize_timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Timestamp {
+        Number(i64),
+        String(String),
+    }
+
+    match Timestamp::deserialize(deserializer).map_err(serde::de::Error::custom)? {
+        Timestamp::Number(timestamp) => Ok(timestamp),
+        Timestamp::String(timestamp) => timestamp.parse().map_err(serde::de::Error::custom),
+    }
 }
 
 impl JwtHandler {
+    /// Create a handler signing and verifying with the HS256 secret only.
+    /// The legacy tokens are accepted if they were issued before the handler was created,
+    /// since it never issues any
     pub fn new(secret: String) -> Result<Self> {
         Ok(Self {
-            key: Hmac::new_from_slice(secret.as_bytes())
-                .expect("Failed to create HMAC from secret:"),
+            legacy_key: JwtKey::hs256(&secret)?,
+            keys: BTreeMap::new(),
+            signing_kid: None,
+            issuer: DEFAULT_ISSUER.to_string(),
+            audience: DEFAULT_AUDIENCE.to_string(),
+            leeway: DEFAULT_LEEWAY,
+            legacy_cutoff: Some(Utc::now().timestamp()),
         })
     }
 
-    pub fn generate_token(&self, claims: JwtClaims) -> Result<String> {
-        claims
-            .to_tree_map()
-            .sign_with_key(&self.key)
-            .map_err(|e| Error::InternalError(format!("Failed to sign token: {}", e)))
+    /// Create a handler from the configuration.
+    ///
+    /// Every `<kid>.pem` file of `JWT_KEYS_DIR` is loaded as a private key and every
+    /// `<kid>.pub.pem` file as the public key of a retired key that still verifies tokens.
+    /// `JWT_SIGNING_KEY_ID` selects the key signing new tokens.
+    ///
+    /// `JWT_ISSUER`, `JWT_AUDIENCE` and `JWT_LEEWAY` override the defaults of the checks
+    /// made by `validate_token`. `JWT_LEGACY_CUTOFF` moves the date before which the legacy
+    /// tokens must be issued, and `JWT_ACCEPT_LEGACY_TOKENS=false` refuses them all.
+    pub fn from_config(config: &Cfg) -> Result<Self> {
+        let mut handler = Self::new(config.jwt_secret.clone())?;
+        if let Some(issuer) = &config.jwt_issuer {
+            handler.issuer = issuer.clone();
+        }
+        if let Some(audience) = &config.jwt_audience {
+            handler.audience = audience.clone();
+        }
+        if let Some(leeway) = config.jwt_leeway {
+            handler.leeway = leeway;
+        }
+        if let Some(cutoff) = config.jwt_legacy_cutoff {
+            handler.legacy_cutoff = Some(cutoff);
+        }
+        if !config.jwt_accept_legacy_tokens {
+            handler.legacy_cutoff = None;
+        }
+        if let Some(keys_dir) = &config.jwt_keys_dir {
+            handler.load_keys(keys_dir)?;
+        }
+        if let Some(kid) = &config.jwt_signing_key_id {
+            handler.set_signing_key(kid)?;
+        }
+        Ok(handler)
+    }
+
+    fn load_keys(&mut self, keys_dir: impl AsRef<Path>) -> Result<()> {
+        let entries = fs::read_dir(keys_dir)
+            .map_err(|e| Error::InternalError(format!("Cannot read the JWT keys: {e}")))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| Error::InternalError(format!("Cannot read the JWT keys: {e}")))?
+                .path();
+            let Some(file_name) = path.file_name().and_then(|f| f.to_str()) else {
+                continue;
+            };
+            let (kid, public) = if let Some(kid) = file_name.strip_suffix(".pub.pem") {
+                (kid.to_string(), true)
+            } else if let Some(kid) = file_name.strip_suffix(".pem") {
+                (kid.to_string(), false)
+            } else {
+                continue;
+            };
+            let pem = fs::read_to_string(&path)
+                .map_err(|e| Error::InternalError(format!("Cannot read {file_name}: {e}")))?;
+            let key = if public {
+                JwtKey::rs256_from_public_pem(&pem)?
+            } else {
+                JwtKey::rs256_from_private_pem(&pem)?
+            };
+            self.add_key(kid, key);
+        }
+        Ok(())
+    }
+
+    pub fn add_key(&mut self, kid: String, key: JwtKey) {
+        self.keys.insert(kid, key);
+    }
+
+    /// Stop verifying the tokens signed by a key
+    pub fn retire_key(&mut self, kid: &str) -> Result<()> {
+        if self.signing_kid.as_deref() == Some(kid) {
+            return Err(Error::InternalError(format!(
+                "Cannot retire {kid}, it is signing the tokens"
+            )));
+        }
+        self.keys.remove(kid);
+        Ok(())
+    }
+
+    pub fn set_signing_key(&mut self, kid: &str) -> Result<()> {
+        match self.keys.get(kid) {
+            Some(key) if key.can_sign() => {
+                self.signing_kid = Some(kid.to_string());
+                Ok(())
+            }
+            Some(_) => Err(Error::InternalError(format!(
+                "The JWT key {kid} has no private key"
+            ))),
+            None => Err(Error::InternalError(format!("Unknown JWT key {kid}"))),
+        }
+    }
+
+    /// The public keys other services can use to verify our tokens
+    pub fn jwks(&self) -> Jwks {
+        Jwks {
+            keys: self
+                .keys
+                .iter()
+                .filter_map(|(kid, key)| key.to_jwk(kid))
+                .collect(),
+        }
     }
 
+    /// Sign the claims, stamping them with our issuer and audience
+    pub fn generate_token(&self, mut claims: JwtClaims) -> Result<String> {
+        claims.iss = self.issuer.clone();
+        claims.aud = vec![self.audience.clone()];
+        let token = match &self.signing_kid {
+            Some(kid) => {
+                let key = &self.keys[kid];
+                let header = Header {
+                    algorithm: key.algorithm_type(),
+                    key_id: Some(kid.clone()),
+                    type_: Some(HeaderType::JsonWebToken),
+                    ..Default::default()
+                };
+                Token::new(header, claims)
+                    .sign_with_key(key)
+                    .map(|token| token.as_str().to_string())
+            }
+            None => claims.sign_with_key(&self.legacy_key),
+        };
+        token.map_err(|e| Error::InternalError(format!("Failed to sign token: {}", e)))
+    }
+
+    /// Check the signature of a token and decode its claims, without validating them
     pub fn decode_token(&self, token: &str) -> Result<JwtClaims> {
-        let tree: BTreeMap<String, String> = token
-            .verify_with_key(&self.key)
-            .map_err(|e| Error::LoginFailed(format!("Couldn't decode JWT: {e}")))?;
-        tree.try_into()
+        let unverified: Token<Header, serde_json::Value, Unverified> =
+            Token::parse_unverified(token).map_err(|e| Error::MalformedToken(e.to_string()))?;
+        let legacy =
+            unverified.header().key_id.is_none() && unverified.claims().get("iss").is_none();
+        let key = match &unverified.header().key_id {
+            Some(kid) => self
+                .keys
+                .get(kid)
+                .ok_or_else(|| Error::UnknownSigningKey(kid.clone()))?,
+            None => &self.legacy_key,
+        };
+        // The algorithm of the header must match the one of the key, so an RSA public key
+        // can never be used as an HMAC secret
+        let verified: Token<Header, serde_json::Value, _> =
+            unverified.verify_with_key(key).map_err(|e| match e {
+                jwt::Error::InvalidSignature | jwt::Error::AlgorithmMismatch(_, _) => {
+                    Error::InvalidTokenSignature
+                }
+                e => Error::MalformedToken(e.to_string()),
+            })?;
+        let (_, claims) = verified.into();
+        if legacy {
+            let claims =
+                serde_json::from_value(claims).map_err(|e| Error::MalformedToken(e.to_string()))?;
+            return self.upgrade_legacy_claims(claims, token);
+        }
+        serde_json::from_value(claims).map_err(|e| Error::MalformedToken(e.to_string()))
+    }
+
+    /// Fill in the claims a legacy token lacks, so it passes the checks until it expires.
+    /// Its `jti`, missing on the oldest tokens, is derived from the token to revoke it.
+    /// Without an issuer to check, only the tokens we could have issued are accepted:
+    /// those issued before the cutoff, for no longer than the legacy lifetime
+    fn upgrade_legacy_claims(&self, claims: LegacyClaims, token: &str) -> Result<JwtClaims> {
+        match self.legacy_cutoff {
+            Some(cutoff) if claims.iat < cutoff && claims.exp - claims.iat <= LEGACY_TOKEN_TTL => {}
+            _ => return Err(Error::InvalidTokenIssuer),
+        }
+        let jti = claims.jti.unwrap_or_else(|| {
+            let digest = Sha256::digest(token.as_bytes());
+            Uuid::from_slice(&digest[..16]).unwrap_or_default()
+        });
+        Ok(JwtClaims {
+            iss: self.issuer.clone(),
+            aud: vec![self.audience.clone()],
+            sub: claims.sub,
+            user_id: claims.user_id,
+            role: claims.role,
+            exp: claims.exp,
+            nbf: claims.iat,
+            iat: claims.iat,
+            jti,
+            amr: vec!["pwd".to_string()],
+            api_key_id: None,
+            scopes: None,
+        })
+    }
+
+    /// Decode a token sent by a client and make sure it is meant for us and still valid
+    pub fn validate_token(&self, token: &str) -> Result<JwtClaims> {
+        let claims = self.decode_token(token)?;
+        self.validate_claims(&claims, Utc::now().timestamp())?;
+        Ok(claims)
+    }
+
+    fn validate_claims(&self, claims: &JwtClaims, now: i64) -> Result<()> {
+        if claims.iss != self.issuer {
+            return Err(Error::InvalidTokenIssuer);
+        }
+        if !claims.aud.contains(&self.audience) {
+            return Err(Error::InvalidTokenAudience);
+        }
+        if claims.exp + self.leeway <= now {
+            return Err(Error::TokenExpired);
+        }
+        if claims.nbf - self.leeway > now {
+            return Err(Error::TokenNotYetValid);
+        }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Utc;
+    use chrono::{Duration, Utc};
     use uuid::Uuid;
 
+    const SIGNING_KEY: &str = include_str!("../../tests/fixtures/jwt_keys/2026-10-signing.pem");
+    const RETIRED_KEY: &str =
+        include_str!("../../tests/fixtures/jwt_keys/private/2026-04-retired.pem");
+
+    fn claims() -> JwtClaims {
+        JwtClaims::new(
+            "username".to_string(),
+            Uuid::new_v4(),
+            "role".to_string(),
+            Duration::days(1),
+        )
+    }
+
     #[test]
     fn test_generate_token() {
         let jwt_handler = JwtHandler::new("secret".to_string()).unwrap();
-        let claims = JwtClaims::new("username".to_string(), Uuid::new_v4(), "role".to_string());
-        let token = jwt_handler.generate_token(claims).unwrap();
+        let token = jwt_handler.generate_token(claims()).unwrap();
         assert!(!token.is_empty());
     }
 
     #[test]
     fn test_decode_token() {
         let jwt_handler = JwtHandler::new("secret".to_string()).unwrap();
-        let claims = JwtClaims::new("username".to_string(), Uuid::new_v4(), "role".to_string());
-        let token = jwt_handler.generate_token(claims).unwrap();
+        let token = jwt_handler.generate_token(claims()).unwrap();
         let decoded_claims = jwt_handler.decode_token(&token).unwrap();
         assert_eq!(decoded_claims.sub, "username");
         assert_eq!(decoded_claims.role, "role");
         assert!(decoded_claims.exp > Utc::now().timestamp());
     }
+
+    #[test]
+    fn test_validate_token_rejects_expired_token() {
+        let jwt_handler = JwtHandler::new("secret".to_string()).unwrap();
+        let mut claims = claims();
+        claims.exp = Utc::now().timestamp() - 60;
+        let token = jwt_handler.generate_token(claims).unwrap();
+        assert!(matches!(
+            jwt_handler.validate_token(&token),
+            Err(Error::TokenExpired)
+        ));
+    }
+
+    #[test]
+    fn test_validate_token_applies_leeway() {
+        let jwt_handler = JwtHandler::new("secret".to_string()).unwrap();
+        let now = Utc::now().timestamp();
+        let mut claims = claims();
+        claims.iss = DEFAULT_ISSUER.to_string();
+        claims.aud = vec![DEFAULT_AUDIENCE.to_string()];
+
+        claims.exp = now - DEFAULT_LEEWAY / 2;
+        assert!(jwt_handler.validate_claims(&claims, now).is_ok());
+        claims.exp = now - DEFAULT_LEEWAY;
+        assert!(jwt_handler.validate_claims(&claims, now).is_err());
+
+        claims.exp = now + 60;
+        claims.nbf = now + DEFAULT_LEEWAY / 2;
+        assert!(jwt_handler.validate_claims(&claims, now).is_ok());
+        claims.nbf = now + DEFAULT_LEEWAY + 1;
+        assert!(matches!(
+            jwt_handler.validate_claims(&claims, now),
+            Err(Error::TokenNotYetValid)
+        ));
+    }
+
+    #[test]
+    fn test_validate_token_checks_issuer_and_audience() {
+        let jwt_handler = JwtHandler::new("secret".to_string()).unwrap();
+        let mut other_handler = JwtHandler::new("secret".to_string()).unwrap();
+        other_handler.issuer = "other issuer".to_string();
+        let token = other_handler.generate_token(claims()).unwrap();
+        assert!(matches!(
+            jwt_handler.validate_token(&token),
+            Err(Error::InvalidTokenIssuer)
+        ));
+
+        let mut other_handler = JwtHandler::new("secret".to_string()).unwrap();
+        other_handler.audience = "other audience".to_string();
+        let token = other_handler.generate_token(claims()).unwrap();
+        assert!(matches!(
+            jwt_handler.validate_token(&token),
+            Err(Error::InvalidTokenAudience)
+        ));
+    }
+
+    /// A token as signed before the claims were typed, every value being a string
+    fn legacy_token(jwt_handler: &JwtHandler, iat: i64, exp: i64, jti: Option<Uuid>) -> String {
+        let mut claims = BTreeMap::new();
+        claims.insert("sub", "username".to_string());
+        claims.insert("user_id", Uuid::new_v4().to_string());
+        claims.insert("role", "user".to_string());
+        claims.insert("exp", exp.to_string());
+        claims.insert("iat", iat.to_string());
+        if let Some(jti) = jti {
+            claims.insert("jti", jti.to_string());
+        }
+        claims.sign_with_key(&jwt_handler.legacy_key).unwrap()
+    }
+
+    #[test]
+    fn test_legacy_tokens_are_valid_until_they_expire() {
+        let jwt_handler = JwtHandler::new("secret".to_string()).unwrap();
+        let jti = Uuid::new_v4();
+        let iat = Utc::now().timestamp() - 60;
+        let exp = iat + 120;
+        let claims = jwt_handler
+            .validate_token(&legacy_token(&jwt_handler, iat, exp, Some(jti)))
+            .unwrap();
+        assert_eq!("username", claims.sub);
+        assert_eq!(exp, claims.exp);
+        assert_eq!(jti, claims.jti);
+
+        // The oldest tokens have no jti, each one still gets its own
+        let token = legacy_token(&jwt_handler, iat, exp, None);
+        let jti = jwt_handler.validate_token(&token).unwrap().jti;
+        assert_eq!(jti, jwt_handler.validate_token(&token).unwrap().jti);
+        assert_ne!(
+            jti,
+            jwt_handler
+                .validate_token(&legacy_token(&jwt_handler, iat - 1, exp, None))
+                .unwrap()
+                .jti
+        );
+
+        let expired = legacy_token(&jwt_handler, iat - 120, iat, None);
+        assert!(matches!(
+            jwt_handler.validate_token(&expired),
+            Err(Error::TokenExpired)
+        ));
+    }
+
+    #[test]
+    fn test_legacy_tokens_are_bounded() {
+        let mut jwt_handler = JwtHandler::new("secret".to_string()).unwrap();
+        let cutoff = Utc::now().timestamp() - 60;
+        jwt_handler.legacy_cutoff = Some(cutoff);
+
+        let before = legacy_token(&jwt_handler, cutoff - 1, cutoff + 60, None);
+        assert!(jwt_handler.validate_token(&before).is_ok());
+        // Issued after the cutoff, it cannot come from us
+        let after = legacy_token(&jwt_handler, cutoff, cutoff + 60, None);
+        assert!(matches!(
+            jwt_handler.validate_token(&after),
+            Err(Error::InvalidTokenIssuer)
+        ));
+        let long_lived = legacy_token(
+            &jwt_handler,
+            cutoff - 1,
+            cutoff - 1 + LEGACY_TOKEN_TTL + 1,
+            None,
+        );
+        assert!(matches!(
+            jwt_handler.validate_token(&long_lived),
+            Err(Error::InvalidTokenIssuer)
+        ));
+
+        jwt_handler.legacy_cutoff = None;
+        assert!(matches!(
+            jwt_handler.validate_token(&before),
+            Err(Error::InvalidTokenIssuer)
+        ));
+    }
+
+    #[test]
+    fn test_validate_token_rejects_other_secret() {
+        let jwt_handler = JwtHandler::new("secret".to_string()).unwrap();
+        let other_handler = JwtHandler::new("other secret".to_string()).unwrap();
+        let token = other_handler.generate_token(claims()).unwrap();
+        assert!(matches!(
+            jwt_handler.validate_token(&token),
+            Err(Error::InvalidTokenSignature)
+        ));
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let mut jwt_handler = JwtHandler::new("secret".to_string()).unwrap();
+        let legacy_token = jwt_handler.generate_token(claims()).unwrap();
+
+        jwt_handler.add_key(
+            "old".to_string(),
+            JwtKey::rs256_from_private_pem(RETIRED_KEY).unwrap(),
+        );
+        jwt_handler.set_signing_key("old").unwrap();
+        let old_token = jwt_handler.generate_token(claims()).unwrap();
+
+        jwt_handler.add_key(
+            "new".to_string(),
+            JwtKey::rs256_from_private_pem(SIGNING_KEY).unwrap(),
+        );
+        jwt_handler.set_signing_key("new").unwrap();
+        let new_token = jwt_handler.generate_token(claims()).unwrap();
+        assert_eq!(jwt_handler.jwks().keys.len(), 2);
+
+        // Every key still verifies the tokens it signed
+        assert!(jwt_handler.validate_token(&legacy_token).is_ok());
+        assert!(jwt_handler.validate_token(&old_token).is_ok());
+        assert!(jwt_handler.validate_token(&new_token).is_ok());
+
+        assert!(jwt_handler.retire_key("new").is_err());
+        jwt_handler.retire_key("old").unwrap();
+        assert!(jwt_handler.validate_token(&old_token).is_err());
+        assert!(jwt_handler.validate_token(&new_token).is_ok());
+        assert_eq!(jwt_handler.jwks().keys.len(), 1);
+    }
+
+    #[test]
+    fn test_public_key_cannot_become_signing_key() {
+        let mut jwt_handler = JwtHandler::new("secret".to_string()).unwrap();
+        let public_key = include_str!("../../tests/fixtures/jwt_keys/2026-04-retired.pub.pem");
+        jwt_handler.add_key(
+            "retired".to_string(),
+            JwtKey::rs256_from_public_pem(public_key).unwrap(),
+        );
+        assert!(jwt_handler.set_signing_key("retired").is_err());
+        assert!(jwt_handler.set_signing_key("unknown").is_err());
+    }
 }
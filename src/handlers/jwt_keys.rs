@@ -0,0 +1,180 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use jwt::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+use rsa::{
+    pkcs1v15,
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+    signature::{SignatureEncoding, Signer, Verifier},
+    traits::PublicKeyParts,
+    RsaPrivateKey, RsaPublicKey,
+};
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::domain::errors::{Error, Result};
+
+/// A key able to verify access tokens, and to sign them when the private part is known
+pub enum JwtKey {
+    Hs256(Hmac<Sha256>),
+    Rs256(Box<RsaKey>),
+}
+
+pub struct RsaKey {
+    public_key: RsaPublicKey,
+    verifying_key: pkcs1v15::VerifyingKey<Sha256>,
+    /// Retired keys only keep their public part
+    signing_key: Option<pkcs1v15::SigningKey<Sha256>>,
+}
+
+impl JwtKey {
+    pub fn hs256(secret: &str) -> Result<Self> {
+        Hmac::new_from_slice(secret.as_bytes())
+            .map(JwtKey::Hs256)
+            .map_err(|e| Error::InternalError(format!("Invalid HMAC secret: {e}")))
+    }
+
+    /// Load an RSA private key from a PKCS#8 PEM document
+    pub fn rs256_from_private_pem(pem: &str) -> Result<Self> {
+        let private_key = RsaPrivateKey::from_pkcs8_pem(pem)
+            .map_err(|e| Error::InternalError(format!("Invalid RSA private key: {e}")))?;
+        let public_key = private_key.to_public_key();
+        Ok(JwtKey::Rs256(Box::new(RsaKey {
+            verifying_key: pkcs1v15::VerifyingKey::new(public_key.clone()),
+            public_key,
+            signing_key: Some(pkcs1v15::SigningKey::new(private_key)),
+        })))
+    }
+
+    /// Load an RSA public key from a SPKI PEM document, the key can only verify tokens
+    pub fn rs256_from_public_pem(pem: &str) -> Result<Self> {
+        let public_key = RsaPublicKey::from_public_key_pem(pem)
+            .map_err(|e| Error::InternalError(format!("Invalid RSA public key: {e}")))?;
+        Ok(JwtKey::Rs256(Box::new(RsaKey {
+            verifying_key: pkcs1v15::VerifyingKey::new(public_key.clone()),
+            public_key,
+            signing_key: None,
+        })))
+    }
+
+    pub fn can_sign(&self) -> bool {
+        match self {
+            JwtKey::Hs256(_) => true,
+            JwtKey::Rs256(key) => key.signing_key.is_some(),
+        }
+    }
+
+    /// The public description of the key, symmetric keys are never published
+    pub fn to_jwk(&self, kid: &str) -> Option<Jwk> {
+        match self {
+            JwtKey::Hs256(_) => None,
+            JwtKey::Rs256(key) => Some(Jwk {
+                kty: "RSA",
+                use_: "sig",
+                alg: "RS256",
+                kid: kid.to_string(),
+                n: URL_SAFE_NO_PAD.encode(key.public_key.n().to_bytes_be()),
+                e: URL_SAFE_NO_PAD.encode(key.public_key.e().to_bytes_be()),
+            }),
+        }
+    }
+}
+
+impl SigningAlgorithm for JwtKey {
+    fn algorithm_type(&self) -> AlgorithmType {
+        match self {
+            JwtKey::Hs256(_) => AlgorithmType::Hs256,
+            JwtKey::Rs256(_) => AlgorithmType::Rs256,
+        }
+    }
+
+    fn sign(&self, header: &str, claims: &str) -> std::result::Result<String, jwt::Error> {
+        match self {
+            JwtKey::Hs256(key) => key.sign(header, claims),
+            JwtKey::Rs256(key) => {
+                let signing_key = key.signing_key.as_ref().ok_or(jwt::Error::Format)?;
+                let signature = signing_key
+                    .try_sign(format!("{header}.{claims}").as_bytes())
+                    .map_err(|_| jwt::Error::Format)?;
+                Ok(URL_SAFE_NO_PAD.encode(signature.to_bytes()))
+            }
+        }
+    }
+}
+
+impl VerifyingAlgorithm for JwtKey {
+    fn algorithm_type(&self) -> AlgorithmType {
+        SigningAlgorithm::algorithm_type(self)
+    }
+
+    fn verify_bytes(
+        &self,
+        header: &str,
+        claims: &str,
+        signature: &[u8],
+    ) -> std::result::Result<bool, jwt::Error> {
+        match self {
+            // The HMAC implementation reports a mismatch as an error, not as `false`
+            JwtKey::Hs256(key) => Ok(key.verify_bytes(header, claims, signature).is_ok()),
+            JwtKey::Rs256(key) => {
+                let Ok(signature) = pkcs1v15::Signature::try_from(signature) else {
+                    return Ok(false);
+                };
+                Ok(key
+                    .verifying_key
+                    .verify(format!("{header}.{claims}").as_bytes(), &signature)
+                    .is_ok())
+            }
+        }
+    }
+}
+
+/// A public key as described by RFC 7517
+#[derive(Serialize, Debug)]
+pub struct Jwk {
+    pub kty: &'static str,
+    #[serde(rename = "use")]
+    pub use_: &'static str,
+    pub alg: &'static str,
+    pub kid: String,
+    pub n: String,
+    pub e: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Jwks {
+    pub keys: Vec<Jwk>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIVATE_KEY: &str = include_str!("../../tests/fixtures/jwt_keys/2026-10-signing.pem");
+    const PUBLIC_KEY: &str = include_str!("../../tests/fixtures/jwt_keys/2026-04-retired.pub.pem");
+
+    #[test]
+    fn test_rs256_sign_and_verify() {
+        let key = JwtKey::rs256_from_private_pem(PRIVATE_KEY).unwrap();
+        let signature = key.sign("header", "claims").unwrap();
+        assert!(key.verify("header", "claims", &signature).unwrap());
+        assert!(!key.verify("header", "other claims", &signature).unwrap());
+    }
+
+    #[test]
+    fn test_public_key_cannot_sign() {
+        let key = JwtKey::rs256_from_public_pem(PUBLIC_KEY).unwrap();
+        assert!(!key.can_sign());
+        assert!(key.sign("header", "claims").is_err());
+    }
+
+    #[test]
+    fn test_only_rsa_keys_are_published() {
+        let rsa_key = JwtKey::rs256_from_public_pem(PUBLIC_KEY).unwrap();
+        let jwk = rsa_key.to_jwk("kid").unwrap();
+        assert_eq!(jwk.kty, "RSA");
+        assert_eq!(jwk.e, "AQAB");
+
+        let hmac_key = JwtKey::hs256("secret").unwrap();
+        assert!(hmac_key.to_jwk("kid").is_none());
+    }
+}
@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        dtos::lockout_dtos::LockoutDto,
+        policy,
+        types::{login_attempt_types::AttemptKind, JwtClaims},
+    },
+    handlers::errors::result_to_warp_reply,
+    AppState,
+};
+
+type ReplyRes<T> = Result<T, Rejection>;
+
+pub async fn get_lockouts(claims: JwtClaims, app_state: Arc<AppState>) -> ReplyRes<impl Reply> {
+    let lockouts = async {
+        policy::authorize_lockout_management(&claims)?;
+        let lockouts = app_state.login_throttle.get_lockouts().await?;
+        Ok(lockouts
+            .into_iter()
+            .map(|l| l.into())
+            .collect::<Vec<LockoutDto>>())
+    }
+    .await;
+    result_to_warp_reply(lockouts)
+}
+
+pub async fn clear_lockout(
+    kind: String,
+    identifier: String,
+    claims: JwtClaims,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = async {
+        policy::authorize_lockout_management(&claims)?;
+        let kind = AttemptKind::try_from(kind.as_str())?;
+        app_state.login_throttle.clear(kind, &identifier).await
+    }
+    .await;
+    result_to_warp_reply(res)
+}
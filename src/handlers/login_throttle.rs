@@ -0,0 +1,293 @@
+use std::net::IpAddr;
+
+use chrono::{Duration, Utc};
+
+use crate::{
+    domain::{
+        errors::{Error, Result},
+        types::{
+            login_attempt_types::{AttemptKind, LoginAttempts},
+            Username,
+        },
+    },
+    models::login_attempts::LoginAttemptsModel,
+    Cfg,
+};
+
+/// Slows down password guessing on `/login`.
+///
+/// Failures are counted per username and per client address. Past the free attempts,
+/// every failure locks the login out for a delay doubling each time, up to `max_lockout`.
+/// The count starts over once `window` elapsed without a failure.
+///
+/// An attempt is counted as a failure before the password is verified, and forgiven if it
+/// was right. Counting afterwards would let a burst of parallel guesses in before the first
+/// one locks the login out.
+pub struct LoginThrottle {
+    model: Box<dyn LoginAttemptsModel>,
+    account_free_attempts: i32,
+    ip_free_attempts: i32,
+    base_delay: Duration,
+    max_lockout: Duration,
+    window: Duration,
+}
+
+impl LoginThrottle {
+    pub fn new(model: Box<dyn LoginAttemptsModel>, config: &Cfg) -> Self {
+        Self {
+            model,
+            account_free_attempts: config.login_account_free_attempts,
+            ip_free_attempts: config.login_ip_free_attempts,
+            base_delay: Duration::seconds(config.login_backoff_base),
+            max_lockout: Duration::seconds(config.login_max_lockout),
+            window: Duration::seconds(config.login_attempts_window),
+        }
+    }
+
+    /// Count an attempt to log in, failing if the client address or the username is
+    /// locked out. The address goes first, so a locked out client cannot keep counting
+    /// failures against the accounts
+    pub async fn attempt(&self, username: &str, ip: Option<IpAddr>) -> Result<()> {
+        let now = Utc::now();
+        for (kind, identifier) in Self::identifiers(username, ip) {
+            let lockout = |failures| self.lockout_delay(kind, failures);
+            let locked_until = self
+                .model
+                .record_attempt(kind, &identifier, now, now - self.window, &lockout)
+                .await?;
+            if let Some(locked_until) = locked_until {
+                // Rounded up so a client waiting that long is let in
+                let retry_in = ((locked_until - now).num_milliseconds() + 999) / 1000;
+                return Err(Error::TooManyLoginAttempts(retry_in));
+            }
+        }
+        Ok(())
+    }
+
+    /// Take back an attempt that turned out right, without forgetting the previous failures
+    pub async fn forgive(&self, username: &str, ip: Option<IpAddr>) -> Result<()> {
+        for (kind, identifier) in Self::identifiers(username, ip) {
+            self.model.forgive(kind, &identifier).await?;
+        }
+        Ok(())
+    }
+
+    /// The account starts over after a successful login, the address does not so a
+    /// single valid account cannot be used to keep guessing the others
+    pub async fn record_success(&self, username: &str, ip: Option<IpAddr>) -> Result<()> {
+        if let Some(ip) = ip {
+            self.model.forgive(AttemptKind::Ip, &ip.to_string()).await?;
+        }
+        self.model
+            .clear(AttemptKind::Account, &Username::comparison_key(username))
+            .await
+    }
+
+    pub async fn get_lockouts(&self) -> Result<Vec<LoginAttempts>> {
+        self.model.get_lockouts().await
+    }
+
+    pub async fn clear(&self, kind: AttemptKind, identifier: &str) -> Result<()> {
+        match kind {
+            AttemptKind::Account => {
+                self.model
+                    .clear(kind, &Username::comparison_key(identifier))
+                    .await
+            }
+            AttemptKind::Ip => self.model.clear(kind, identifier).await,
+        }
+    }
+
+    /// Forget the failures older than the window, returns the number of entries removed
+    pub async fn purge_stale(&self) -> Result<u64> {
+        self.model.purge_stale(Utc::now() - self.window).await
+    }
+
+    fn identifiers(username: &str, ip: Option<IpAddr>) -> Vec<(AttemptKind, String)> {
+        let mut identifiers: Vec<_> = ip
+            .map(|ip| (AttemptKind::Ip, ip.to_string()))
+            .into_iter()
+            .collect();
+        identifiers.push((AttemptKind::Account, Username::comparison_key(username)));
+        identifiers
+    }
+
+    fn lockout_delay(&self, kind: AttemptKind, failures: i32) -> Duration {
+        let free_attempts = match kind {
+            AttemptKind::Account => self.account_free_attempts,
+            AttemptKind::Ip => self.ip_free_attempts,
+        };
+        if failures < free_attempts {
+            return Duration::zero();
+        }
+        // Capped before shifting, the delay reaches the maximum long before anyway
+        let exponent = (failures - free_attempts).min(30) as u32;
+        (self.base_delay * 2_i32.pow(exponent)).min(self.max_lockout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use chrono::DateTime;
+
+    use crate::models::login_attempts::LockoutDelay;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct InMemoryLoginAttempts {
+        attempts: Mutex<Vec<LoginAttempts>>,
+    }
+
+    #[async_trait]
+    impl LoginAttemptsModel for InMemoryLoginAttempts {
+        async fn record_attempt(
+            &self,
+            kind: AttemptKind,
+            identifier: &str,
+            now: DateTime<Utc>,
+            window_start: DateTime<Utc>,
+            lockout: &LockoutDelay<'_>,
+        ) -> Result<Option<DateTime<Utc>>> {
+            let mut attempts = self.attempts.lock().unwrap();
+            let position = attempts
+                .iter()
+                .position(|a| a.kind == kind && a.identifier == identifier);
+            let attempt = match position {
+                Some(i) => &mut attempts[i],
+                None => {
+                    attempts.push(LoginAttempts {
+                        kind,
+                        identifier: identifier.to_string(),
+                        failures: 0,
+                        last_failure_at: now,
+                        locked_until: None,
+                    });
+                    attempts.last_mut().unwrap()
+                }
+            };
+            if let Some(locked_until) = attempt.locked_until.filter(|until| *until > now) {
+                return Ok(Some(locked_until));
+            }
+            attempt.failures = if attempt.last_failure_at < window_start {
+                1
+            } else {
+                attempt.failures + 1
+            };
+            attempt.last_failure_at = now;
+            let delay = lockout(attempt.failures);
+            if delay > Duration::zero() {
+                attempt.locked_until = Some(now + delay);
+            }
+            Ok(None)
+        }
+
+        async fn forgive(&self, kind: AttemptKind, identifier: &str) -> Result<()> {
+            let mut attempts = self.attempts.lock().unwrap();
+            if let Some(a) = attempts
+                .iter_mut()
+                .find(|a| a.kind == kind && a.identifier == identifier)
+            {
+                a.failures = (a.failures - 1).max(0);
+                a.locked_until = None;
+            }
+            Ok(())
+        }
+
+        async fn clear(&self, kind: AttemptKind, identifier: &str) -> Result<()> {
+            self.attempts
+                .lock()
+                .unwrap()
+                .retain(|a| a.kind != kind || a.identifier != identifier);
+            Ok(())
+        }
+
+        async fn get_lockouts(&self) -> Result<Vec<LoginAttempts>> {
+            Ok(vec![])
+        }
+
+        async fn purge_stale(&self, _window_start: DateTime<Utc>) -> Result<u64> {
+            Ok(0)
+        }
+    }
+
+    fn throttle() -> LoginThrottle {
+        LoginThrottle {
+            model: Box::new(InMemoryLoginAttempts::default()),
+            account_free_attempts: 3,
+            ip_free_attempts: 5,
+            base_delay: Duration::seconds(1),
+            max_lockout: Duration::minutes(15),
+            window: Duration::hours(1),
+        }
+    }
+
+    #[test]
+    fn test_lockout_delay_doubles_up_to_the_maximum() {
+        let throttle = throttle();
+        assert_eq!(
+            Duration::zero(),
+            throttle.lockout_delay(AttemptKind::Account, 2)
+        );
+        assert_eq!(
+            Duration::seconds(1),
+            throttle.lockout_delay(AttemptKind::Account, 3)
+        );
+        assert_eq!(
+            Duration::seconds(4),
+            throttle.lockout_delay(AttemptKind::Account, 5)
+        );
+        assert_eq!(Duration::zero(), throttle.lockout_delay(AttemptKind::Ip, 4));
+        assert_eq!(
+            Duration::minutes(15),
+            throttle.lockout_delay(AttemptKind::Account, 100)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_account_is_locked_after_the_free_attempts() {
+        let throttle = throttle();
+        let ip = Some("127.0.0.1".parse().unwrap());
+        for _ in 0..3 {
+            assert!(throttle.attempt("username", ip).await.is_ok());
+        }
+        assert!(matches!(
+            throttle.attempt("username", ip).await,
+            Err(Error::TooManyLoginAttempts(1))
+        ));
+        // The address is not locked yet, so other accounts can still log in
+        assert!(throttle.attempt("other", ip).await.is_ok());
+
+        throttle.record_success("username", ip).await.unwrap();
+        assert!(throttle.attempt("username", ip).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_address_is_locked_for_every_account() {
+        let throttle = throttle();
+        let ip = Some("127.0.0.1".parse().unwrap());
+        for i in 0..5 {
+            throttle.attempt(&format!("user{i}"), ip).await.unwrap();
+        }
+        assert!(throttle.attempt("other", ip).await.is_err());
+        assert!(throttle.attempt("other", None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_right_attempts_are_forgiven() {
+        let throttle = throttle();
+        let ip = Some("127.0.0.1".parse().unwrap());
+        throttle.attempt("username", ip).await.unwrap();
+        throttle.attempt("username", ip).await.unwrap();
+        // The third attempt would lock the account, unless it was right
+        for _ in 0..5 {
+            throttle.attempt("username", ip).await.unwrap();
+            throttle.forgive("username", ip).await.unwrap();
+        }
+        throttle.attempt("Username", ip).await.unwrap();
+        assert!(throttle.attempt("username", ip).await.is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use std::{net::IpAddr, sync::Arc};
+
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        dtos::mfa_dtos::{
+            MfaChallengeDto, MfaCodeDto, MfaConfirmDto, MfaEnrollmentDto, MfaLoginDto,
+            RecoveryCodesDto,
+        },
+        errors::{Error, Result},
+        policy,
+        types::{
+            mfa_types::{NewMfaChallenge, UserMfa},
+            JwtClaims,
+        },
+    },
+    handlers::{errors::result_to_warp_reply, opaque_token, totp},
+    AppState,
+};
+
+use super::tokens::issue_token_pair;
+
+type ReplyRes<T> = std::result::Result<T, Rejection>;
+
+/// Wrong codes allowed on a challenge before the login has to start over. The wrong
+/// codes are also counted against the account by the login throttle, so starting over
+/// does not give more guesses
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+
+/// Generate a new secret for the caller, it is only used once confirmed
+pub async fn enroll(claims: JwtClaims, app_state: Arc<AppState>) -> ReplyRes<impl Reply> {
+    let enrollment = async {
+        policy::authorize_credential_management(&claims)?;
+        if let Some(mfa) = app_state.mfa_model.get_mfa(claims.user_id).await? {
+            if mfa.is_enabled() {
+                return Err(Error::MfaAlreadyEnabled);
+            }
+        }
+        let secret = totp::generate_secret();
+        app_state
+            .mfa_model
+            .start_enrollment(claims.user_id, secret.clone())
+            .await?;
+        let otpauth_uri = totp::otpauth_uri(&secret, &app_state.totp_issuer, &claims.sub)?;
+        Ok(MfaEnrollmentDto {
+            secret,
+            otpauth_uri,
+        })
+    }
+    .await;
+    result_to_warp_reply(enrollment)
+}
+
+/// Enable the 2FA once the caller proved their authenticator works
+pub async fn confirm(
+    claims: JwtClaims,
+    input: MfaConfirmDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let recovery_codes = async {
+        policy::authorize_credential_management(&claims)?;
+        let mfa = match app_state.mfa_model.get_mfa(claims.user_id).await? {
+            Some(mfa) if mfa.is_enabled() => return Err(Error::MfaAlreadyEnabled),
+            Some(mfa) => mfa,
+            None => return Err(Error::MfaNotEnabled),
+        };
+        let step = totp::verify_code(&mfa.secret, &input.code, app_state.clock.now())?
+            .ok_or(Error::InvalidMfaCode)?;
+        let recovery_codes = totp::generate_recovery_codes();
+        let recovery_code_hashes = recovery_codes
+            .iter()
+            .map(|code| opaque_token::hash(&totp::normalize_recovery_code(code)))
+            .collect();
+        app_state
+            .mfa_model
+            .enable_mfa(claims.user_id, step, recovery_code_hashes)
+            .await?;
+        Ok(RecoveryCodesDto { recovery_codes })
+    }
+    .await;
+    result_to_warp_reply(recovery_codes)
+}
+
+pub async fn disable(
+    claims: JwtClaims,
+    input: MfaCodeDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = async {
+        policy::authorize_credential_management(&claims)?;
+        let mfa = get_enabled_mfa(&app_state, claims.user_id)
+            .await?
+            .ok_or(Error::MfaNotEnabled)?;
+        if !verify_second_factor(&app_state, &mfa, &input).await? {
+            return Err(Error::InvalidMfaCode);
+        }
+        app_state.mfa_model.disable_mfa(claims.user_id).await
+    }
+    .await;
+    result_to_warp_reply(res)
+}
+
+/// Second step of the login, trade the challenge token and a code for the tokens
+pub async fn login_mfa(
+    input: MfaLoginDto,
+    client_ip: Option<IpAddr>,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let token_pair = async {
+        let challenge = app_state
+            .mfa_model
+            .get_challenge(opaque_token::hash(&input.challenge_token))
+            .await?
+            .filter(|c| c.is_usable(app_state.clock.now(), MAX_CHALLENGE_ATTEMPTS))
+            .ok_or(Error::InvalidMfaChallenge)?;
+        let user = app_state.user_model.get_user(challenge.user_id).await?;
+        let username = user.username.as_ref();
+        app_state
+            .login_throttle
+            .attempt(username, client_ip)
+            .await?;
+        let mfa = get_enabled_mfa(&app_state, challenge.user_id)
+            .await?
+            .ok_or(Error::InvalidMfaChallenge)?;
+        if !verify_second_factor(&app_state, &mfa, &input.code).await? {
+            app_state
+                .mfa_model
+                .record_challenge_failure(challenge.id)
+                .await?;
+            return Err(Error::InvalidMfaCode);
+        }
+        if !app_state.mfa_model.complete_challenge(challenge.id).await? {
+            return Err(Error::InvalidMfaChallenge);
+        }
+        app_state
+            .login_throttle
+            .record_success(username, client_ip)
+            .await?;
+        issue_token_pair(&app_state, &user.username, user.id, &user.role, true).await
+    }
+    .await;
+    result_to_warp_reply(token_pair)
+}
+
+/// Issue a challenge if the user enabled the 2FA, called once the password is verified
+pub async fn start_challenge(
+    app_state: &AppState,
+    user_id: Uuid,
+) -> Result<Option<MfaChallengeDto>> {
+    if get_enabled_mfa(app_state, user_id).await?.is_none() {
+        return Ok(None);
+    }
+    let challenge_token = opaque_token::generate();
+    app_state
+        .mfa_model
+        .create_challenge(NewMfaChallenge {
+            user_id,
+            token_hash: opaque_token::hash(&challenge_token),
+            expires_at: app_state.clock.now() + app_state.mfa_challenge_ttl,
+        })
+        .await?;
+    Ok(Some(MfaChallengeDto {
+        mfa_required: true,
+        challenge_token,
+        expires_in: app_state.mfa_challenge_ttl.num_seconds(),
+    }))
+}
+
+async fn get_enabled_mfa(app_state: &AppState, user_id: Uuid) -> Result<Option<UserMfa>> {
+    Ok(app_state
+        .mfa_model
+        .get_mfa(user_id)
+        .await?
+        .filter(|mfa| mfa.is_enabled()))
+}
+
+/// Check a TOTP code, refusing a step already used, or use up a recovery code
+async fn verify_second_factor(
+    app_state: &AppState,
+    mfa: &UserMfa,
+    input: &MfaCodeDto,
+) -> Result<bool> {
+    match (&input.code, &input.recovery_code) {
+        (Some(code), _) => match totp::verify_code(&mfa.secret, code, app_state.clock.now())? {
+            Some(step) => app_state.mfa_model.use_totp_step(mfa.user_id, step).await,
+            None => Ok(false),
+        },
+        (None, Some(recovery_code)) => {
+            let code_hash = opaque_token::hash(&totp::normalize_recovery_code(recovery_code));
+            app_state
+                .mfa_model
+                .use_recovery_code(mfa.user_id, code_hash)
+                .await
+        }
+        (None, None) => Ok(false),
+    }
+}
@@ -1,5 +1,23 @@
+pub mod api_keys;
+pub mod categories;
+pub mod concerts;
+pub mod emails;
 pub mod errors;
+pub mod holds;
+pub mod idempotency;
 pub mod jwt_handler;
+pub mod jwt_keys;
+pub mod lockouts;
+pub mod login_throttle;
+pub mod mfa;
+pub mod opaque_token;
+pub mod orders;
 pub mod password_hasher;
+pub mod passwords;
+pub mod revocation_list;
+pub mod search;
 pub mod tickets;
+pub mod tokens;
+pub mod totp;
 pub mod users;
+pub mod venues;
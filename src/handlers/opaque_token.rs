@@ -0,0 +1,40 @@
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// Generate a random token meant to be handed out to a client.
+/// Only its hash should ever be stored
+pub fn generate() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    to_hex(&bytes)
+}
+
+/// Hash a token so it can be stored and looked up.
+/// The tokens are random and long enough that a fast hash is sufficient
+pub fn hash(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let token = generate();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, generate());
+    }
+
+    #[test]
+    fn test_hash() {
+        let token = generate();
+        assert_eq!(hash(&token), hash(&token));
+        assert_ne!(hash(&token), token);
+        assert_ne!(hash(&token), hash(&generate()));
+    }
+}
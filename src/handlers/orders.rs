@@ -0,0 +1,200 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        dtos::{
+            order_dtos::{OrderDto, OrderInputDto, OrderListQueryDto},
+            page_dtos::PageDto,
+        },
+        errors::Error,
+        policy::{self, Action},
+        types::{
+            order_types::{NewOrder, OrderItem},
+            JwtClaims,
+        },
+    },
+    AppState,
+};
+
+use super::{
+    errors::{result_to_warp_reply, tagged_result_to_warp_reply},
+    idempotency, opaque_token,
+    tickets::check_seat,
+};
+
+type ReplyRes<T> = Result<T, Rejection>;
+
+pub async fn get_order_by_id(
+    id: Uuid,
+    claims: JwtClaims,
+    if_none_match: Option<String>,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let order = async {
+        let order = app_state.order_model.get_order(id).await?;
+        policy::authorize_order(&claims, Action::Read, &order)?;
+        let version = order.version;
+        Ok((OrderDto::from(order), version))
+    }
+    .await;
+    tagged_result_to_warp_reply(order, if_none_match)
+}
+
+/// The order history of a user, the latest orders last unless sorted otherwise
+pub async fn get_user_orders(
+    user_id: Uuid,
+    claims: JwtClaims,
+    query: OrderListQueryDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let orders = async {
+        policy::authorize_user(&claims, Action::Read, user_id)?;
+        let page = app_state
+            .order_model
+            .query_orders(query.into_query(user_id)?)
+            .await?;
+        Ok(PageDto::<OrderDto>::from(page))
+    }
+    .await;
+    result_to_warp_reply(orders)
+}
+
+/// The order history of the caller, found from their token
+pub async fn get_my_orders(
+    claims: JwtClaims,
+    query: OrderListQueryDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    get_user_orders(claims.user_id, claims, query, app_state).await
+}
+
+/// Place an order for the caller, priced with the current prices of the categories.
+/// A retry sent with the same `Idempotency-Key` gets the first response back
+pub async fn create_order(
+    claims: JwtClaims,
+    idempotency_key: Option<String>,
+    order: OrderInputDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let request_hash = idempotency::fingerprint("POST /orders", &order);
+    let order_id = async {
+        let user = app_state.user_model.get_user(claims.user_id).await?;
+        policy::authorize_ticket_recipient(&user)?;
+        let items = match (order.hold_id, order.items.is_empty()) {
+            (Some(hold_id), true) => {
+                let hold = app_state.hold_model.get_hold(hold_id).await?;
+                policy::authorize_checkout(&claims, &hold)?;
+                if hold.is_expired(app_state.clock.now()) {
+                    return Err(Error::HoldExpired);
+                }
+                let category = app_state
+                    .category_model
+                    .get_category(hold.category_id)
+                    .await?;
+                vec![OrderItem::new(
+                    hold.concert_id,
+                    hold.category_id,
+                    Some(hold.quantity),
+                    hold.seat_ids,
+                    category.price,
+                )?]
+            }
+            (None, false) => {
+                let mut items = vec![];
+                for item in order.items {
+                    let category = app_state
+                        .category_model
+                        .get_category(item.category_id)
+                        .await?;
+                    for seat_id in &item.seat_ids {
+                        check_seat(&app_state, category.concert_id, *seat_id).await?;
+                    }
+                    items.push(OrderItem::new(
+                        category.concert_id,
+                        category.id,
+                        item.quantity,
+                        item.seat_ids,
+                        category.price,
+                    )?);
+                }
+                items
+            }
+            _ => {
+                return Err(Error::InvalidOrder(
+                    "either items or a hold are ordered".to_string(),
+                ))
+            }
+        };
+        let order = NewOrder::new(claims.user_id, order.hold_id, items)?;
+        app_state.order_model.create_order(order).await
+    };
+    idempotency::idempotent(
+        &app_state,
+        claims.user_id,
+        idempotency_key,
+        request_hash,
+        order_id,
+    )
+    .await
+}
+
+/// Confirm the payment of the order, its tickets are issued to the buyer.
+/// Returns the ids of the tickets
+pub async fn pay_order(
+    id: Uuid,
+    claims: JwtClaims,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let ticket_ids = async {
+        policy::authorize_payment(&claims)?;
+        let order = app_state.order_model.get_order(id).await?;
+        let count: i32 = order.items.iter().map(|item| item.quantity).sum();
+        let barcodes = (0..count).map(|_| opaque_token::generate()).collect();
+        app_state
+            .order_model
+            .pay_order(id, barcodes, app_state.clock.now())
+            .await
+    }
+    .await;
+    result_to_warp_reply(ticket_ids)
+}
+
+/// Cancel a pending order, its hold is released
+pub async fn cancel_order(
+    id: Uuid,
+    claims: JwtClaims,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = async {
+        let order = app_state.order_model.get_order(id).await?;
+        policy::authorize_order(&claims, Action::Update, &order)?;
+        let version = app_state
+            .order_model
+            .cancel_order(id, app_state.clock.now())
+            .await?;
+        Ok((id, version))
+    }
+    .await;
+    tagged_result_to_warp_reply(res, None)
+}
+
+/// Refund a paid order, its tickets are voided
+pub async fn refund_order(
+    id: Uuid,
+    claims: JwtClaims,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = async {
+        policy::authorize_refund(&claims)?;
+        let version = app_state
+            .order_model
+            .refund_order(id, app_state.clock.now())
+            .await?;
+        Ok((id, version))
+    }
+    .await;
+    tagged_result_to_warp_reply(res, None)
+}
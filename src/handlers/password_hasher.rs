@@ -1,45 +1,204 @@
 use argon2::{
-    password_hash::SaltString, Argon2, PasswordHash, PasswordHasher as ArgonPasswordHasher,
-    PasswordVerifier,
+    password_hash::SaltString, Algorithm, Argon2, Params, PasswordHash,
+    PasswordHasher as ArgonPasswordHasher, PasswordVerifier, Version,
 };
 use rand_core::OsRng;
+use secrecy::{ExposeSecret, SecretString};
 
-pub fn hash_password(password: &str) -> Result<String, String> {
-    let salt = SaltString::generate(&mut OsRng);
-
-    let password_hash = Argon2::default()
-        .hash_password(password.as_bytes(), &salt)
-        .map_err(|_| {
-            "Failed to hash password, please try again or contact support if the problem persists"
-                .to_string()
-        })?
-        .to_string();
-    Ok(password_hash)
+use crate::{
+    domain::errors::{Error, Result},
+    Cfg,
+};
+
+/// Hashes the passwords with the Argon2 parameters of the configuration.
+///
+/// Hashing is slow on purpose, the `_blocking` methods run it on the blocking pool
+/// so a login does not hold up the other requests.
+#[derive(Clone)]
+pub struct PasswordHasher {
+    algorithm: Algorithm,
+    params: Params,
+    /// Secret mixed in every hash, it is not stored in the database
+    pepper: Option<SecretString>,
+    /// Verified when the user does not exist, so an unknown username takes as long as a wrong password
+    dummy_hash: String,
+}
+
+impl Default for PasswordHasher {
+    /// The recommended parameters of the argon2 crate, without pepper
+    fn default() -> Self {
+        Self::new(Algorithm::default(), Params::default(), None)
+            .expect("cannot create the default password hasher")
+    }
 }
 
-pub fn verify(password_sent: &str, db_hash: &str) -> bool {
-    PasswordHash::new(db_hash).map_or(false, |parsed_hash| {
-        Argon2::default()
-            .verify_password(password_sent.as_bytes(), &parsed_hash)
-            .is_ok()
-    })
+impl PasswordHasher {
+    pub fn new(algorithm: Algorithm, params: Params, pepper: Option<SecretString>) -> Result<Self> {
+        let mut hasher = Self {
+            algorithm,
+            params,
+            pepper,
+            dummy_hash: String::new(),
+        };
+        hasher.dummy_hash = hasher.hash("dummy password")?;
+        Ok(hasher)
+    }
+
+    pub fn from_config(config: &Cfg) -> Result<Self> {
+        let algorithm = config
+            .password_hash_algorithm
+            .parse::<Algorithm>()
+            .map_err(|e| Error::InternalError(format!("Invalid password hash algorithm: {e}")))?;
+        let params = Params::new(
+            config.password_hash_m_cost,
+            config.password_hash_t_cost,
+            config.password_hash_p_cost,
+            None,
+        )
+        .map_err(|e| Error::InternalError(format!("Invalid password hash parameters: {e}")))?;
+        let pepper = config.password_pepper.clone().map(SecretString::from);
+        Self::new(algorithm, params, pepper)
+    }
+
+    pub fn hash(&self, password: &str) -> Result<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        let password_hash = self
+            .argon2()?
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| Error::PasswordHashingFailed(e.to_string()))?
+            .to_string();
+        Ok(password_hash)
+    }
+
+    pub fn verify(&self, password_sent: &str, db_hash: &str) -> bool {
+        let Ok(argon2) = self.argon2() else {
+            return false;
+        };
+        PasswordHash::new(db_hash).is_ok_and(|parsed_hash| {
+            argon2
+                .verify_password(password_sent.as_bytes(), &parsed_hash)
+                .is_ok()
+        })
+    }
+
+    /// Whether the hash was made with other parameters than the current ones.
+    /// A change of pepper cannot be detected, the previous hashes no longer verify
+    pub fn needs_rehash(&self, db_hash: &str) -> bool {
+        let Ok(parsed_hash) = PasswordHash::new(db_hash) else {
+            return true;
+        };
+        let same_params = Params::try_from(&parsed_hash).is_ok_and(|params| {
+            params.m_cost() == self.params.m_cost()
+                && params.t_cost() == self.params.t_cost()
+                && params.p_cost() == self.params.p_cost()
+        });
+        let same_version = parsed_hash
+            .version
+            .is_some_and(|v| v == u32::from(Version::default()));
+        parsed_hash.algorithm != self.algorithm.ident() || !same_version || !same_params
+    }
+
+    pub async fn hash_blocking(&self, password: String) -> Result<String> {
+        let hasher = self.clone();
+        tokio::task::spawn_blocking(move || hasher.hash(&password))
+            .await
+            .map_err(|e| Error::PasswordHashingFailed(e.to_string()))?
+    }
+
+    pub async fn verify_blocking(&self, password_sent: String, db_hash: String) -> bool {
+        let hasher = self.clone();
+        tokio::task::spawn_blocking(move || hasher.verify(&password_sent, &db_hash))
+            .await
+            .unwrap_or(false)
+    }
+
+    /// Spend the time of a verification without a real hash to compare with
+    pub async fn dummy_verify_blocking(&self, password_sent: String) {
+        self.verify_blocking(password_sent, self.dummy_hash.clone())
+            .await;
+    }
+
+    fn argon2(&self) -> Result<Argon2<'_>> {
+        match &self.pepper {
+            Some(pepper) => Argon2::new_with_secret(
+                pepper.expose_secret().as_bytes(),
+                self.algorithm,
+                Version::default(),
+                self.params.clone(),
+            )
+            .map_err(|e| Error::PasswordHashingFailed(e.to_string())),
+            None => Ok(Argon2::new(
+                self.algorithm,
+                Version::default(),
+                self.params.clone(),
+            )),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Cheap parameters, the tests do not need a strong hash
+    fn hasher(m_cost: u32, pepper: Option<&str>) -> PasswordHasher {
+        PasswordHasher::new(
+            Algorithm::Argon2id,
+            Params::new(m_cost, 1, 1, None).unwrap(),
+            pepper.map(|p| SecretString::from(p.to_string())),
+        )
+        .unwrap()
+    }
+
     #[test]
     fn test_hash_password() {
         let password = "password";
-        let hashed_password = hash_password(password).unwrap();
+        let hashed_password = hasher(1024, None).hash(password).unwrap();
         assert_ne!(password, hashed_password);
+        assert!(hashed_password.contains("m=1024,t=1,p=1"));
     }
 
     #[test]
     fn test_verify() {
-        let password = "password";
-        let hashed_password = hash_password(password).unwrap();
-        assert!(verify(password, &hashed_password));
+        let hasher = hasher(1024, None);
+        let hashed_password = hasher.hash("password").unwrap();
+        assert!(hasher.verify("password", &hashed_password));
+        assert!(!hasher.verify("other", &hashed_password));
+        assert!(!hasher.verify("password", "not a hash"));
+    }
+
+    #[test]
+    fn test_pepper_is_required_to_verify() {
+        let peppered = hasher(1024, Some("pepper"));
+        let hashed_password = peppered.hash("password").unwrap();
+        assert!(peppered.verify("password", &hashed_password));
+        assert!(!hasher(1024, None).verify("password", &hashed_password));
+        assert!(!hasher(1024, Some("other")).verify("password", &hashed_password));
+    }
+
+    #[test]
+    fn test_needs_rehash_when_the_parameters_change() {
+        let hashed_password = hasher(1024, None).hash("password").unwrap();
+        assert!(!hasher(1024, None).needs_rehash(&hashed_password));
+        assert!(hasher(2048, None).needs_rehash(&hashed_password));
+
+        let argon2i = PasswordHasher::new(
+            Algorithm::Argon2i,
+            Params::new(1024, 1, 1, None).unwrap(),
+            None,
+        )
+        .unwrap();
+        assert!(argon2i.needs_rehash(&hashed_password));
+    }
+
+    #[tokio::test]
+    async fn test_blocking_hash_and_verify() {
+        let hasher = hasher(1024, None);
+        let hashed_password = hasher.hash_blocking("password".to_string()).await.unwrap();
+        assert!(
+            hasher
+                .verify_blocking("password".to_string(), hashed_password)
+                .await
+        );
     }
 }
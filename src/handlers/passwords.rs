@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        dtos::user_dtos::{ChangePasswordDto, ResetPasswordDto},
+        errors::Error,
+        policy,
+        types::{password_reset_types::NewPasswordResetToken, JwtClaims, PasswordHash},
+    },
+    handlers::{errors::result_to_warp_reply, opaque_token},
+    notifiers::notifier::Notification,
+    AppState,
+};
+
+use super::tokens::{issue_token_pair, revoke_all_sessions};
+
+type ReplyRes<T> = Result<T, Rejection>;
+
+/// Change the password of the caller, the other sessions are logged out
+pub async fn change_password(
+    claims: JwtClaims,
+    input: ChangePasswordDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let token_pair = async {
+        policy::authorize_credential_management(&claims)?;
+        let user = app_state
+            .user_model
+            .get_user_internal(claims.user_id)
+            .await?;
+        let verified = app_state
+            .password_hasher
+            .verify_blocking(
+                input.current_password,
+                user.password_hash.expose_secret().to_string(),
+            )
+            .await;
+        if !verified {
+            return Err(Error::Forbidden(
+                "the current password is incorrect".to_string(),
+            ));
+        }
+        let password_hash = PasswordHash::new(
+            &input.new_password,
+            user.username.as_ref(),
+            &app_state.password_policy,
+            &app_state.password_hasher,
+        )
+        .await?;
+        app_state
+            .user_model
+            .update_password(user.id, password_hash)
+            .await?;
+        revoke_all_sessions(&app_state, user.id).await?;
+        // The caller gets a fresh session instead of being logged out as well
+        issue_token_pair(
+            &app_state,
+            &user.username,
+            user.id,
+            &user.role,
+            claims.has_mfa(),
+        )
+        .await
+    }
+    .await;
+    result_to_warp_reply(token_pair)
+}
+
+/// Issue a one-time reset token and deliver it to the user through the notifier
+pub async fn request_password_reset(
+    id: uuid::Uuid,
+    claims: JwtClaims,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = async {
+        policy::authorize_password_reset(&claims)?;
+        let user = app_state.user_model.get_user(id).await?;
+        let email = user.email.ok_or(Error::MissingEmail)?;
+        let token = opaque_token::generate();
+        let expires_at = Utc::now() + app_state.password_reset_ttl;
+        app_state
+            .password_reset_model
+            .create_reset_token(NewPasswordResetToken {
+                user_id: user.id,
+                token_hash: opaque_token::hash(&token),
+                expires_at,
+            })
+            .await?;
+        app_state
+            .notifier
+            .send(Notification {
+                recipient: email.as_ref().to_string(),
+                subject: "Password reset".to_string(),
+                body: format!(
+                    "Use this token to choose a new password: {token}\nIt expires at {expires_at}."
+                ),
+            })
+            .await
+    }
+    .await;
+    result_to_warp_reply(res)
+}
+
+/// Redeem a reset token, every session of the user is logged out
+pub async fn reset_password(
+    input: ResetPasswordDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = async {
+        let token_hash = opaque_token::hash(&input.token);
+        let token = app_state
+            .password_reset_model
+            .find_reset_token(token_hash.clone())
+            .await?;
+        // Validated before the token is consumed so a rejected password does not burn it
+        let user = app_state.user_model.get_user(token.user_id).await?;
+        let password_hash = PasswordHash::new(
+            &input.new_password,
+            user.username.as_ref(),
+            &app_state.password_policy,
+            &app_state.password_hasher,
+        )
+        .await?;
+        let token = app_state
+            .password_reset_model
+            .consume_reset_token(token_hash)
+            .await?;
+        app_state
+            .user_model
+            .update_password(token.user_id, password_hash)
+            .await?;
+        revoke_all_sessions(&app_state, token.user_id).await
+    }
+    .await;
+    result_to_warp_reply(res)
+}
@@ -0,0 +1,235 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        errors::Result,
+        types::{
+            revocation_types::{RevokedSession, RevokedToken},
+            JwtClaims,
+        },
+    },
+    models::revoked_tokens::RevokedTokensModel,
+};
+
+/// Denylist of the access tokens revoked before their expiry.
+///
+/// Revocations are stored in the database and mirrored in memory so checking a token
+/// does not need a query. The cache is reloaded by `sweep`, which lets revocations made
+/// by other instances propagate.
+pub struct RevocationList {
+    model: Box<dyn RevokedTokensModel>,
+    cache: RwLock<RevocationCache>,
+}
+
+#[derive(Default)]
+struct RevocationCache {
+    /// jti -> expiry of the token
+    tokens: HashMap<Uuid, DateTime<Utc>>,
+    /// user id -> tokens issued before this date are revoked
+    sessions: HashMap<Uuid, DateTime<Utc>>,
+}
+
+impl RevocationList {
+    pub fn new(model: Box<dyn RevokedTokensModel>) -> Self {
+        Self {
+            model,
+            cache: RwLock::new(RevocationCache::default()),
+        }
+    }
+
+    pub fn is_revoked(&self, claims: &JwtClaims) -> bool {
+        let cache = self.cache.read().expect("revocation cache poisoned");
+        cache.tokens.contains_key(&claims.jti)
+            || cache
+                .sessions
+                .get(&claims.user_id)
+                .is_some_and(|revoked_before| claims.iat < revoked_before.timestamp())
+    }
+
+    /// The `iat` of a token of the user issued at `now`, never before the revocation of
+    /// their sessions
+    pub fn issued_at(&self, user_id: Uuid, now: i64) -> i64 {
+        let cache = self.cache.read().expect("revocation cache poisoned");
+        cache
+            .sessions
+            .get(&user_id)
+            .map_or(now, |revoked_before| now.max(revoked_before.timestamp()))
+    }
+
+    pub async fn revoke_token(&self, claims: &JwtClaims) -> Result<()> {
+        let expires_at = claims.expires_at();
+        self.model
+            .revoke_token(RevokedToken {
+                jti: claims.jti,
+                user_id: claims.user_id,
+                expires_at,
+            })
+            .await?;
+        self.cache
+            .write()
+            .expect("revocation cache poisoned")
+            .tokens
+            .insert(claims.jti, expires_at);
+        Ok(())
+    }
+
+    /// Revoke every access token issued to the user so far.
+    /// `token_ttl` is the longest lifetime of the accepted tokens, after which the entry
+    /// is useless
+    pub async fn revoke_sessions(&self, user_id: Uuid, token_ttl: Duration) -> Result<()> {
+        // `iat` has a precision of one second, the cutoff is rounded up so the tokens issued
+        // earlier in the current second are revoked too. The ones issued afterwards are
+        // dated from the cutoff by `issued_at`, so logging in right after a revocation works
+        let now = Utc::now();
+        let revoked_before = DateTime::from_timestamp(now.timestamp() + 1, 0).unwrap_or(now);
+        self.model
+            .revoke_sessions(RevokedSession {
+                user_id,
+                revoked_before,
+                expires_at: revoked_before + token_ttl,
+            })
+            .await?;
+        self.cache
+            .write()
+            .expect("revocation cache poisoned")
+            .sessions
+            .insert(user_id, revoked_before);
+        Ok(())
+    }
+
+    /// Purge the expired revocations and reload the cache from the database
+    pub async fn sweep(&self) -> Result<u64> {
+        let purged = self.model.purge_expired().await?;
+        self.reload().await?;
+        Ok(purged)
+    }
+
+    pub async fn reload(&self) -> Result<()> {
+        let tokens = self.model.get_revoked_tokens().await?;
+        let sessions = self.model.get_revoked_sessions().await?;
+        let cache = RevocationCache {
+            tokens: tokens.into_iter().map(|t| (t.jti, t.expires_at)).collect(),
+            sessions: sessions
+                .into_iter()
+                .map(|s| (s.user_id, s.revoked_before))
+                .collect(),
+        };
+        *self.cache.write().expect("revocation cache poisoned") = cache;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct InMemoryRevokedTokens {
+        tokens: Mutex<Vec<RevokedToken>>,
+        sessions: Mutex<Vec<RevokedSession>>,
+    }
+
+    #[async_trait]
+    impl RevokedTokensModel for InMemoryRevokedTokens {
+        async fn revoke_token(&self, token: RevokedToken) -> Result<()> {
+            self.tokens.lock().unwrap().push(token);
+            Ok(())
+        }
+
+        async fn revoke_sessions(&self, session: RevokedSession) -> Result<()> {
+            let mut sessions = self.sessions.lock().unwrap();
+            sessions.retain(|s| s.user_id != session.user_id);
+            sessions.push(session);
+            Ok(())
+        }
+
+        async fn get_revoked_tokens(&self) -> Result<Vec<RevokedToken>> {
+            Ok(self
+                .tokens
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|t| t.expires_at > Utc::now())
+                .map(|t| RevokedToken { ..*t })
+                .collect())
+        }
+
+        async fn get_revoked_sessions(&self) -> Result<Vec<RevokedSession>> {
+            Ok(self
+                .sessions
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|s| s.expires_at > Utc::now())
+                .map(|s| RevokedSession { ..*s })
+                .collect())
+        }
+
+        async fn purge_expired(&self) -> Result<u64> {
+            let mut tokens = self.tokens.lock().unwrap();
+            let before = tokens.len();
+            tokens.retain(|t| t.expires_at > Utc::now());
+            Ok((before - tokens.len()) as u64)
+        }
+    }
+
+    fn claims(user_id: Uuid, ttl: Duration) -> JwtClaims {
+        JwtClaims::new("username".to_string(), user_id, "user".to_string(), ttl)
+    }
+
+    #[tokio::test]
+    async fn test_revoke_token() {
+        let list = RevocationList::new(Box::new(InMemoryRevokedTokens::default()));
+        let user_id = Uuid::new_v4();
+        let revoked = claims(user_id, Duration::minutes(15));
+        let other = claims(user_id, Duration::minutes(15));
+
+        list.revoke_token(&revoked).await.unwrap();
+        assert!(list.is_revoked(&revoked));
+        assert!(!list.is_revoked(&other));
+
+        // The revocation survives a reload from the store
+        list.reload().await.unwrap();
+        assert!(list.is_revoked(&revoked));
+    }
+
+    #[tokio::test]
+    async fn test_revoke_sessions() {
+        let list = RevocationList::new(Box::new(InMemoryRevokedTokens::default()));
+        let user_id = Uuid::new_v4();
+        let mut old_token = claims(user_id, Duration::minutes(15));
+        old_token.iat -= 60;
+        let other_user = claims(Uuid::new_v4(), Duration::minutes(15));
+
+        let same_second = claims(user_id, Duration::minutes(15));
+
+        list.revoke_sessions(user_id, Duration::minutes(15))
+            .await
+            .unwrap();
+        assert!(list.is_revoked(&old_token));
+        assert!(list.is_revoked(&same_second));
+        assert!(!list.is_revoked(&other_user));
+        // The tokens issued afterwards are dated after the revocation
+        let mut new_token = claims(user_id, Duration::minutes(15));
+        new_token.iat = list.issued_at(user_id, new_token.iat);
+        assert!(!list.is_revoked(&new_token));
+    }
+
+    #[tokio::test]
+    async fn test_sweep_purges_expired_tokens() {
+        let list = RevocationList::new(Box::new(InMemoryRevokedTokens::default()));
+        let mut expired = claims(Uuid::new_v4(), Duration::minutes(15));
+        expired.exp = Utc::now().timestamp() - 60;
+
+        list.revoke_token(&expired).await.unwrap();
+        assert_eq!(1, list.sweep().await.unwrap());
+        assert!(!list.is_revoked(&expired));
+    }
+}
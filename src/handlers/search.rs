@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        dtos::search_dtos::{SearchQueryDto, SearchResultsDto, TicketHitDto, UserHitDto},
+        errors::Error,
+        policy,
+        types::{
+            api_key_types::ScopeResource,
+            search_types::{SearchQuery, DEFAULT_LIMIT, MAX_LIMIT},
+            JwtClaims,
+        },
+    },
+    handlers::errors::result_to_warp_reply,
+    AppState,
+};
+
+type ReplyRes<T> = std::result::Result<T, Rejection>;
+
+/// Search the concerts of the tickets and the usernames. An API key only searches
+/// the resources its scopes can read
+pub async fn search(
+    claims: JwtClaims,
+    input: SearchQueryDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let results = async {
+        let query = SearchQuery::new(&input.q)?;
+        let limit = input.limit.unwrap_or(DEFAULT_LIMIT);
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(Error::InvalidSearch(format!(
+                "the limit must be between 1 and {MAX_LIMIT}"
+            )));
+        }
+        let owner = policy::search_owner(&claims);
+
+        let mut results = SearchResultsDto {
+            tickets: vec![],
+            users: vec![],
+        };
+        if policy::authorize_api_key_scope(&claims, Some(ScopeResource::Tickets), false).is_ok() {
+            let hits = app_state
+                .ticket_model
+                .search_tickets(&query, owner, limit)
+                .await?;
+            results.tickets = hits
+                .into_iter()
+                .map(|hit| TicketHitDto::new(hit, &query))
+                .collect();
+        }
+        if policy::authorize_api_key_scope(&claims, Some(ScopeResource::Users), false).is_ok() {
+            let hits = app_state
+                .user_model
+                .search_users(&query, owner, limit)
+                .await?;
+            results.users = hits
+                .into_iter()
+                .map(|hit| UserHitDto::new(hit, &query))
+                .collect();
+        }
+        Ok::<_, Error>(results)
+    }
+    .await;
+    result_to_warp_reply(results)
+}
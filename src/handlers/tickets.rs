@@ -5,43 +5,70 @@ use warp::{reject::Rejection, reply::Reply};
 
 use crate::{
     domain::{
-        dtos::ticket_dtos::{TicketDto, TicketInputDto},
+        dtos::{
+            page_dtos::PageDto,
+            ticket_dtos::{TicketDto, TicketInputDto, TicketListQueryDto, TicketPatchDto},
+        },
         errors::Error,
+        policy::{self, Action},
+        preconditions,
+        types::{
+            ticket_types::{NewTicket, TicketChanges},
+            JwtClaims,
+        },
     },
-    models::tickets::TicketsModel,
     AppState,
 };
 
-use super::errors::result_to_warp_reply;
+use super::{
+    errors::{result_to_warp_reply, tagged_result_to_warp_reply},
+    idempotency,
+};
 
 type ReplyRes<T> = Result<T, Rejection>;
 
-pub async fn get_all_tickets(app_state: Arc<AppState>) -> ReplyRes<impl Reply> {
-    let tickets = app_state.ticket_model.get_tickets().await;
-    let tickets = match tickets {
-        Ok(tickets) => Ok(tickets
-            .into_iter()
-            .map(|t| t.into())
-            .collect::<Vec<TicketDto>>()),
-        Err(e) => Err(e),
-    };
+pub async fn get_all_tickets(
+    claims: JwtClaims,
+    query: TicketListQueryDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let tickets = async {
+        policy::authorize_listing(&claims)?;
+        let page = app_state
+            .ticket_model
+            .query_tickets(query.try_into()?)
+            .await?;
+        Ok(PageDto::<TicketDto>::from(page))
+    }
+    .await;
     result_to_warp_reply(tickets)
 }
 
-pub async fn get_ticket_by_id(id: Uuid, app_state: Arc<AppState>) -> ReplyRes<impl Reply> {
-    let ticket = app_state.ticket_model.get_ticket(id).await;
-    let ticket: Result<TicketDto, Error> = match ticket {
-        Ok(tickets) => Ok(tickets.into()),
-        Err(e) => Err(e),
-    };
-    result_to_warp_reply(ticket)
+pub async fn get_ticket_by_id(
+    id: Uuid,
+    claims: JwtClaims,
+    if_none_match: Option<String>,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let ticket = async {
+        let ticket = app_state.ticket_model.get_ticket(id).await?;
+        policy::authorize_ticket(&claims, Action::Read, &ticket)?;
+        let version = ticket.version;
+        Ok((TicketDto::from(ticket), version))
+    }
+    .await;
+    tagged_result_to_warp_reply(ticket, if_none_match)
 }
 
 pub async fn get_ticket_by_user_id(
     user_id: Uuid,
+    claims: JwtClaims,
     app_state: Arc<AppState>,
 ) -> ReplyRes<impl Reply> {
-    let tickets = app_state.ticket_model.get_tickets_by_user(user_id).await;
+    let tickets = match policy::authorize_user(&claims, Action::Read, user_id) {
+        Ok(()) => app_state.ticket_model.get_tickets_by_user(user_id).await,
+        Err(e) => Err(e),
+    };
     let tickets = match tickets {
         Ok(tickets) => Ok(tickets
             .into_iter()
@@ -52,27 +79,140 @@ pub async fn get_ticket_by_user_id(
     result_to_warp_reply(tickets)
 }
 
+/// The tickets of the caller, found from their token
+pub async fn get_my_tickets(claims: JwtClaims, app_state: Arc<AppState>) -> ReplyRes<impl Reply> {
+    let tickets = app_state
+        .ticket_model
+        .get_tickets_by_user(claims.user_id)
+        .await
+        .map(|tickets| {
+            tickets
+                .into_iter()
+                .map(|t| t.into())
+                .collect::<Vec<TicketDto>>()
+        });
+    result_to_warp_reply(tickets)
+}
+
+/// Issue a ticket outside of any order, for admins only.
+/// A retry sent with the same `Idempotency-Key` gets the first response back
 pub async fn create_ticket(
+    claims: JwtClaims,
+    idempotency_key: Option<String>,
     ticket: TicketInputDto,
     app_state: Arc<AppState>,
 ) -> ReplyRes<impl Reply> {
-    // For the future: check if the user exists
-    result_to_warp_reply(app_state.ticket_model.create_ticket(ticket.into()).await)
+    let request_hash = idempotency::fingerprint("POST /tickets", &ticket);
+    let ticket_id = async {
+        policy::authorize_ticket_issuance(&claims)?;
+        let owner = app_state.user_model.get_user(ticket.owner_id).await?;
+        policy::authorize_ticket_recipient(&owner)?;
+        if let Some(seat_id) = ticket.seat_id {
+            check_seat(&app_state, ticket.concert_id, seat_id).await?;
+        }
+        app_state
+            .ticket_model
+            .create_ticket(ticket.try_into()?)
+            .await
+    };
+    idempotency::idempotent(
+        &app_state,
+        claims.user_id,
+        idempotency_key,
+        request_hash,
+        ticket_id,
+    )
+    .await
 }
 
 pub async fn update_ticket(
     id: Uuid,
+    claims: JwtClaims,
+    if_match: Option<String>,
+    patch: TicketPatchDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = modify_ticket(id, &claims, if_match, patch.into(), &app_state).await;
+    tagged_result_to_warp_reply(res, None)
+}
+
+pub async fn replace_ticket(
+    id: Uuid,
+    claims: JwtClaims,
+    if_match: Option<String>,
     ticket_input: TicketInputDto,
     app_state: Arc<AppState>,
 ) -> ReplyRes<impl Reply> {
-    let ticket_id = app_state
+    let res = match NewTicket::try_from(ticket_input) {
+        Ok(ticket) => modify_ticket(id, &claims, if_match, ticket.into(), &app_state).await,
+        Err(e) => Err(e),
+    };
+    tagged_result_to_warp_reply(res, None)
+}
+
+/// Returns the id and the new version of the ticket
+async fn modify_ticket(
+    id: Uuid,
+    claims: &JwtClaims,
+    if_match: Option<String>,
+    changes: TicketChanges,
+    app_state: &AppState,
+) -> Result<(Uuid, i32), Error> {
+    let ticket = app_state.ticket_model.get_ticket(id).await?;
+    policy::authorize_ticket(claims, Action::Update, &ticket)?;
+    preconditions::check_if_match(if_match.as_deref(), ticket.version)?;
+    policy::authorize_ticket_changes(claims, &ticket, &changes)?;
+    if let Some(owner_id) = changes.owner_id {
+        policy::authorize_ticket_owner(claims, owner_id)?;
+        if owner_id != ticket.owner_id {
+            let owner = app_state.user_model.get_user(owner_id).await?;
+            policy::authorize_ticket_recipient(&owner)?;
+        }
+    }
+    if changes.concert_id.is_some() || changes.seat_id.is_some() {
+        let concert_id = changes.concert_id.unwrap_or(ticket.concert_id);
+        if let Some(seat_id) = changes.seat_id.unwrap_or(ticket.seat_id) {
+            check_seat(app_state, concert_id, seat_id).await?;
+        }
+    }
+    let version = app_state
         .ticket_model
-        .update_ticket(id, ticket_input.into())
-        .await;
-    result_to_warp_reply(ticket_id)
+        .update_ticket(id, changes, ticket.version)
+        .await?;
+    Ok((id, version))
 }
 
-pub async fn delete_ticket(id: Uuid, app_state: Arc<AppState>) -> ReplyRes<impl Reply> {
-    let ticket_id = app_state.ticket_model.delete_ticket(id).await;
+/// A ticket can only be bound to a seat of the venue of its concert.
+/// Whether the seat is still free is left to the database
+pub(super) async fn check_seat(
+    app_state: &AppState,
+    concert_id: Uuid,
+    seat_id: Uuid,
+) -> Result<(), Error> {
+    let concert = app_state.concert_model.get_concert(concert_id).await?;
+    let seat = app_state.venue_model.get_seat(seat_id).await?;
+    if concert.venue_id == Some(seat.venue_id) {
+        Ok(())
+    } else {
+        Err(Error::SeatNotInVenue)
+    }
+}
+
+pub async fn delete_ticket(
+    id: Uuid,
+    claims: JwtClaims,
+    if_match: Option<String>,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let ticket_id = async {
+        let ticket = app_state.ticket_model.get_ticket(id).await?;
+        policy::authorize_ticket(&claims, Action::Delete, &ticket)?;
+        preconditions::check_if_match(if_match.as_deref(), ticket.version)?;
+        app_state
+            .ticket_model
+            .delete_ticket(id, ticket.version)
+            .await
+    }
+    .await;
     result_to_warp_reply(ticket_id)
 }
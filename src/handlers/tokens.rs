@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+use warp::{
+    reject::Rejection,
+    reply::{self, Reply},
+};
+
+use crate::{
+    domain::{
+        dtos::token_dtos::{LogoutInputDto, RefreshTokenInputDto, TokenPairDto},
+        errors::Result,
+        policy,
+        types::{refresh_token_types::NewRefreshToken, JwtClaims, Role, Username},
+    },
+    handlers::{errors::result_to_warp_reply, jwt_handler::LEGACY_TOKEN_TTL, opaque_token},
+    AppState,
+};
+
+type ReplyRes<T> = std::result::Result<T, Rejection>;
+
+pub async fn refresh_token(
+    input: RefreshTokenInputDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let token_pair = async {
+        let refresh_token = opaque_token::generate();
+        let rotated = app_state
+            .refresh_token_model
+            .rotate_refresh_token(
+                opaque_token::hash(&input.refresh_token),
+                opaque_token::hash(&refresh_token),
+                Utc::now() + app_state.refresh_token_ttl,
+            )
+            .await?;
+        // The user is loaded again so a role change is reflected in the new access token
+        let user = app_state.user_model.get_user(rotated.user_id).await?;
+        let access_token = generate_access_token(
+            &app_state,
+            &user.username,
+            user.id,
+            &user.role,
+            rotated.mfa_verified,
+        )?;
+        Ok(token_pair(&app_state, access_token, refresh_token))
+    }
+    .await;
+    result_to_warp_reply(token_pair)
+}
+
+pub async fn logout(
+    claims: JwtClaims,
+    input: LogoutInputDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = async {
+        // An API key is revoked through its own route
+        policy::authorize_credential_management(&claims)?;
+        app_state.revocation_list.revoke_token(&claims).await?;
+        if let Some(refresh_token) = input.refresh_token {
+            app_state
+                .refresh_token_model
+                .revoke_refresh_token_family(claims.user_id, opaque_token::hash(&refresh_token))
+                .await?;
+        }
+        Ok(())
+    }
+    .await;
+    result_to_warp_reply(res)
+}
+
+/// Publish the public keys verifying our access tokens
+pub async fn get_jwks(app_state: Arc<AppState>) -> ReplyRes<impl Reply> {
+    Ok(reply::json(&app_state.jwt_handler.jwks()))
+}
+
+/// Revoke every access and refresh token of a user, used when their account changes.
+/// The revocation is kept as long as any accepted token lives, the legacy ones included
+pub async fn revoke_all_sessions(app_state: &AppState, user_id: Uuid) -> Result<()> {
+    let longest_ttl = app_state
+        .access_token_ttl
+        .max(Duration::seconds(LEGACY_TOKEN_TTL));
+    app_state
+        .revocation_list
+        .revoke_sessions(user_id, longest_ttl)
+        .await?;
+    app_state
+        .refresh_token_model
+        .revoke_user_refresh_tokens(user_id)
+        .await
+}
+
+/// Issue an access token along with a refresh token starting a new family.
+/// `mfa_verified` tells whether the login went through two-factor authentication
+pub async fn issue_token_pair(
+    app_state: &AppState,
+    username: &Username,
+    user_id: Uuid,
+    role: &Role,
+    mfa_verified: bool,
+) -> Result<TokenPairDto> {
+    let access_token = generate_access_token(app_state, username, user_id, role, mfa_verified)?;
+    let refresh_token = opaque_token::generate();
+    app_state
+        .refresh_token_model
+        .create_refresh_token(NewRefreshToken {
+            user_id,
+            family_id: Uuid::new_v4(),
+            token_hash: opaque_token::hash(&refresh_token),
+            mfa_verified,
+            expires_at: Utc::now() + app_state.refresh_token_ttl,
+        })
+        .await?;
+    Ok(token_pair(app_state, access_token, refresh_token))
+}
+
+fn generate_access_token(
+    app_state: &AppState,
+    username: &Username,
+    user_id: Uuid,
+    role: &Role,
+    mfa_verified: bool,
+) -> Result<String> {
+    let mut claims = JwtClaims::new(
+        username.as_ref().to_string(),
+        user_id,
+        role.as_ref().to_string(),
+        app_state.access_token_ttl,
+    );
+    if mfa_verified {
+        claims = claims.with_mfa();
+    }
+    claims.iat = app_state.revocation_list.issued_at(user_id, claims.iat);
+    app_state.jwt_handler.generate_token(claims)
+}
+
+fn token_pair(app_state: &AppState, access_token: String, refresh_token: String) -> TokenPairDto {
+    TokenPairDto {
+        access_token,
+        refresh_token,
+        token_type: "Bearer".to_string(),
+        expires_in: app_state.access_token_ttl.num_seconds(),
+    }
+}
@@ -1,111 +1,343 @@
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc};
 
 use warp::{reject::Rejection, reply::Reply};
 
 use crate::{
     domain::{
-        dtos::user_dtos::{NewUserDto, UserDto, UserLoginInputDto},
+        dtos::{
+            mfa_dtos::LoginDto,
+            page_dtos::PageDto,
+            user_dtos::{
+                NewUserDto, ProfileDto, UserDto, UserInputDto, UserListQueryDto, UserLoginInputDto,
+                UserPatchDto,
+            },
+        },
         errors::Error,
-        types::JwtClaims,
+        policy::{self, Action},
+        preconditions,
+        types::{user_types::UserChanges, Email, JwtClaims, PasswordHash, Role, Username},
     },
-    handlers::errors::result_to_warp_reply,
+    handlers::errors::{result_to_warp_reply, tagged_result_to_warp_reply},
     AppState,
 };
 
-use super::password_hasher::verify;
+use super::{
+    emails::send_verification,
+    mfa::start_challenge,
+    tokens::{issue_token_pair, revoke_all_sessions},
+};
 
 type ReplyRes<T> = Result<T, Rejection>;
 
-pub async fn get_all_users(app_state: Arc<AppState>) -> ReplyRes<impl Reply> {
-    let users_res = app_state.user_model.get_users().await;
-    let users_res = match users_res {
-        Ok(users) => Ok(users
-            .into_iter()
-            .map(|u| u.into())
-            .collect::<Vec<UserDto>>()),
-        Err(e) => Err(e),
-    };
+pub async fn get_all_users(
+    claims: JwtClaims,
+    query: UserListQueryDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let users_res = async {
+        policy::authorize_listing(&claims)?;
+        let page = app_state.user_model.query_users(query.try_into()?).await?;
+        Ok(PageDto::<UserDto>::from(page))
+    }
+    .await;
     result_to_warp_reply(users_res)
 }
 
-pub async fn get_user_by_id(id: uuid::Uuid, app_state: Arc<AppState>) -> ReplyRes<impl Reply> {
-    let user = app_state.user_model.get_user(id).await;
-    let user: Result<UserDto, Error> = match user {
-        Ok(user) => Ok(user.into()),
-        Err(e) => Err(e),
-    };
-    result_to_warp_reply(user)
+pub async fn get_user_by_id(
+    id: uuid::Uuid,
+    claims: JwtClaims,
+    if_none_match: Option<String>,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let user = async {
+        policy::authorize_user(&claims, Action::Read, id)?;
+        let user = app_state.user_model.get_user(id).await?;
+        let version = user.version;
+        Ok((UserDto::from(user), version))
+    }
+    .await;
+    tagged_result_to_warp_reply(user, if_none_match)
+}
+
+/// The profile of the caller, found from their token
+pub async fn get_me(claims: JwtClaims, app_state: Arc<AppState>) -> ReplyRes<impl Reply> {
+    let profile = async {
+        let user = app_state.user_model.get_user(claims.user_id).await?;
+        let ticket_count = app_state
+            .ticket_model
+            .count_tickets_by_user(user.id)
+            .await?;
+        Ok(ProfileDto::new(user, ticket_count))
+    }
+    .await;
+    result_to_warp_reply(profile)
 }
 
 pub async fn get_user_by_username(
     username: String,
+    claims: JwtClaims,
     app_state: Arc<AppState>,
 ) -> ReplyRes<impl Reply> {
-    let user = app_state.user_model.get_user_by_username(username).await;
-    let user: Result<UserDto, Error> = match user {
-        Ok(user) => Ok(user.into()),
-        Err(e) => Err(e),
-    };
+    let user = async {
+        policy::authorize_username_lookup(&claims, &username)?;
+        let user = app_state
+            .user_model
+            .get_user_by_username(Username::normalize(&username))
+            .await?;
+        Ok(UserDto::from(user))
+    }
+    .await;
     result_to_warp_reply(user)
 }
 
 pub async fn update_user(
     id: uuid::Uuid,
-    user_input: NewUserDto,
+    claims: JwtClaims,
+    if_match: Option<String>,
+    patch: UserPatchDto,
     app_state: Arc<AppState>,
 ) -> ReplyRes<impl Reply> {
-    let user_id = match user_input.try_into() {
-        Ok(user_modifications) => {
-            app_state
-                .user_model
-                .update_user(id, user_modifications)
-                .await
-        }
+    let res = patch_user(id, &claims, if_match, patch, &app_state).await;
+    tagged_result_to_warp_reply(res, None)
+}
+
+pub async fn update_me(
+    claims: JwtClaims,
+    if_match: Option<String>,
+    patch: UserPatchDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = patch_user(claims.user_id, &claims, if_match, patch, &app_state).await;
+    tagged_result_to_warp_reply(res, None)
+}
+
+pub async fn replace_user(
+    id: uuid::Uuid,
+    claims: JwtClaims,
+    if_match: Option<String>,
+    user_input: UserInputDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = match UserChanges::try_from(user_input) {
+        Ok(changes) => modify_user(id, &claims, if_match, changes, &app_state).await,
         Err(e) => Err(e),
     };
-    result_to_warp_reply(user_id)
+    tagged_result_to_warp_reply(res, None)
+}
+
+async fn patch_user(
+    id: uuid::Uuid,
+    claims: &JwtClaims,
+    if_match: Option<String>,
+    patch: UserPatchDto,
+    app_state: &AppState,
+) -> Result<(uuid::Uuid, i32), Error> {
+    modify_user(id, claims, if_match, patch.try_into()?, app_state).await
+}
+
+/// Returns the id and the new version of the user
+async fn modify_user(
+    id: uuid::Uuid,
+    claims: &JwtClaims,
+    if_match: Option<String>,
+    changes: UserChanges,
+    app_state: &AppState,
+) -> Result<(uuid::Uuid, i32), Error> {
+    policy::authorize_user(claims, Action::Update, id)?;
+    let current_user = app_state.user_model.get_user(id).await?;
+    preconditions::check_if_match(if_match.as_deref(), current_user.version)?;
+    let role_changed = match &changes.role {
+        Some(role) => {
+            policy::authorize_role_change(claims, &current_user.role, role)?;
+            current_user.role.as_ref() != role.as_ref()
+        }
+        None => false,
+    };
+    let new_email = changes.email.clone().filter(|email| {
+        current_user
+            .email
+            .as_ref()
+            .is_none_or(|e| !e.as_ref().eq_ignore_ascii_case(email.as_ref()))
+    });
+    let version = app_state
+        .user_model
+        .update_user(id, changes, current_user.version)
+        .await?;
+    if let Some(email) = new_email {
+        notify_verification(app_state, id, &email).await;
+    }
+    // The tokens already issued still carry the previous role
+    if role_changed {
+        revoke_all_sessions(app_state, id).await?;
+    }
+    Ok((id, version))
+}
+
+pub async fn delete_user(
+    id: uuid::Uuid,
+    claims: JwtClaims,
+    if_match: Option<String>,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    result_to_warp_reply(remove_user(id, &claims, if_match, &app_state).await)
+}
+
+pub async fn delete_me(
+    claims: JwtClaims,
+    if_match: Option<String>,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    result_to_warp_reply(remove_user(claims.user_id, &claims, if_match, &app_state).await)
 }
 
-pub async fn delete_user(id: uuid::Uuid, app_state: Arc<AppState>) -> ReplyRes<impl Reply> {
-    let user_id = app_state.user_model.delete_user(id).await;
-    result_to_warp_reply(user_id)
+async fn remove_user(
+    id: uuid::Uuid,
+    claims: &JwtClaims,
+    if_match: Option<String>,
+    app_state: &AppState,
+) -> Result<(), Error> {
+    policy::authorize_user(claims, Action::Delete, id)?;
+    let user = app_state.user_model.get_user(id).await?;
+    preconditions::check_if_match(if_match.as_deref(), user.version)?;
+    app_state.user_model.delete_user(id, user.version).await?;
+    revoke_all_sessions(app_state, id).await
+}
+
+pub async fn revoke_user_sessions(
+    id: uuid::Uuid,
+    claims: JwtClaims,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = match policy::authorize_session_revocation(&claims) {
+        Ok(()) => revoke_all_sessions(&app_state, id).await,
+        Err(e) => Err(e),
+    };
+    result_to_warp_reply(res)
 }
 
 pub async fn register_user(
     user_input: NewUserDto,
     app_state: Arc<AppState>,
 ) -> ReplyRes<impl Reply> {
-    match user_input.try_into() {
-        Ok(new_user) => result_to_warp_reply(app_state.user_model.create_user(new_user).await),
-        Err(e) => result_to_warp_reply(Err(e)),
+    result_to_warp_reply(create_new_user(None, user_input, &app_state).await)
+}
+
+pub async fn create_user(
+    claims: JwtClaims,
+    user_input: NewUserDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    result_to_warp_reply(create_new_user(Some(&claims), user_input, &app_state).await)
+}
+
+async fn create_new_user(
+    claims: Option<&JwtClaims>,
+    user_input: NewUserDto,
+    app_state: &AppState,
+) -> Result<uuid::Uuid, Error> {
+    // Checked before hashing the password, a forbidden request must not cost a hash
+    policy::authorize_user_creation(claims, &Role::new(user_input.role.clone())?)?;
+    let new_user = user_input
+        .into_new_user(&app_state.password_policy, &app_state.password_hasher)
+        .await?;
+    let email = new_user.email.clone();
+    let user_id = app_state.user_model.create_user(new_user).await?;
+    notify_verification(app_state, user_id, &email).await;
+    Ok(user_id)
+}
+
+/// The account is saved either way, a failed delivery can be retried from
+/// `/users/me/email/verification`
+async fn notify_verification(app_state: &AppState, user_id: uuid::Uuid, email: &Email) {
+    if let Err(e) = send_verification(app_state, user_id, email).await {
+        eprintln!("Failed to send the email verification of {user_id}: {e}");
     }
 }
 
 pub async fn login_user(
     user_login_input: UserLoginInputDto,
+    client_ip: Option<IpAddr>,
     app_state: Arc<AppState>,
 ) -> ReplyRes<impl Reply> {
-    let res = match app_state
-        .user_model
-        .get_user_by_username_interal(user_login_input.username)
-        .await
-    {
-        Ok(db_user) => {
-            if verify(
-                &user_login_input.password,
-                db_user.password_hash.expose_secret(),
-            ) {
-                let claims = JwtClaims::new(
-                    db_user.username.as_ref().to_string(),
+    let res = async {
+        let username = Username::normalize(&user_login_input.username);
+        app_state
+            .login_throttle
+            .attempt(&username, client_ip)
+            .await?;
+        let db_user = match app_state
+            .user_model
+            .get_user_by_username_interal(username.clone())
+            .await
+        {
+            Ok(db_user) => Some(db_user),
+            Err(Error::UserNotFound) => None,
+            Err(e) => return Err(e),
+        };
+        let password = user_login_input.password;
+        let hasher = &app_state.password_hasher;
+        // Unknown users and wrong passwords must look the same, timing included
+        let authenticated = match db_user {
+            Some(db_user) => hasher
+                .verify_blocking(
+                    password.clone(),
+                    db_user.password_hash.expose_secret().to_string(),
+                )
+                .await
+                .then_some(db_user),
+            None => {
+                hasher.dummy_verify_blocking(password.clone()).await;
+                None
+            }
+        };
+        match authenticated {
+            Some(db_user) => {
+                if hasher.needs_rehash(db_user.password_hash.expose_secret()) {
+                    rehash_password(&app_state, db_user.id, &password).await;
+                }
+                // With 2FA enabled, the tokens are only issued by `/login/mfa`, and the
+                // failures of the account are only forgotten once the code is right
+                if let Some(challenge) = start_challenge(&app_state, db_user.id).await? {
+                    app_state
+                        .login_throttle
+                        .forgive(&username, client_ip)
+                        .await?;
+                    return Ok(LoginDto::MfaChallenge(challenge));
+                }
+                app_state
+                    .login_throttle
+                    .record_success(&username, client_ip)
+                    .await?;
+                let token_pair = issue_token_pair(
+                    &app_state,
+                    &db_user.username,
                     db_user.id,
-                    db_user.role.as_ref().to_string(),
-                );
-                app_state.jwt_handler.generate_token(claims)
-            } else {
-                Err(Error::InvalidPassword("Wrong password".to_string()))
+                    &db_user.role,
+                    false,
+                )
+                .await?;
+                Ok(LoginDto::Tokens(token_pair))
             }
+            // The failure was counted with the attempt
+            None => Err(Error::InvalidCredentials),
         }
-        Err(e) => Err(e),
-    };
+    }
+    .await;
     result_to_warp_reply(res)
 }
+
+/// Upgrade a hash made with outdated parameters. A failure does not prevent the login,
+/// the password is rehashed on the next one
+async fn rehash_password(app_state: &AppState, user_id: uuid::Uuid, password: &str) {
+    let res = async {
+        let password_hash = PasswordHash::rehash(password, &app_state.password_hasher).await?;
+        app_state
+            .user_model
+            .update_password(user_id, password_hash)
+            .await
+    }
+    .await;
+    if let Err(e) = res {
+        eprintln!("Failed to rehash the password of {user_id}: {e}");
+    }
+}
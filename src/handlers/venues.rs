@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        dtos::{
+            page_dtos::PageDto,
+            venue_dtos::{
+                SeatingChartDto, VenueDetailsDto, VenueDto, VenueInputDto, VenueListQueryDto,
+                VenuePatchDto,
+            },
+        },
+        policy, preconditions,
+        types::{
+            venue_types::{NewVenue, SeatingChart, VenueChanges},
+            JwtClaims,
+        },
+    },
+    AppState,
+};
+
+use super::errors::{result_to_warp_reply, tagged_result_to_warp_reply};
+
+type ReplyRes<T> = Result<T, Rejection>;
+
+pub async fn get_all_venues(
+    _claims: JwtClaims,
+    query: VenueListQueryDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let venues = async {
+        let page = app_state
+            .venue_model
+            .query_venues(query.try_into()?)
+            .await?;
+        Ok(PageDto::<VenueDto>::from(page))
+    }
+    .await;
+    result_to_warp_reply(venues)
+}
+
+/// The venue along with its seating chart
+pub async fn get_venue_by_id(
+    id: Uuid,
+    _claims: JwtClaims,
+    if_none_match: Option<String>,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let venue = async {
+        let venue = app_state.venue_model.get_venue(id).await?;
+        let seats = app_state.venue_model.get_seats(id).await?;
+        let version = venue.version;
+        Ok((VenueDetailsDto::from((venue, seats)), version))
+    }
+    .await;
+    tagged_result_to_warp_reply(venue, if_none_match)
+}
+
+pub async fn create_venue(
+    claims: JwtClaims,
+    venue: VenueInputDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let venue_id = async {
+        policy::authorize_venue_management(&claims)?;
+        let venue: NewVenue = venue.try_into()?;
+        app_state.venue_model.create_venue(venue).await
+    }
+    .await;
+    result_to_warp_reply(venue_id)
+}
+
+pub async fn update_venue(
+    id: Uuid,
+    claims: JwtClaims,
+    if_match: Option<String>,
+    patch: VenuePatchDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = async {
+        policy::authorize_venue_management(&claims)?;
+        let changes: VenueChanges = patch.try_into()?;
+        let venue = app_state.venue_model.get_venue(id).await?;
+        preconditions::check_if_match(if_match.as_deref(), venue.version)?;
+        let version = app_state
+            .venue_model
+            .update_venue(id, changes, venue.version)
+            .await?;
+        Ok((id, version))
+    }
+    .await;
+    tagged_result_to_warp_reply(res, None)
+}
+
+/// Import a seating chart file, it replaces every seat of the venue.
+/// Refused while tickets are bound to the current seats
+pub async fn replace_seating_chart(
+    id: Uuid,
+    claims: JwtClaims,
+    if_match: Option<String>,
+    chart: SeatingChartDto,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let res = async {
+        policy::authorize_venue_management(&claims)?;
+        let chart: SeatingChart = chart.try_into()?;
+        let venue = app_state.venue_model.get_venue(id).await?;
+        preconditions::check_if_match(if_match.as_deref(), venue.version)?;
+        let version = app_state
+            .venue_model
+            .replace_seating_chart(id, chart, venue.version)
+            .await?;
+        Ok((id, version))
+    }
+    .await;
+    tagged_result_to_warp_reply(res, None)
+}
+
+/// A venue can only be deleted once no concert takes place there
+pub async fn delete_venue(
+    id: Uuid,
+    claims: JwtClaims,
+    if_match: Option<String>,
+    app_state: Arc<AppState>,
+) -> ReplyRes<impl Reply> {
+    let venue_id = async {
+        policy::authorize_venue_management(&claims)?;
+        let venue = app_state.venue_model.get_venue(id).await?;
+        preconditions::check_if_match(if_match.as_deref(), venue.version)?;
+        app_state.venue_model.delete_venue(id, venue.version).await
+    }
+    .await;
+    result_to_warp_reply(venue_id)
+}
@@ -1,9 +1,12 @@
+pub mod clock;
 pub mod config;
 pub mod domain;
 pub mod handlers;
 pub mod models;
+pub mod notifiers;
 pub mod routes;
 pub mod state;
+pub mod tasks;
 
 pub use config::Cfg;
 pub use state::AppState;
@@ -1,8 +1,17 @@
 use std::sync::Arc;
 
 use iomentum_backend_practice::{
-    models::{pg_tickets::PgTicketsModel, pg_users::PgUsersModel},
+    models::{
+        pg_api_keys::PgApiKeysModel, pg_categories::PgCategoriesModel,
+        pg_concerts::PgConcertsModel, pg_email_verifications::PgEmailVerificationsModel,
+        pg_holds::PgHoldsModel, pg_idempotency_keys::PgIdempotencyKeysModel,
+        pg_login_attempts::PgLoginAttemptsModel, pg_mfa::PgMfaModel, pg_orders::PgOrdersModel,
+        pg_password_resets::PgPasswordResetsModel, pg_refresh_tokens::PgRefreshTokensModel,
+        pg_revoked_tokens::PgRevokedTokensModel, pg_tickets::PgTicketsModel,
+        pg_users::PgUsersModel, pg_venues::PgVenuesModel,
+    },
     routes::get_routes,
+    tasks::{spawn_hold_sweep, spawn_revocation_sweep},
     AppState, Cfg,
 };
 
@@ -13,15 +22,74 @@ async fn main() {
     let ticket_model = PgTicketsModel::new(config.db_url())
         .await
         .expect("Failed to create ticket model");
+    let concert_model = PgConcertsModel::new(config.db_url())
+        .await
+        .expect("Failed to create concert model");
+    let venue_model = PgVenuesModel::new(config.db_url())
+        .await
+        .expect("Failed to create venue model");
+    let category_model = PgCategoriesModel::new(config.db_url())
+        .await
+        .expect("Failed to create category model");
+    let hold_model = PgHoldsModel::new(config.db_url())
+        .await
+        .expect("Failed to create hold model");
+    let order_model = PgOrdersModel::new(config.db_url())
+        .await
+        .expect("Failed to create order model");
     let user_model = PgUsersModel::new(config.db_url())
         .await
         .expect("Failed to create user model");
+    let refresh_token_model = PgRefreshTokensModel::new(config.db_url())
+        .await
+        .expect("Failed to create refresh token model");
+    let revoked_tokens_model = PgRevokedTokensModel::new(config.db_url())
+        .await
+        .expect("Failed to create revoked tokens model");
+    let password_reset_model = PgPasswordResetsModel::new(config.db_url())
+        .await
+        .expect("Failed to create password reset model");
+    let login_attempts_model = PgLoginAttemptsModel::new(config.db_url())
+        .await
+        .expect("Failed to create login attempts model");
+    let mfa_model = PgMfaModel::new(config.db_url())
+        .await
+        .expect("Failed to create mfa model");
+    let api_key_model = PgApiKeysModel::new(config.db_url())
+        .await
+        .expect("Failed to create api key model");
+    let email_verification_model = PgEmailVerificationsModel::new(config.db_url())
+        .await
+        .expect("Failed to create email verification model");
+    let idempotency_key_model = PgIdempotencyKeysModel::new(config.db_url())
+        .await
+        .expect("Failed to create idempotency key model");
     let app_state = AppState::new(
-        config.jwt_secret,
+        &config,
         Box::new(user_model),
         Box::new(ticket_model),
+        Box::new(concert_model),
+        Box::new(venue_model),
+        Box::new(category_model),
+        Box::new(hold_model),
+        Box::new(order_model),
+        Box::new(refresh_token_model),
+        Box::new(revoked_tokens_model),
+        Box::new(password_reset_model),
+        Box::new(login_attempts_model),
+        Box::new(mfa_model),
+        Box::new(api_key_model),
+        Box::new(email_verification_model),
+        Box::new(idempotency_key_model),
     );
+    app_state
+        .revocation_list
+        .reload()
+        .await
+        .expect("Failed to load revoked tokens");
     let app_state = Arc::new(app_state);
+    spawn_revocation_sweep(app_state.clone(), config.revocation_sweep_interval());
+    spawn_hold_sweep(app_state.clone(), config.hold_sweep_interval());
 
     let routes = get_routes(app_state);
 
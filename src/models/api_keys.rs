@@ -0,0 +1,20 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Result,
+    types::api_key_types::{ApiKey, NewApiKey},
+};
+
+#[async_trait]
+pub trait ApiKeysModel: Send + Sync {
+    async fn create_api_key(&self, key: NewApiKey) -> Result<ApiKey>;
+
+    /// The keys of the user that were not revoked, expired ones included
+    async fn get_user_api_keys(&self, user_id: Uuid) -> Result<Vec<ApiKey>>;
+
+    async fn revoke_api_key(&self, user_id: Uuid, id: Uuid) -> Result<()>;
+
+    /// Find the usable key matching `key_hash` and record that it was used
+    async fn use_api_key(&self, key_hash: String) -> Result<Option<ApiKey>>;
+}
@@ -0,0 +1,29 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Result,
+    types::category_types::{NewTicketCategory, TicketCategory, TicketCategoryChanges},
+};
+
+#[async_trait]
+pub trait CategoriesModel: Send + Sync {
+    /// The categories of the concert, the cheapest first
+    async fn get_categories(&self, concert_id: Uuid) -> Result<Vec<TicketCategory>>;
+
+    async fn get_category(&self, id: Uuid) -> Result<TicketCategory>;
+
+    async fn create_category(&self, concert_id: Uuid, category: NewTicketCategory) -> Result<Uuid>;
+
+    /// Only the columns present in `changes` are written, if the category is still at
+    /// `version`. The capacity cannot go below the tickets sold or held. Returns the new version
+    async fn update_category(
+        &self,
+        id: Uuid,
+        changes: TicketCategoryChanges,
+        version: i32,
+    ) -> Result<i32>;
+
+    /// Delete the category if it is still at `version` and no ticket was sold in it
+    async fn delete_category(&self, id: Uuid, version: i32) -> Result<()>;
+}
@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Result,
+    types::{
+        concert_types::{Concert, ConcertChanges, ConcertQuery, NewConcert},
+        pagination::Page,
+    },
+};
+
+#[async_trait]
+pub trait ConcertsModel: Send + Sync {
+    /// A page of the concerts matching the filters of `query`
+    async fn query_concerts(&self, query: ConcertQuery) -> Result<Page<Concert>>;
+
+    async fn get_concert(&self, id: Uuid) -> Result<Concert>;
+
+    async fn create_concert(&self, concert: NewConcert) -> Result<Uuid>;
+
+    /// Only the columns present in `changes` are written, if the concert is still at
+    /// `version`. Returns the new version
+    async fn update_concert(&self, id: Uuid, changes: ConcertChanges, version: i32) -> Result<i32>;
+
+    /// Delete the concert if it is still at `version` and no ticket was sold for it
+    async fn delete_concert(&self, id: Uuid, version: i32) -> Result<()>;
+}
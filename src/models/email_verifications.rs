@@ -0,0 +1,20 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Result,
+    types::email_verification_types::{EmailVerificationToken, NewEmailVerificationToken},
+};
+
+#[async_trait]
+pub trait EmailVerificationsModel: Send + Sync {
+    /// Store a new verification token, the tokens previously issued to the user can no longer be used
+    async fn create_verification_token(&self, token: NewEmailVerificationToken) -> Result<Uuid>;
+
+    /// Mark the verification token matching `token_hash` as used.
+    /// Fails if the token is unknown, expired or was already used
+    async fn consume_verification_token(
+        &self,
+        token_hash: String,
+    ) -> Result<EmailVerificationToken>;
+}
@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Result,
+    types::hold_types::{Hold, NewHold},
+};
+
+#[async_trait]
+pub trait HoldsModel: Send + Sync {
+    async fn get_hold(&self, id: Uuid) -> Result<Hold>;
+
+    /// Set the tickets aside in their category, and the seats if any, as long as
+    /// they are neither sold nor held by an unexpired hold at `now`
+    async fn create_hold(&self, hold: NewHold, now: DateTime<Utc>) -> Result<Uuid>;
+
+    /// Give the tickets of the hold back to its category. The tickets of a hold are
+    /// issued by paying an order for it
+    async fn release_hold(&self, id: Uuid) -> Result<()>;
+
+    /// Release the holds expired at `now`, returns the number of holds released
+    async fn release_expired_holds(&self, now: DateTime<Utc>) -> Result<u64>;
+}
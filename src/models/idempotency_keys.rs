@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Result,
+    types::idempotency_types::{
+        IdempotencyClaim, IdempotencyKey, NewIdempotencyRecord, StoredResponse,
+    },
+};
+
+#[async_trait]
+pub trait IdempotencyKeysModel: Send + Sync {
+    /// Claim the key for a request, an expired entry is taken over.
+    /// Only one of the concurrent requests with the same key gets `Started`
+    async fn begin(&self, record: NewIdempotencyRecord) -> Result<IdempotencyClaim>;
+
+    /// Store the response of the request that started the key
+    async fn complete(
+        &self,
+        user_id: Uuid,
+        key: &IdempotencyKey,
+        response: StoredResponse,
+    ) -> Result<()>;
+
+    /// Forget a key whose request failed, so it can be retried
+    async fn release(&self, user_id: Uuid, key: &IdempotencyKey) -> Result<()>;
+
+    /// Remove the expired entries, returns the number of entries removed
+    async fn purge_expired(&self) -> Result<u64>;
+}
@@ -0,0 +1,39 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::domain::{
+    errors::Result,
+    types::login_attempt_types::{AttemptKind, LoginAttempts},
+};
+
+/// The lockout delay after a number of failures
+pub type LockoutDelay<'a> = dyn Fn(i32) -> Duration + Send + Sync + 'a;
+
+#[async_trait]
+pub trait LoginAttemptsModel: Send + Sync {
+    /// Count an attempt as a failure, until `forgive` says otherwise, and lock the entry
+    /// for `lockout(failures)`. The count starts over when the previous failure is older
+    /// than `window_start`. Both are done at once, so concurrent attempts see the lockout
+    /// of the previous ones. Nothing is counted while the entry is locked out.
+    /// Returns the running lockout, None when the attempt was counted
+    async fn record_attempt(
+        &self,
+        kind: AttemptKind,
+        identifier: &str,
+        now: DateTime<Utc>,
+        window_start: DateTime<Utc>,
+        lockout: &LockoutDelay<'_>,
+    ) -> Result<Option<DateTime<Utc>>>;
+
+    /// Take back an attempt that succeeded, along with the lockout it caused
+    async fn forgive(&self, kind: AttemptKind, identifier: &str) -> Result<()>;
+
+    async fn clear(&self, kind: AttemptKind, identifier: &str) -> Result<()>;
+
+    /// Every entry still locked out
+    async fn get_lockouts(&self) -> Result<Vec<LoginAttempts>>;
+
+    /// Remove the entries without a failure since `window_start` and no running lockout.
+    /// Returns the number of entries removed
+    async fn purge_stale(&self, window_start: DateTime<Utc>) -> Result<u64>;
+}
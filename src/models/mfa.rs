@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Result,
+    types::mfa_types::{MfaChallenge, NewMfaChallenge, UserMfa},
+};
+
+#[async_trait]
+pub trait MfaModel: Send + Sync {
+    async fn get_mfa(&self, user_id: Uuid) -> Result<Option<UserMfa>>;
+
+    /// Store a new secret waiting for confirmation, replacing a previous unconfirmed one
+    async fn start_enrollment(&self, user_id: Uuid, secret: String) -> Result<()>;
+
+    /// Confirm the enrollment, `step` is the time step of the code used to confirm it.
+    /// The recovery codes replace the previous ones
+    async fn enable_mfa(
+        &self,
+        user_id: Uuid,
+        step: i64,
+        recovery_code_hashes: Vec<String>,
+    ) -> Result<()>;
+
+    async fn disable_mfa(&self, user_id: Uuid) -> Result<()>;
+
+    /// Record the use of a TOTP time step.
+    /// Returns false if this step or a later one was already used
+    async fn use_totp_step(&self, user_id: Uuid, step: i64) -> Result<bool>;
+
+    /// Returns false if the code is unknown or was already used
+    async fn use_recovery_code(&self, user_id: Uuid, code_hash: String) -> Result<bool>;
+
+    async fn create_challenge(&self, challenge: NewMfaChallenge) -> Result<Uuid>;
+
+    async fn get_challenge(&self, token_hash: String) -> Result<Option<MfaChallenge>>;
+
+    async fn record_challenge_failure(&self, id: Uuid) -> Result<()>;
+
+    /// Returns false if the challenge was already completed
+    async fn complete_challenge(&self, id: Uuid) -> Result<bool>;
+}
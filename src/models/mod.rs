@@ -1,4 +1,31 @@
+pub mod api_keys;
+pub mod categories;
+pub mod concerts;
+pub mod email_verifications;
+pub mod holds;
+pub mod idempotency_keys;
+pub mod login_attempts;
+pub mod mfa;
+pub mod orders;
+pub mod password_resets;
+pub mod pg_api_keys;
+pub mod pg_categories;
+pub mod pg_concerts;
+pub mod pg_email_verifications;
+pub mod pg_holds;
+pub mod pg_idempotency_keys;
+pub mod pg_login_attempts;
+pub mod pg_mfa;
+pub mod pg_orders;
+pub mod pg_pagination;
+pub mod pg_password_resets;
+pub mod pg_refresh_tokens;
+pub mod pg_revoked_tokens;
 pub mod pg_tickets;
 pub mod pg_users;
+pub mod pg_venues;
+pub mod refresh_tokens;
+pub mod revoked_tokens;
 pub mod tickets;
 pub mod users;
+pub mod venues;
@@ -0,0 +1,40 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Result,
+    types::{
+        order_types::{NewOrder, Order, OrderQuery},
+        pagination::Page,
+    },
+};
+
+#[async_trait]
+pub trait OrdersModel: Send + Sync {
+    /// A page of the orders of a user
+    async fn query_orders(&self, query: OrderQuery) -> Result<Page<Order>>;
+
+    async fn get_order(&self, id: Uuid) -> Result<Order>;
+
+    /// Place the order, pending until it is paid
+    async fn create_order(&self, order: NewOrder) -> Result<Uuid>;
+
+    /// Issue the tickets of the order and mark it paid, all at once or not at all.
+    /// The tickets of an order checking out a hold unexpired at `now` come from the
+    /// hold, the others are taken from their categories. Each ticket gets one of
+    /// `barcodes` and the price of its item. Returns the ids of the tickets
+    async fn pay_order(
+        &self,
+        id: Uuid,
+        barcodes: Vec<String>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Uuid>>;
+
+    /// Cancel a pending order, its hold is released. Returns the new version
+    async fn cancel_order(&self, id: Uuid, now: DateTime<Utc>) -> Result<i32>;
+
+    /// Mark a paid order refunded, its tickets are removed and given back to their
+    /// categories. Returns the new version
+    async fn refund_order(&self, id: Uuid, now: DateTime<Utc>) -> Result<i32>;
+}
@@ -0,0 +1,20 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Result,
+    types::password_reset_types::{NewPasswordResetToken, PasswordResetToken},
+};
+
+#[async_trait]
+pub trait PasswordResetsModel: Send + Sync {
+    /// Store a new reset token, the tokens previously issued to the user can no longer be used
+    async fn create_reset_token(&self, token: NewPasswordResetToken) -> Result<Uuid>;
+
+    /// The reset token matching `token_hash`, if it can still be used
+    async fn find_reset_token(&self, token_hash: String) -> Result<PasswordResetToken>;
+
+    /// Mark the reset token matching `token_hash` as used.
+    /// Fails if the token is unknown, expired or was already used
+    async fn consume_reset_token(&self, token_hash: String) -> Result<PasswordResetToken>;
+}
@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::api_key_types::{ApiKey, NewApiKey, Scope},
+};
+use crate::models::api_keys::ApiKeysModel;
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct PgApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub hint: String,
+    pub scopes: Option<Vec<String>>,
+
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<PgApiKey> for ApiKey {
+    fn from(key: PgApiKey) -> Self {
+        ApiKey {
+            id: key.id,
+            user_id: key.user_id,
+            name: key.name,
+            hint: key.hint,
+            // An unknown scope is dropped, which can only restrict the key further
+            scopes: key
+                .scopes
+                .map(|scopes| scopes.iter().filter_map(|s| Scope::new(s).ok()).collect()),
+            expires_at: key.expires_at,
+            last_used_at: key.last_used_at,
+            revoked_at: key.revoked_at,
+            created_at: key.created_at,
+        }
+    }
+}
+
+pub struct PgApiKeysModel {
+    db_pool: PgPool,
+}
+
+#[async_trait]
+impl ApiKeysModel for PgApiKeysModel {
+    async fn create_api_key(&self, key: NewApiKey) -> Result<ApiKey> {
+        let scopes: Option<Vec<String>> = key
+            .scopes
+            .map(|scopes| scopes.iter().map(|s| s.to_string()).collect());
+        let created: PgApiKey = sqlx::query_as(
+            "INSERT INTO api_keys (user_id, name, key_hash, hint, scopes, expires_at) VALUES ($1, $2, $3, $4, $5, $6)
+            returning id, user_id, name, hint, scopes, expires_at, last_used_at, revoked_at, created_at",
+        )
+        .bind(key.user_id)
+        .bind(key.name)
+        .bind(key.key_hash)
+        .bind(key.hint)
+        .bind(scopes)
+        .bind(key.expires_at)
+        .fetch_one(&self.db_pool)
+        .await
+        .map_err(Error::ApiKeyCreationFailed)?;
+        Ok(created.into())
+    }
+
+    async fn get_user_api_keys(&self, user_id: Uuid) -> Result<Vec<ApiKey>> {
+        let keys: Vec<PgApiKey> = sqlx::query_as(
+            "SELECT id, user_id, name, hint, scopes, expires_at, last_used_at, revoked_at, created_at
+            FROM api_keys WHERE user_id = $1 AND revoked_at IS NULL ORDER BY created_at",
+        )
+        .bind(user_id)
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(Error::ApiKeyFetchFailed)?;
+        Ok(keys.into_iter().map(|k| k.into()).collect())
+    }
+
+    async fn revoke_api_key(&self, user_id: Uuid, id: Uuid) -> Result<()> {
+        let res = sqlx::query!(
+            "UPDATE api_keys SET revoked_at = $1 WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL",
+            Utc::now(),
+            id,
+            user_id
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::ApiKeyUpdateFailed)?;
+        if res.rows_affected() == 0 {
+            return Err(Error::ApiKeyNotFound);
+        }
+        Ok(())
+    }
+
+    async fn use_api_key(&self, key_hash: String) -> Result<Option<ApiKey>> {
+        let now = Utc::now();
+        let key: Option<PgApiKey> = sqlx::query_as(
+            "UPDATE api_keys SET last_used_at = $1
+            WHERE key_hash = $2 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > $1)
+            returning id, user_id, name, hint, scopes, expires_at, last_used_at, revoked_at, created_at",
+        )
+        .bind(now)
+        .bind(key_hash)
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(Error::ApiKeyUpdateFailed)?;
+        Ok(key.map(|k| k.into()))
+    }
+}
+
+impl PgApiKeysModel {
+    pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
+        let db_pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&db_url)
+            .await?;
+        Ok(Self { db_pool })
+    }
+}
@@ -0,0 +1,312 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::category_types::{NewTicketCategory, TicketCategory, TicketCategoryChanges},
+};
+use crate::models::categories::CategoriesModel;
+
+#[derive(FromRow)]
+pub struct PgTicketCategory {
+    pub id: Uuid,
+    pub concert_id: Uuid,
+    pub name: String,
+    pub price: f64,
+    pub capacity: i32,
+    pub sold: i32,
+    pub held: i32,
+    pub version: i32,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<PgTicketCategory> for TicketCategory {
+    fn from(category: PgTicketCategory) -> Self {
+        TicketCategory {
+            id: category.id,
+            concert_id: category.concert_id,
+            name: category.name,
+            price: category.price,
+            capacity: category.capacity,
+            sold: category.sold,
+            held: category.held,
+            version: category.version,
+            created_at: category.created_at,
+            updated_at: category.updated_at,
+        }
+    }
+}
+
+pub struct PgCategoriesModel {
+    db_pool: PgPool,
+}
+
+#[async_trait]
+impl CategoriesModel for PgCategoriesModel {
+    async fn get_categories(&self, concert_id: Uuid) -> Result<Vec<TicketCategory>> {
+        let categories: Vec<PgTicketCategory> = sqlx::query_as("SELECT id, concert_id, name, price, capacity, sold, held, version, created_at, updated_at FROM ticket_categories WHERE concert_id = $1 ORDER BY price, name")
+            .bind(concert_id)
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(Error::CategoryFetchFailed)?;
+        Ok(categories.into_iter().map(TicketCategory::from).collect())
+    }
+
+    async fn get_category(&self, id: Uuid) -> Result<TicketCategory> {
+        let category: Option<PgTicketCategory> = sqlx::query_as("SELECT id, concert_id, name, price, capacity, sold, held, version, created_at, updated_at FROM ticket_categories WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db_pool)
+            .await
+            .map_err(Error::CategoryFetchFailed)?;
+        match category {
+            Some(category) => Ok(category.into()),
+            None => Err(Error::CategoryNotFound),
+        }
+    }
+
+    async fn create_category(&self, concert_id: Uuid, category: NewTicketCategory) -> Result<Uuid> {
+        let created_id = sqlx::query!("INSERT INTO ticket_categories (concert_id, name, price, capacity) VALUES ($1, $2, $3, $4) returning id",
+            concert_id,
+            category.name,
+            category.price,
+            category.capacity
+        )
+            .fetch_one(&self.db_pool)
+            .await
+            .map_err(|e| constraint_violation(e, Error::CategoryCreationFailed))?
+            .id;
+        Ok(created_id)
+    }
+
+    async fn update_category(
+        &self,
+        id: Uuid,
+        changes: TicketCategoryChanges,
+        version: i32,
+    ) -> Result<i32> {
+        let updated = sqlx::query!(
+            "UPDATE ticket_categories SET name = COALESCE($1, name), price = COALESCE($2, price),
+            capacity = COALESCE($3, capacity), updated_at = $4, version = version + 1
+            WHERE id = $5 AND version = $6 returning version",
+            changes.name,
+            changes.price,
+            changes.capacity,
+            Utc::now(),
+            id,
+            version
+        )
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(|e| constraint_violation(e, Error::CategoryUpdateFailed))?;
+        match updated {
+            Some(updated) => Ok(updated.version),
+            None => Err(Error::PreconditionFailed),
+        }
+    }
+
+    async fn delete_category(&self, id: Uuid, version: i32) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM ticket_categories WHERE id = $1 AND version = $2 returning id",
+            id,
+            version
+        )
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(|e| constraint_violation(e, Error::CategoryDeletionFailed))?
+        .ok_or(Error::PreconditionFailed)?;
+        Ok(())
+    }
+}
+
+/// Take `count` tickets off the remaining capacity of the category, for the concert.
+/// The row stays locked until the end of the transaction of `conn`, so concurrent
+/// buyers queue up on it and the capacity is checked against the latest count.
+/// Returns the price of a ticket of the category
+pub(crate) async fn take_from_category(
+    conn: &mut PgConnection,
+    category_id: Uuid,
+    concert_id: Uuid,
+    count: i32,
+    otherwise: fn(sqlx::Error) -> Error,
+) -> Result<f64> {
+    let taken = sqlx::query!(
+        "UPDATE ticket_categories SET sold = sold + $3 WHERE id = $1 AND concert_id = $2 AND sold + held + $3 <= capacity returning price",
+        category_id,
+        concert_id,
+        count
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(otherwise)?;
+    match taken {
+        Some(taken) => Ok(taken.price),
+        None => Err(unavailable(conn, category_id, concert_id, otherwise).await),
+    }
+}
+
+/// Set `count` tickets of the category aside for a hold, like `take_from_category`
+pub(crate) async fn hold_in_category(
+    conn: &mut PgConnection,
+    category_id: Uuid,
+    concert_id: Uuid,
+    count: i32,
+    otherwise: fn(sqlx::Error) -> Error,
+) -> Result<()> {
+    let held = sqlx::query!(
+        "UPDATE ticket_categories SET held = held + $3 WHERE id = $1 AND concert_id = $2 AND sold + held + $3 <= capacity returning id",
+        category_id,
+        concert_id,
+        count
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(otherwise)?;
+    match held {
+        Some(_) => Ok(()),
+        None => Err(unavailable(conn, category_id, concert_id, otherwise).await),
+    }
+}
+
+/// Why the tickets could not be taken from the category
+async fn unavailable(
+    conn: &mut PgConnection,
+    category_id: Uuid,
+    concert_id: Uuid,
+    otherwise: fn(sqlx::Error) -> Error,
+) -> Error {
+    let category = sqlx::query!(
+        "SELECT concert_id FROM ticket_categories WHERE id = $1",
+        category_id
+    )
+    .fetch_optional(conn)
+    .await;
+    match category {
+        Err(e) => otherwise(e),
+        Ok(None) => Error::CategoryNotFound,
+        Ok(Some(category)) if category.concert_id != concert_id => Error::CategoryNotInConcert,
+        Ok(Some(_)) => Error::SoldOut,
+    }
+}
+
+/// Lock the rows of the categories, in the order of their ids, until the end of the
+/// transaction of `conn`. A transaction updating several categories takes them all up
+/// front, so two of them never wait on each other
+pub(crate) async fn lock_categories(
+    conn: &mut PgConnection,
+    category_ids: &[Uuid],
+    otherwise: fn(sqlx::Error) -> Error,
+) -> Result<()> {
+    sqlx::query!(
+        "SELECT id FROM ticket_categories WHERE id = ANY($1) ORDER BY id FOR UPDATE",
+        category_ids
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(otherwise)?;
+    Ok(())
+}
+
+/// Put `count` tickets back into the capacity of the category
+pub(crate) async fn give_back_to_category(
+    conn: &mut PgConnection,
+    category_id: Uuid,
+    count: i32,
+    otherwise: fn(sqlx::Error) -> Error,
+) -> Result<()> {
+    sqlx::query!(
+        "UPDATE ticket_categories SET sold = sold - $2 WHERE id = $1",
+        category_id,
+        count
+    )
+    .execute(conn)
+    .await
+    .map_err(otherwise)?;
+    Ok(())
+}
+
+/// Count `count` held tickets of the category as sold, the capacity is unchanged.
+/// Returns the price of a ticket of the category
+pub(crate) async fn sell_held(
+    conn: &mut PgConnection,
+    category_id: Uuid,
+    count: i32,
+    otherwise: fn(sqlx::Error) -> Error,
+) -> Result<f64> {
+    let sold = sqlx::query!(
+        "UPDATE ticket_categories SET held = held - $2, sold = sold + $2 WHERE id = $1 returning price",
+        category_id,
+        count
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(otherwise)?;
+    Ok(sold.price)
+}
+
+/// Put `count` held tickets back into the capacity of the category
+pub(crate) async fn release_held(
+    conn: &mut PgConnection,
+    category_id: Uuid,
+    count: i32,
+    otherwise: fn(sqlx::Error) -> Error,
+) -> Result<()> {
+    sqlx::query!(
+        "UPDATE ticket_categories SET held = held - $2 WHERE id = $1",
+        category_id,
+        count
+    )
+    .execute(conn)
+    .await
+    .map_err(otherwise)?;
+    Ok(())
+}
+
+/// Once a concert has categories, each of its tickets has to be issued in one
+pub(crate) async fn check_uncategorized(
+    conn: &mut PgConnection,
+    concert_id: Uuid,
+    otherwise: fn(sqlx::Error) -> Error,
+) -> Result<()> {
+    let has_categories = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM ticket_categories WHERE concert_id = $1) AS "exists!""#,
+        concert_id
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(otherwise)?;
+    if has_categories {
+        Err(Error::CategoryRequired)
+    } else {
+        Ok(())
+    }
+}
+
+/// Tell the known failures apart from the others
+fn constraint_violation(e: sqlx::Error, otherwise: fn(sqlx::Error) -> Error) -> Error {
+    let constraint = e
+        .as_database_error()
+        .and_then(|db_error| db_error.constraint());
+    match constraint {
+        Some("ticket_categories_concert_id_fkey") => Error::ConcertNotFound,
+        Some("ticket_categories_concert_id_name_key") => Error::CategoryAlreadyExists,
+        Some("ticket_categories_sold_check") => Error::CapacityBelowSold,
+        Some("tickets_category_id_fkey" | "order_items_category_id_fkey") => {
+            Error::CategoryHasTickets
+        }
+        _ => otherwise(e),
+    }
+}
+
+impl PgCategoriesModel {
+    pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
+        let db_pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&db_url)
+            .await?;
+        Ok(Self { db_pool })
+    }
+}
@@ -0,0 +1,199 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::{
+        concert_types::{
+            Concert, ConcertChanges, ConcertFilter, ConcertQuery, ConcertSortField, NewConcert,
+        },
+        pagination::Page,
+    },
+};
+use crate::models::{concerts::ConcertsModel, pg_pagination::push_page};
+
+#[derive(FromRow)]
+pub struct PgConcert {
+    pub id: Uuid,
+    pub name: String,
+    pub date: DateTime<Utc>,
+    pub venue_id: Option<Uuid>,
+    pub capacity: Option<i32>,
+    pub description: Option<String>,
+    pub version: i32,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<PgConcert> for Concert {
+    fn from(concert: PgConcert) -> Self {
+        Concert {
+            id: concert.id,
+            name: concert.name,
+            date: concert.date,
+            venue_id: concert.venue_id,
+            capacity: concert.capacity,
+            description: concert.description,
+            version: concert.version,
+            created_at: concert.created_at,
+            updated_at: concert.updated_at,
+        }
+    }
+}
+
+pub struct PgConcertsModel {
+    db_pool: PgPool,
+}
+
+#[async_trait]
+impl ConcertsModel for PgConcertsModel {
+    async fn query_concerts(&self, query: ConcertQuery) -> Result<Page<Concert>> {
+        let mut builder = QueryBuilder::new("SELECT id, name, date, venue_id, capacity, description, version, created_at, updated_at FROM concerts WHERE true");
+        push_filter(&mut builder, &query.filter);
+        let sql_type = match query.page.sort.field {
+            ConcertSortField::Date | ConcertSortField::CreatedAt => "timestamptz",
+            ConcertSortField::Name => "text",
+        };
+        push_page(&mut builder, &query.page, sql_type);
+        let concerts: Vec<PgConcert> = builder
+            .build_query_as()
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(Error::ConcertFetchFailed)?;
+
+        let total = if query.page.with_total {
+            let mut builder = QueryBuilder::new("SELECT count(*) FROM concerts WHERE true");
+            push_filter(&mut builder, &query.filter);
+            let total: i64 = builder
+                .build_query_scalar()
+                .fetch_one(&self.db_pool)
+                .await
+                .map_err(Error::ConcertFetchFailed)?;
+            Some(total)
+        } else {
+            None
+        };
+        let concerts = concerts.into_iter().map(Concert::from).collect();
+        let field = query.page.sort.field;
+        Ok(Page::from_items(concerts, &query.page, total, |c| {
+            (field.value(c), c.id)
+        }))
+    }
+
+    async fn get_concert(&self, id: Uuid) -> Result<Concert> {
+        let concert: Option<PgConcert> = sqlx::query_as("SELECT id, name, date, venue_id, capacity, description, version, created_at, updated_at FROM concerts WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.db_pool)
+            .await
+            .map_err(Error::ConcertFetchFailed)?;
+        match concert {
+            Some(concert) => Ok(concert.into()),
+            None => Err(Error::ConcertNotFound),
+        }
+    }
+
+    async fn create_concert(&self, concert: NewConcert) -> Result<Uuid> {
+        let created_id = sqlx::query!("INSERT INTO concerts (name, date, venue_id, capacity, description) VALUES ($1, $2, $3, $4, $5) returning id",
+            concert.name,
+            concert.date,
+            concert.venue_id,
+            concert.capacity,
+            concert.description
+        )
+            .fetch_one(&self.db_pool)
+            .await
+            .map_err(|e| unknown_venue(e, Error::ConcertCreationFailed))?
+            .id;
+        Ok(created_id)
+    }
+
+    async fn update_concert(&self, id: Uuid, changes: ConcertChanges, version: i32) -> Result<i32> {
+        // The optional columns are only written when their flag is set, so they can be cleared
+        let updated = sqlx::query!("UPDATE concerts SET name = COALESCE($1, name), date = COALESCE($2, date),
+            venue_id = CASE WHEN $3 THEN $4 ELSE venue_id END, capacity = CASE WHEN $5 THEN $6 ELSE capacity END,
+            description = CASE WHEN $7 THEN $8 ELSE description END, updated_at = $9, version = version + 1
+            WHERE id = $10 AND version = $11 returning version",
+            changes.name,
+            changes.date,
+            changes.venue_id.is_some(),
+            changes.venue_id.flatten(),
+            changes.capacity.is_some(),
+            changes.capacity.flatten(),
+            changes.description.is_some(),
+            changes.description.flatten(),
+            Utc::now(),
+            id,
+            version
+        )
+            .fetch_optional(&self.db_pool)
+            .await
+            .map_err(|e| unknown_venue(e, Error::ConcertUpdateFailed))?;
+        match updated {
+            Some(updated) => Ok(updated.version),
+            None => Err(Error::PreconditionFailed),
+        }
+    }
+
+    async fn delete_concert(&self, id: Uuid, version: i32) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM concerts WHERE id = $1 AND version = $2 returning id",
+            id,
+            version
+        )
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(foreign_key_violation)?
+        .ok_or(Error::PreconditionFailed)?;
+        Ok(())
+    }
+}
+
+/// The tickets sold for a concert keep it from being deleted
+fn foreign_key_violation(e: sqlx::Error) -> Error {
+    let constraint = e
+        .as_database_error()
+        .and_then(|db_error| db_error.constraint());
+    match constraint {
+        Some("tickets_concert_id_fkey" | "order_items_concert_id_fkey") => Error::ConcertHasTickets,
+        _ => Error::ConcertDeletionFailed(e),
+    }
+}
+
+/// A concert can only take place in a known venue
+fn unknown_venue(e: sqlx::Error, otherwise: fn(sqlx::Error) -> Error) -> Error {
+    let constraint = e
+        .as_database_error()
+        .and_then(|db_error| db_error.constraint());
+    match constraint {
+        Some("concerts_venue_id_fkey") => Error::VenueNotFound,
+        _ => otherwise(e),
+    }
+}
+
+fn push_filter(builder: &mut QueryBuilder<'_, Postgres>, filter: &ConcertFilter) {
+    if let Some(name) = &filter.name {
+        builder
+            .push(" AND lower(name) = lower(")
+            .push_bind(name.clone())
+            .push(")");
+    }
+    if let Some(from) = filter.date_from {
+        builder.push(" AND date >= ").push_bind(from);
+    }
+    if let Some(to) = filter.date_to {
+        builder.push(" AND date < ").push_bind(to);
+    }
+}
+
+impl PgConcertsModel {
+    pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
+        let db_pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&db_url)
+            .await?;
+        Ok(Self { db_pool })
+    }
+}
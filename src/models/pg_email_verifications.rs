@@ -0,0 +1,105 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::{
+        email_verification_types::{EmailVerificationToken, NewEmailVerificationToken},
+        Email,
+    },
+};
+use crate::models::email_verifications::EmailVerificationsModel;
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct PgEmailVerificationToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub email: String,
+
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<PgEmailVerificationToken> for EmailVerificationToken {
+    fn from(token: PgEmailVerificationToken) -> Self {
+        EmailVerificationToken {
+            id: token.id,
+            user_id: token.user_id,
+            email: unsafe { Email::new_unchecked(token.email) },
+            expires_at: token.expires_at,
+            used_at: token.used_at,
+            created_at: token.created_at,
+        }
+    }
+}
+
+pub struct PgEmailVerificationsModel {
+    db_pool: PgPool,
+}
+
+#[async_trait]
+impl EmailVerificationsModel for PgEmailVerificationsModel {
+    async fn create_verification_token(&self, token: NewEmailVerificationToken) -> Result<Uuid> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::EmailVerificationFailed)?;
+        sqlx::query!(
+            "UPDATE email_verification_tokens SET used_at = $1 WHERE user_id = $2 AND used_at IS NULL",
+            Utc::now(),
+            token.user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::EmailVerificationFailed)?;
+        let created_id = sqlx::query!(
+            "INSERT INTO email_verification_tokens (user_id, email, token_hash, expires_at) VALUES ($1, $2::text, $3, $4) returning id",
+            token.user_id,
+            token.email.as_ref() as &str,
+            token.token_hash,
+            token.expires_at,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Error::EmailVerificationFailed)?
+        .id;
+        tx.commit().await.map_err(Error::EmailVerificationFailed)?;
+        Ok(created_id)
+    }
+
+    async fn consume_verification_token(
+        &self,
+        token_hash: String,
+    ) -> Result<EmailVerificationToken> {
+        // A single statement, so two concurrent requests cannot both use the token
+        let token: Option<PgEmailVerificationToken> = sqlx::query_as(
+            "UPDATE email_verification_tokens SET used_at = $1
+            WHERE token_hash = $2 AND used_at IS NULL AND expires_at > $1
+            returning id, user_id, email::text, expires_at, used_at, created_at",
+        )
+        .bind(Utc::now())
+        .bind(token_hash)
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(Error::EmailVerificationFailed)?;
+        token
+            .map(|t| t.into())
+            .ok_or(Error::InvalidVerificationToken)
+    }
+}
+
+impl PgEmailVerificationsModel {
+    pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
+        let db_pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&db_url)
+            .await?;
+        Ok(Self { db_pool })
+    }
+}
@@ -0,0 +1,259 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::hold_types::{Hold, NewHold},
+};
+use crate::models::{
+    holds::HoldsModel,
+    pg_categories::{hold_in_category, release_held, sell_held},
+};
+
+#[derive(FromRow)]
+pub struct PgHold {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub concert_id: Uuid,
+    pub category_id: Uuid,
+    pub quantity: i32,
+    pub seat_ids: Vec<Uuid>,
+    pub expires_at: DateTime<Utc>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<PgHold> for Hold {
+    fn from(hold: PgHold) -> Self {
+        Hold {
+            id: hold.id,
+            user_id: hold.user_id,
+            concert_id: hold.concert_id,
+            category_id: hold.category_id,
+            quantity: hold.quantity,
+            seat_ids: hold.seat_ids,
+            expires_at: hold.expires_at,
+            created_at: hold.created_at,
+        }
+    }
+}
+
+pub struct PgHoldsModel {
+    db_pool: PgPool,
+}
+
+#[async_trait]
+impl HoldsModel for PgHoldsModel {
+    async fn get_hold(&self, id: Uuid) -> Result<Hold> {
+        let hold: Option<PgHold> = sqlx::query_as(
+            "SELECT id, user_id, concert_id, category_id, quantity, expires_at, created_at,
+                ARRAY(SELECT seat_id FROM hold_seats WHERE hold_id = holds.id ORDER BY seat_id) AS seat_ids
+            FROM holds WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(Error::HoldFetchFailed)?;
+        match hold {
+            Some(hold) => Ok(hold.into()),
+            None => Err(Error::HoldNotFound),
+        }
+    }
+
+    async fn create_hold(&self, hold: NewHold, now: DateTime<Utc>) -> Result<Uuid> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::HoldCreationFailed)?;
+        // The seats are locked before the category, like when a ticket is issued
+        check_seats_free(
+            &mut tx,
+            hold.concert_id,
+            &hold.seat_ids,
+            now,
+            Error::HoldCreationFailed,
+        )
+        .await?;
+        hold_in_category(
+            &mut tx,
+            hold.category_id,
+            hold.concert_id,
+            hold.quantity,
+            Error::HoldCreationFailed,
+        )
+        .await?;
+        let created_id = sqlx::query!(
+            "INSERT INTO holds (user_id, concert_id, category_id, quantity, expires_at) VALUES ($1, $2, $3, $4, $5) returning id",
+            hold.user_id,
+            hold.concert_id,
+            hold.category_id,
+            hold.quantity,
+            hold.expires_at
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Error::HoldCreationFailed)?
+        .id;
+        sqlx::query!(
+            "INSERT INTO hold_seats (hold_id, concert_id, seat_id) SELECT $1, $2, UNNEST($3::uuid[])",
+            created_id,
+            hold.concert_id,
+            &hold.seat_ids
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::HoldCreationFailed)?;
+        tx.commit().await.map_err(Error::HoldCreationFailed)?;
+        Ok(created_id)
+    }
+
+    async fn release_hold(&self, id: Uuid) -> Result<()> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::HoldDeletionFailed)?;
+        drop_hold(&mut tx, id, Error::HoldDeletionFailed).await?;
+        tx.commit().await.map_err(Error::HoldDeletionFailed)?;
+        Ok(())
+    }
+
+    async fn release_expired_holds(&self, now: DateTime<Utc>) -> Result<u64> {
+        // One statement, so the holds and the counts of their categories move together
+        let released: i64 = sqlx::query_scalar(
+            "WITH released AS (DELETE FROM holds WHERE expires_at <= $1 returning category_id, quantity),
+                totals AS (SELECT category_id, sum(quantity) AS quantity FROM released GROUP BY category_id),
+                restored AS (UPDATE ticket_categories SET held = held - totals.quantity
+                    FROM totals WHERE ticket_categories.id = totals.category_id)
+            SELECT count(*) FROM released",
+        )
+        .bind(now)
+        .fetch_one(&self.db_pool)
+        .await
+        .map_err(Error::HoldDeletionFailed)?;
+        Ok(released as u64)
+    }
+}
+
+/// The tickets of a hold taken out of it, to be issued
+pub(crate) struct TakenHold {
+    pub concert_id: Uuid,
+    pub category_id: Uuid,
+    pub quantity: i32,
+    pub seat_ids: Vec<Uuid>,
+    /// Current price of the category
+    pub price: f64,
+}
+
+/// Remove the hold unexpired at `now`, its places go from held to sold
+pub(crate) async fn take_hold(
+    conn: &mut PgConnection,
+    id: Uuid,
+    now: DateTime<Utc>,
+    otherwise: fn(sqlx::Error) -> Error,
+) -> Result<TakenHold> {
+    // Locked so the hold is taken once, and not released by the sweep meanwhile
+    let hold = sqlx::query!(
+        "SELECT concert_id, category_id, quantity, expires_at FROM holds WHERE id = $1 FOR UPDATE",
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(otherwise)?
+    .ok_or(Error::HoldNotFound)?;
+    if hold.expires_at <= now {
+        return Err(Error::HoldExpired);
+    }
+    let seat_ids = sqlx::query_scalar!(
+        "SELECT seat_id FROM hold_seats WHERE hold_id = $1 ORDER BY seat_id",
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(otherwise)?;
+    sqlx::query!("DELETE FROM holds WHERE id = $1", id)
+        .execute(&mut *conn)
+        .await
+        .map_err(otherwise)?;
+    let price = sell_held(conn, hold.category_id, hold.quantity, otherwise).await?;
+    Ok(TakenHold {
+        concert_id: hold.concert_id,
+        category_id: hold.category_id,
+        quantity: hold.quantity,
+        seat_ids,
+        price,
+    })
+}
+
+/// Remove the hold, its places are available again
+pub(crate) async fn drop_hold(
+    conn: &mut PgConnection,
+    id: Uuid,
+    otherwise: fn(sqlx::Error) -> Error,
+) -> Result<()> {
+    let released = sqlx::query!(
+        "DELETE FROM holds WHERE id = $1 returning category_id, quantity",
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(otherwise)?
+    .ok_or(Error::HoldNotFound)?;
+    release_held(conn, released.category_id, released.quantity, otherwise).await
+}
+
+/// Lock the seats and check that none of them is sold or held for the concert by a hold
+/// unexpired at `now`. The seats are locked in the same order by everyone so concurrent
+/// buyers cannot deadlock, and the second of two buyers of a seat sees the first one
+pub(crate) async fn check_seats_free(
+    conn: &mut PgConnection,
+    concert_id: Uuid,
+    seat_ids: &[Uuid],
+    now: DateTime<Utc>,
+    otherwise: fn(sqlx::Error) -> Error,
+) -> Result<()> {
+    if seat_ids.is_empty() {
+        return Ok(());
+    }
+    let locked = sqlx::query_scalar!(
+        "SELECT id FROM seats WHERE id = ANY($1) ORDER BY id FOR NO KEY UPDATE",
+        seat_ids
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(otherwise)?;
+    if locked.len() != seat_ids.len() {
+        return Err(Error::SeatNotFound);
+    }
+    let taken = sqlx::query!(
+        r#"SELECT EXISTS (SELECT 1 FROM tickets WHERE concert_id = $1 AND seat_id = ANY($2)) AS "sold!",
+            EXISTS (SELECT 1 FROM hold_seats JOIN holds ON holds.id = hold_seats.hold_id
+                WHERE hold_seats.concert_id = $1 AND hold_seats.seat_id = ANY($2) AND holds.expires_at > $3) AS "held!""#,
+        concert_id,
+        seat_ids,
+        now
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(otherwise)?;
+    if taken.sold {
+        Err(Error::SeatTaken)
+    } else if taken.held {
+        Err(Error::SeatHeld)
+    } else {
+        Ok(())
+    }
+}
+
+impl PgHoldsModel {
+    pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
+        let db_pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&db_url)
+            .await?;
+        Ok(Self { db_pool })
+    }
+}
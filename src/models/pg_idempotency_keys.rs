@@ -0,0 +1,120 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{postgres::PgPoolOptions, PgPool};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::idempotency_types::{
+        IdempotencyClaim, IdempotencyKey, NewIdempotencyRecord, StoredResponse,
+    },
+};
+use crate::models::idempotency_keys::IdempotencyKeysModel;
+
+pub struct PgIdempotencyKeysModel {
+    db_pool: PgPool,
+}
+
+#[async_trait]
+impl IdempotencyKeysModel for PgIdempotencyKeysModel {
+    async fn begin(&self, record: NewIdempotencyRecord) -> Result<IdempotencyClaim> {
+        // The primary key lets a single request insert the entry
+        let started = sqlx::query!(
+            "INSERT INTO idempotency_keys (user_id, key, request_hash, expires_at) VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id, key) DO UPDATE SET
+                request_hash = EXCLUDED.request_hash,
+                status_code = NULL,
+                response_body = NULL,
+                created_at = $5,
+                expires_at = EXCLUDED.expires_at
+            WHERE idempotency_keys.expires_at <= $5
+            returning user_id",
+            record.user_id,
+            record.key.as_ref(),
+            record.request_hash,
+            record.expires_at,
+            Utc::now(),
+        )
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(Error::IdempotencyKeyFailed)?;
+        if started.is_some() {
+            return Ok(IdempotencyClaim::Started);
+        }
+
+        let existing = sqlx::query!(
+            "SELECT request_hash, status_code, response_body FROM idempotency_keys WHERE user_id = $1 AND key = $2",
+            record.user_id,
+            record.key.as_ref(),
+        )
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(Error::IdempotencyKeyFailed)?;
+        // Released in the meantime, the client can try again
+        let Some(existing) = existing else {
+            return Ok(IdempotencyClaim::InProgress);
+        };
+        if existing.request_hash != record.request_hash {
+            return Ok(IdempotencyClaim::Mismatch);
+        }
+        match (existing.status_code, existing.response_body) {
+            (Some(status_code), Some(body)) => Ok(IdempotencyClaim::Replay(StoredResponse {
+                status_code: status_code as u16,
+                body,
+            })),
+            _ => Ok(IdempotencyClaim::InProgress),
+        }
+    }
+
+    async fn complete(
+        &self,
+        user_id: Uuid,
+        key: &IdempotencyKey,
+        response: StoredResponse,
+    ) -> Result<()> {
+        sqlx::query!(
+            "UPDATE idempotency_keys SET status_code = $1, response_body = $2 WHERE user_id = $3 AND key = $4",
+            response.status_code as i16,
+            response.body,
+            user_id,
+            key.as_ref(),
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::IdempotencyKeyFailed)?;
+        Ok(())
+    }
+
+    async fn release(&self, user_id: Uuid, key: &IdempotencyKey) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM idempotency_keys WHERE user_id = $1 AND key = $2 AND status_code IS NULL",
+            user_id,
+            key.as_ref(),
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::IdempotencyKeyFailed)?;
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> Result<u64> {
+        let res = sqlx::query!(
+            "DELETE FROM idempotency_keys WHERE expires_at <= $1",
+            Utc::now()
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::IdempotencyKeyFailed)?;
+        Ok(res.rows_affected())
+    }
+}
+
+impl PgIdempotencyKeysModel {
+    pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
+        let db_pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&db_url)
+            .await?;
+        Ok(Self { db_pool })
+    }
+}
@@ -0,0 +1,161 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgPool};
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::login_attempt_types::{AttemptKind, LoginAttempts},
+};
+use crate::models::login_attempts::{LockoutDelay, LoginAttemptsModel};
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct PgLoginAttempts {
+    pub kind: String,
+    pub identifier: String,
+    pub failures: i32,
+
+    pub last_failure_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<PgLoginAttempts> for LoginAttempts {
+    type Error = Error;
+
+    fn try_from(attempts: PgLoginAttempts) -> Result<Self> {
+        let kind = AttemptKind::try_from(attempts.kind.as_str()).map_err(|_| {
+            Error::InternalError(format!(
+                "Unknown login attempt kind {} in the database",
+                attempts.kind
+            ))
+        })?;
+        Ok(LoginAttempts {
+            kind,
+            identifier: attempts.identifier,
+            failures: attempts.failures,
+            last_failure_at: attempts.last_failure_at,
+            locked_until: attempts.locked_until,
+        })
+    }
+}
+
+pub struct PgLoginAttemptsModel {
+    db_pool: PgPool,
+}
+
+#[async_trait]
+impl LoginAttemptsModel for PgLoginAttemptsModel {
+    async fn record_attempt(
+        &self,
+        kind: AttemptKind,
+        identifier: &str,
+        now: DateTime<Utc>,
+        window_start: DateTime<Utc>,
+        lockout: &LockoutDelay<'_>,
+    ) -> Result<Option<DateTime<Utc>>> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::LoginAttemptsFailed)?;
+        // The entry stays locked by the upsert until the lockout is saved, even when it is
+        // not updated, so the next attempt waits for it
+        let counted = sqlx::query!(
+            "INSERT INTO login_attempts (kind, identifier, failures, last_failure_at) VALUES ($1, $2, 1, $3)
+            ON CONFLICT (kind, identifier) DO UPDATE SET
+                failures = CASE WHEN login_attempts.last_failure_at < $4 THEN 1 ELSE login_attempts.failures + 1 END,
+                last_failure_at = EXCLUDED.last_failure_at
+            WHERE login_attempts.locked_until IS NULL OR login_attempts.locked_until <= $3
+            returning failures",
+            kind.as_ref(),
+            identifier,
+            now,
+            window_start,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Error::LoginAttemptsFailed)?;
+        let Some(counted) = counted else {
+            let locked_until = sqlx::query_scalar!(
+                "SELECT locked_until FROM login_attempts WHERE kind = $1 AND identifier = $2",
+                kind.as_ref(),
+                identifier,
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(Error::LoginAttemptsFailed)?;
+            return Ok(locked_until);
+        };
+        let delay = lockout(counted.failures);
+        if delay > Duration::zero() {
+            sqlx::query!(
+                "UPDATE login_attempts SET locked_until = $1 WHERE kind = $2 AND identifier = $3",
+                now + delay,
+                kind.as_ref(),
+                identifier,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::LoginAttemptsFailed)?;
+        }
+        tx.commit().await.map_err(Error::LoginAttemptsFailed)?;
+        Ok(None)
+    }
+
+    async fn forgive(&self, kind: AttemptKind, identifier: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE login_attempts SET failures = greatest(failures - 1, 0), locked_until = NULL WHERE kind = $1 AND identifier = $2",
+            kind.as_ref(),
+            identifier,
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::LoginAttemptsFailed)?;
+        Ok(())
+    }
+
+    async fn clear(&self, kind: AttemptKind, identifier: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM login_attempts WHERE kind = $1 AND identifier = $2",
+            kind.as_ref(),
+            identifier,
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::LoginAttemptsFailed)?;
+        Ok(())
+    }
+
+    async fn get_lockouts(&self) -> Result<Vec<LoginAttempts>> {
+        let lockouts: Vec<PgLoginAttempts> = sqlx::query_as(
+            "SELECT kind, identifier, failures, last_failure_at, locked_until FROM login_attempts WHERE locked_until > $1 ORDER BY locked_until DESC",
+        )
+        .bind(Utc::now())
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(Error::LoginAttemptsFailed)?;
+        lockouts.into_iter().map(LoginAttempts::try_from).collect()
+    }
+
+    async fn purge_stale(&self, window_start: DateTime<Utc>) -> Result<u64> {
+        let res = sqlx::query!(
+            "DELETE FROM login_attempts WHERE last_failure_at < $1 AND (locked_until IS NULL OR locked_until < $2)",
+            window_start,
+            Utc::now(),
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::LoginAttemptsFailed)?;
+        Ok(res.rows_affected())
+    }
+}
+
+impl PgLoginAttemptsModel {
+    pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
+        let db_pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&db_url)
+            .await?;
+        Ok(Self { db_pool })
+    }
+}
@@ -0,0 +1,216 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::mfa_types::{MfaChallenge, NewMfaChallenge, UserMfa},
+};
+use crate::models::mfa::MfaModel;
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct PgUserMfa {
+    pub user_id: Uuid,
+    pub secret: String,
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub last_used_step: Option<i64>,
+}
+
+impl From<PgUserMfa> for UserMfa {
+    fn from(mfa: PgUserMfa) -> Self {
+        UserMfa {
+            user_id: mfa.user_id,
+            secret: mfa.secret,
+            enabled_at: mfa.enabled_at,
+            last_used_step: mfa.last_used_step,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct PgMfaChallenge {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub failed_attempts: i32,
+
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl From<PgMfaChallenge> for MfaChallenge {
+    fn from(challenge: PgMfaChallenge) -> Self {
+        MfaChallenge {
+            id: challenge.id,
+            user_id: challenge.user_id,
+            failed_attempts: challenge.failed_attempts,
+            expires_at: challenge.expires_at,
+            used_at: challenge.used_at,
+        }
+    }
+}
+
+pub struct PgMfaModel {
+    db_pool: PgPool,
+}
+
+#[async_trait]
+impl MfaModel for PgMfaModel {
+    async fn get_mfa(&self, user_id: Uuid) -> Result<Option<UserMfa>> {
+        let mfa: Option<PgUserMfa> = sqlx::query_as(
+            "SELECT user_id, secret, enabled_at, last_used_step FROM user_mfa WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(Error::MfaFetchFailed)?;
+        Ok(mfa.map(|m| m.into()))
+    }
+
+    async fn start_enrollment(&self, user_id: Uuid, secret: String) -> Result<()> {
+        // An enabled secret is never replaced, it has to be disabled first
+        sqlx::query!(
+            "INSERT INTO user_mfa (user_id, secret) VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET secret = EXCLUDED.secret, created_at = now()
+            WHERE user_mfa.enabled_at IS NULL",
+            user_id,
+            secret,
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::MfaUpdateFailed)?;
+        Ok(())
+    }
+
+    async fn enable_mfa(
+        &self,
+        user_id: Uuid,
+        step: i64,
+        recovery_code_hashes: Vec<String>,
+    ) -> Result<()> {
+        let mut tx = self.db_pool.begin().await.map_err(Error::MfaUpdateFailed)?;
+        sqlx::query!(
+            "UPDATE user_mfa SET enabled_at = $1, last_used_step = $2 WHERE user_id = $3",
+            Utc::now(),
+            step,
+            user_id,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::MfaUpdateFailed)?;
+        sqlx::query!("DELETE FROM mfa_recovery_codes WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::MfaUpdateFailed)?;
+        sqlx::query!(
+            "INSERT INTO mfa_recovery_codes (user_id, code_hash) SELECT $1, unnest($2::text[])",
+            user_id,
+            &recovery_code_hashes,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::MfaUpdateFailed)?;
+        tx.commit().await.map_err(Error::MfaUpdateFailed)?;
+        Ok(())
+    }
+
+    async fn disable_mfa(&self, user_id: Uuid) -> Result<()> {
+        let mut tx = self.db_pool.begin().await.map_err(Error::MfaUpdateFailed)?;
+        sqlx::query!("DELETE FROM user_mfa WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::MfaUpdateFailed)?;
+        sqlx::query!("DELETE FROM mfa_recovery_codes WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::MfaUpdateFailed)?;
+        tx.commit().await.map_err(Error::MfaUpdateFailed)?;
+        Ok(())
+    }
+
+    async fn use_totp_step(&self, user_id: Uuid, step: i64) -> Result<bool> {
+        let res = sqlx::query!(
+            "UPDATE user_mfa SET last_used_step = $1
+            WHERE user_id = $2 AND (last_used_step IS NULL OR last_used_step < $1)",
+            step,
+            user_id,
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::MfaUpdateFailed)?;
+        Ok(res.rows_affected() == 1)
+    }
+
+    async fn use_recovery_code(&self, user_id: Uuid, code_hash: String) -> Result<bool> {
+        let res = sqlx::query!(
+            "UPDATE mfa_recovery_codes SET used_at = $1
+            WHERE user_id = $2 AND code_hash = $3 AND used_at IS NULL",
+            Utc::now(),
+            user_id,
+            code_hash,
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::MfaUpdateFailed)?;
+        Ok(res.rows_affected() == 1)
+    }
+
+    async fn create_challenge(&self, challenge: NewMfaChallenge) -> Result<Uuid> {
+        let created_id = sqlx::query!(
+            "INSERT INTO mfa_challenges (user_id, token_hash, expires_at) VALUES ($1, $2, $3) returning id",
+            challenge.user_id,
+            challenge.token_hash,
+            challenge.expires_at,
+        )
+        .fetch_one(&self.db_pool)
+        .await
+        .map_err(Error::MfaUpdateFailed)?
+        .id;
+        Ok(created_id)
+    }
+
+    async fn get_challenge(&self, token_hash: String) -> Result<Option<MfaChallenge>> {
+        let challenge: Option<PgMfaChallenge> = sqlx::query_as(
+            "SELECT id, user_id, failed_attempts, expires_at, used_at FROM mfa_challenges WHERE token_hash = $1",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(Error::MfaFetchFailed)?;
+        Ok(challenge.map(|c| c.into()))
+    }
+
+    async fn record_challenge_failure(&self, id: Uuid) -> Result<()> {
+        sqlx::query!(
+            "UPDATE mfa_challenges SET failed_attempts = failed_attempts + 1 WHERE id = $1",
+            id
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::MfaUpdateFailed)?;
+        Ok(())
+    }
+
+    async fn complete_challenge(&self, id: Uuid) -> Result<bool> {
+        let res = sqlx::query!(
+            "UPDATE mfa_challenges SET used_at = $1 WHERE id = $2 AND used_at IS NULL",
+            Utc::now(),
+            id
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::MfaUpdateFailed)?;
+        Ok(res.rows_affected() == 1)
+    }
+}
+
+impl PgMfaModel {
+    pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
+        let db_pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&db_url)
+            .await?;
+        Ok(Self { db_pool })
+    }
+}
@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgConnection, PgPool, QueryBuilder};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::{
+        order_types::{NewOrder, Order, OrderItem, OrderQuery, OrderSortField, OrderStatus},
+        pagination::Page,
+    },
+};
+use crate::models::{
+    orders::OrdersModel,
+    pg_categories::{give_back_to_category, take_from_category},
+    pg_holds::{check_seats_free, drop_hold, take_hold},
+    pg_pagination::push_page,
+    pg_tickets::{foreign_key_violation, insert_tickets, IssuedTickets},
+};
+
+#[derive(FromRow)]
+pub struct PgOrder {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub status: String,
+    pub total: f64,
+    pub hold_id: Option<Uuid>,
+    pub ticket_ids: Vec<Uuid>,
+    pub version: i32,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub paid_at: Option<DateTime<Utc>>,
+}
+
+#[derive(FromRow)]
+pub struct PgOrderItem {
+    pub order_id: Uuid,
+    pub concert_id: Uuid,
+    pub category_id: Uuid,
+    pub quantity: i32,
+    pub seat_ids: Vec<Uuid>,
+    pub unit_price: f64,
+}
+
+impl From<PgOrderItem> for OrderItem {
+    fn from(item: PgOrderItem) -> Self {
+        OrderItem {
+            concert_id: item.concert_id,
+            category_id: item.category_id,
+            quantity: item.quantity,
+            seat_ids: item.seat_ids,
+            unit_price: item.unit_price,
+        }
+    }
+}
+
+/// A status read from the database, which only holds valid ones
+fn stored_status(status: &str) -> Result<OrderStatus> {
+    OrderStatus::try_from(status)
+        .map_err(|_| Error::InternalError(format!("Unknown order status {status} in the database")))
+}
+
+impl PgOrder {
+    fn into_order(self, items: Vec<OrderItem>) -> Result<Order> {
+        Ok(Order {
+            id: self.id,
+            user_id: self.user_id,
+            status: stored_status(&self.status)?,
+            total: self.total,
+            hold_id: self.hold_id,
+            items,
+            ticket_ids: self.ticket_ids,
+            version: self.version,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            paid_at: self.paid_at,
+        })
+    }
+}
+
+/// The columns of a `PgOrder`, along with the tickets issued for the order
+const COLUMNS: &str = "id, user_id, status, total, hold_id, version, created_at, updated_at, paid_at,
+    ARRAY(SELECT tickets.id FROM tickets WHERE tickets.order_id = orders.id ORDER BY tickets.id) AS ticket_ids";
+
+pub struct PgOrdersModel {
+    db_pool: PgPool,
+}
+
+#[async_trait]
+impl OrdersModel for PgOrdersModel {
+    async fn query_orders(&self, query: OrderQuery) -> Result<Page<Order>> {
+        let mut builder =
+            QueryBuilder::new(format!("SELECT {COLUMNS} FROM orders WHERE user_id = "));
+        builder.push_bind(query.user_id);
+        if let Some(status) = query.status {
+            builder
+                .push(" AND status = ")
+                .push_bind(status.as_ref().to_string());
+        }
+        let sql_type = match query.page.sort.field {
+            OrderSortField::CreatedAt => "timestamptz",
+            OrderSortField::Total => "float8",
+        };
+        push_page(&mut builder, &query.page, sql_type);
+        let orders: Vec<PgOrder> = builder
+            .build_query_as()
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(Error::OrderFetchFailed)?;
+
+        let total = if query.page.with_total {
+            let mut builder = QueryBuilder::new("SELECT count(*) FROM orders WHERE user_id = ");
+            builder.push_bind(query.user_id);
+            if let Some(status) = query.status {
+                builder
+                    .push(" AND status = ")
+                    .push_bind(status.as_ref().to_string());
+            }
+            let total: i64 = builder
+                .build_query_scalar()
+                .fetch_one(&self.db_pool)
+                .await
+                .map_err(Error::OrderFetchFailed)?;
+            Some(total)
+        } else {
+            None
+        };
+        let ids: Vec<Uuid> = orders.iter().map(|o| o.id).collect();
+        let mut conn = self
+            .db_pool
+            .acquire()
+            .await
+            .map_err(Error::OrderFetchFailed)?;
+        let mut items = fetch_items(&mut conn, &ids)
+            .await
+            .map_err(Error::OrderFetchFailed)?;
+        let orders = orders
+            .into_iter()
+            .map(|o| {
+                let order_items = items.remove(&o.id).unwrap_or_default();
+                o.into_order(order_items)
+            })
+            .collect::<Result<Vec<Order>>>()?;
+        let field = query.page.sort.field;
+        Ok(Page::from_items(orders, &query.page, total, |o| {
+            (field.value(o), o.id)
+        }))
+    }
+
+    async fn get_order(&self, id: Uuid) -> Result<Order> {
+        let mut conn = self
+            .db_pool
+            .acquire()
+            .await
+            .map_err(Error::OrderFetchFailed)?;
+        let order: Option<PgOrder> =
+            sqlx::query_as(&format!("SELECT {COLUMNS} FROM orders WHERE id = $1"))
+                .bind(id)
+                .fetch_optional(&mut *conn)
+                .await
+                .map_err(Error::OrderFetchFailed)?;
+        let order = order.ok_or(Error::OrderNotFound)?;
+        let mut items = fetch_items(&mut conn, &[id])
+            .await
+            .map_err(Error::OrderFetchFailed)?;
+        order.into_order(items.remove(&id).unwrap_or_default())
+    }
+
+    async fn create_order(&self, order: NewOrder) -> Result<Uuid> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::OrderCreationFailed)?;
+        let created_id = sqlx::query!(
+            "INSERT INTO orders (user_id, total, hold_id) VALUES ($1, $2, $3) returning id",
+            order.user_id,
+            order.total,
+            order.hold_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| foreign_key_violation(e, Error::OrderCreationFailed))?
+        .id;
+        for (position, item) in order.items.iter().enumerate() {
+            sqlx::query!(
+                "INSERT INTO order_items (order_id, position, concert_id, category_id, quantity, seat_ids, unit_price)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                created_id,
+                position as i32,
+                item.concert_id,
+                item.category_id,
+                item.quantity,
+                &item.seat_ids,
+                item.unit_price
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| foreign_key_violation(e, Error::OrderCreationFailed))?;
+        }
+        tx.commit().await.map_err(Error::OrderCreationFailed)?;
+        Ok(created_id)
+    }
+
+    async fn pay_order(
+        &self,
+        id: Uuid,
+        barcodes: Vec<String>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Uuid>> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::OrderUpdateFailed)?;
+        let order = lock_order(&mut tx, id, OrderStatus::Paid).await?;
+        let mut items = fetch_items(&mut tx, &[id])
+            .await
+            .map_err(Error::OrderUpdateFailed)?
+            .remove(&id)
+            .unwrap_or_default();
+        let mut barcodes = barcodes.into_iter();
+        let mut ticket_ids = vec![];
+        match order.hold_id {
+            // The hold already set the tickets aside, they only change hands
+            Some(hold_id) => {
+                let hold = take_hold(&mut tx, hold_id, now, Error::OrderUpdateFailed).await?;
+                let unit_price = items.first().map_or(hold.price, |item| item.unit_price);
+                let tickets = IssuedTickets {
+                    owner_id: order.user_id,
+                    concert_id: hold.concert_id,
+                    category_id: hold.category_id,
+                    price: unit_price,
+                    seat_ids: hold.seat_ids,
+                    barcodes: barcodes.by_ref().take(hold.quantity as usize).collect(),
+                    order_id: Some(id),
+                };
+                ticket_ids = insert_tickets(&mut tx, tickets, Error::OrderUpdateFailed).await?;
+            }
+            // Every seat is locked before any category, and the categories are taken in
+            // the same order by everyone, so concurrent orders cannot deadlock
+            None => {
+                for item in &items {
+                    check_seats_free(
+                        &mut tx,
+                        item.concert_id,
+                        &item.seat_ids,
+                        now,
+                        Error::OrderUpdateFailed,
+                    )
+                    .await?;
+                }
+                items.sort_by_key(|item| item.category_id);
+                for item in items {
+                    take_from_category(
+                        &mut tx,
+                        item.category_id,
+                        item.concert_id,
+                        item.quantity,
+                        Error::OrderUpdateFailed,
+                    )
+                    .await?;
+                    let tickets = IssuedTickets {
+                        owner_id: order.user_id,
+                        concert_id: item.concert_id,
+                        category_id: item.category_id,
+                        price: item.unit_price,
+                        seat_ids: item.seat_ids,
+                        barcodes: barcodes.by_ref().take(item.quantity as usize).collect(),
+                        order_id: Some(id),
+                    };
+                    ticket_ids
+                        .extend(insert_tickets(&mut tx, tickets, Error::OrderUpdateFailed).await?);
+                }
+            }
+        }
+        sqlx::query!(
+            "UPDATE orders SET status = 'paid', paid_at = $2, updated_at = $2, version = version + 1 WHERE id = $1",
+            id,
+            now
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::OrderUpdateFailed)?;
+        tx.commit().await.map_err(Error::OrderUpdateFailed)?;
+        Ok(ticket_ids)
+    }
+
+    async fn cancel_order(&self, id: Uuid, now: DateTime<Utc>) -> Result<i32> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::OrderUpdateFailed)?;
+        let order = lock_order(&mut tx, id, OrderStatus::Cancelled).await?;
+        if let Some(hold_id) = order.hold_id {
+            drop_hold(&mut tx, hold_id, Error::OrderUpdateFailed).await?;
+        }
+        let version = set_status(&mut tx, id, OrderStatus::Cancelled, now).await?;
+        tx.commit().await.map_err(Error::OrderUpdateFailed)?;
+        Ok(version)
+    }
+
+    async fn refund_order(&self, id: Uuid, now: DateTime<Utc>) -> Result<i32> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::OrderUpdateFailed)?;
+        lock_order(&mut tx, id, OrderStatus::Refunded).await?;
+        let returned = sqlx::query!(
+            r#"WITH removed AS (DELETE FROM tickets WHERE order_id = $1 returning category_id)
+            SELECT category_id AS "category_id!", count(*)::int AS "count!" FROM removed
+            WHERE category_id IS NOT NULL GROUP BY category_id ORDER BY category_id"#,
+            id
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(Error::OrderUpdateFailed)?;
+        for category in returned {
+            give_back_to_category(
+                &mut tx,
+                category.category_id,
+                category.count,
+                Error::OrderUpdateFailed,
+            )
+            .await?;
+        }
+        let version = set_status(&mut tx, id, OrderStatus::Refunded, now).await?;
+        tx.commit().await.map_err(Error::OrderUpdateFailed)?;
+        Ok(version)
+    }
+}
+
+struct LockedOrder {
+    user_id: Uuid,
+    hold_id: Option<Uuid>,
+}
+
+/// Lock the order until the end of the transaction and check it can move to `next`.
+/// Of two concurrent changes, the second one sees the status left by the first.
+/// The hold is locked before the order: removing it updates every order placed for it,
+/// so two orders of the same hold locked the other way around would deadlock
+async fn lock_order(conn: &mut PgConnection, id: Uuid, next: OrderStatus) -> Result<LockedOrder> {
+    sqlx::query!(
+        "SELECT id FROM holds WHERE id = (SELECT hold_id FROM orders WHERE id = $1) FOR UPDATE",
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(Error::OrderUpdateFailed)?;
+    let order = sqlx::query!(
+        "SELECT user_id, status, hold_id FROM orders WHERE id = $1 FOR UPDATE",
+        id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(Error::OrderUpdateFailed)?
+    .ok_or(Error::OrderNotFound)?;
+    stored_status(&order.status)?.check_transition(next)?;
+    Ok(LockedOrder {
+        user_id: order.user_id,
+        hold_id: order.hold_id,
+    })
+}
+
+/// Returns the new version of the order
+async fn set_status(
+    conn: &mut PgConnection,
+    id: Uuid,
+    status: OrderStatus,
+    now: DateTime<Utc>,
+) -> Result<i32> {
+    let updated = sqlx::query!(
+        "UPDATE orders SET status = $2, updated_at = $3, version = version + 1 WHERE id = $1 returning version",
+        id,
+        status.as_ref(),
+        now
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(Error::OrderUpdateFailed)?;
+    Ok(updated.version)
+}
+
+/// The items of the orders, in the order they were placed in
+async fn fetch_items(
+    conn: &mut PgConnection,
+    order_ids: &[Uuid],
+) -> std::result::Result<HashMap<Uuid, Vec<OrderItem>>, sqlx::Error> {
+    let rows: Vec<PgOrderItem> = sqlx::query_as(
+        "SELECT order_id, concert_id, category_id, quantity, seat_ids, unit_price FROM order_items
+        WHERE order_id = ANY($1) ORDER BY order_id, position",
+    )
+    .bind(order_ids)
+    .fetch_all(conn)
+    .await?;
+    let mut items: HashMap<Uuid, Vec<OrderItem>> = HashMap::new();
+    for row in rows {
+        items.entry(row.order_id).or_default().push(row.into());
+    }
+    Ok(items)
+}
+
+impl PgOrdersModel {
+    pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
+        let db_pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&db_url)
+            .await?;
+        Ok(Self { db_pool })
+    }
+}
@@ -0,0 +1,29 @@
+use sqlx::{Postgres, QueryBuilder};
+
+use crate::domain::types::pagination::{PageRequest, SortDirection, SortField};
+
+/// Append the page to a query ending with its `WHERE` clause: the items up to the
+/// cursor are skipped, then the order and the limit follow. The sort fields are named
+/// after their column, `sql_type` is the type of that column the cursor value is cast to.
+/// One more item than the limit is fetched to tell whether there is a next page
+pub fn push_page<F>(builder: &mut QueryBuilder<'_, Postgres>, page: &PageRequest<F>, sql_type: &str)
+where
+    F: SortField,
+{
+    let column = page.sort.field.as_ref();
+    let (comparison, order) = match page.sort.direction {
+        SortDirection::Asc => (">", "ASC"),
+        SortDirection::Desc => ("<", "DESC"),
+    };
+    if let Some(after) = &page.after {
+        builder
+            .push(format!(" AND ({column}, id) {comparison} (CAST("))
+            .push_bind(after.value.clone())
+            .push(format!(" AS {sql_type}), "))
+            .push_bind(after.id)
+            .push(")");
+    }
+    builder
+        .push(format!(" ORDER BY {column} {order}, id {order} LIMIT "))
+        .push_bind(page.limit + 1);
+}
@@ -0,0 +1,107 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::password_reset_types::{NewPasswordResetToken, PasswordResetToken},
+};
+use crate::models::password_resets::PasswordResetsModel;
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct PgPasswordResetToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<PgPasswordResetToken> for PasswordResetToken {
+    fn from(token: PgPasswordResetToken) -> Self {
+        PasswordResetToken {
+            id: token.id,
+            user_id: token.user_id,
+            expires_at: token.expires_at,
+            used_at: token.used_at,
+            created_at: token.created_at,
+        }
+    }
+}
+
+pub struct PgPasswordResetsModel {
+    db_pool: PgPool,
+}
+
+#[async_trait]
+impl PasswordResetsModel for PgPasswordResetsModel {
+    async fn create_reset_token(&self, token: NewPasswordResetToken) -> Result<Uuid> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::PasswordResetFailed)?;
+        sqlx::query!(
+            "UPDATE password_reset_tokens SET used_at = $1 WHERE user_id = $2 AND used_at IS NULL",
+            Utc::now(),
+            token.user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::PasswordResetFailed)?;
+        let created_id = sqlx::query!(
+            "INSERT INTO password_reset_tokens (user_id, token_hash, expires_at) VALUES ($1, $2, $3) returning id",
+            token.user_id,
+            token.token_hash,
+            token.expires_at,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Error::PasswordResetFailed)?
+        .id;
+        tx.commit().await.map_err(Error::PasswordResetFailed)?;
+        Ok(created_id)
+    }
+
+    async fn find_reset_token(&self, token_hash: String) -> Result<PasswordResetToken> {
+        let token: Option<PgPasswordResetToken> = sqlx::query_as(
+            "SELECT id, user_id, expires_at, used_at, created_at FROM password_reset_tokens
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2",
+        )
+        .bind(token_hash)
+        .bind(Utc::now())
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(Error::PasswordResetFailed)?;
+        token.map(|t| t.into()).ok_or(Error::InvalidResetToken)
+    }
+
+    async fn consume_reset_token(&self, token_hash: String) -> Result<PasswordResetToken> {
+        // A single statement, so two concurrent requests cannot both use the token
+        let token: Option<PgPasswordResetToken> = sqlx::query_as(
+            "UPDATE password_reset_tokens SET used_at = $1
+            WHERE token_hash = $2 AND used_at IS NULL AND expires_at > $1
+            returning id, user_id, expires_at, used_at, created_at",
+        )
+        .bind(Utc::now())
+        .bind(token_hash)
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(Error::PasswordResetFailed)?;
+        token.map(|t| t.into()).ok_or(Error::InvalidResetToken)
+    }
+}
+
+impl PgPasswordResetsModel {
+    pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
+        let db_pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&db_url)
+            .await?;
+        Ok(Self { db_pool })
+    }
+}
@@ -0,0 +1,159 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::refresh_token_types::{NewRefreshToken, RefreshToken},
+};
+use crate::models::refresh_tokens::RefreshTokensModel;
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct PgRefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    pub mfa_verified: bool,
+
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<PgRefreshToken> for RefreshToken {
+    fn from(token: PgRefreshToken) -> Self {
+        RefreshToken {
+            id: token.id,
+            user_id: token.user_id,
+            family_id: token.family_id,
+            mfa_verified: token.mfa_verified,
+            expires_at: token.expires_at,
+            used_at: token.used_at,
+            revoked_at: token.revoked_at,
+            created_at: token.created_at,
+        }
+    }
+}
+
+pub struct PgRefreshTokensModel {
+    db_pool: PgPool,
+}
+
+#[async_trait]
+impl RefreshTokensModel for PgRefreshTokensModel {
+    async fn create_refresh_token(&self, token: NewRefreshToken) -> Result<Uuid> {
+        let created_id = sqlx::query!(
+            "INSERT INTO refresh_tokens (user_id, family_id, token_hash, mfa_verified, expires_at) VALUES ($1, $2, $3, $4, $5) returning id",
+            token.user_id,
+            token.family_id,
+            token.token_hash,
+            token.mfa_verified,
+            token.expires_at,
+        )
+        .fetch_one(&self.db_pool)
+        .await
+        .map_err(Error::RefreshTokenCreationFailed)?
+        .id;
+        Ok(created_id)
+    }
+
+    async fn rotate_refresh_token(
+        &self,
+        token_hash: String,
+        new_token_hash: String,
+        expires_at: DateTime<Utc>,
+    ) -> Result<RefreshToken> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::RefreshTokenUpdateFailed)?;
+
+        // Lock the row so two concurrent refreshes cannot both consume the same token
+        let token: Option<PgRefreshToken> = sqlx::query_as("SELECT id, user_id, family_id, mfa_verified, expires_at, used_at, revoked_at, created_at FROM refresh_tokens WHERE token_hash = $1 FOR UPDATE")
+            .bind(&token_hash)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(Error::RefreshTokenUpdateFailed)?;
+        let Some(token) = token else {
+            return Err(Error::InvalidRefreshToken);
+        };
+
+        if token.revoked_at.is_some() || token.expires_at <= Utc::now() {
+            return Err(Error::InvalidRefreshToken);
+        }
+        if token.used_at.is_some() {
+            sqlx::query!(
+                "UPDATE refresh_tokens SET revoked_at = $1 WHERE family_id = $2 AND revoked_at IS NULL",
+                Utc::now(),
+                token.family_id
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::RefreshTokenUpdateFailed)?;
+            tx.commit().await.map_err(Error::RefreshTokenUpdateFailed)?;
+            return Err(Error::RefreshTokenReused);
+        }
+
+        sqlx::query!(
+            "UPDATE refresh_tokens SET used_at = $1 WHERE id = $2",
+            Utc::now(),
+            token.id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::RefreshTokenUpdateFailed)?;
+        let new_token: PgRefreshToken = sqlx::query_as("INSERT INTO refresh_tokens (user_id, family_id, token_hash, mfa_verified, expires_at) VALUES ($1, $2, $3, $4, $5) returning id, user_id, family_id, mfa_verified, expires_at, used_at, revoked_at, created_at")
+            .bind(token.user_id)
+            .bind(token.family_id)
+            .bind(new_token_hash)
+            .bind(token.mfa_verified)
+            .bind(expires_at)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(Error::RefreshTokenCreationFailed)?;
+        tx.commit().await.map_err(Error::RefreshTokenUpdateFailed)?;
+        Ok(new_token.into())
+    }
+
+    async fn revoke_refresh_token_family(&self, user_id: Uuid, token_hash: String) -> Result<()> {
+        sqlx::query!(
+            "UPDATE refresh_tokens SET revoked_at = $1
+            WHERE family_id = (SELECT family_id FROM refresh_tokens WHERE token_hash = $2 AND user_id = $3)
+            AND revoked_at IS NULL",
+            Utc::now(),
+            token_hash,
+            user_id
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::RefreshTokenUpdateFailed)?;
+        Ok(())
+    }
+
+    async fn revoke_user_refresh_tokens(&self, user_id: Uuid) -> Result<()> {
+        sqlx::query!(
+            "UPDATE refresh_tokens SET revoked_at = $1 WHERE user_id = $2 AND revoked_at IS NULL",
+            Utc::now(),
+            user_id
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::RefreshTokenUpdateFailed)?;
+        Ok(())
+    }
+}
+
+impl PgRefreshTokensModel {
+    pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
+        let db_pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&db_url)
+            .await?;
+        Ok(Self { db_pool })
+    }
+}
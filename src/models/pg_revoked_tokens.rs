@@ -0,0 +1,123 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::revocation_types::{RevokedSession, RevokedToken},
+};
+use crate::models::revoked_tokens::RevokedTokensModel;
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct PgRevokedToken {
+    pub jti: Uuid,
+    pub user_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<PgRevokedToken> for RevokedToken {
+    fn from(token: PgRevokedToken) -> Self {
+        RevokedToken {
+            jti: token.jti,
+            user_id: token.user_id,
+            expires_at: token.expires_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct PgRevokedSession {
+    pub user_id: Uuid,
+    pub revoked_before: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<PgRevokedSession> for RevokedSession {
+    fn from(session: PgRevokedSession) -> Self {
+        RevokedSession {
+            user_id: session.user_id,
+            revoked_before: session.revoked_before,
+            expires_at: session.expires_at,
+        }
+    }
+}
+
+pub struct PgRevokedTokensModel {
+    db_pool: PgPool,
+}
+
+#[async_trait]
+impl RevokedTokensModel for PgRevokedTokensModel {
+    async fn revoke_token(&self, token: RevokedToken) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO revoked_tokens (jti, user_id, expires_at) VALUES ($1, $2, $3) ON CONFLICT (jti) DO NOTHING",
+            token.jti,
+            token.user_id,
+            token.expires_at,
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::RevocationFailed)?;
+        Ok(())
+    }
+
+    async fn revoke_sessions(&self, session: RevokedSession) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO revoked_sessions (user_id, revoked_before, expires_at) VALUES ($1, $2, $3)
+            ON CONFLICT (user_id) DO UPDATE SET revoked_before = EXCLUDED.revoked_before, expires_at = EXCLUDED.expires_at",
+            session.user_id,
+            session.revoked_before,
+            session.expires_at,
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::RevocationFailed)?;
+        Ok(())
+    }
+
+    async fn get_revoked_tokens(&self) -> Result<Vec<RevokedToken>> {
+        let tokens: Vec<PgRevokedToken> = sqlx::query_as(
+            "SELECT jti, user_id, expires_at FROM revoked_tokens WHERE expires_at > now()",
+        )
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(Error::RevocationFetchFailed)?;
+        Ok(tokens.into_iter().map(|t| t.into()).collect())
+    }
+
+    async fn get_revoked_sessions(&self) -> Result<Vec<RevokedSession>> {
+        let sessions: Vec<PgRevokedSession> = sqlx::query_as(
+            "SELECT user_id, revoked_before, expires_at FROM revoked_sessions WHERE expires_at > now()",
+        )
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(Error::RevocationFetchFailed)?;
+        Ok(sessions.into_iter().map(|s| s.into()).collect())
+    }
+
+    async fn purge_expired(&self) -> Result<u64> {
+        let tokens = sqlx::query!("DELETE FROM revoked_tokens WHERE expires_at <= now()")
+            .execute(&self.db_pool)
+            .await
+            .map_err(Error::RevocationFailed)?
+            .rows_affected();
+        let sessions = sqlx::query!("DELETE FROM revoked_sessions WHERE expires_at <= now()")
+            .execute(&self.db_pool)
+            .await
+            .map_err(Error::RevocationFailed)?
+            .rows_affected();
+        Ok(tokens + sessions)
+    }
+}
+
+impl PgRevokedTokensModel {
+    pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
+        let db_pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&db_url)
+            .await?;
+        Ok(Self { db_pool })
+    }
+}
@@ -1,38 +1,92 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgPool};
+use sqlx::{
+    postgres::PgPoolOptions, prelude::FromRow, PgConnection, PgPool, Postgres, QueryBuilder,
+};
 use uuid::Uuid;
 
 use crate::domain::{
     errors::{Error, Result},
-    types::ticket_types::{NewTicket, Ticket},
+    types::{
+        pagination::Page,
+        search_types::{SearchHit, SearchQuery},
+        ticket_types::{
+            NewTicket, Ticket, TicketChanges, TicketFilter, TicketQuery, TicketSortField,
+        },
+    },
+};
+use crate::models::{
+    pg_categories::{
+        check_uncategorized, give_back_to_category, lock_categories, take_from_category,
+    },
+    pg_holds::check_seats_free,
+    pg_pagination::push_page,
+    tickets::TicketsModel,
 };
-use crate::models::tickets::TicketsModel;
 
 #[derive(Serialize, Deserialize, FromRow)]
 pub struct PgTicket {
     pub id: Uuid,
     pub owner_id: Uuid,
 
+    pub concert_id: Uuid,
     pub concert_name: String,
     pub concert_date: DateTime<Utc>,
+    pub seat_id: Option<Uuid>,
+    pub seat_section: Option<String>,
+    pub seat_row: Option<String>,
+    pub seat_number: Option<String>,
+    pub category_id: Option<Uuid>,
+    pub category_name: Option<String>,
     pub barcode_data: String,
     pub price: f64,
+    pub version: i32,
 
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(FromRow)]
+struct PgTicketHit {
+    #[sqlx(flatten)]
+    ticket: PgTicket,
+    rank: f32,
+}
+
+/// The tickets along with the name and date of their concert, their seat and the name of
+/// their category, to select from. The columns keep their name so the sorts and filters
+/// can use them unqualified
+const TICKETS: &str = "(SELECT tickets.*, concerts.name AS concert_name, concerts.date AS concert_date,
+        seats.section AS seat_section, seats.row AS seat_row, seats.number AS seat_number,
+        ticket_categories.name AS category_name
+    FROM tickets JOIN concerts ON concerts.id = tickets.concert_id LEFT JOIN seats ON seats.id = tickets.seat_id
+        LEFT JOIN ticket_categories ON ticket_categories.id = tickets.category_id) tickets";
+
+/// The columns of a `PgTicket`
+const COLUMNS: &str = "id, owner_id, concert_id, concert_name, concert_date, seat_id, seat_section, seat_row, seat_number, category_id, category_name, barcode_data, price, version, created_at, updated_at";
+
+/// Lowest word similarity of a fuzzy match, below the default of `pg_trgm` so one
+/// typo in a word of a long concert name is still found
+const WORD_SIMILARITY_THRESHOLD: f32 = 0.5;
+
 impl From<PgTicket> for Ticket {
     fn from(ticket: PgTicket) -> Self {
         Ticket {
             id: ticket.id,
             owner_id: ticket.owner_id,
+            concert_id: ticket.concert_id,
             concert_name: ticket.concert_name,
             concert_date: ticket.concert_date,
+            seat_id: ticket.seat_id,
+            seat_section: ticket.seat_section,
+            seat_row: ticket.seat_row,
+            seat_number: ticket.seat_number,
+            category_id: ticket.category_id,
+            category_name: ticket.category_name,
             barcode_data: ticket.barcode_data,
             price: ticket.price,
+            version: ticket.version,
             created_at: ticket.created_at,
             updated_at: ticket.updated_at,
         }
@@ -45,79 +99,358 @@ pub struct PgTicketsModel {
 
 #[async_trait]
 impl TicketsModel for PgTicketsModel {
-    async fn get_tickets(&self) -> Result<Vec<Ticket>> {
-        let tickets: Vec<PgTicket> = sqlx::query_as("SELECT id, owner_id, concert_name, concert_date, barcode_data, price, created_at, updated_at FROM tickets")
+    async fn query_tickets(&self, query: TicketQuery) -> Result<Page<Ticket>> {
+        let mut builder = QueryBuilder::new(format!("SELECT {COLUMNS} FROM {TICKETS} WHERE true"));
+        push_filter(&mut builder, &query.filter);
+        let sql_type = match query.page.sort.field {
+            TicketSortField::CreatedAt | TicketSortField::ConcertDate => "timestamptz",
+            TicketSortField::Price => "float8",
+        };
+        push_page(&mut builder, &query.page, sql_type);
+        let tickets: Vec<PgTicket> = builder
+            .build_query_as()
             .fetch_all(&self.db_pool)
             .await
             .map_err(Error::TicketFetchFailed)?;
-        Ok(tickets.into_iter().map(|t| t.into()).collect())
+
+        let total = if query.page.with_total {
+            let mut builder =
+                QueryBuilder::new(format!("SELECT count(*) FROM {TICKETS} WHERE true"));
+            push_filter(&mut builder, &query.filter);
+            let total: i64 = builder
+                .build_query_scalar()
+                .fetch_one(&self.db_pool)
+                .await
+                .map_err(Error::TicketFetchFailed)?;
+            Some(total)
+        } else {
+            None
+        };
+        let tickets = tickets.into_iter().map(Ticket::from).collect();
+        let field = query.page.sort.field;
+        Ok(Page::from_items(tickets, &query.page, total, |t| {
+            (field.value(t), t.id)
+        }))
     }
 
     async fn get_ticket(&self, id: Uuid) -> Result<Ticket> {
-        let ticket: Option<PgTicket> = sqlx::query_as("SELECT id, owner_id, concert_name, concert_date, barcode_data, price, created_at, updated_at FROM tickets WHERE id = $1")
-            .bind(id)
-            .fetch_optional(&self.db_pool)
-            .await
-            .map_err(Error::TicketFetchFailed)?;
+        let ticket: Option<PgTicket> =
+            sqlx::query_as(&format!("SELECT {COLUMNS} FROM {TICKETS} WHERE id = $1"))
+                .bind(id)
+                .fetch_optional(&self.db_pool)
+                .await
+                .map_err(Error::TicketFetchFailed)?;
         match ticket {
             Some(ticket) => Ok(ticket.into()),
             None => Err(Error::TicketNotFound),
         }
     }
 
-    async fn get_tickets_by_user(&self, user_id: Uuid) -> Result<Vec<Ticket>> {
-        let ticket: Vec<PgTicket> = sqlx::query_as("SELECT id, owner_id, concert_name, concert_date, barcode_data, price, created_at, updated_at FROM tickets WHERE owner_id = $1")
-            .bind(user_id)
-            .fetch_all(&self.db_pool)
+    async fn search_tickets(
+        &self,
+        query: &SearchQuery,
+        owner_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<SearchHit<Ticket>>> {
+        let mut tx = self
+            .db_pool
+            .begin()
             .await
             .map_err(Error::TicketFetchFailed)?;
+        sqlx::query(&format!(
+            "SET LOCAL pg_trgm.word_similarity_threshold = {WORD_SIMILARITY_THRESHOLD}"
+        ))
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::TicketFetchFailed)?;
+        let hits: Vec<PgTicketHit> = sqlx::query_as(&format!(
+            "SELECT {COLUMNS},
+                greatest(word_similarity($1, concert_name), ts_rank(to_tsvector('simple', concert_name), to_tsquery('simple', $2))) AS rank
+            FROM {TICKETS}
+            WHERE ($1 <% concert_name OR to_tsvector('simple', concert_name) @@ to_tsquery('simple', $2))
+                AND ($3::uuid IS NULL OR owner_id = $3)
+            ORDER BY rank DESC, id LIMIT $4"
+        ))
+        .bind(query.text())
+        .bind(query.prefix_tsquery())
+        .bind(owner_id)
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(Error::TicketFetchFailed)?;
+        tx.commit().await.map_err(Error::TicketFetchFailed)?;
+        Ok(hits
+            .into_iter()
+            .map(|hit| SearchHit {
+                item: hit.ticket.into(),
+                rank: hit.rank,
+            })
+            .collect())
+    }
+
+    async fn get_tickets_by_user(&self, user_id: Uuid) -> Result<Vec<Ticket>> {
+        let ticket: Vec<PgTicket> = sqlx::query_as(&format!(
+            "SELECT {COLUMNS} FROM {TICKETS} WHERE owner_id = $1"
+        ))
+        .bind(user_id)
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(Error::TicketFetchFailed)?;
         if ticket.is_empty() {
             return Err(Error::TicketNotFound);
         }
         Ok(ticket.into_iter().map(|t| t.into()).collect())
     }
 
+    async fn count_tickets_by_user(&self, user_id: Uuid) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar("SELECT count(*) FROM tickets WHERE owner_id = $1")
+            .bind(user_id)
+            .fetch_one(&self.db_pool)
+            .await
+            .map_err(Error::TicketFetchFailed)?;
+        Ok(count)
+    }
+
     async fn create_ticket(&self, new_ticket: NewTicket) -> Result<Uuid> {
-        let created_id = sqlx::query!("INSERT INTO tickets (owner_id, concert_name, concert_date, barcode_data, price) VALUES ($1, $2, $3, $4, $5) returning id",
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::TicketCreationFailed)?;
+        // The ticket and the capacity it takes are written together. The seat is locked
+        // first, as when it is held
+        if let Some(seat_id) = new_ticket.seat_id {
+            check_seats_free(
+                &mut tx,
+                new_ticket.concert_id,
+                &[seat_id],
+                Utc::now(),
+                Error::TicketCreationFailed,
+            )
+            .await?;
+        }
+        let price = match new_ticket.category_id {
+            Some(category_id) => {
+                take_from_category(
+                    &mut tx,
+                    category_id,
+                    new_ticket.concert_id,
+                    1,
+                    Error::TicketCreationFailed,
+                )
+                .await?
+            }
+            None => {
+                check_uncategorized(&mut tx, new_ticket.concert_id, Error::TicketCreationFailed)
+                    .await?;
+                new_ticket.price.ok_or_else(|| {
+                    Error::InvalidTicket("the price is required without a category".to_string())
+                })?
+            }
+        };
+        let created_id = sqlx::query!("INSERT INTO tickets (owner_id, concert_id, seat_id, category_id, barcode_data, price) VALUES ($1, $2, $3, $4, $5, $6) returning id",
             new_ticket.owner_id,
-            new_ticket.concert_name,
-            new_ticket.concert_date,
+            new_ticket.concert_id,
+            new_ticket.seat_id,
+            new_ticket.category_id,
             new_ticket.barcode_data,
-            new_ticket.price
+            price
         )
-            .fetch_one(&self.db_pool)
+            .fetch_one(&mut *tx)
             .await
-            .map_err(Error::TicketCreationFailed)?
+            .map_err(|e| foreign_key_violation(e, Error::TicketCreationFailed))?
             .id;
+        tx.commit().await.map_err(Error::TicketCreationFailed)?;
         Ok(created_id)
     }
 
-    async fn update_ticket(&self, id: Uuid, ticket: NewTicket) -> Result<Uuid> {
-        let updated_id = sqlx::query!("UPDATE tickets SET owner_id = $1, concert_name = $2, concert_date = $3, barcode_data = $4, price = $5, updated_at = $6 WHERE id = $7 returning id",
-            ticket.owner_id,
-            ticket.concert_name,
-            ticket.concert_date,
-            ticket.barcode_data,
-            ticket.price,
+    async fn update_ticket(&self, id: Uuid, changes: TicketChanges, version: i32) -> Result<i32> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::TicketUpdateFailed)?;
+        let current = sqlx::query!(
+            "SELECT concert_id, seat_id, category_id FROM tickets WHERE id = $1 AND version = $2 FOR UPDATE",
+            id,
+            version
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Error::TicketUpdateFailed)?
+        .ok_or(Error::PreconditionFailed)?;
+        let concert_id = changes.concert_id.unwrap_or(current.concert_id);
+        let seat_id = changes.seat_id.unwrap_or(current.seat_id);
+        let category_id = changes.category_id.unwrap_or(current.category_id);
+        if category_id.is_some() && changes.price.is_some() {
+            return Err(Error::InvalidTicket(
+                "a ticket of a category costs the price of its category".to_string(),
+            ));
+        }
+        if let Some(seat_id) = seat_id {
+            if Some(seat_id) != current.seat_id || concert_id != current.concert_id {
+                check_seats_free(
+                    &mut tx,
+                    concert_id,
+                    &[seat_id],
+                    Utc::now(),
+                    Error::TicketUpdateFailed,
+                )
+                .await?;
+            }
+        }
+        // A ticket moved to another category or concert gives its place back
+        let mut price = changes.price;
+        if category_id != current.category_id || concert_id != current.concert_id {
+            let moved: Vec<Uuid> = current.category_id.into_iter().chain(category_id).collect();
+            lock_categories(&mut tx, &moved, Error::TicketUpdateFailed).await?;
+            if let Some(previous) = current.category_id {
+                give_back_to_category(&mut tx, previous, 1, Error::TicketUpdateFailed).await?;
+            }
+            match category_id {
+                Some(category_id) => {
+                    let category_price = take_from_category(
+                        &mut tx,
+                        category_id,
+                        concert_id,
+                        1,
+                        Error::TicketUpdateFailed,
+                    )
+                    .await?;
+                    price = Some(category_price);
+                }
+                None => check_uncategorized(&mut tx, concert_id, Error::TicketUpdateFailed).await?,
+            }
+        }
+        let updated = sqlx::query!("UPDATE tickets SET owner_id = COALESCE($1, owner_id), concert_id = $2,
+            seat_id = CASE WHEN $3 THEN $4 ELSE seat_id END, category_id = $5, barcode_data = COALESCE($6, barcode_data),
+            price = COALESCE($7, price), updated_at = $8, version = version + 1 WHERE id = $9 returning version",
+            changes.owner_id,
+            concert_id,
+            changes.seat_id.is_some(),
+            changes.seat_id.flatten(),
+            category_id,
+            changes.barcode_data,
+            price,
             Utc::now(),
             id
         )
-            .fetch_one(&self.db_pool)
+            .fetch_one(&mut *tx)
             .await
-            .map_err(Error::TicketUpdateFailed)?
-            .id;
-        Ok(updated_id)
+            .map_err(|e| foreign_key_violation(e, Error::TicketUpdateFailed))?;
+        tx.commit().await.map_err(Error::TicketUpdateFailed)?;
+        Ok(updated.version)
     }
 
-    async fn delete_ticket(&self, id: Uuid) -> Result<()> {
-        sqlx::query!("DELETE FROM tickets WHERE id = $1 returning id", id)
-            .fetch_one(&self.db_pool)
+    async fn delete_ticket(&self, id: Uuid, version: i32) -> Result<()> {
+        let mut tx = self
+            .db_pool
+            .begin()
             .await
             .map_err(Error::TicketDeletionFailed)?;
+        let deleted = sqlx::query!(
+            "DELETE FROM tickets WHERE id = $1 AND version = $2 returning category_id",
+            id,
+            version
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Error::TicketDeletionFailed)?
+        .ok_or(Error::PreconditionFailed)?;
+        if let Some(category_id) = deleted.category_id {
+            give_back_to_category(&mut tx, category_id, 1, Error::TicketDeletionFailed).await?;
+        }
+        tx.commit().await.map_err(Error::TicketDeletionFailed)?;
         Ok(())
     }
 }
 
+/// Tell the tickets and orders of a missing concert, seat, category or hold, or of a seat
+/// already sold for the concert, apart from the other failures
+pub(crate) fn foreign_key_violation(e: sqlx::Error, otherwise: fn(sqlx::Error) -> Error) -> Error {
+    let constraint = e
+        .as_database_error()
+        .and_then(|db_error| db_error.constraint());
+    match constraint {
+        Some("tickets_concert_id_fkey" | "order_items_concert_id_fkey") => Error::ConcertNotFound,
+        Some("tickets_seat_id_fkey") => Error::SeatNotFound,
+        Some("tickets_concert_id_seat_id_key") => Error::SeatTaken,
+        Some("order_items_category_id_fkey") => Error::CategoryNotFound,
+        Some("orders_hold_id_fkey") => Error::HoldNotFound,
+        _ => otherwise(e),
+    }
+}
+
+/// Tickets of one category issued at once, from a hold or a paid order
+pub(crate) struct IssuedTickets {
+    pub owner_id: Uuid,
+    pub concert_id: Uuid,
+    pub category_id: Uuid,
+    pub price: f64,
+    /// The seats of the tickets, empty for unnumbered tickets
+    pub seat_ids: Vec<Uuid>,
+    /// One per ticket
+    pub barcodes: Vec<String>,
+    pub order_id: Option<Uuid>,
+}
+
+/// Insert the tickets in one statement, the seats and categories are already taken.
+/// Returns the ids of the tickets
+pub(crate) async fn insert_tickets(
+    conn: &mut PgConnection,
+    tickets: IssuedTickets,
+    otherwise: fn(sqlx::Error) -> Error,
+) -> Result<Vec<Uuid>> {
+    // The unnumbered tickets get no seat
+    let seat_ids: Vec<Option<Uuid>> = if tickets.seat_ids.is_empty() {
+        vec![None; tickets.barcodes.len()]
+    } else {
+        tickets.seat_ids.into_iter().map(Some).collect()
+    };
+    sqlx::query_scalar(
+        "INSERT INTO tickets (owner_id, concert_id, seat_id, category_id, barcode_data, price, order_id)
+        SELECT $1, $2, seat_id, $3, barcode_data, $4, $5 FROM UNNEST($6::uuid[], $7::text[]) AS t(seat_id, barcode_data)
+        returning id",
+    )
+    .bind(tickets.owner_id)
+    .bind(tickets.concert_id)
+    .bind(tickets.category_id)
+    .bind(tickets.price)
+    .bind(tickets.order_id)
+    .bind(seat_ids)
+    .bind(tickets.barcodes)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| foreign_key_violation(e, otherwise))
+}
+
+fn push_filter(builder: &mut QueryBuilder<'_, Postgres>, filter: &TicketFilter) {
+    if let Some(owner_id) = filter.owner_id {
+        builder.push(" AND owner_id = ").push_bind(owner_id);
+    }
+    if let Some(concert_id) = filter.concert_id {
+        builder.push(" AND concert_id = ").push_bind(concert_id);
+    }
+    if let Some(concert_name) = &filter.concert_name {
+        builder
+            .push(" AND lower(concert_name) = lower(")
+            .push_bind(concert_name.clone())
+            .push(")");
+    }
+    if let Some(from) = filter.concert_date_from {
+        builder.push(" AND concert_date >= ").push_bind(from);
+    }
+    if let Some(to) = filter.concert_date_to {
+        builder.push(" AND concert_date < ").push_bind(to);
+    }
+    if let Some(min) = filter.price_min {
+        builder.push(" AND price >= ").push_bind(min);
+    }
+    if let Some(max) = filter.price_max {
+        builder.push(" AND price <= ").push_bind(max);
+    }
+}
+
 impl PgTicketsModel {
     pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
         let db_pool = PgPoolOptions::new()
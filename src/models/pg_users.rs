@@ -1,38 +1,69 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgPool};
+use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgPool, Postgres, QueryBuilder};
 use uuid::Uuid;
 
 use crate::domain::{
     errors::{Error, Result},
     types::{
-        user_types::{InternalUseUser, NewUser, User},
-        PasswordHash, Role, Username,
+        pagination::Page,
+        search_types::{SearchHit, SearchQuery},
+        user_types::{
+            InternalUseUser, NewUser, User, UserChanges, UserFilter, UserQuery, UserSortField,
+        },
+        Email, PasswordHash, Role, Username,
     },
 };
 
-use super::users::UsersModel;
+use super::{pg_pagination::push_page, users::UsersModel};
 
 #[derive(Serialize, Deserialize, FromRow)]
 pub struct PgUser {
     pub id: Uuid,
     pub username: String,
+    pub email: Option<String>,
+    pub email_verified_at: Option<DateTime<Utc>>,
     pub role: String,
+    pub version: i32,
 
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(FromRow)]
+struct PgUserHit {
+    #[sqlx(flatten)]
+    user: PgUser,
+    rank: f32,
+}
+
 impl From<PgUser> for User {
     fn from(user: PgUser) -> User {
         User {
             id: user.id,
-            username: Username::new(&user.username).unwrap(),
+            username: unsafe { Username::new_unchecked(user.username) },
+            email: user.email.map(|e| unsafe { Email::new_unchecked(e) }),
+            email_verified: user.email_verified_at.is_some(),
             role: Role::new(user.role).unwrap(),
+            version: user.version,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
         }
     }
 }
 
+/// Tell the duplicated usernames and emails apart from the other failures
+fn unique_violation(e: sqlx::Error, otherwise: fn(sqlx::Error) -> Error) -> Error {
+    let constraint = e
+        .as_database_error()
+        .and_then(|db_error| db_error.constraint());
+    match constraint {
+        Some("users_username_key") => Error::UsernameAlreadyExists,
+        Some("users_email_key") => Error::EmailAlreadyExists,
+        _ => otherwise(e),
+    }
+}
+
 #[derive(Serialize, Deserialize, FromRow)]
 pub struct PgInternalUseUser {
     pub id: Uuid,
@@ -45,7 +76,7 @@ impl From<PgInternalUseUser> for InternalUseUser {
     fn from(user: PgInternalUseUser) -> InternalUseUser {
         InternalUseUser {
             id: user.id,
-            username: Username::new(&user.username).unwrap(),
+            username: unsafe { Username::new_unchecked(user.username) },
             password_hash: unsafe { PasswordHash::new_unchecked(user.password_hash) },
             role: Role::new(user.role).unwrap(),
         }
@@ -66,18 +97,42 @@ pub struct PgUsersModel {
 
 #[async_trait::async_trait]
 impl UsersModel for PgUsersModel {
-    async fn get_users(&self) -> Result<Vec<User>> {
-        let users: Vec<PgUser> =
-            sqlx::query_as("SELECT id, username, role, created_at, updated_at FROM users")
-                .fetch_all(&self.db_pool)
+    async fn query_users(&self, query: UserQuery) -> Result<Page<User>> {
+        let mut builder = QueryBuilder::new("SELECT id, username, email::text, email_verified_at, role, version, created_at, updated_at FROM users WHERE true");
+        push_filter(&mut builder, &query.filter);
+        let sql_type = match query.page.sort.field {
+            UserSortField::CreatedAt => "timestamptz",
+            UserSortField::Username => "citext",
+        };
+        push_page(&mut builder, &query.page, sql_type);
+        let users: Vec<PgUser> = builder
+            .build_query_as()
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(Error::UserFetchFailed)?;
+
+        let total = if query.page.with_total {
+            let mut builder = QueryBuilder::new("SELECT count(*) FROM users WHERE true");
+            push_filter(&mut builder, &query.filter);
+            let total: i64 = builder
+                .build_query_scalar()
+                .fetch_one(&self.db_pool)
                 .await
                 .map_err(Error::UserFetchFailed)?;
-        Ok(users.into_iter().map(|u| u.into()).collect())
+            Some(total)
+        } else {
+            None
+        };
+        let users = users.into_iter().map(User::from).collect();
+        let field = query.page.sort.field;
+        Ok(Page::from_items(users, &query.page, total, |u| {
+            (field.value(u), u.id)
+        }))
     }
 
     async fn get_user(&self, id: Uuid) -> Result<User> {
         let user: Option<PgUser> = sqlx::query_as(
-            "SELECT id, username, role, created_at, updated_at FROM users WHERE id = $1",
+            "SELECT id, username, email::text, email_verified_at, role, version, created_at, updated_at FROM users WHERE id = $1",
         )
         .bind(id)
         .fetch_optional(&self.db_pool)
@@ -89,9 +144,37 @@ impl UsersModel for PgUsersModel {
         }
     }
 
+    async fn search_users(
+        &self,
+        query: &SearchQuery,
+        user_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<SearchHit<User>>> {
+        let hits: Vec<PgUserHit> = sqlx::query_as(
+            "SELECT id, username, email::text, email_verified_at, role, version, created_at, updated_at,
+                greatest(similarity($1, username::text), word_similarity($1, username::text)) AS rank
+            FROM users
+            WHERE (username::text % $1 OR $1 <% username::text) AND ($2::uuid IS NULL OR id = $2)
+            ORDER BY rank DESC, id LIMIT $3",
+        )
+        .bind(query.text())
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(Error::UserFetchFailed)?;
+        Ok(hits
+            .into_iter()
+            .map(|hit| SearchHit {
+                item: hit.user.into(),
+                rank: hit.rank,
+            })
+            .collect())
+    }
+
     async fn get_user_by_username(&self, username: String) -> Result<User> {
         let user: Option<PgUser> = sqlx::query_as(
-            "SELECT id, username, role, created_at, updated_at FROM users WHERE username = $1",
+            "SELECT id, username, email::text, email_verified_at, role, version, created_at, updated_at FROM users WHERE username = $1::citext",
         )
         .bind(username)
         .fetch_optional(&self.db_pool)
@@ -105,7 +188,7 @@ impl UsersModel for PgUsersModel {
 
     async fn get_user_by_username_interal(&self, username: String) -> Result<InternalUseUser> {
         let user: Option<PgInternalUseUser> = sqlx::query_as(
-            "SELECT id, username, password_hash, role FROM users WHERE username = $1",
+            "SELECT id, username, password_hash, role FROM users WHERE username = $1::citext",
         )
         .bind(username)
         .fetch_optional(&self.db_pool)
@@ -117,45 +200,117 @@ impl UsersModel for PgUsersModel {
         }
     }
 
+    async fn get_user_internal(&self, id: Uuid) -> Result<InternalUseUser> {
+        let user: Option<PgInternalUseUser> =
+            sqlx::query_as("SELECT id, username, password_hash, role FROM users WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.db_pool)
+                .await
+                .map_err(Error::UserFetchFailed)?;
+        match user {
+            Some(user) => Ok(user.into()),
+            None => Err(Error::UserNotFound),
+        }
+    }
+
     async fn create_user(&self, user: NewUser) -> Result<Uuid> {
         let res = sqlx::query!(
-            "INSERT INTO users (username, password_hash, role) VALUES ($1, $2, $3) returning id",
+            "INSERT INTO users (username, email, password_hash, role) VALUES ($1, $2::text, $3, $4) returning id",
             user.username.as_ref(),
+            user.email.as_ref() as &str,
             user.password_hash.expose_secret(),
             user.role.as_ref(),
         )
         .fetch_one(&self.db_pool)
         .await
-        .map_err(Error::UserCreationFailed)?
+        .map_err(|e| unique_violation(e, Error::UserCreationFailed))?
         .id;
         Ok(res)
     }
 
-    async fn update_user(&self, id: Uuid, user: NewUser) -> Result<Uuid> {
+    async fn update_user(&self, id: Uuid, changes: UserChanges, version: i32) -> Result<i32> {
+        // A new address has to be verified again
         let res = sqlx::query!(
-                "UPDATE users SET username = $1, password_hash = $2, role = $3, updated_at = $4 WHERE id = $5 returning id",
-                user.username.as_ref(),
-                user.password_hash.expose_secret(),
-                user.role.as_ref(),
+                "UPDATE users SET username = COALESCE($1::text, username), email = COALESCE($2::text, email),
+                role = COALESCE($3, role), updated_at = $4,
+                email_verified_at = CASE WHEN $2::text IS NULL OR email = $2::text::citext THEN email_verified_at END,
+                version = version + 1
+                WHERE id = $5 AND version = $6 returning version",
+                changes.username.as_ref().map(|u| u.as_ref()),
+                changes.email.as_ref().map(|e| e.as_ref()),
+                changes.role.as_ref().map(|r| r.as_ref()),
                 Utc::now(),
-                id
+                id,
+                version
             )
-            .fetch_one(&self.db_pool)
+            .fetch_optional(&self.db_pool)
             .await
-            .map_err(Error::UserUpdateFailed)?
-            .id;
-        Ok(res)
+            .map_err(|e| unique_violation(e, Error::UserUpdateFailed))?;
+        match res {
+            Some(res) => Ok(res.version),
+            None => Err(Error::PreconditionFailed),
+        }
     }
 
-    async fn delete_user(&self, id: Uuid) -> Result<()> {
-        sqlx::query!("DELETE FROM users WHERE id = $1", id)
-            .execute(&self.db_pool)
-            .await
-            .map_err(Error::UserDeletionFailed)?;
+    async fn update_password(&self, id: Uuid, password_hash: PasswordHash) -> Result<()> {
+        let res = sqlx::query!(
+            "UPDATE users SET password_hash = $1, updated_at = $2, version = version + 1 WHERE id = $3",
+            password_hash.expose_secret(),
+            Utc::now(),
+            id
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::UserUpdateFailed)?;
+        if res.rows_affected() == 0 {
+            return Err(Error::UserNotFound);
+        }
+        Ok(())
+    }
+
+    async fn verify_email(&self, id: Uuid, email: Email) -> Result<bool> {
+        let res = sqlx::query!(
+            "UPDATE users SET email_verified_at = $1, version = version + 1 WHERE id = $2 AND email = $3::text::citext",
+            Utc::now(),
+            id,
+            email.as_ref() as &str,
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::UserUpdateFailed)?;
+        Ok(res.rows_affected() == 1)
+    }
+
+    async fn delete_user(&self, id: Uuid, version: i32) -> Result<()> {
+        let res = sqlx::query!(
+            "DELETE FROM users WHERE id = $1 AND version = $2",
+            id,
+            version
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(Error::UserDeletionFailed)?;
+        if res.rows_affected() == 0 {
+            return Err(Error::PreconditionFailed);
+        }
         Ok(())
     }
 }
 
+fn push_filter(builder: &mut QueryBuilder<'_, Postgres>, filter: &UserFilter) {
+    if let Some(role) = &filter.role {
+        builder
+            .push(" AND role = ")
+            .push_bind(role.as_ref().to_string());
+    }
+    if let Some(from) = filter.created_from {
+        builder.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(to) = filter.created_to {
+        builder.push(" AND created_at < ").push_bind(to);
+    }
+}
+
 impl PgUsersModel {
     pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
         let db_pool = PgPoolOptions::new()
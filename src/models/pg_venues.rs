@@ -0,0 +1,320 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgPoolOptions, prelude::FromRow, PgConnection, PgPool, QueryBuilder};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::{Error, Result},
+    types::{
+        pagination::Page,
+        venue_types::{
+            NewSeat, NewVenue, Seat, SeatingChart, Venue, VenueChanges, VenueQuery, VenueSortField,
+        },
+    },
+};
+use crate::models::{pg_pagination::push_page, venues::VenuesModel};
+
+#[derive(FromRow)]
+pub struct PgVenue {
+    pub id: Uuid,
+    pub name: String,
+    pub address: Option<String>,
+    pub version: i32,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<PgVenue> for Venue {
+    fn from(venue: PgVenue) -> Self {
+        Venue {
+            id: venue.id,
+            name: venue.name,
+            address: venue.address,
+            version: venue.version,
+            created_at: venue.created_at,
+            updated_at: venue.updated_at,
+        }
+    }
+}
+
+#[derive(FromRow)]
+pub struct PgSeat {
+    pub id: Uuid,
+    pub venue_id: Uuid,
+    pub section: String,
+    pub row: String,
+    pub number: String,
+}
+
+impl From<PgSeat> for Seat {
+    fn from(seat: PgSeat) -> Self {
+        Seat {
+            id: seat.id,
+            venue_id: seat.venue_id,
+            section: seat.section,
+            row: seat.row,
+            number: seat.number,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct PgConcertSeat {
+    #[sqlx(flatten)]
+    seat: PgSeat,
+    available: bool,
+}
+
+pub struct PgVenuesModel {
+    db_pool: PgPool,
+}
+
+#[async_trait]
+impl VenuesModel for PgVenuesModel {
+    async fn query_venues(&self, query: VenueQuery) -> Result<Page<Venue>> {
+        let mut builder = QueryBuilder::new(
+            "SELECT id, name, address, version, created_at, updated_at FROM venues WHERE true",
+        );
+        let sql_type = match query.page.sort.field {
+            VenueSortField::Name => "text",
+            VenueSortField::CreatedAt => "timestamptz",
+        };
+        push_page(&mut builder, &query.page, sql_type);
+        let venues: Vec<PgVenue> = builder
+            .build_query_as()
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(Error::VenueFetchFailed)?;
+
+        let total = if query.page.with_total {
+            let total: i64 = sqlx::query_scalar("SELECT count(*) FROM venues")
+                .fetch_one(&self.db_pool)
+                .await
+                .map_err(Error::VenueFetchFailed)?;
+            Some(total)
+        } else {
+            None
+        };
+        let venues = venues.into_iter().map(Venue::from).collect();
+        let field = query.page.sort.field;
+        Ok(Page::from_items(venues, &query.page, total, |v| {
+            (field.value(v), v.id)
+        }))
+    }
+
+    async fn get_venue(&self, id: Uuid) -> Result<Venue> {
+        let venue: Option<PgVenue> = sqlx::query_as(
+            "SELECT id, name, address, version, created_at, updated_at FROM venues WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(Error::VenueFetchFailed)?;
+        match venue {
+            Some(venue) => Ok(venue.into()),
+            None => Err(Error::VenueNotFound),
+        }
+    }
+
+    async fn get_seats(&self, venue_id: Uuid) -> Result<Vec<Seat>> {
+        let seats: Vec<PgSeat> = sqlx::query_as(
+            "SELECT id, venue_id, section, row, number FROM seats WHERE venue_id = $1 ORDER BY position",
+        )
+        .bind(venue_id)
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(Error::VenueFetchFailed)?;
+        Ok(seats.into_iter().map(Seat::from).collect())
+    }
+
+    async fn get_seat(&self, id: Uuid) -> Result<Seat> {
+        let seat: Option<PgSeat> =
+            sqlx::query_as("SELECT id, venue_id, section, row, number FROM seats WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.db_pool)
+                .await
+                .map_err(Error::VenueFetchFailed)?;
+        match seat {
+            Some(seat) => Ok(seat.into()),
+            None => Err(Error::SeatNotFound),
+        }
+    }
+
+    async fn get_concert_seats(
+        &self,
+        venue_id: Uuid,
+        concert_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<(Seat, bool)>> {
+        let seats: Vec<PgConcertSeat> = sqlx::query_as(
+            "SELECT seats.id, seats.venue_id, seats.section, seats.row, seats.number,
+                tickets.id IS NULL AND NOT EXISTS (SELECT 1 FROM hold_seats JOIN holds ON holds.id = hold_seats.hold_id
+                    WHERE hold_seats.concert_id = $2 AND hold_seats.seat_id = seats.id AND holds.expires_at > $3) AS available
+            FROM seats LEFT JOIN tickets ON tickets.seat_id = seats.id AND tickets.concert_id = $2
+            WHERE seats.venue_id = $1 ORDER BY seats.position",
+        )
+        .bind(venue_id)
+        .bind(concert_id)
+        .bind(now)
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(Error::VenueFetchFailed)?;
+        Ok(seats
+            .into_iter()
+            .map(|s| (s.seat.into(), s.available))
+            .collect())
+    }
+
+    async fn count_seated_tickets(&self, concert_id: Uuid) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT count(*) FROM tickets WHERE concert_id = $1 AND seat_id IS NOT NULL",
+        )
+        .bind(concert_id)
+        .fetch_one(&self.db_pool)
+        .await
+        .map_err(Error::VenueFetchFailed)?;
+        Ok(count)
+    }
+
+    async fn create_venue(&self, venue: NewVenue) -> Result<Uuid> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::VenueCreationFailed)?;
+        let created_id = sqlx::query!(
+            "INSERT INTO venues (name, address) VALUES ($1, $2) returning id",
+            venue.name,
+            venue.address
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Error::VenueCreationFailed)?
+        .id;
+        insert_seats(&mut tx, created_id, venue.seating_chart.seats())
+            .await
+            .map_err(Error::VenueCreationFailed)?;
+        tx.commit().await.map_err(Error::VenueCreationFailed)?;
+        Ok(created_id)
+    }
+
+    async fn update_venue(&self, id: Uuid, changes: VenueChanges, version: i32) -> Result<i32> {
+        let updated = sqlx::query!(
+            "UPDATE venues SET name = COALESCE($1, name),
+            address = CASE WHEN $2 THEN $3 ELSE address END, updated_at = $4, version = version + 1
+            WHERE id = $5 AND version = $6 returning version",
+            changes.name,
+            changes.address.is_some(),
+            changes.address.flatten(),
+            Utc::now(),
+            id,
+            version
+        )
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(Error::VenueUpdateFailed)?;
+        match updated {
+            Some(updated) => Ok(updated.version),
+            None => Err(Error::PreconditionFailed),
+        }
+    }
+
+    async fn replace_seating_chart(
+        &self,
+        id: Uuid,
+        chart: SeatingChart,
+        version: i32,
+    ) -> Result<i32> {
+        let mut tx = self
+            .db_pool
+            .begin()
+            .await
+            .map_err(Error::VenueUpdateFailed)?;
+        let updated = sqlx::query!(
+            "UPDATE venues SET updated_at = $1, version = version + 1 WHERE id = $2 AND version = $3 returning version",
+            Utc::now(),
+            id,
+            version
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Error::VenueUpdateFailed)?
+        .ok_or(Error::PreconditionFailed)?;
+        sqlx::query!("DELETE FROM seats WHERE venue_id = $1", id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| foreign_key_violation(e, Error::VenueUpdateFailed))?;
+        insert_seats(&mut tx, id, chart.seats())
+            .await
+            .map_err(Error::VenueUpdateFailed)?;
+        tx.commit().await.map_err(Error::VenueUpdateFailed)?;
+        Ok(updated.version)
+    }
+
+    async fn delete_venue(&self, id: Uuid, version: i32) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM venues WHERE id = $1 AND version = $2 returning id",
+            id,
+            version
+        )
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(|e| foreign_key_violation(e, Error::VenueDeletionFailed))?
+        .ok_or(Error::PreconditionFailed)?;
+        Ok(())
+    }
+}
+
+/// Insert every seat in one statement, a chart can hold thousands of them
+async fn insert_seats(
+    conn: &mut PgConnection,
+    venue_id: Uuid,
+    seats: Vec<NewSeat>,
+) -> std::result::Result<(), sqlx::Error> {
+    let mut sections = Vec::with_capacity(seats.len());
+    let mut rows = Vec::with_capacity(seats.len());
+    let mut numbers = Vec::with_capacity(seats.len());
+    let mut positions = Vec::with_capacity(seats.len());
+    for seat in seats {
+        sections.push(seat.section);
+        rows.push(seat.row);
+        numbers.push(seat.number);
+        positions.push(seat.position);
+    }
+    sqlx::query!(
+        "INSERT INTO seats (venue_id, section, row, number, position)
+        SELECT $1, * FROM UNNEST($2::text[], $3::text[], $4::text[], $5::int4[])",
+        venue_id,
+        &sections,
+        &rows,
+        &numbers,
+        &positions
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// The concerts taking place in a venue and the tickets and holds on its seats keep them
+/// from being removed
+fn foreign_key_violation(e: sqlx::Error, otherwise: fn(sqlx::Error) -> Error) -> Error {
+    let constraint = e
+        .as_database_error()
+        .and_then(|db_error| db_error.constraint());
+    match constraint {
+        Some("concerts_venue_id_fkey") => Error::VenueHasConcerts,
+        Some("tickets_seat_id_fkey" | "hold_seats_seat_id_fkey") => Error::SeatsInUse,
+        _ => otherwise(e),
+    }
+}
+
+impl PgVenuesModel {
+    pub async fn new(db_url: String) -> std::result::Result<Self, sqlx::Error> {
+        let db_pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&db_url)
+            .await?;
+        Ok(Self { db_pool })
+    }
+}
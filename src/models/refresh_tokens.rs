@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Result,
+    types::refresh_token_types::{NewRefreshToken, RefreshToken},
+};
+
+#[async_trait]
+pub trait RefreshTokensModel: Send + Sync {
+    async fn create_refresh_token(&self, token: NewRefreshToken) -> Result<Uuid>;
+
+    /// Consume the refresh token matching `token_hash` and store its replacement in the same family.
+    /// Presenting a token that was already consumed revokes its whole family
+    async fn rotate_refresh_token(
+        &self,
+        token_hash: String,
+        new_token_hash: String,
+        expires_at: DateTime<Utc>,
+    ) -> Result<RefreshToken>;
+
+    /// Revoke the family of the refresh token matching `token_hash`, if it belongs to the user
+    async fn revoke_refresh_token_family(&self, user_id: Uuid, token_hash: String) -> Result<()>;
+
+    async fn revoke_user_refresh_tokens(&self, user_id: Uuid) -> Result<()>;
+}
@@ -0,0 +1,22 @@
+use async_trait::async_trait;
+
+use crate::domain::{
+    errors::Result,
+    types::revocation_types::{RevokedSession, RevokedToken},
+};
+
+#[async_trait]
+pub trait RevokedTokensModel: Send + Sync {
+    async fn revoke_token(&self, token: RevokedToken) -> Result<()>;
+
+    /// Only the latest revocation of a user is kept
+    async fn revoke_sessions(&self, session: RevokedSession) -> Result<()>;
+
+    async fn get_revoked_tokens(&self) -> Result<Vec<RevokedToken>>;
+
+    async fn get_revoked_sessions(&self) -> Result<Vec<RevokedSession>>;
+
+    /// Remove the revocations of tokens that are expired anyway.
+    /// Returns the number of entries removed
+    async fn purge_expired(&self) -> Result<u64>;
+}
@@ -3,20 +3,39 @@ use uuid::Uuid;
 
 use crate::domain::{
     errors::Result,
-    types::ticket_types::{NewTicket, Ticket},
+    types::{
+        pagination::Page,
+        search_types::{SearchHit, SearchQuery},
+        ticket_types::{NewTicket, Ticket, TicketChanges, TicketQuery},
+    },
 };
 
 #[async_trait]
 pub trait TicketsModel: Send + Sync {
-    async fn get_tickets(&self) -> Result<Vec<Ticket>>;
+    /// A page of the tickets matching the filters of `query`
+    async fn query_tickets(&self, query: TicketQuery) -> Result<Page<Ticket>>;
 
     async fn get_ticket(&self, id: Uuid) -> Result<Ticket>;
 
+    /// The tickets whose concert matches the search, the best first.
+    /// Only the tickets of `owner_id` are searched when it is set
+    async fn search_tickets(
+        &self,
+        query: &SearchQuery,
+        owner_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<SearchHit<Ticket>>>;
+
     async fn get_tickets_by_user(&self, user_id: Uuid) -> Result<Vec<Ticket>>;
 
+    async fn count_tickets_by_user(&self, user_id: Uuid) -> Result<i64>;
+
     async fn create_ticket(&self, ticket: NewTicket) -> Result<Uuid>;
 
-    async fn update_ticket(&self, id: Uuid, ticket: NewTicket) -> Result<Uuid>;
+    /// Only the columns present in `changes` are written, if the ticket is still at
+    /// `version`. Returns the new version
+    async fn update_ticket(&self, id: Uuid, changes: TicketChanges, version: i32) -> Result<i32>;
 
-    async fn delete_ticket(&self, id: Uuid) -> Result<()>;
+    /// Delete the ticket if it is still at `version`
+    async fn delete_ticket(&self, id: Uuid, version: i32) -> Result<()>;
 }
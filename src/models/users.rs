@@ -2,16 +2,31 @@ use uuid::Uuid;
 
 use crate::domain::{
     errors::Result,
-    types::user_types::{InternalUseUser, NewUser, User},
+    types::{
+        pagination::Page,
+        search_types::{SearchHit, SearchQuery},
+        user_types::{InternalUseUser, NewUser, User, UserChanges, UserQuery},
+        Email, PasswordHash,
+    },
 };
 
 #[async_trait::async_trait]
 pub trait UsersModel: Send + Sync {
-    async fn get_users(&self) -> Result<Vec<User>>;
+    /// A page of the users matching the filters of `query`
+    async fn query_users(&self, query: UserQuery) -> Result<Page<User>>;
 
     /// This function is used to get the user without the password hash
     async fn get_user(&self, id: Uuid) -> Result<User>;
 
+    /// The users whose username matches the search, the best first.
+    /// Only `user_id` is searched when it is set
+    async fn search_users(
+        &self,
+        query: &SearchQuery,
+        user_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<SearchHit<User>>>;
+
     /// This function is used to get the user without the password hash
     async fn get_user_by_username(&self, username: String) -> Result<User>;
 
@@ -19,9 +34,22 @@ pub trait UsersModel: Send + Sync {
     /// Do not expose this function to the outside world
     async fn get_user_by_username_interal(&self, username: String) -> Result<InternalUseUser>;
 
+    /// This function is used internally to get the user with the password hash
+    /// Do not expose this function to the outside world
+    async fn get_user_internal(&self, id: Uuid) -> Result<InternalUseUser>;
+
     async fn create_user(&self, user: NewUser) -> Result<Uuid>;
 
-    async fn update_user(&self, id: Uuid, user: NewUser) -> Result<Uuid>;
+    /// Only the columns present in `changes` are written, if the user is still at
+    /// `version`. Returns the new version
+    async fn update_user(&self, id: Uuid, changes: UserChanges, version: i32) -> Result<i32>;
+
+    async fn update_password(&self, id: Uuid, password_hash: PasswordHash) -> Result<()>;
+
+    /// Mark `email` as verified, unless the user changed their address since.
+    /// Returns whether the user was updated
+    async fn verify_email(&self, id: Uuid, email: Email) -> Result<bool>;
 
-    async fn delete_user(&self, id: Uuid) -> Result<()>;
+    /// Delete the user if they are still at `version`
+    async fn delete_user(&self, id: Uuid, version: i32) -> Result<()>;
 }
@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::{
+    errors::Result,
+    types::{
+        pagination::Page,
+        venue_types::{NewVenue, Seat, SeatingChart, Venue, VenueChanges, VenueQuery},
+    },
+};
+
+#[async_trait]
+pub trait VenuesModel: Send + Sync {
+    /// A page of the venues
+    async fn query_venues(&self, query: VenueQuery) -> Result<Page<Venue>>;
+
+    async fn get_venue(&self, id: Uuid) -> Result<Venue>;
+
+    /// The seats of the venue, in the order of its seating chart
+    async fn get_seats(&self, venue_id: Uuid) -> Result<Vec<Seat>>;
+
+    async fn get_seat(&self, id: Uuid) -> Result<Seat>;
+
+    /// The seats of the venue of the concert, in the order of its seating chart,
+    /// along with whether they are still available for the concert: neither sold nor
+    /// held by a hold unexpired at `now`
+    async fn get_concert_seats(
+        &self,
+        venue_id: Uuid,
+        concert_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<(Seat, bool)>>;
+
+    /// How many tickets of the concert are bound to a seat
+    async fn count_seated_tickets(&self, concert_id: Uuid) -> Result<i64>;
+
+    /// Create the venue along with the seats of its chart
+    async fn create_venue(&self, venue: NewVenue) -> Result<Uuid>;
+
+    /// Only the columns present in `changes` are written, if the venue is still at
+    /// `version`. Returns the new version
+    async fn update_venue(&self, id: Uuid, changes: VenueChanges, version: i32) -> Result<i32>;
+
+    /// Replace every seat of the venue by the ones of `chart`, if the venue is still at
+    /// `version` and none of its seats is held by a ticket or a hold. Returns the new version
+    async fn replace_seating_chart(
+        &self,
+        id: Uuid,
+        chart: SeatingChart,
+        version: i32,
+    ) -> Result<i32>;
+
+    /// Delete the venue and its seats if it is still at `version` and no concert takes
+    /// place there
+    async fn delete_venue(&self, id: Uuid, version: i32) -> Result<()>;
+}
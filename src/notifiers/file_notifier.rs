@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::domain::errors::{Error, Result};
+
+use super::notifier::{Notification, Notifier};
+
+/// Writes every notification as a JSON file of a spool directory
+pub struct FileNotifier {
+    spool_dir: PathBuf,
+}
+
+impl FileNotifier {
+    pub fn new(spool_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            spool_dir: spool_dir.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for FileNotifier {
+    async fn send(&self, notification: Notification) -> Result<()> {
+        tokio::fs::create_dir_all(&self.spool_dir)
+            .await
+            .map_err(|e| Error::NotificationFailed(e.to_string()))?;
+        // Prefixed by the date so the files sort in the order they were sent
+        let file_name = format!(
+            "{}-{}.json",
+            Utc::now().format("%Y%m%dT%H%M%S%.6f"),
+            Uuid::new_v4()
+        );
+        let content = serde_json::to_vec_pretty(&notification)
+            .map_err(|e| Error::NotificationFailed(e.to_string()))?;
+        tokio::fs::write(self.spool_dir.join(file_name), content)
+            .await
+            .map_err(|e| Error::NotificationFailed(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_notifications_are_spooled() {
+        let spool_dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        let notifier = FileNotifier::new(&spool_dir);
+        notifier
+            .send(Notification {
+                recipient: "username".to_string(),
+                subject: "subject".to_string(),
+                body: "body".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let mut files = std::fs::read_dir(&spool_dir).unwrap();
+        let file = files.next().unwrap().unwrap();
+        let content: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(file.path()).unwrap()).unwrap();
+        assert_eq!("username", content["recipient"]);
+        assert_eq!("body", content["body"]);
+        std::fs::remove_dir_all(spool_dir).unwrap();
+    }
+}
@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+
+use crate::domain::errors::Result;
+
+use super::notifier::{Notification, Notifier};
+
+/// Prints the notifications on the standard output, for local development only
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn send(&self, notification: Notification) -> Result<()> {
+        println!(
+            "Notification to {}: {}\n{}",
+            notification.recipient, notification.subject, notification.body
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+pub mod file_notifier;
+pub mod log_notifier;
+pub mod notifier;
+pub mod smtp_notifier;
+
+use crate::{
+    domain::errors::{Error, Result},
+    Cfg,
+};
+
+use self::{
+    file_notifier::FileNotifier, log_notifier::LogNotifier, notifier::Notifier,
+    smtp_notifier::SmtpNotifier,
+};
+
+/// Build the notifier selected by `NOTIFIER`
+pub fn from_config(config: &Cfg) -> Result<Box<dyn Notifier>> {
+    match config.notifier.as_str() {
+        "log" => Ok(Box::new(LogNotifier)),
+        "file" => {
+            let spool_dir = config.notifier_spool_dir.clone().ok_or_else(|| {
+                Error::InternalError("NOTIFIER_SPOOL_DIR is required by the file notifier".into())
+            })?;
+            Ok(Box::new(FileNotifier::new(spool_dir)))
+        }
+        "smtp" => Ok(Box::new(SmtpNotifier::from_config(config)?)),
+        other => Err(Error::InternalError(format!("Unknown notifier {other}"))),
+    }
+}
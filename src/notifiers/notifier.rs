@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+use serde::Serialize;
+
+use crate::domain::errors::Result;
+
+/// A message sent to a user outside of the API, such as a password reset token
+#[derive(Serialize, Debug)]
+pub struct Notification {
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn send(&self, notification: Notification) -> Result<()>;
+}
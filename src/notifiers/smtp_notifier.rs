@@ -0,0 +1,122 @@
+use async_trait::async_trait;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::{
+    domain::errors::{Error, Result},
+    Cfg,
+};
+
+use super::notifier::{Notification, Notifier};
+
+/// Sends the notifications as plain text emails through an SMTP relay
+pub struct SmtpNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpNotifier {
+    pub fn new(transport: AsyncSmtpTransport<Tokio1Executor>, from: Mailbox) -> Self {
+        Self { transport, from }
+    }
+
+    /// Build the transport from the `SMTP_*` settings
+    pub fn from_config(config: &Cfg) -> Result<Self> {
+        let host = config.smtp_host.as_deref().ok_or_else(|| {
+            Error::InternalError("SMTP_HOST is required by the smtp notifier".into())
+        })?;
+        let from = config
+            .smtp_from
+            .as_deref()
+            .ok_or_else(|| {
+                Error::InternalError("SMTP_FROM is required by the smtp notifier".into())
+            })?
+            .parse::<Mailbox>()
+            .map_err(|e| Error::InternalError(format!("Invalid SMTP_FROM: {e}")))?;
+        let mut builder = match config.smtp_tls.as_str() {
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(host),
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host),
+            "none" => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                host,
+            )),
+            other => {
+                return Err(Error::InternalError(format!(
+                    "Unknown SMTP_TLS {other}, expected tls, starttls or none"
+                )))
+            }
+        }
+        .map_err(|e| Error::InternalError(format!("Invalid SMTP relay {host}: {e}")))?;
+        if let Some(port) = config.smtp_port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(Self::new(builder.build(), from))
+    }
+
+    fn message(&self, notification: Notification) -> Result<Message> {
+        let to = notification.recipient.parse::<Mailbox>().map_err(|e| {
+            Error::NotificationFailed(format!("invalid recipient {}: {e}", notification.recipient))
+        })?;
+        Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(notification.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(notification.body)
+            .map_err(|e| Error::NotificationFailed(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl Notifier for SmtpNotifier {
+    async fn send(&self, notification: Notification) -> Result<()> {
+        let message = self.message(notification)?;
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| Error::NotificationFailed(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notifier() -> SmtpNotifier {
+        SmtpNotifier::new(
+            AsyncSmtpTransport::<Tokio1Executor>::unencrypted_localhost(),
+            "Tickets <noreply@example.com>".parse().unwrap(),
+        )
+    }
+
+    fn notification(recipient: &str) -> Notification {
+        Notification {
+            recipient: recipient.to_string(),
+            subject: "subject".to_string(),
+            body: "body".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_message() {
+        let message = notifier()
+            .message(notification("john.doe@example.com"))
+            .unwrap();
+        let formatted = String::from_utf8(message.formatted()).unwrap();
+        assert!(formatted.contains("From: Tickets <noreply@example.com>"));
+        assert!(formatted.contains("To: john.doe@example.com"));
+        assert!(formatted.contains("Subject: subject"));
+        assert!(formatted.ends_with("body"));
+    }
+
+    #[test]
+    fn test_message_invalid_recipient() {
+        assert!(notifier().message(notification("username")).is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use crate::{handlers, AppState};
+
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply, Filter};
+
+use super::{with_auth, with_state};
+
+pub fn get_api_key_routes(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    create_api_key(app_state.clone())
+        .or(get_api_keys(app_state.clone()))
+        .or(revoke_api_key(app_state))
+}
+
+fn create_api_key(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / "me" / "api-keys")
+        .and(warp::post())
+        .and(with_auth(app_state.clone()))
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::api_keys::create_api_key)
+}
+
+fn get_api_keys(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / "me" / "api-keys")
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::api_keys::get_api_keys)
+}
+
+fn revoke_api_key(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / "me" / "api-keys" / Uuid)
+        .and(warp::delete())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::api_keys::revoke_api_key)
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use crate::{handlers, AppState};
+
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply, Filter};
+
+use super::{with_auth, with_merge_patch, with_state};
+
+pub fn get_category_routes(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    get_concert_categories(app_state.clone())
+        .or(create_category(app_state.clone()))
+        .or(get_by_id(app_state.clone()))
+        .or(update_category(app_state.clone()))
+        .or(replace_category(app_state.clone()))
+        .or(delete_category(app_state))
+}
+
+fn get_concert_categories(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("concerts" / Uuid / "categories")
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::categories::get_concert_categories)
+}
+
+fn create_category(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("concerts" / Uuid / "categories")
+        .and(warp::post())
+        .and(with_auth(app_state.clone()))
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::categories::create_category)
+}
+
+fn get_by_id(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("categories" / Uuid)
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-none-match"))
+        .and(with_state(app_state))
+        .and_then(handlers::categories::get_category_by_id)
+}
+
+fn update_category(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("categories" / Uuid)
+        .and(warp::patch())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(with_merge_patch())
+        .and(with_state(app_state))
+        .and_then(handlers::categories::update_category)
+}
+
+fn replace_category(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("categories" / Uuid)
+        .and(warp::put())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::categories::replace_category)
+}
+
+fn delete_category(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("categories" / Uuid)
+        .and(warp::delete())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(with_state(app_state))
+        .and_then(handlers::categories::delete_category)
+}
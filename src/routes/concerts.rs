@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use crate::{handlers, AppState};
+
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply, Filter};
+
+use super::{with_auth, with_merge_patch, with_state};
+
+pub fn get_concert_routes(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    get_all_concerts(app_state.clone())
+        .or(get_by_id(app_state.clone()))
+        .or(get_seats(app_state.clone()))
+        .or(create_concert(app_state.clone()))
+        .or(update_concert(app_state.clone()))
+        .or(replace_concert(app_state.clone()))
+        .or(delete_concert(app_state))
+}
+
+fn get_all_concerts(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("concerts")
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(warp::query())
+        .and(with_state(app_state))
+        .and_then(handlers::concerts::get_all_concerts)
+}
+
+fn get_by_id(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("concerts" / Uuid)
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-none-match"))
+        .and(with_state(app_state))
+        .and_then(handlers::concerts::get_concert_by_id)
+}
+
+fn get_seats(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("concerts" / Uuid / "seats")
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::concerts::get_concert_seats)
+}
+
+fn create_concert(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("concerts")
+        .and(warp::post())
+        .and(with_auth(app_state.clone()))
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::concerts::create_concert)
+}
+
+fn update_concert(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("concerts" / Uuid)
+        .and(warp::patch())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(with_merge_patch())
+        .and(with_state(app_state))
+        .and_then(handlers::concerts::update_concert)
+}
+
+fn replace_concert(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("concerts" / Uuid)
+        .and(warp::put())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::concerts::replace_concert)
+}
+
+fn delete_concert(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("concerts" / Uuid)
+        .and(warp::delete())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(with_state(app_state))
+        .and_then(handlers::concerts::delete_concert)
+}
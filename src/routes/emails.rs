@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use crate::{handlers, AppState};
+
+use warp::{reject::Rejection, reply::Reply, Filter};
+
+use super::{with_auth, with_state};
+
+pub fn get_email_routes(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    request_verification(app_state.clone()).or(verify_email(app_state))
+}
+
+fn request_verification(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / "me" / "email" / "verification")
+        .and(warp::post())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::emails::request_verification)
+}
+
+fn verify_email(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("email-verification")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::emails::verify_email)
+}
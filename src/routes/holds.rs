@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use crate::{handlers, AppState};
+
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply, Filter};
+
+use super::{with_auth, with_state};
+
+pub fn get_hold_routes(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    get_by_id(app_state.clone())
+        .or(create_hold(app_state.clone()))
+        .or(release_hold(app_state))
+}
+
+fn get_by_id(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("holds" / Uuid)
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::holds::get_hold_by_id)
+}
+
+fn create_hold(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("holds")
+        .and(warp::post())
+        .and(with_auth(app_state.clone()))
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::holds::create_hold)
+}
+
+fn release_hold(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("holds" / Uuid)
+        .and(warp::delete())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::holds::release_hold)
+}
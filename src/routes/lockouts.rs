@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use crate::{handlers, AppState};
+
+use warp::{reject::Rejection, reply::Reply, Filter};
+
+use super::{with_auth, with_state};
+
+pub fn get_lockout_routes(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    get_lockouts(app_state.clone()).or(clear_lockout(app_state))
+}
+
+fn get_lockouts(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("lockouts")
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::lockouts::get_lockouts)
+}
+
+fn clear_lockout(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("lockouts" / String / String)
+        .and(warp::delete())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::lockouts::clear_lockout)
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use crate::{handlers, AppState};
+
+use warp::{reject::Rejection, reply::Reply, Filter};
+
+use super::{with_client_ip, with_partial_auth, with_state};
+
+pub fn get_mfa_routes(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    enroll(app_state.clone())
+        .or(confirm(app_state.clone()))
+        .or(disable(app_state.clone()))
+        .or(login_mfa(app_state))
+}
+
+// The enrollment routes skip the admin 2FA requirement, it could not be met otherwise
+
+fn enroll(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / "me" / "mfa")
+        .and(warp::post())
+        .and(with_partial_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::mfa::enroll)
+}
+
+fn confirm(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / "me" / "mfa" / "confirm")
+        .and(warp::post())
+        .and(with_partial_auth(app_state.clone()))
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::mfa::confirm)
+}
+
+fn disable(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / "me" / "mfa")
+        .and(warp::delete())
+        .and(with_partial_auth(app_state.clone()))
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::mfa::disable)
+}
+
+fn login_mfa(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("login" / "mfa")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_client_ip(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::mfa::login_mfa)
+}
@@ -1,21 +1,52 @@
-use std::sync::Arc;
+use std::{convert::Infallible, sync::Arc};
 
 use warp::{reject::Rejection, reply::Reply, Filter};
 
+mod api_keys;
+mod categories;
+mod concerts;
+mod emails;
+mod holds;
+mod lockouts;
+mod mfa;
+mod orders;
+mod passwords;
+mod search;
 mod tickets;
+mod tokens;
 mod users;
+mod venues;
+mod with_auth;
+mod with_client_ip;
+mod with_merge_patch;
 mod with_state;
 
+use with_auth::{with_auth, with_partial_auth};
+use with_client_ip::with_client_ip;
+use with_merge_patch::with_merge_patch;
 use with_state::with_state;
 
-use crate::AppState;
+use crate::{handlers, AppState};
 
 pub fn get_routes(
     app_state: Arc<AppState>,
-) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+) -> impl Filter<Extract = impl Reply, Error = Infallible> + Clone {
     health()
         .or(tickets::get_ticket_routes(app_state.clone()))
-        .or(users::get_user_routes(app_state))
+        .or(concerts::get_concert_routes(app_state.clone()))
+        .or(venues::get_venue_routes(app_state.clone()))
+        .or(categories::get_category_routes(app_state.clone()))
+        .or(holds::get_hold_routes(app_state.clone()))
+        .or(orders::get_order_routes(app_state.clone()))
+        .or(search::get_search_routes(app_state.clone()))
+        .or(passwords::get_password_routes(app_state.clone()))
+        .or(emails::get_email_routes(app_state.clone()))
+        .or(mfa::get_mfa_routes(app_state.clone()))
+        .or(api_keys::get_api_key_routes(app_state.clone()))
+        .or(users::get_user_routes(app_state.clone()))
+        .or(tokens::get_token_routes(app_state.clone()))
+        .or(lockouts::get_lockout_routes(app_state))
+        .recover(handlers::errors::handle_rejection)
 }
 
 /// A simple health-check route
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use crate::{handlers, AppState};
+
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply, Filter};
+
+use super::{with_auth, with_state};
+
+pub fn get_order_routes(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    get_by_id(app_state.clone())
+        .or(create_order(app_state.clone()))
+        .or(pay_order(app_state.clone()))
+        .or(cancel_order(app_state.clone()))
+        .or(refund_order(app_state.clone()))
+        .or(get_my_orders(app_state.clone()))
+        .or(get_user_orders(app_state))
+}
+
+fn get_by_id(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("orders" / Uuid)
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-none-match"))
+        .and(with_state(app_state))
+        .and_then(handlers::orders::get_order_by_id)
+}
+
+fn create_order(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("orders")
+        .and(warp::post())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("idempotency-key"))
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::orders::create_order)
+}
+
+fn pay_order(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("orders" / Uuid / "pay")
+        .and(warp::post())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::orders::pay_order)
+}
+
+fn cancel_order(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("orders" / Uuid / "cancel")
+        .and(warp::post())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::orders::cancel_order)
+}
+
+fn refund_order(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("orders" / Uuid / "refund")
+        .and(warp::post())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::orders::refund_order)
+}
+
+fn get_my_orders(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / "me" / "orders")
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(warp::query())
+        .and(with_state(app_state))
+        .and_then(handlers::orders::get_my_orders)
+}
+
+fn get_user_orders(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / Uuid / "orders")
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(warp::query())
+        .and(with_state(app_state))
+        .and_then(handlers::orders::get_user_orders)
+}
@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use crate::{handlers, AppState};
+
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply, Filter};
+
+use super::{with_auth, with_state};
+
+pub fn get_password_routes(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    change_password(app_state.clone())
+        .or(request_password_reset(app_state.clone()))
+        .or(reset_password(app_state))
+}
+
+fn change_password(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / "me" / "password")
+        .and(warp::post())
+        .and(with_auth(app_state.clone()))
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::passwords::change_password)
+}
+
+fn request_password_reset(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / Uuid / "password-reset")
+        .and(warp::post())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::passwords::request_password_reset)
+}
+
+fn reset_password(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("password-reset")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::passwords::reset_password)
+}
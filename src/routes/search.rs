@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+use crate::{handlers, AppState};
+
+use warp::{reject::Rejection, reply::Reply, Filter};
+
+use super::{with_auth, with_state};
+
+pub fn get_search_routes(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("search")
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(warp::query())
+        .and(with_state(app_state))
+        .and_then(handlers::search::search)
+}
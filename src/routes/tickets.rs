@@ -5,7 +5,7 @@ use crate::{handlers, AppState};
 use uuid::Uuid;
 use warp::{reject::Rejection, reply::Reply, Filter};
 
-use super::with_state;
+use super::{with_auth, with_merge_patch, with_state};
 
 pub fn get_ticket_routes(
     app_state: Arc<AppState>,
@@ -15,6 +15,7 @@ pub fn get_ticket_routes(
         .or(get_by_user(app_state.clone()))
         .or(create_ticket(app_state.clone()))
         .or(update_ticket(app_state.clone()))
+        .or(replace_ticket(app_state.clone()))
         .or(delete_ticket(app_state))
 }
 
@@ -23,6 +24,8 @@ fn get_all_tickets(
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("tickets")
         .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(warp::query())
         .and(with_state(app_state))
         .and_then(handlers::tickets::get_all_tickets)
 }
@@ -32,6 +35,8 @@ fn get_by_id(
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("tickets" / Uuid)
         .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-none-match"))
         .and(with_state(app_state))
         .and_then(handlers::tickets::get_ticket_by_id)
 }
@@ -41,6 +46,7 @@ fn get_by_user(
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("tickets" / "by-user" / Uuid)
         .and(warp::get())
+        .and(with_auth(app_state.clone()))
         .and(with_state(app_state))
         .and_then(handlers::tickets::get_ticket_by_user_id)
 }
@@ -50,6 +56,8 @@ fn create_ticket(
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("tickets")
         .and(warp::post())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("idempotency-key"))
         .and(warp::body::json())
         .and(with_state(app_state))
         .and_then(handlers::tickets::create_ticket)
@@ -60,16 +68,32 @@ fn update_ticket(
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("tickets" / Uuid)
         .and(warp::patch())
-        .and(warp::body::json())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(with_merge_patch())
         .and(with_state(app_state))
         .and_then(handlers::tickets::update_ticket)
 }
 
+fn replace_ticket(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("tickets" / Uuid)
+        .and(warp::put())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::tickets::replace_ticket)
+}
+
 fn delete_ticket(
     app_state: Arc<AppState>,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("tickets" / Uuid)
         .and(warp::delete())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
         .and(with_state(app_state))
         .and_then(handlers::tickets::delete_ticket)
 }
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use crate::{handlers, AppState};
+
+use warp::{reject::Rejection, reply::Reply, Filter};
+
+use super::{with_partial_auth, with_state};
+use crate::domain::dtos::token_dtos::LogoutInputDto;
+
+pub fn get_token_routes(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    refresh(app_state.clone())
+        .or(logout(app_state.clone()))
+        .or(jwks(app_state))
+}
+
+fn refresh(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("token" / "refresh")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::tokens::refresh_token)
+}
+
+fn logout(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    // The body is optional, a client without a refresh token can send none
+    let input = warp::body::json()
+        .or(warp::any().map(LogoutInputDto::default))
+        .unify();
+    warp::path!("logout")
+        .and(warp::post())
+        .and(with_partial_auth(app_state.clone()))
+        .and(input)
+        .and(with_state(app_state))
+        .and_then(handlers::tokens::logout)
+}
+
+fn jwks(app_state: Arc<AppState>) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!(".well-known" / "jwks.json")
+        .and(warp::get())
+        .and(with_state(app_state))
+        .and_then(handlers::tokens::get_jwks)
+}
@@ -5,18 +5,24 @@ use crate::{handlers, AppState};
 use uuid::Uuid;
 use warp::{reject::Rejection, reply::Reply, Filter};
 
-use super::with_state;
+use super::{with_auth, with_client_ip, with_merge_patch, with_state};
 
 pub fn get_user_routes(
     app_state: Arc<AppState>,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     get_all_users(app_state.clone())
+        .or(get_me(app_state.clone()))
+        .or(update_me(app_state.clone()))
+        .or(delete_me(app_state.clone()))
+        .or(get_my_tickets(app_state.clone()))
         .or(get_by_id(app_state.clone()))
         .or(get_by_username(app_state.clone()))
         .or(create_user(app_state.clone())) // Should I delete this?
         .or(update_user(app_state.clone()))
+        .or(replace_user(app_state.clone()))
         .or(register(app_state.clone()))
         .or(delete_user(app_state.clone()))
+        .or(revoke_sessions(app_state.clone()))
         .or(login(app_state))
 }
 
@@ -25,15 +31,62 @@ fn get_all_users(
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("users")
         .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(warp::query())
         .and(with_state(app_state))
         .and_then(handlers::users::get_all_users)
 }
 
+fn get_me(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / "me")
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::users::get_me)
+}
+
+fn update_me(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / "me")
+        .and(warp::patch())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(with_merge_patch())
+        .and(with_state(app_state))
+        .and_then(handlers::users::update_me)
+}
+
+fn delete_me(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / "me")
+        .and(warp::delete())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(with_state(app_state))
+        .and_then(handlers::users::delete_me)
+}
+
+fn get_my_tickets(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / "me" / "tickets")
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::tickets::get_my_tickets)
+}
+
 fn get_by_id(
     app_state: Arc<AppState>,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("users" / Uuid)
         .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-none-match"))
         .and(with_state(app_state))
         .and_then(handlers::users::get_user_by_id)
 }
@@ -43,6 +96,7 @@ fn get_by_username(
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("users" / "by-username" / String)
         .and(warp::get())
+        .and(with_auth(app_state.clone()))
         .and(with_state(app_state))
         .and_then(handlers::users::get_user_by_username)
 }
@@ -52,9 +106,10 @@ fn create_user(
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("users")
         .and(warp::post())
+        .and(with_auth(app_state.clone()))
         .and(warp::body::json())
         .and(with_state(app_state))
-        .and_then(handlers::users::register_user)
+        .and_then(handlers::users::create_user)
 }
 
 fn update_user(
@@ -62,20 +117,46 @@ fn update_user(
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("users" / Uuid)
         .and(warp::patch())
-        .and(warp::body::json())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(with_merge_patch())
         .and(with_state(app_state))
         .and_then(handlers::users::update_user)
 }
 
+fn replace_user(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / Uuid)
+        .and(warp::put())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::users::replace_user)
+}
+
 fn delete_user(
     app_state: Arc<AppState>,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("users" / Uuid)
         .and(warp::delete())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
         .and(with_state(app_state))
         .and_then(handlers::users::delete_user)
 }
 
+fn revoke_sessions(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("users" / Uuid / "sessions")
+        .and(warp::delete())
+        .and(with_auth(app_state.clone()))
+        .and(with_state(app_state))
+        .and_then(handlers::users::revoke_user_sessions)
+}
+
 fn register(
     app_state: Arc<AppState>,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
@@ -90,6 +171,7 @@ fn login(app_state: Arc<AppState>) -> impl Filter<Extract = impl Reply, Error =
     warp::path!("login")
         .and(warp::post())
         .and(warp::body::json())
+        .and(with_client_ip(app_state.clone()))
         .and(with_state(app_state))
         .and_then(handlers::users::login_user)
 }
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use crate::{handlers, AppState};
+
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply, Filter};
+
+use super::{with_auth, with_merge_patch, with_state};
+
+pub fn get_venue_routes(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    get_all_venues(app_state.clone())
+        .or(get_by_id(app_state.clone()))
+        .or(create_venue(app_state.clone()))
+        .or(update_venue(app_state.clone()))
+        .or(replace_seating_chart(app_state.clone()))
+        .or(delete_venue(app_state))
+}
+
+fn get_all_venues(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("venues")
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(warp::query())
+        .and(with_state(app_state))
+        .and_then(handlers::venues::get_all_venues)
+}
+
+fn get_by_id(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("venues" / Uuid)
+        .and(warp::get())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-none-match"))
+        .and(with_state(app_state))
+        .and_then(handlers::venues::get_venue_by_id)
+}
+
+fn create_venue(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("venues")
+        .and(warp::post())
+        .and(with_auth(app_state.clone()))
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::venues::create_venue)
+}
+
+fn update_venue(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("venues" / Uuid)
+        .and(warp::patch())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(with_merge_patch())
+        .and(with_state(app_state))
+        .and_then(handlers::venues::update_venue)
+}
+
+fn replace_seating_chart(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("venues" / Uuid / "seating-chart")
+        .and(warp::put())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(warp::body::json())
+        .and(with_state(app_state))
+        .and_then(handlers::venues::replace_seating_chart)
+}
+
+fn delete_venue(
+    app_state: Arc<AppState>,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("venues" / Uuid)
+        .and(warp::delete())
+        .and(with_auth(app_state.clone()))
+        .and(warp::header::optional::<String>("if-match"))
+        .and(with_state(app_state))
+        .and_then(handlers::venues::delete_venue)
+}
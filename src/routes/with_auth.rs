@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use warp::{http::Method, path::FullPath, reject::Rejection, Filter};
+
+use crate::{
+    domain::{
+        errors::Error,
+        policy,
+        types::{api_key_types::ScopeResource, JwtClaims},
+    },
+    handlers::api_keys::{authenticate_api_key, API_KEY_PREFIX},
+    AppState,
+};
+
+use super::with_state;
+
+/// This function is used to require a valid bearer token or API key on a route.
+/// The decoded claims are passed to the handler functions
+pub fn with_auth(
+    state: Arc<AppState>,
+) -> impl Filter<Extract = (JwtClaims,), Error = Rejection> + Clone {
+    with_partial_auth(state.clone()).and_then(move |claims: JwtClaims| {
+        let state = state.clone();
+        async move {
+            policy::authorize_mfa(&claims, state.require_admin_mfa)?;
+            Ok::<_, Rejection>(claims)
+        }
+    })
+}
+
+/// Like `with_auth`, but also lets in the admins who have yet to set up the
+/// two-factor authentication the configuration requires, so they can enroll
+pub fn with_partial_auth(
+    state: Arc<AppState>,
+) -> impl Filter<Extract = (JwtClaims,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and(warp::method())
+        .and(warp::path::full())
+        .and(with_state(state))
+        .and_then(authenticate)
+}
+
+async fn authenticate(
+    header: Option<String>,
+    method: Method,
+    path: FullPath,
+    state: Arc<AppState>,
+) -> Result<JwtClaims, Rejection> {
+    let header =
+        header.ok_or_else(|| Error::Unauthorized("missing authorization header".to_string()))?;
+    let token = header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| Error::Unauthorized("expected a bearer token".to_string()))?;
+    let token = token.trim();
+    let claims = if token.starts_with(API_KEY_PREFIX) {
+        authenticate_api_key(&state, token).await?
+    } else {
+        let claims = state.jwt_handler.validate_token(token)?;
+        if state.revocation_list.is_revoked(&claims) {
+            return Err(Error::Unauthorized("token revoked".to_string()).into());
+        }
+        claims
+    };
+    let write = !matches!(method, Method::GET | Method::HEAD);
+    policy::authorize_api_key_scope(&claims, ScopeResource::of_path(path.as_str()), write)?;
+    Ok(claims)
+}
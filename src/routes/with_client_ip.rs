@@ -0,0 +1,27 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use warp::{reject::Rejection, Filter};
+
+use crate::AppState;
+
+/// This function is used to pass the address of the client to the handler functions.
+/// The last `X-Forwarded-For` entry is used instead of the peer address when
+/// `TRUST_FORWARDED_FOR` is set: it is the one appended by the trusted proxy, the
+/// entries before it come from the client and can be forged
+pub fn with_client_ip(
+    state: Arc<AppState>,
+) -> impl Filter<Extract = (Option<IpAddr>,), Error = Rejection> + Clone {
+    warp::addr::remote()
+        .and(warp::header::optional::<String>("x-forwarded-for"))
+        .map(
+            move |remote: Option<SocketAddr>, forwarded_for: Option<String>| {
+                let forwarded = forwarded_for
+                    .filter(|_| state.trust_forwarded_for)
+                    .and_then(|header| header.rsplit(',').next()?.trim().parse().ok());
+                forwarded.or(remote.map(|addr| addr.ip()))
+            },
+        )
+}
@@ -0,0 +1,41 @@
+use serde::de::DeserializeOwned;
+use warp::{
+    http::header::CONTENT_TYPE,
+    hyper::body::Bytes,
+    reject::{self, Rejection},
+    Filter,
+};
+
+use crate::domain::errors::Error;
+
+const MERGE_PATCH: &str = "application/merge-patch+json";
+const JSON: &str = "application/json";
+
+/// This function is used to pass a JSON merge patch (RFC 7396) to the handler functions.
+/// It is sent as `application/merge-patch+json`, plain JSON and a missing
+/// content type are accepted too
+pub fn with_merge_patch<T>() -> impl Filter<Extract = (T,), Error = Rejection> + Clone
+where
+    T: DeserializeOwned + Send,
+{
+    warp::header::optional::<String>(CONTENT_TYPE.as_str())
+        .and_then(|content_type: Option<String>| async move {
+            let Some(content_type) = content_type else {
+                return Ok(());
+            };
+            let mime = content_type.split(';').next().unwrap_or_default().trim();
+            if mime.eq_ignore_ascii_case(MERGE_PATCH) || mime.eq_ignore_ascii_case(JSON) {
+                Ok(())
+            } else {
+                Err(reject::custom(Error::UnsupportedMediaType(format!(
+                    "expected {MERGE_PATCH}"
+                ))))
+            }
+        })
+        .untuple_one()
+        .and(warp::body::bytes())
+        .and_then(|body: Bytes| async move {
+            serde_json::from_slice(&body)
+                .map_err(|e| reject::custom(Error::InvalidPatch(e.to_string())))
+        })
+}
@@ -1,26 +1,123 @@
+use std::sync::Arc;
+
+use chrono::Duration;
+
 use crate::{
-    handlers::jwt_handler::JwtHandler,
-    models::{tickets::TicketsModel, users::UsersModel},
+    clock::{Clock, SystemClock},
+    domain::password_policy::PasswordPolicy,
+    handlers::{
+        jwt_handler::JwtHandler, login_throttle::LoginThrottle, password_hasher::PasswordHasher,
+        revocation_list::RevocationList,
+    },
+    models::{
+        api_keys::ApiKeysModel, categories::CategoriesModel, concerts::ConcertsModel,
+        email_verifications::EmailVerificationsModel, holds::HoldsModel,
+        idempotency_keys::IdempotencyKeysModel, login_attempts::LoginAttemptsModel, mfa::MfaModel,
+        orders::OrdersModel, password_resets::PasswordResetsModel,
+        refresh_tokens::RefreshTokensModel, revoked_tokens::RevokedTokensModel,
+        tickets::TicketsModel, users::UsersModel, venues::VenuesModel,
+    },
+    notifiers::{self, notifier::Notifier},
+    Cfg,
 };
 
 pub struct AppState {
     pub jwt_handler: JwtHandler,
+    pub password_hasher: PasswordHasher,
+    pub password_policy: PasswordPolicy,
+    pub revocation_list: RevocationList,
+    pub login_throttle: LoginThrottle,
     pub user_model: Box<dyn UsersModel>,
     pub ticket_model: Box<dyn TicketsModel>,
+    pub concert_model: Box<dyn ConcertsModel>,
+    pub venue_model: Box<dyn VenuesModel>,
+    pub category_model: Box<dyn CategoriesModel>,
+    pub hold_model: Box<dyn HoldsModel>,
+    pub order_model: Box<dyn OrdersModel>,
+    pub refresh_token_model: Box<dyn RefreshTokensModel>,
+    pub password_reset_model: Box<dyn PasswordResetsModel>,
+    pub email_verification_model: Box<dyn EmailVerificationsModel>,
+    pub mfa_model: Box<dyn MfaModel>,
+    pub api_key_model: Box<dyn ApiKeysModel>,
+    pub idempotency_key_model: Box<dyn IdempotencyKeysModel>,
+    pub notifier: Box<dyn Notifier>,
+    pub access_token_ttl: Duration,
+    pub refresh_token_ttl: Duration,
+    pub password_reset_ttl: Duration,
+    pub email_verification_ttl: Duration,
+    pub idempotency_key_ttl: Duration,
+    pub hold_ttl: Duration,
+    pub trust_forwarded_for: bool,
+    pub totp_issuer: String,
+    pub require_admin_mfa: bool,
+    pub mfa_challenge_ttl: Duration,
+    /// Time of the TOTP codes, MFA challenges, holds and orders, replaced in the tests
+    pub clock: Arc<dyn Clock>,
 }
 
 impl AppState {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        jwt_secret: String,
+        config: &Cfg,
         user_model: Box<dyn UsersModel>,
         ticket_model: Box<dyn TicketsModel>,
+        concert_model: Box<dyn ConcertsModel>,
+        venue_model: Box<dyn VenuesModel>,
+        category_model: Box<dyn CategoriesModel>,
+        hold_model: Box<dyn HoldsModel>,
+        order_model: Box<dyn OrdersModel>,
+        refresh_token_model: Box<dyn RefreshTokensModel>,
+        revoked_tokens_model: Box<dyn RevokedTokensModel>,
+        password_reset_model: Box<dyn PasswordResetsModel>,
+        login_attempts_model: Box<dyn LoginAttemptsModel>,
+        mfa_model: Box<dyn MfaModel>,
+        api_key_model: Box<dyn ApiKeysModel>,
+        email_verification_model: Box<dyn EmailVerificationsModel>,
+        idempotency_key_model: Box<dyn IdempotencyKeysModel>,
     ) -> Self {
-        let jwt_handler = JwtHandler::new(jwt_secret).expect("cannot create jwt handler");
+        let jwt_handler = JwtHandler::from_config(config).expect("cannot create jwt handler");
+        let notifier = notifiers::from_config(config).expect("cannot create notifier");
+        let password_hasher =
+            PasswordHasher::from_config(config).expect("cannot create password hasher");
+        let password_policy =
+            PasswordPolicy::from_config(config).expect("cannot load the password policy");
 
         AppState {
             jwt_handler,
+            password_hasher,
+            password_policy,
+            revocation_list: RevocationList::new(revoked_tokens_model),
+            login_throttle: LoginThrottle::new(login_attempts_model, config),
             user_model,
             ticket_model,
+            concert_model,
+            venue_model,
+            category_model,
+            hold_model,
+            order_model,
+            refresh_token_model,
+            password_reset_model,
+            email_verification_model,
+            mfa_model,
+            api_key_model,
+            idempotency_key_model,
+            notifier,
+            access_token_ttl: config.access_token_ttl(),
+            refresh_token_ttl: config.refresh_token_ttl(),
+            password_reset_ttl: config.password_reset_ttl(),
+            email_verification_ttl: config.email_verification_ttl(),
+            idempotency_key_ttl: config.idempotency_key_ttl(),
+            hold_ttl: config.hold_ttl(),
+            trust_forwarded_for: config.trust_forwarded_for,
+            totp_issuer: config.totp_issuer.clone(),
+            require_admin_mfa: config.require_admin_mfa,
+            mfa_challenge_ttl: config.mfa_challenge_ttl(),
+            clock: Arc::new(SystemClock),
         }
     }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
 }
@@ -0,0 +1,42 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::task::JoinHandle;
+
+use crate::AppState;
+
+/// Periodically purge the expired revoked tokens and reload the revocation cache.
+/// The stale failed login attempts and the expired idempotency keys are purged along the way
+pub fn spawn_revocation_sweep(app_state: Arc<AppState>, every: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(every);
+        loop {
+            interval.tick().await;
+            if let Err(e) = app_state.revocation_list.sweep().await {
+                eprintln!("Failed to sweep revoked tokens: {e}");
+            }
+            if let Err(e) = app_state.login_throttle.purge_stale().await {
+                eprintln!("Failed to purge the login attempts: {e}");
+            }
+            if let Err(e) = app_state.idempotency_key_model.purge_expired().await {
+                eprintln!("Failed to purge the idempotency keys: {e}");
+            }
+        }
+    })
+}
+
+/// Periodically give the tickets of the expired holds back to their categories
+pub fn spawn_hold_sweep(app_state: Arc<AppState>, every: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(every);
+        loop {
+            interval.tick().await;
+            if let Err(e) = app_state
+                .hold_model
+                .release_expired_holds(app_state.clock.now())
+                .await
+            {
+                eprintln!("Failed to release the expired holds: {e}");
+            }
+        }
+    })
+}
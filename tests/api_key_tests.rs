@@ -0,0 +1,206 @@
+mod helper;
+use chrono::{Duration, Utc};
+use helper::{generate_token, insert_concert, insert_user, spawn_app, TestApp};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+async fn create_key(
+    test_app: &TestApp,
+    client: &reqwest::Client,
+    token: &str,
+    body: Value,
+) -> reqwest::Response {
+    client
+        .post(format!("{}/users/me/api-keys", test_app.address))
+        .bearer_auth(token)
+        .body(body.to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn get(test_app: &TestApp, client: &reqwest::Client, path: &str, key: &str) -> StatusCode {
+    client
+        .get(format!("{}{}", test_app.address, path))
+        .bearer_auth(key)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+}
+
+#[tokio::test]
+async fn api_key_lifecycle_works() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+    let token = generate_token(&test_app, user_id, "test1", "user");
+
+    let response = create_key(&test_app, &client, &token, json!({ "name": "scanner" })).await;
+    assert!(response.status().is_success());
+    let created = response.json::<Value>().await.unwrap();
+    let key = created["key"].as_str().unwrap().to_string();
+    assert!(key.starts_with("iom_"));
+    assert!(created["scopes"].is_null());
+
+    // Only the hash of the key is stored
+    let saved = sqlx::query!("SELECT key_hash FROM api_keys")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_ne!(key, saved.key_hash);
+
+    let path = format!("/users/{}", user_id);
+    assert!(get(&test_app, &client, &path, &key).await.is_success());
+
+    // Without scopes, the key can only read
+    let response = client
+        .delete(format!("{}{}", test_app.address, path))
+        .bearer_auth(&key)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+
+    let response = client
+        .get(format!("{}/users/me/api-keys", test_app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let keys = response.json::<Value>().await.unwrap();
+    assert_eq!(1, keys.as_array().unwrap().len());
+    assert_eq!("scanner", keys[0]["name"]);
+    assert!(key.starts_with(keys[0]["hint"].as_str().unwrap()));
+    assert!(keys[0].get("key").is_none());
+    assert!(keys[0]["last_used_at"].is_string());
+
+    // A key cannot manage the keys
+    let response = create_key(&test_app, &client, &key, json!({ "name": "other" })).await;
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+
+    let response = client
+        .delete(format!(
+            "{}/users/me/api-keys/{}",
+            test_app.address,
+            created["id"].as_str().unwrap()
+        ))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    assert_eq!(
+        StatusCode::UNAUTHORIZED,
+        get(&test_app, &client, &path, &key).await
+    );
+}
+
+#[tokio::test]
+async fn api_keys_are_restricted_to_their_scopes() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let concert_id = insert_concert(&test_app, "Trivium").await;
+    let client = reqwest::Client::new();
+    let token = generate_token(&test_app, user_id, "test1", "user");
+
+    let response = create_key(
+        &test_app,
+        &client,
+        &token,
+        json!({ "name": "scanner", "scopes": ["tickets:delete"] }),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response = create_key(
+        &test_app,
+        &client,
+        &token,
+        json!({ "name": "scanner", "scopes": ["tickets:read"] }),
+    )
+    .await;
+    let key = response.json::<Value>().await.unwrap()["key"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let path = format!("/tickets/by-user/{}", user_id);
+    assert!(get(&test_app, &client, &path, &key).await.is_success());
+    let path = format!("/concerts/{}", concert_id);
+    assert!(get(&test_app, &client, &path, &key).await.is_success());
+    // The tickets of the user are tickets, the user is not
+    assert!(get(&test_app, &client, "/users/me/tickets", &key)
+        .await
+        .is_success());
+    let path = format!("/users/{}", user_id);
+    assert_eq!(
+        StatusCode::FORBIDDEN,
+        get(&test_app, &client, &path, &key).await
+    );
+    let response = client
+        .post(format!("{}/tickets", test_app.address))
+        .bearer_auth(&key)
+        .body(
+            json!({
+                "owner_id": user_id,
+                "concert_id": concert_id,
+                "barcode_data": "12345-abcde-67890",
+                "price": 50.0,
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+}
+
+#[tokio::test]
+async fn expired_or_unknown_api_keys_are_rejected() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+    let token = generate_token(&test_app, user_id, "test1", "user");
+
+    let response = create_key(
+        &test_app,
+        &client,
+        &token,
+        json!({ "name": "scanner", "expires_at": Utc::now() - Duration::minutes(1) }),
+    )
+    .await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response = create_key(
+        &test_app,
+        &client,
+        &token,
+        json!({ "name": "scanner", "expires_at": Utc::now() + Duration::days(1) }),
+    )
+    .await;
+    let key = response.json::<Value>().await.unwrap()["key"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let path = format!("/users/{}", user_id);
+    assert!(get(&test_app, &client, &path, &key).await.is_success());
+
+    sqlx::query!(
+        "UPDATE api_keys SET expires_at = $1",
+        Utc::now() - Duration::minutes(1)
+    )
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to update the db.");
+    assert_eq!(
+        StatusCode::UNAUTHORIZED,
+        get(&test_app, &client, &path, &key).await
+    );
+    let unknown = format!("iom_{}", Uuid::new_v4().simple());
+    assert_eq!(
+        StatusCode::UNAUTHORIZED,
+        get(&test_app, &client, &path, &unknown).await
+    );
+}
@@ -1,6 +1,12 @@
 mod helper;
 
-use helper::spawn_app;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use helper::{generate_token, insert_concert, insert_user, spawn_app};
+use iomentum_backend_practice::{
+    domain::types::JwtClaims,
+    handlers::jwt_handler::{JwtHandler, DEFAULT_AUDIENCE, DEFAULT_ISSUER},
+};
+use reqwest::StatusCode;
 use serde_json::json;
 
 #[tokio::test]
@@ -14,7 +20,8 @@ async fn crud_users_works() {
         .body(
             json!({
                 "username": "test1",
-                "password": "test1234",
+                "email": "test1@example.com",
+                "password": "secret1234",
                 "role": "user",
             })
             .to_string(),
@@ -36,15 +43,32 @@ async fn crud_users_works() {
         .send()
         .await
         .expect("Failed to execute request.");
-    assert!(!response.status().is_success());
-    assert!(response.text().await.unwrap().contains("Wrong password"));
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    let wrong_password = response.text().await.unwrap();
+    assert!(wrong_password.contains("invalid credentials"));
+
+    // An unknown username cannot be told apart from a wrong password
+    let response = client
+        .post(format!("{}/login", test_app.address))
+        .body(
+            json!({
+                "username": "unknown",
+                "password": "wrongpassword",
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    assert_eq!(wrong_password, response.text().await.unwrap());
 
     let response = client
         .post(format!("{}/login", test_app.address))
         .body(
             json!({
                 "username": "test1",
-                "password": "test1234",
+                "password": "secret1234",
             })
             .to_string(),
         )
@@ -52,8 +76,10 @@ async fn crud_users_works() {
         .await
         .expect("Failed to execute request.");
     assert!(response.status().is_success());
-    let jwt = response.text().await.unwrap();
-    let jwt = jwt.trim_matches('"');
+    let data = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!("Bearer", data["token_type"]);
+    assert!(data["refresh_token"].is_string());
+    let jwt = data["access_token"].as_str().unwrap();
     let jwt_claims = test_app
         .app_state
         .jwt_handler
@@ -63,4 +89,153 @@ async fn crud_users_works() {
     assert_eq!("user", jwt_claims.role);
     assert!(jwt_claims.exp > chrono::Utc::now().timestamp());
     assert!(jwt_claims.iat <= chrono::Utc::now().timestamp());
+
+    // The registered claims use the JSON types other JWT libraries expect
+    let payload = jwt.split('.').nth(1).unwrap();
+    let payload: serde_json::Value =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+    assert!(payload["exp"].is_i64());
+    assert!(payload["nbf"].is_i64());
+    assert!(payload["iat"].is_i64());
+    assert_eq!(DEFAULT_ISSUER, payload["iss"]);
+    assert_eq!(DEFAULT_AUDIENCE, payload["aud"]);
+}
+
+#[tokio::test]
+async fn mutating_routes_require_a_token() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let concert_id = insert_concert(&test_app, "Trivium").await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .delete(format!("{}/users/{}", test_app.address, user_id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    let response = client
+        .post(format!("{}/tickets", test_app.address))
+        .body(
+            json!({
+                "owner_id": user_id,
+                "concert_id": concert_id,
+                "barcode_data": "12345-abcde-67890",
+                "price": 50.0,
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    let saved = sqlx::query!("SELECT id FROM Users")
+        .fetch_optional(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert!(saved.is_some());
+}
+
+#[tokio::test]
+async fn malformed_token_is_rejected() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .delete(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth("not-a-jwt")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let response = client
+        .delete(format!("{}/users/{}", test_app.address, user_id))
+        .header("Authorization", format!("Basic {token}"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    let data = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!("fail", data["status"]);
+}
+
+#[tokio::test]
+async fn expired_token_is_rejected() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    let mut claims = JwtClaims::new(
+        "test1".to_string(),
+        user_id,
+        "user".to_string(),
+        test_app.app_state.access_token_ttl,
+    );
+    claims.exp = chrono::Utc::now().timestamp() - 60;
+    let token = test_app
+        .app_state
+        .jwt_handler
+        .generate_token(claims)
+        .unwrap();
+
+    let response = client
+        .delete(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    assert!(response.text().await.unwrap().contains("token expired"));
+}
+
+#[tokio::test]
+async fn forged_token_is_rejected() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    let forger = JwtHandler::new("not the server secret".to_string()).unwrap();
+    let claims = JwtClaims::new(
+        "test1".to_string(),
+        user_id,
+        "admin".to_string(),
+        test_app.app_state.access_token_ttl,
+    );
+    let token = forger.generate_token(claims).unwrap();
+
+    let response = client
+        .delete(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+}
+
+#[tokio::test]
+async fn valid_token_is_accepted() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let response = client
+        .delete(format!("{}/users/{}", test_app.address, user_id))
+        .header("If-Match", "*")
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+
+    let saved = sqlx::query!("SELECT id FROM Users")
+        .fetch_optional(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert!(saved.is_none());
 }
@@ -0,0 +1,267 @@
+mod helper;
+use helper::{generate_token, insert_category, insert_concert, insert_user, spawn_app};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn admins_manage_the_categories_of_a_concert() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let client = reqwest::Client::new();
+    let concert_id = insert_concert(&test_app, "Trivium").await;
+
+    let create = |token: &str, concert_id: Uuid, body: Value| {
+        client
+            .post(format!(
+                "{}/concerts/{}/categories",
+                test_app.address, concert_id
+            ))
+            .bearer_auth(token)
+            .body(body.to_string())
+            .send()
+    };
+    let pit = json!({ "name": "Pit", "price": 80.0, "capacity": 2 });
+    let response = create(&token, concert_id, pit.clone()).await.unwrap();
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let response = create(&admin_token, concert_id, pit.clone()).await.unwrap();
+    assert!(response.status().is_success());
+    let pit_id: Uuid = response.json().await.unwrap();
+    let response = create(&admin_token, concert_id, pit.clone()).await.unwrap();
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    let response = create(&admin_token, Uuid::new_v4(), pit).await.unwrap();
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+    let balcony = json!({ "name": "Balcony", "price": 40.0, "capacity": 100 });
+    let response = create(&admin_token, concert_id, balcony).await.unwrap();
+    assert!(response.status().is_success());
+    let response = create(
+        &admin_token,
+        concert_id,
+        json!({ "name": "VIP", "price": -1.0, "capacity": 10 }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let create_ticket = |body: Value| {
+        client
+            .post(format!("{}/tickets", test_app.address))
+            .bearer_auth(&admin_token)
+            .body(body.to_string())
+            .send()
+    };
+    let ticket = |category_id: Option<Uuid>, price: Option<f64>| {
+        json!({
+            "owner_id": user_id,
+            "concert_id": concert_id,
+            "category_id": category_id,
+            "barcode_data": "12345-abcde-67890",
+            "price": price,
+        })
+    };
+    // The tickets of a concert with categories are sold at the price of one
+    for (category_id, price) in [(None, Some(50.0)), (Some(pit_id), Some(50.0))] {
+        let response = create_ticket(ticket(category_id, price)).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+    let response = create_ticket(ticket(Some(pit_id), None)).await.unwrap();
+    assert!(response.status().is_success());
+    let ticket_id: Uuid = response.json().await.unwrap();
+    let saved: Value = client
+        .get(format!("{}/tickets/{}", test_app.address, ticket_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(80.0, saved["price"]);
+    assert_eq!("Pit", saved["category_name"]);
+
+    let categories: Value = client
+        .get(format!(
+            "{}/concerts/{}/categories",
+            test_app.address, concert_id
+        ))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    assert_eq!("Balcony", categories[0]["name"]);
+    assert_eq!("Pit", categories[1]["name"]);
+    assert_eq!(1, categories[1]["sold"]);
+    assert_eq!(1, categories[1]["remaining"]);
+
+    let category = |method: reqwest::Method, body: Option<Value>| {
+        let request = client
+            .request(
+                method,
+                format!("{}/categories/{}", test_app.address, pit_id),
+            )
+            .header("If-Match", "*")
+            .header("Content-Type", "application/merge-patch+json")
+            .bearer_auth(&admin_token);
+        match body {
+            Some(body) => request.body(body.to_string()).send(),
+            None => request.send(),
+        }
+    };
+    let response = category(reqwest::Method::PATCH, Some(json!({ "capacity": 0 })))
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let response = category(reqwest::Method::DELETE, None).await.unwrap();
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    // The price of a category only applies to the tickets sold afterwards
+    let response = category(reqwest::Method::PATCH, Some(json!({ "price": 90.0 })))
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let price = sqlx::query_scalar!("SELECT price FROM tickets WHERE id = $1", ticket_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!(80.0, price);
+
+    let response = client
+        .delete(format!("{}/tickets/{}", test_app.address, ticket_id))
+        .header("If-Match", "*")
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let response = category(reqwest::Method::DELETE, None).await.unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn concurrent_buyers_cannot_oversell_a_category() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let concert_id = insert_concert(&test_app, "Trivium").await;
+    let category_id = sqlx::query!(
+        "INSERT INTO ticket_categories (concert_id, name, price, capacity) VALUES ($1, 'Pit', 80, 3) RETURNING id",
+        concert_id
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to insert a category.")
+    .id;
+
+    let client = reqwest::Client::new();
+    let mut buyers = tokio::task::JoinSet::new();
+    for _ in 0..10 {
+        let request = client
+            .post(format!("{}/tickets", test_app.address))
+            .bearer_auth(&admin_token)
+            .body(
+                json!({
+                    "owner_id": user_id,
+                    "concert_id": concert_id,
+                    "category_id": category_id,
+                    "barcode_data": "12345-abcde-67890",
+                })
+                .to_string(),
+            );
+        buyers.spawn(async move { request.send().await.unwrap().status() });
+    }
+    let mut statuses = vec![];
+    while let Some(status) = buyers.join_next().await {
+        statuses.push(status.unwrap());
+    }
+    assert_eq!(3, statuses.iter().filter(|s| s.is_success()).count());
+    assert_eq!(
+        7,
+        statuses
+            .iter()
+            .filter(|s| **s == StatusCode::CONFLICT)
+            .count()
+    );
+
+    let sold = sqlx::query!(
+        "SELECT sold, (SELECT count(*) FROM tickets WHERE category_id = $1) AS tickets FROM ticket_categories WHERE id = $1",
+        category_id
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch from db.");
+    assert_eq!(3, sold.sold);
+    assert_eq!(Some(3), sold.tickets);
+}
+
+#[tokio::test]
+async fn tickets_can_swap_categories_concurrently() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let concert_id = insert_concert(&test_app, "Trivium").await;
+    let pit = insert_category(&test_app, concert_id, "Pit", 80.0, 20).await;
+    let balcony = insert_category(&test_app, concert_id, "Balcony", 40.0, 20).await;
+    let client = reqwest::Client::new();
+
+    // Tickets of each category, along with their ETag
+    let mut tickets = vec![];
+    for category_id in [pit, balcony].repeat(10) {
+        let id: Uuid = client
+            .post(format!("{}/tickets", test_app.address))
+            .bearer_auth(&admin_token)
+            .body(
+                json!({
+                    "owner_id": user_id,
+                    "concert_id": concert_id,
+                    "category_id": category_id,
+                    "barcode_data": "12345-abcde-67890",
+                })
+                .to_string(),
+            )
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .json()
+            .await
+            .unwrap();
+        let response = client
+            .get(format!("{}/tickets/{}", test_app.address, id))
+            .bearer_auth(&admin_token)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        let etag = response.headers()["etag"].to_str().unwrap().to_string();
+        tickets.push((id, etag, category_id));
+    }
+
+    // Moves in opposite directions wait on each other instead of deadlocking
+    let mut moves = tokio::task::JoinSet::new();
+    for (id, etag, category_id) in tickets {
+        let other = if category_id == pit { balcony } else { pit };
+        let request = client
+            .patch(format!("{}/tickets/{}", test_app.address, id))
+            .bearer_auth(&admin_token)
+            .header("If-Match", etag)
+            .body(json!({ "category_id": other }).to_string());
+        moves.spawn(async move { request.send().await.unwrap().status() });
+    }
+    while let Some(status) = moves.join_next().await {
+        assert!(status.unwrap().is_success());
+    }
+
+    let sold = sqlx::query!(
+        "SELECT sold FROM ticket_categories WHERE id = ANY($1)",
+        &[pit, balcony]
+    )
+    .fetch_all(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch from db.");
+    assert!(sold.iter().all(|category| category.sold == 10));
+}
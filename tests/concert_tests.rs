@@ -0,0 +1,240 @@
+mod helper;
+use helper::{generate_token, insert_user, insert_venue, spawn_app};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn admins_manage_the_concerts_and_users_browse_them() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let client = reqwest::Client::new();
+    let venue_id = insert_venue(&test_app, "Zénith").await;
+
+    let concert = json!({
+        "name": " Trivium ",
+        "date": "2021-08-01T20:00:00Z",
+        "venue_id": venue_id,
+        "capacity": 6000,
+    });
+    let create = |token: &str, body: &Value| {
+        client
+            .post(format!("{}/concerts", test_app.address))
+            .bearer_auth(token)
+            .body(body.to_string())
+            .send()
+    };
+    let response = create(&token, &concert).await.unwrap();
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let response = create(&admin_token, &concert).await.unwrap();
+    assert!(response.status().is_success());
+    let id: Uuid = response.json().await.unwrap();
+
+    let response = client
+        .get(format!("{}/concerts/{}", test_app.address, id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    let saved: Value = response.json().await.unwrap();
+    assert_eq!("Trivium", saved["name"]);
+    assert_eq!(venue_id.to_string(), saved["venue_id"]);
+    assert_eq!(6000, saved["capacity"]);
+    assert!(saved["description"].is_null());
+
+    // The optional fields are removed with null
+    let patch = |token: &str, body: Value| {
+        client
+            .patch(format!("{}/concerts/{}", test_app.address, id))
+            .header("If-Match", "*")
+            .bearer_auth(token)
+            .header("Content-Type", "application/merge-patch+json")
+            .body(body.to_string())
+            .send()
+    };
+    let response = patch(&token, json!({ "name": "Gojira" })).await.unwrap();
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let response = patch(
+        &admin_token,
+        json!({ "capacity": null, "description": "Live" }),
+    )
+    .await
+    .unwrap();
+    assert!(response.status().is_success());
+    assert_ne!(etag, response.headers()["etag"].to_str().unwrap());
+    let saved = sqlx::query!(
+        "SELECT name, venue_id, capacity, description FROM concerts WHERE id = $1",
+        id
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch from db.");
+    assert_eq!("Trivium", saved.name);
+    assert_eq!(Some(venue_id), saved.venue_id);
+    assert_eq!(None, saved.capacity);
+    assert_eq!(Some("Live".to_string()), saved.description);
+    for body in [json!({ "name": null }), json!({ "capacity": 0 })] {
+        let response = patch(&admin_token, body).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+    let response = patch(&admin_token, json!({ "venue_id": Uuid::new_v4() }))
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+    // A replacement clears the optional fields left out
+    let response = client
+        .put(format!("{}/concerts/{}", test_app.address, id))
+        .header("If-Match", "*")
+        .bearer_auth(&admin_token)
+        .body(json!({ "name": "Trivium", "date": "2021-08-02T20:00:00Z" }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let saved = sqlx::query!(
+        "SELECT venue_id, description FROM concerts WHERE id = $1",
+        id
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch from db.");
+    assert!(saved.venue_id.is_none() && saved.description.is_none());
+
+    let response = client
+        .get(format!("{}/concerts/{}", test_app.address, Uuid::new_v4()))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+}
+
+#[tokio::test]
+async fn concerts_with_tickets_cannot_be_deleted() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let client = reqwest::Client::new();
+
+    let concert_id: Uuid = client
+        .post(format!("{}/concerts", test_app.address))
+        .bearer_auth(&admin_token)
+        .body(json!({ "name": "Trivium", "date": "2021-08-01T20:00:00Z" }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    let create_ticket = |concert_id: Uuid| {
+        client
+            .post(format!("{}/tickets", test_app.address))
+            .bearer_auth(&admin_token)
+            .body(
+                json!({
+                    "owner_id": user_id,
+                    "concert_id": concert_id,
+                    "barcode_data": "12345-abcde-67890",
+                    "price": 50.0,
+                })
+                .to_string(),
+            )
+            .send()
+    };
+    let response = create_ticket(Uuid::new_v4()).await.unwrap();
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+    let response = create_ticket(concert_id).await.unwrap();
+    assert!(response.status().is_success());
+    let ticket_id: Uuid = response.json().await.unwrap();
+
+    let delete = |id: String| {
+        client
+            .delete(format!("{}/{}", test_app.address, id))
+            .header("If-Match", "*")
+            .bearer_auth(&admin_token)
+            .send()
+    };
+    let response = delete(format!("concerts/{concert_id}")).await.unwrap();
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    let response = delete(format!("tickets/{ticket_id}")).await.unwrap();
+    assert!(response.status().is_success());
+    let response = delete(format!("concerts/{concert_id}")).await.unwrap();
+    assert!(response.status().is_success());
+    let saved = sqlx::query!("SELECT id FROM concerts")
+        .fetch_optional(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert!(saved.is_none());
+}
+
+#[tokio::test]
+async fn concerts_are_listed_by_pages() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let client = reqwest::Client::new();
+
+    for (name, date) in [
+        ("Trivium", "2021-08-03T20:00:00Z"),
+        ("Gojira", "2021-08-01T20:00:00Z"),
+        ("Metallica", "2021-09-01T20:00:00Z"),
+    ] {
+        sqlx::query!(
+            "INSERT INTO concerts (name, date) VALUES ($1, $2::text::timestamptz)",
+            name,
+            date
+        )
+        .execute(&test_app.db_pool)
+        .await
+        .expect("Failed to insert a concert.");
+    }
+
+    let list = |query: &str| {
+        client
+            .get(format!("{}/concerts?{}", test_app.address, query))
+            .bearer_auth(&token)
+            .send()
+    };
+    let names = |page: &Value| -> Vec<String> {
+        page["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let page: Value = list("limit=2").await.unwrap().json().await.unwrap();
+    assert_eq!(vec!["Gojira", "Trivium"], names(&page));
+    let cursor = page["next_cursor"].as_str().unwrap();
+    let page: Value = list(&format!("limit=2&cursor={cursor}"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(vec!["Metallica"], names(&page));
+    assert!(page["next_cursor"].is_null());
+
+    let page: Value = list("sort=-name&date_to=2021-09-01T00:00:00Z")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(vec!["Trivium", "Gojira"], names(&page));
+    let page: Value = list("name=GOJIRA").await.unwrap().json().await.unwrap();
+    assert_eq!(vec!["Gojira"], names(&page));
+
+    for query in ["limit=0", "sort=venue", "cursor=nope", "unknown=1"] {
+        let response = list(query).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, response.status(), "{query}");
+    }
+}
@@ -0,0 +1,208 @@
+mod helper;
+use helper::{
+    generate_token, insert_concert, insert_user, last_notification, spawn_app,
+    spawn_app_with_spool, TestApp,
+};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+fn verification_token(notification: &Value) -> String {
+    notification["body"]
+        .as_str()
+        .unwrap()
+        .split("address: ")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .unwrap()
+        .to_string()
+}
+
+async fn register(test_app: &TestApp, client: &reqwest::Client, email: &str) -> reqwest::Response {
+    client
+        .post(format!("{}/register", test_app.address))
+        .body(
+            json!({
+                "username": "test1",
+                "email": email,
+                "password": "secret1234",
+                "role": "user",
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn verify(test_app: &TestApp, client: &reqwest::Client, token: &str) -> StatusCode {
+    client
+        .post(format!("{}/email-verification", test_app.address))
+        .body(json!({ "token": token }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+}
+
+async fn create_ticket(
+    test_app: &TestApp,
+    client: &reqwest::Client,
+    token: &str,
+    owner_id: Uuid,
+) -> StatusCode {
+    let concert_id = insert_concert(test_app, "Trivium").await;
+    client
+        .post(format!("{}/tickets", test_app.address))
+        .bearer_auth(token)
+        .body(
+            json!({
+                "owner_id": owner_id,
+                "concert_id": concert_id,
+                "barcode_data": "12345-abcde-67890",
+                "price": 50.0,
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+}
+
+#[tokio::test]
+async fn registered_users_must_verify_their_email_to_get_tickets() {
+    let (test_app, spool_dir) = spawn_app_with_spool(|_| {}).await;
+    let client = reqwest::Client::new();
+
+    let response = register(&test_app, &client, "Test1@Example.com").await;
+    assert!(response.status().is_success());
+    let user_id: Uuid = response.json().await.unwrap();
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let notification = last_notification(&spool_dir);
+    assert_eq!("Test1@Example.com", notification["recipient"]);
+    let verification = verification_token(&notification);
+
+    let response = client
+        .get(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let user = response.json::<Value>().await.unwrap();
+    assert_eq!("Test1@Example.com", user["email"]);
+    assert_eq!(false, user["email_verified"]);
+
+    // Not even an admin can give them a ticket
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    assert_eq!(
+        StatusCode::FORBIDDEN,
+        create_ticket(&test_app, &client, &admin_token, user_id).await
+    );
+
+    assert!(verify(&test_app, &client, &verification).await.is_success());
+    assert!(create_ticket(&test_app, &client, &admin_token, user_id)
+        .await
+        .is_success());
+    // The token can only be used once
+    assert_eq!(
+        StatusCode::BAD_REQUEST,
+        verify(&test_app, &client, &verification).await
+    );
+    std::fs::remove_dir_all(spool_dir).unwrap();
+}
+
+#[tokio::test]
+async fn emails_are_unique_and_validated() {
+    let test_app = spawn_app().await;
+    insert_user(&test_app, "other", "user").await;
+    let client = reqwest::Client::new();
+
+    let response = register(&test_app, &client, "not an email").await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    // The addresses are compared without case
+    let response = register(&test_app, &client, "OTHER@example.com").await;
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    let saved = sqlx::query!("SELECT id FROM users WHERE username = 'test1'")
+        .fetch_optional(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert!(saved.is_none());
+}
+
+#[tokio::test]
+async fn changing_the_email_requires_a_new_verification() {
+    let (test_app, spool_dir) = spawn_app_with_spool(|_| {}).await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let client = reqwest::Client::new();
+
+    let request_verification = || {
+        client
+            .post(format!("{}/users/me/email/verification", test_app.address))
+            .bearer_auth(&token)
+            .send()
+    };
+    let response = request_verification().await.unwrap();
+    assert_eq!(StatusCode::CONFLICT, response.status());
+
+    let update = |email: &str| {
+        client
+            .patch(format!("{}/users/{}", test_app.address, user_id))
+            .header("If-Match", "*")
+            .bearer_auth(&token)
+            .body(
+                json!({
+                    "username": "test1",
+                    "email": email,
+                    "role": "user",
+                })
+                .to_string(),
+            )
+            .send()
+    };
+    assert!(update("new@example.com")
+        .await
+        .unwrap()
+        .status()
+        .is_success());
+    let first_token = verification_token(&last_notification(&spool_dir));
+    assert_eq!(
+        StatusCode::FORBIDDEN,
+        create_ticket(&test_app, &client, &admin_token, user_id).await
+    );
+
+    // A token sent to a previous address is worthless
+    assert!(update("newer@example.com")
+        .await
+        .unwrap()
+        .status()
+        .is_success());
+    assert_eq!(
+        StatusCode::BAD_REQUEST,
+        verify(&test_app, &client, &first_token).await
+    );
+
+    // Asking again invalidates the previous token
+    let second_token = verification_token(&last_notification(&spool_dir));
+    assert!(request_verification().await.unwrap().status().is_success());
+    let notification = last_notification(&spool_dir);
+    assert_eq!("newer@example.com", notification["recipient"]);
+    assert_eq!(
+        StatusCode::BAD_REQUEST,
+        verify(&test_app, &client, &second_token).await
+    );
+    assert!(
+        verify(&test_app, &client, &verification_token(&notification))
+            .await
+            .is_success()
+    );
+    assert!(create_ticket(&test_app, &client, &admin_token, user_id)
+        .await
+        .is_success());
+    std::fs::remove_dir_all(spool_dir).unwrap();
+}
@@ -1,9 +1,25 @@
 use std::net::TcpListener;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use iomentum_backend_practice::handlers::password_hasher;
+use chrono::Utc;
+use iomentum_backend_practice::clock::FixedClock;
+use iomentum_backend_practice::domain::types::JwtClaims;
+use iomentum_backend_practice::models::pg_api_keys::PgApiKeysModel;
+use iomentum_backend_practice::models::pg_categories::PgCategoriesModel;
+use iomentum_backend_practice::models::pg_concerts::PgConcertsModel;
+use iomentum_backend_practice::models::pg_email_verifications::PgEmailVerificationsModel;
+use iomentum_backend_practice::models::pg_holds::PgHoldsModel;
+use iomentum_backend_practice::models::pg_idempotency_keys::PgIdempotencyKeysModel;
+use iomentum_backend_practice::models::pg_login_attempts::PgLoginAttemptsModel;
+use iomentum_backend_practice::models::pg_mfa::PgMfaModel;
+use iomentum_backend_practice::models::pg_orders::PgOrdersModel;
+use iomentum_backend_practice::models::pg_password_resets::PgPasswordResetsModel;
+use iomentum_backend_practice::models::pg_refresh_tokens::PgRefreshTokensModel;
+use iomentum_backend_practice::models::pg_revoked_tokens::PgRevokedTokensModel;
 use iomentum_backend_practice::models::pg_tickets::PgTicketsModel;
 use iomentum_backend_practice::models::pg_users::PgUsersModel;
+use iomentum_backend_practice::models::pg_venues::PgVenuesModel;
 use iomentum_backend_practice::routes::get_routes;
 use iomentum_backend_practice::{AppState, Cfg};
 use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
@@ -15,9 +31,18 @@ pub struct TestApp {
     pub address: String,
     pub app_state: Arc<AppState>,
     pub db_pool: PgPool,
+    /// The clock of the app, it only moves when told to
+    pub clock: Arc<FixedClock>,
 }
 
+#[allow(dead_code)]
 pub async fn spawn_app() -> TestApp {
+    spawn_app_with(|_| {}).await
+}
+
+#[allow(dead_code)]
+/// Spawn the app after letting the caller tweak the configuration
+pub async fn spawn_app_with(configure: impl FnOnce(&mut Cfg)) -> TestApp {
     let available_port;
     {
         let listener = TcpListener::bind("0.0.0.0:0").unwrap();
@@ -26,16 +51,46 @@ pub async fn spawn_app() -> TestApp {
 
     let mut config = Cfg::init();
     config.db_name = Uuid::new_v4().to_string();
+    configure(&mut config);
     let db_pool = configure_database(&config).await;
 
     let ticket_model = PgTicketsModel::new(config.db_url()).await.unwrap();
+    let concert_model = PgConcertsModel::new(config.db_url()).await.unwrap();
+    let venue_model = PgVenuesModel::new(config.db_url()).await.unwrap();
+    let category_model = PgCategoriesModel::new(config.db_url()).await.unwrap();
+    let hold_model = PgHoldsModel::new(config.db_url()).await.unwrap();
+    let order_model = PgOrdersModel::new(config.db_url()).await.unwrap();
     let user_model = PgUsersModel::new(config.db_url()).await.unwrap();
+    let refresh_token_model = PgRefreshTokensModel::new(config.db_url()).await.unwrap();
+    let revoked_tokens_model = PgRevokedTokensModel::new(config.db_url()).await.unwrap();
+    let password_reset_model = PgPasswordResetsModel::new(config.db_url()).await.unwrap();
+    let login_attempts_model = PgLoginAttemptsModel::new(config.db_url()).await.unwrap();
+    let mfa_model = PgMfaModel::new(config.db_url()).await.unwrap();
+    let api_key_model = PgApiKeysModel::new(config.db_url()).await.unwrap();
+    let email_verification_model = PgEmailVerificationsModel::new(config.db_url())
+        .await
+        .unwrap();
+    let idempotency_key_model = PgIdempotencyKeysModel::new(config.db_url()).await.unwrap();
     let app_state = AppState::new(
-        config.jwt_secret,
+        &config,
         Box::new(user_model),
         Box::new(ticket_model),
+        Box::new(concert_model),
+        Box::new(venue_model),
+        Box::new(category_model),
+        Box::new(hold_model),
+        Box::new(order_model),
+        Box::new(refresh_token_model),
+        Box::new(revoked_tokens_model),
+        Box::new(password_reset_model),
+        Box::new(login_attempts_model),
+        Box::new(mfa_model),
+        Box::new(api_key_model),
+        Box::new(email_verification_model),
+        Box::new(idempotency_key_model),
     );
-    let app_state = Arc::new(app_state);
+    let clock = Arc::new(FixedClock::new(Utc::now()));
+    let app_state = Arc::new(app_state.with_clock(clock.clone()));
 
     let routes = get_routes(app_state.clone());
 
@@ -50,9 +105,35 @@ pub async fn spawn_app() -> TestApp {
         address: format!("http://127.0.0.1:{}", available_port),
         app_state,
         db_pool,
+        clock,
     }
 }
 
+#[allow(dead_code)]
+/// Spawn the app with the file notifier, returning the spool directory
+pub async fn spawn_app_with_spool(configure: impl FnOnce(&mut Cfg)) -> (TestApp, PathBuf) {
+    let spool_dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+    let dir = spool_dir.to_str().unwrap().to_string();
+    let test_app = spawn_app_with(|config| {
+        config.notifier = "file".to_string();
+        config.notifier_spool_dir = Some(dir);
+        configure(config);
+    })
+    .await;
+    (test_app, spool_dir)
+}
+
+#[allow(dead_code)]
+/// The last notification written to the spool
+pub fn last_notification(spool_dir: &Path) -> serde_json::Value {
+    let mut files: Vec<_> = std::fs::read_dir(spool_dir)
+        .unwrap()
+        .map(|f| f.unwrap().path())
+        .collect();
+    files.sort();
+    serde_json::from_slice(&std::fs::read(files.last().unwrap()).unwrap()).unwrap()
+}
+
 async fn configure_database(config: &Cfg) -> PgPool {
     let connection_options = PgConnectOptions::new()
         .host("localhost")
@@ -85,18 +166,21 @@ async fn configure_database(config: &Cfg) -> PgPool {
 /// Insert a user into the database
 /// Returns the id of the user
 /// The password will always be "test1234"
+/// The email will be "<username>@example.com", already verified
 /// The arguments must be valid data
 pub async fn insert_user(test_app: &TestApp, username: &str, role: &str) -> Uuid {
     let password = "test1234";
-    let hash = password_hasher::hash_password(password).unwrap();
+    let hash = test_app.app_state.password_hasher.hash(password).unwrap();
+    let email = format!("{username}@example.com");
 
     let user = sqlx::query!(
         r#"
-        INSERT INTO Users (username, password_hash, role)
-        VALUES ($1, $2, $3)
+        INSERT INTO Users (username, email, password_hash, role, email_verified_at)
+        VALUES ($1, $2::text, $3, $4, now())
         RETURNING id
         "#,
         username,
+        email,
         hash,
         role
     )
@@ -106,3 +190,105 @@ pub async fn insert_user(test_app: &TestApp, username: &str, role: &str) -> Uuid
 
     user.id
 }
+
+#[allow(dead_code)]
+/// Insert a concert into the database, on 2021-08-01
+/// Returns the id of the concert
+pub async fn insert_concert(test_app: &TestApp, name: &str) -> Uuid {
+    sqlx::query!(
+        "INSERT INTO concerts (name, date) VALUES ($1, '2021-08-01T00:00:00Z') RETURNING id",
+        name
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .unwrap()
+    .id
+}
+
+#[allow(dead_code)]
+/// Insert a venue without seats into the database
+/// Returns the id of the venue
+pub async fn insert_venue(test_app: &TestApp, name: &str) -> Uuid {
+    sqlx::query!("INSERT INTO venues (name) VALUES ($1) RETURNING id", name)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+#[allow(dead_code)]
+/// Insert a ticket category of the concert into the database
+/// Returns the id of the category
+pub async fn insert_category(
+    test_app: &TestApp,
+    concert_id: Uuid,
+    name: &str,
+    price: f64,
+    capacity: i32,
+) -> Uuid {
+    sqlx::query!(
+        "INSERT INTO ticket_categories (concert_id, name, price, capacity) VALUES ($1, $2, $3, $4) RETURNING id",
+        concert_id,
+        name,
+        price,
+        capacity
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .unwrap()
+    .id
+}
+
+#[allow(dead_code)]
+/// Insert a concert in a venue with a single seat into the database
+/// Returns the ids of the concert and of the seat
+pub async fn insert_seated_concert(test_app: &TestApp) -> (Uuid, Uuid) {
+    let venue_id = insert_venue(test_app, "Zénith").await;
+    let seat_id = sqlx::query!(
+        "INSERT INTO seats (venue_id, section, row, number, position) VALUES ($1, 'Pit', 'A', '1', 0) RETURNING id",
+        venue_id
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to insert a seat.")
+    .id;
+    let concert_id = insert_concert(test_app, "Trivium").await;
+    sqlx::query!(
+        "UPDATE concerts SET venue_id = $1 WHERE id = $2",
+        venue_id,
+        concert_id
+    )
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to move the concert.");
+    (concert_id, seat_id)
+}
+
+#[allow(dead_code)]
+/// The places of the category held and sold
+pub async fn held_and_sold(test_app: &TestApp, category_id: Uuid) -> (i32, i32) {
+    let category = sqlx::query!(
+        "SELECT held, sold FROM ticket_categories WHERE id = $1",
+        category_id
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch from db.");
+    (category.held, category.sold)
+}
+
+#[allow(dead_code)]
+/// Generate a valid bearer token for the given user
+pub fn generate_token(test_app: &TestApp, user_id: Uuid, username: &str, role: &str) -> String {
+    let claims = JwtClaims::new(
+        username.to_string(),
+        user_id,
+        role.to_string(),
+        test_app.app_state.access_token_ttl,
+    );
+    test_app
+        .app_state
+        .jwt_handler
+        .generate_token(claims)
+        .unwrap()
+}
@@ -0,0 +1,394 @@
+mod helper;
+use std::sync::Arc;
+
+use chrono::Duration;
+use helper::{
+    generate_token, held_and_sold, insert_category, insert_seated_concert, insert_user, spawn_app,
+    TestApp,
+};
+use iomentum_backend_practice::clock::Clock;
+use iomentum_backend_practice::domain::{
+    errors::Error,
+    types::{
+        hold_types::NewHold,
+        order_types::{NewOrder, OrderItem},
+        ticket_types::NewTicket,
+    },
+};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+fn new_hold(
+    test_app: &TestApp,
+    user_id: Uuid,
+    concert_id: Uuid,
+    category_id: Uuid,
+    quantity: Option<i32>,
+    seat_ids: Vec<Uuid>,
+) -> NewHold {
+    let expires_at = test_app.clock.now() + test_app.app_state.hold_ttl;
+    NewHold::new(
+        user_id,
+        concert_id,
+        category_id,
+        quantity,
+        seat_ids,
+        expires_at,
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn holds_are_turned_into_tickets() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let other_id = insert_user(&test_app, "test2", "user").await;
+    let other_token = generate_token(&test_app, other_id, "test2", "user");
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let client = reqwest::Client::new();
+    let (concert_id, seat_id) = insert_seated_concert(&test_app).await;
+    let category_id = insert_category(&test_app, concert_id, "Pit", 80.0, 3).await;
+
+    let hold = |token: &str, body: Value| {
+        client
+            .post(format!("{}/holds", test_app.address))
+            .bearer_auth(token)
+            .body(body.to_string())
+            .send()
+    };
+    let response = hold(
+        &token,
+        json!({ "concert_id": concert_id, "category_id": category_id, "seat_ids": [seat_id] }),
+    )
+    .await
+    .unwrap();
+    assert!(response.status().is_success());
+    let seat_hold_id: Uuid = response.json().await.unwrap();
+    // The seat is no longer available, even for the tickets issued directly
+    let response = hold(
+        &other_token,
+        json!({ "concert_id": concert_id, "category_id": category_id, "seat_ids": [seat_id] }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    let response = client
+        .post(format!("{}/tickets", test_app.address))
+        .bearer_auth(&admin_token)
+        .body(
+            json!({
+                "owner_id": other_id,
+                "concert_id": concert_id,
+                "seat_id": seat_id,
+                "category_id": category_id,
+                "barcode_data": "12345-abcde-67890",
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    let seats: Value = client
+        .get(format!(
+            "{}/concerts/{}/seats",
+            test_app.address, concert_id
+        ))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(0, seats["available"]);
+
+    // Only two tickets are left in the category
+    let response = hold(
+        &other_token,
+        json!({ "concert_id": concert_id, "category_id": category_id, "quantity": 3 }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    let response = hold(
+        &other_token,
+        json!({ "concert_id": concert_id, "category_id": category_id, "quantity": 2 }),
+    )
+    .await
+    .unwrap();
+    assert!(response.status().is_success());
+    let hold_id: Uuid = response.json().await.unwrap();
+
+    let hold_url = |id: Uuid| format!("{}/holds/{}", test_app.address, id);
+    let response = client
+        .get(hold_url(seat_hold_id))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let saved: Value = client
+        .get(hold_url(seat_hold_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(1, saved["quantity"]);
+    assert_eq!(json!([seat_id]), saved["seat_ids"]);
+
+    // The tickets of a hold are only issued by paying an order for it
+    let order = |token: &str, id: Uuid| {
+        client
+            .post(format!("{}/orders", test_app.address))
+            .bearer_auth(token)
+            .body(json!({ "hold_id": id }).to_string())
+            .send()
+    };
+    let response = order(&other_token, seat_hold_id).await.unwrap();
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let response = order(&token, seat_hold_id).await.unwrap();
+    assert!(response.status().is_success());
+    let order_id: Uuid = response.json().await.unwrap();
+    let response = client
+        .post(format!("{}/orders/{}/pay", test_app.address, order_id))
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let ticket_ids: Vec<Uuid> = response.json().await.unwrap();
+    assert_eq!(1, ticket_ids.len());
+    let ticket: Value = client
+        .get(format!("{}/tickets/{}", test_app.address, ticket_ids[0]))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(user_id.to_string(), ticket["owner_id"]);
+    assert_eq!(seat_id.to_string(), ticket["seat_id"]);
+    assert_eq!(80.0, ticket["price"]);
+    // A hold is only checked out once
+    let response = order(&token, seat_hold_id).await.unwrap();
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+    assert_eq!((2, 1), held_and_sold(&test_app, category_id).await);
+
+    let response = client
+        .delete(hold_url(hold_id))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    assert_eq!((0, 1), held_and_sold(&test_app, category_id).await);
+}
+
+#[tokio::test]
+async fn expired_holds_are_released() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let (concert_id, seat_id) = insert_seated_concert(&test_app).await;
+    let category_id = insert_category(&test_app, concert_id, "Pit", 80.0, 1).await;
+    let holds = &test_app.app_state.hold_model;
+
+    let hold_id = holds
+        .create_hold(
+            new_hold(
+                &test_app,
+                user_id,
+                concert_id,
+                category_id,
+                None,
+                vec![seat_id],
+            ),
+            test_app.clock.now(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        0,
+        holds
+            .release_expired_holds(test_app.clock.now())
+            .await
+            .unwrap()
+    );
+
+    test_app
+        .clock
+        .advance(test_app.app_state.hold_ttl + Duration::seconds(1));
+    // The expired hold cannot be ordered, even before it is released
+    let response = reqwest::Client::new()
+        .post(format!("{}/orders", test_app.address))
+        .bearer_auth(&token)
+        .body(json!({ "hold_id": hold_id }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::GONE, response.status());
+    // Its seat can already be held again, its place in the category once released
+    let again = || {
+        new_hold(
+            &test_app,
+            user_id,
+            concert_id,
+            category_id,
+            None,
+            vec![seat_id],
+        )
+    };
+    assert!(matches!(
+        holds.create_hold(again(), test_app.clock.now()).await,
+        Err(Error::SoldOut)
+    ));
+    assert_eq!(
+        1,
+        holds
+            .release_expired_holds(test_app.clock.now())
+            .await
+            .unwrap()
+    );
+    assert_eq!((0, 0), held_and_sold(&test_app, category_id).await);
+    assert!(matches!(
+        holds.get_hold(hold_id).await,
+        Err(Error::HoldNotFound)
+    ));
+    assert!(holds
+        .create_hold(again(), test_app.clock.now())
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn concurrent_holds_cannot_overbook() {
+    let test_app = Arc::new(spawn_app().await);
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let (concert_id, seat_id) = insert_seated_concert(&test_app).await;
+    let category_id = insert_category(&test_app, concert_id, "Pit", 80.0, 3).await;
+
+    // Ten buyers race for the three tickets of the category
+    let mut buyers = tokio::task::JoinSet::new();
+    for _ in 0..10 {
+        let test_app = test_app.clone();
+        buyers.spawn(async move {
+            let hold = new_hold(&test_app, user_id, concert_id, category_id, Some(1), vec![]);
+            test_app
+                .app_state
+                .hold_model
+                .create_hold(hold, test_app.clock.now())
+                .await
+        });
+    }
+    let mut hold_ids = vec![];
+    let mut sold_out = 0;
+    while let Some(result) = buyers.join_next().await {
+        match result.unwrap() {
+            Ok(hold_id) => hold_ids.push(hold_id),
+            Err(Error::SoldOut) => sold_out += 1,
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+    assert_eq!(3, hold_ids.len());
+    assert_eq!(7, sold_out);
+    assert_eq!((3, 0), held_and_sold(&test_app, category_id).await);
+
+    // Each hold is paid once, however many orders are placed for it
+    let mut order_ids = vec![];
+    for hold_id in hold_ids.iter().chain(hold_ids.iter()) {
+        let item = OrderItem::new(concert_id, category_id, Some(1), vec![], 80.0).unwrap();
+        let order = NewOrder::new(user_id, Some(*hold_id), vec![item]).unwrap();
+        order_ids.push(
+            test_app
+                .app_state
+                .order_model
+                .create_order(order)
+                .await
+                .unwrap(),
+        );
+    }
+    let mut payers = tokio::task::JoinSet::new();
+    for order_id in order_ids {
+        let test_app = test_app.clone();
+        payers.spawn(async move {
+            test_app
+                .app_state
+                .order_model
+                .pay_order(
+                    order_id,
+                    vec![Uuid::new_v4().to_string()],
+                    test_app.clock.now(),
+                )
+                .await
+        });
+    }
+    let mut converted = 0;
+    while let Some(result) = payers.join_next().await {
+        match result.unwrap() {
+            Ok(_) => converted += 1,
+            // Once its hold is gone, an order falls back on the sold out category
+            Err(Error::HoldNotFound | Error::SoldOut) => {}
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+    assert_eq!(3, converted);
+    assert_eq!((0, 3), held_and_sold(&test_app, category_id).await);
+
+    // Holds on the same seat and direct sales of it exclude each other
+    let other_category_id = insert_category(&test_app, concert_id, "Balcony", 40.0, 20).await;
+    let mut buyers = tokio::task::JoinSet::new();
+    for _ in 0..5 {
+        let holder = test_app.clone();
+        buyers.spawn(async move {
+            let hold = new_hold(
+                &holder,
+                user_id,
+                concert_id,
+                other_category_id,
+                None,
+                vec![seat_id],
+            );
+            holder
+                .app_state
+                .hold_model
+                .create_hold(hold, holder.clock.now())
+                .await
+                .map(|_| ())
+        });
+        let seller = test_app.clone();
+        buyers.spawn(async move {
+            let ticket = NewTicket::new(
+                user_id,
+                concert_id,
+                Some(seat_id),
+                Some(other_category_id),
+                Uuid::new_v4().to_string(),
+                None,
+            )
+            .unwrap();
+            seller
+                .app_state
+                .ticket_model
+                .create_ticket(ticket)
+                .await
+                .map(|_| ())
+        });
+    }
+    let mut taken = 0;
+    while let Some(result) = buyers.join_next().await {
+        match result.unwrap() {
+            Ok(()) => taken += 1,
+            Err(Error::SeatHeld | Error::SeatTaken) => {}
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+    assert_eq!(1, taken);
+}
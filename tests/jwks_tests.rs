@@ -0,0 +1,131 @@
+mod helper;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use helper::{insert_user, spawn_app_with, TestApp};
+use iomentum_backend_practice::{
+    domain::types::JwtClaims,
+    handlers::jwt_handler::{JwtHandler, DEFAULT_AUDIENCE, DEFAULT_ISSUER},
+};
+use reqwest::StatusCode;
+use rsa::{pkcs1v15, signature::Verifier, BigUint, RsaPublicKey};
+use serde_json::json;
+use sha2::Sha256;
+
+async fn spawn_app_with_rsa_keys() -> TestApp {
+    spawn_app_with(|config| {
+        config.jwt_keys_dir = Some("tests/fixtures/jwt_keys".to_string());
+        config.jwt_signing_key_id = Some("2026-10-signing".to_string());
+    })
+    .await
+}
+
+fn decode_segment(segment: &str) -> serde_json::Value {
+    serde_json::from_slice(&URL_SAFE_NO_PAD.decode(segment).unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn tokens_can_be_verified_with_the_jwks() {
+    let test_app = spawn_app_with_rsa_keys().await;
+    insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("{}/login", test_app.address))
+        .body(json!({ "username": "test1", "password": "test1234" }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let tokens = response.json::<serde_json::Value>().await.unwrap();
+    let access_token = tokens["access_token"].as_str().unwrap();
+    let [header, claims, signature]: [&str; 3] = access_token
+        .split('.')
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap();
+    let header = decode_segment(header);
+    assert_eq!("RS256", header["alg"]);
+    assert_eq!("2026-10-signing", header["kid"]);
+
+    let response = client
+        .get(format!("{}/.well-known/jwks.json", test_app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::OK, response.status());
+    let jwks = response.json::<serde_json::Value>().await.unwrap();
+    let keys = jwks["keys"].as_array().unwrap();
+    assert_eq!(2, keys.len());
+    assert!(keys.iter().any(|k| k["kid"] == "2026-04-retired"));
+
+    // Verify the token the way another service would, with the published key only
+    let jwk = keys.iter().find(|k| k["kid"] == header["kid"]).unwrap();
+    assert_eq!("RSA", jwk["kty"]);
+    let n = URL_SAFE_NO_PAD.decode(jwk["n"].as_str().unwrap()).unwrap();
+    let e = URL_SAFE_NO_PAD.decode(jwk["e"].as_str().unwrap()).unwrap();
+    let public_key =
+        RsaPublicKey::new(BigUint::from_bytes_be(&n), BigUint::from_bytes_be(&e)).unwrap();
+    let signature = URL_SAFE_NO_PAD.decode(signature).unwrap();
+    let signature = pkcs1v15::Signature::try_from(signature.as_slice()).unwrap();
+    pkcs1v15::VerifyingKey::<Sha256>::new(public_key)
+        .verify(
+            format!("{}.{}", access_token.split('.').next().unwrap(), claims).as_bytes(),
+            &signature,
+        )
+        .expect("The token signature should be valid");
+    assert_eq!("test1", decode_segment(claims)["sub"]);
+}
+
+#[tokio::test]
+async fn legacy_hs256_tokens_keep_validating() {
+    let test_app = spawn_app_with_rsa_keys().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    let legacy_handler = JwtHandler::new(std::env::var("JWT_SECRET").unwrap()).unwrap();
+    let claims = JwtClaims::new(
+        "test1".to_string(),
+        user_id,
+        "user".to_string(),
+        test_app.app_state.access_token_ttl,
+    );
+    let token = legacy_handler.generate_token(claims).unwrap();
+
+    let response = client
+        .get(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn hs256_tokens_claiming_an_rsa_key_are_rejected() {
+    let test_app = spawn_app_with_rsa_keys().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    // Sign with the public key as an HMAC secret, the classic algorithm confusion attack
+    let public_key = include_str!("fixtures/jwt_keys/2026-04-retired.pub.pem");
+    let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","kid":"2026-04-retired"}"#);
+    let mut claims = JwtClaims::new(
+        "test1".to_string(),
+        user_id,
+        "admin".to_string(),
+        test_app.app_state.access_token_ttl,
+    );
+    claims.iss = DEFAULT_ISSUER.to_string();
+    claims.aud = vec![DEFAULT_AUDIENCE.to_string()];
+    let claims = URL_SAFE_NO_PAD.encode(serde_json::to_string(&claims).unwrap());
+    let mut mac = <hmac::Hmac<Sha256> as hmac::Mac>::new_from_slice(public_key.as_bytes()).unwrap();
+    hmac::Mac::update(&mut mac, format!("{header}.{claims}").as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(hmac::Mac::finalize(mac).into_bytes());
+
+    let response = client
+        .get(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth(format!("{header}.{claims}.{signature}"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+}
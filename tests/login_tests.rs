@@ -0,0 +1,271 @@
+mod helper;
+use argon2::{Algorithm, Params};
+use helper::{generate_token, insert_user, spawn_app_with, TestApp};
+use iomentum_backend_practice::handlers::password_hasher::PasswordHasher;
+use reqwest::StatusCode;
+use serde_json::json;
+
+async fn login(
+    test_app: &TestApp,
+    client: &reqwest::Client,
+    username: &str,
+    password: &str,
+) -> reqwest::Response {
+    client
+        .post(format!("{}/login", test_app.address))
+        .body(json!({ "username": username, "password": password }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn account_is_locked_out_after_repeated_failures() {
+    let test_app = spawn_app_with(|config| {
+        config.login_account_free_attempts = 3;
+        config.login_backoff_base = 60;
+    })
+    .await;
+    insert_user(&test_app, "test1", "user").await;
+    let admin_id = insert_user(&test_app, "admin", "admin").await;
+    let client = reqwest::Client::new();
+
+    for _ in 0..3 {
+        let response = login(&test_app, &client, "test1", "wrongpassword").await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    }
+
+    // Even the right password is refused during the lockout
+    let response = login(&test_app, &client, "test1", "test1234").await;
+    assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+    assert!(response.text().await.unwrap().contains("retry in"));
+    // Other accounts are not affected
+    let response = login(&test_app, &client, "admin", "test1234").await;
+    assert!(response.status().is_success());
+
+    let user_token = generate_token(&test_app, admin_id, "test1", "user");
+    let response = client
+        .get(format!("{}/lockouts", test_app.address))
+        .bearer_auth(&user_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+
+    let admin_token = generate_token(&test_app, admin_id, "admin", "admin");
+    let response = client
+        .get(format!("{}/lockouts", test_app.address))
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let lockouts = response.json::<serde_json::Value>().await.unwrap();
+    let lockouts = lockouts.as_array().unwrap();
+    assert_eq!(1, lockouts.len());
+    assert_eq!("account", lockouts[0]["kind"]);
+    assert_eq!("test1", lockouts[0]["identifier"]);
+    assert_eq!(3, lockouts[0]["failures"]);
+
+    let response = client
+        .delete(format!("{}/lockouts/account/test1", test_app.address))
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let response = login(&test_app, &client, "test1", "test1234").await;
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn parallel_guesses_cannot_outrun_the_lockout() {
+    let test_app = spawn_app_with(|config| {
+        config.login_account_free_attempts = 3;
+        config.login_backoff_base = 60;
+    })
+    .await;
+    insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    // Every guess is sent before the first one is verified
+    let mut guesses = tokio::task::JoinSet::new();
+    for _ in 0..10 {
+        let request = client
+            .post(format!("{}/login", test_app.address))
+            .body(json!({ "username": "test1", "password": "wrongpassword" }).to_string());
+        guesses.spawn(async move { request.send().await.unwrap().status() });
+    }
+    let mut statuses = vec![];
+    while let Some(status) = guesses.join_next().await {
+        statuses.push(status.unwrap());
+    }
+    let verified = statuses
+        .iter()
+        .filter(|s| **s == StatusCode::UNAUTHORIZED)
+        .count();
+    assert_eq!(3, verified);
+    assert_eq!(
+        7,
+        statuses
+            .iter()
+            .filter(|s| **s == StatusCode::TOO_MANY_REQUESTS)
+            .count()
+    );
+}
+
+#[tokio::test]
+async fn unknown_usernames_are_locked_out_too() {
+    let test_app = spawn_app_with(|config| {
+        config.login_account_free_attempts = 2;
+        config.login_backoff_base = 60;
+    })
+    .await;
+    let client = reqwest::Client::new();
+
+    for _ in 0..2 {
+        let response = login(&test_app, &client, "unknown", "wrongpassword").await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    }
+    let response = login(&test_app, &client, "unknown", "wrongpassword").await;
+    assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+}
+
+#[tokio::test]
+async fn client_address_is_locked_out_across_accounts() {
+    let test_app = spawn_app_with(|config| {
+        config.login_ip_free_attempts = 3;
+        config.login_backoff_base = 60;
+    })
+    .await;
+    insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    for i in 0..3 {
+        let response = login(&test_app, &client, &format!("user{i}"), "wrongpassword").await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    }
+    let response = login(&test_app, &client, "test1", "test1234").await;
+    assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+
+    // The header is ignored unless the proxy is trusted
+    let response = client
+        .post(format!("{}/login", test_app.address))
+        .header("X-Forwarded-For", "203.0.113.7")
+        .body(json!({ "username": "test1", "password": "test1234" }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+}
+
+#[tokio::test]
+async fn forwarded_address_is_used_behind_a_trusted_proxy() {
+    let test_app = spawn_app_with(|config| {
+        config.login_ip_free_attempts = 2;
+        config.login_backoff_base = 60;
+        config.trust_forwarded_for = true;
+    })
+    .await;
+    insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    for i in 0..2 {
+        let response = client
+            .post(format!("{}/login", test_app.address))
+            .header("X-Forwarded-For", "203.0.113.7")
+            .body(json!({ "username": format!("user{i}"), "password": "wrong" }).to_string())
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    }
+
+    let saved = sqlx::query!("SELECT identifier FROM login_attempts WHERE kind = 'ip'")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!("203.0.113.7", saved.identifier);
+    // Another client behind the same proxy can still log in
+    let response = login(&test_app, &client, "test1", "test1234").await;
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn forged_forwarded_entries_are_ignored() {
+    let test_app = spawn_app_with(|config| {
+        config.login_ip_free_attempts = 2;
+        config.login_backoff_base = 60;
+        config.trust_forwarded_for = true;
+    })
+    .await;
+    insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    // The client makes up a new first entry each time, the proxy appends the real one
+    let login_from = |forged: String, username: &str, password: &str| {
+        client
+            .post(format!("{}/login", test_app.address))
+            .header("X-Forwarded-For", format!("{forged}, 203.0.113.7"))
+            .body(json!({ "username": username, "password": password }).to_string())
+            .send()
+    };
+    for i in 0..2 {
+        let response = login_from(format!("198.51.100.{i}"), &format!("user{i}"), "wrong")
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    }
+    let response = login_from("198.51.100.9".to_string(), "test1", "test1234")
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+    let saved = sqlx::query!("SELECT identifier FROM login_attempts WHERE kind = 'ip'")
+        .fetch_all(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!(1, saved.len());
+    assert_eq!("203.0.113.7", saved[0].identifier);
+}
+
+#[tokio::test]
+async fn outdated_password_hashes_are_upgraded_on_login() {
+    let test_app = spawn_app_with(|config| {
+        config.password_hash_m_cost = 4096;
+        config.password_hash_t_cost = 1;
+    })
+    .await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    // A hash from before the parameters were raised
+    let outdated = PasswordHasher::new(
+        Algorithm::Argon2i,
+        Params::new(1024, 1, 1, None).unwrap(),
+        None,
+    )
+    .unwrap()
+    .hash("test1234")
+    .unwrap();
+    sqlx::query!(
+        "UPDATE users SET password_hash = $1 WHERE id = $2",
+        outdated,
+        user_id
+    )
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to update the db.");
+
+    let response = login(&test_app, &client, "test1", "test1234").await;
+    assert!(response.status().is_success());
+    let saved = sqlx::query!("SELECT password_hash FROM users WHERE id = $1", user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert!(saved.password_hash.starts_with("$argon2id$"));
+    assert!(saved.password_hash.contains("m=4096,t=1,p=1"));
+
+    // The new hash still verifies
+    let response = login(&test_app, &client, "test1", "test1234").await;
+    assert!(response.status().is_success());
+}
@@ -0,0 +1,414 @@
+mod helper;
+use chrono::Duration;
+use helper::{generate_token, insert_user, spawn_app, spawn_app_with, TestApp};
+use iomentum_backend_practice::{clock::Clock, handlers::totp};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+async fn post(
+    test_app: &TestApp,
+    client: &reqwest::Client,
+    path: &str,
+    token: Option<&str>,
+    body: Value,
+) -> reqwest::Response {
+    let mut request = client
+        .post(format!("{}{}", test_app.address, path))
+        .body(body.to_string());
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    request.send().await.expect("Failed to execute request.")
+}
+
+fn current_code(test_app: &TestApp, secret: &str) -> String {
+    totp::generate_code(secret, test_app.clock.now()).unwrap()
+}
+
+/// Enroll and confirm the 2FA of a user, returns the secret and the recovery codes
+async fn enable_mfa(
+    test_app: &TestApp,
+    client: &reqwest::Client,
+    token: &str,
+) -> (String, Vec<String>) {
+    let response = post(test_app, client, "/users/me/mfa", Some(token), json!({})).await;
+    assert!(response.status().is_success());
+    let enrollment = response.json::<Value>().await.unwrap();
+    let secret = enrollment["secret"].as_str().unwrap().to_string();
+
+    let code = current_code(test_app, &secret);
+    let response = post(
+        test_app,
+        client,
+        "/users/me/mfa/confirm",
+        Some(token),
+        json!({ "code": code }),
+    )
+    .await;
+    assert!(response.status().is_success());
+    let recovery_codes = response.json::<Value>().await.unwrap()["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c.as_str().unwrap().to_string())
+        .collect();
+    (secret, recovery_codes)
+}
+
+/// First step of the login, returns the challenge token
+async fn login_challenge(test_app: &TestApp, client: &reqwest::Client, username: &str) -> String {
+    let response = post(
+        test_app,
+        client,
+        "/login",
+        None,
+        json!({ "username": username, "password": "test1234" }),
+    )
+    .await;
+    assert!(response.status().is_success());
+    let body = response.json::<Value>().await.unwrap();
+    assert_eq!(true, body["mfa_required"]);
+    assert!(body.get("access_token").is_none());
+    body["challenge_token"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn enrollment_must_be_confirmed_with_a_valid_code() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+    let token = generate_token(&test_app, user_id, "test1", "user");
+
+    let response = post(&test_app, &client, "/users/me/mfa", Some(&token), json!({})).await;
+    assert!(response.status().is_success());
+    let enrollment = response.json::<Value>().await.unwrap();
+    let uri = enrollment["otpauth_uri"].as_str().unwrap();
+    assert!(uri.starts_with("otpauth://totp/iomentum-backend:test1?"));
+    assert!(uri.contains(enrollment["secret"].as_str().unwrap()));
+
+    let response = post(
+        &test_app,
+        &client,
+        "/users/me/mfa/confirm",
+        Some(&token),
+        json!({ "code": "000000" }),
+    )
+    .await;
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    // Until confirmed, the login does not ask for a code
+    let response = post(
+        &test_app,
+        &client,
+        "/login",
+        None,
+        json!({ "username": "test1", "password": "test1234" }),
+    )
+    .await;
+    assert!(response.json::<Value>().await.unwrap()["access_token"].is_string());
+
+    let (_, recovery_codes) = enable_mfa(&test_app, &client, &token).await;
+    assert_eq!(10, recovery_codes.len());
+    // Only the hashes of the recovery codes are stored
+    let saved = sqlx::query!("SELECT code_hash FROM mfa_recovery_codes")
+        .fetch_all(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!(10, saved.len());
+    assert!(saved.iter().all(|s| !recovery_codes.contains(&s.code_hash)));
+
+    let response = post(&test_app, &client, "/users/me/mfa", Some(&token), json!({})).await;
+    assert_eq!(StatusCode::CONFLICT, response.status());
+}
+
+#[tokio::test]
+async fn login_requires_a_fresh_totp_code() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let (secret, _) = enable_mfa(&test_app, &client, &token).await;
+
+    let challenge_token = login_challenge(&test_app, &client, "test1").await;
+    // The code used to confirm the enrollment cannot be replayed
+    let code = current_code(&test_app, &secret);
+    let response = post(
+        &test_app,
+        &client,
+        "/login/mfa",
+        None,
+        json!({ "challenge_token": challenge_token, "code": code }),
+    )
+    .await;
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    test_app.clock.advance(Duration::seconds(30));
+    let code = current_code(&test_app, &secret);
+    let response = post(
+        &test_app,
+        &client,
+        "/login/mfa",
+        None,
+        json!({ "challenge_token": challenge_token, "code": code }),
+    )
+    .await;
+    assert!(response.status().is_success());
+    let tokens = response.json::<Value>().await.unwrap();
+    let claims = test_app
+        .app_state
+        .jwt_handler
+        .validate_token(tokens["access_token"].as_str().unwrap())
+        .unwrap();
+    assert!(claims.has_mfa());
+
+    // The refreshed access tokens keep the second factor
+    let response = post(
+        &test_app,
+        &client,
+        "/token/refresh",
+        None,
+        json!({ "refresh_token": tokens["refresh_token"] }),
+    )
+    .await;
+    let tokens = response.json::<Value>().await.unwrap();
+    let claims = test_app
+        .app_state
+        .jwt_handler
+        .validate_token(tokens["access_token"].as_str().unwrap())
+        .unwrap();
+    assert!(claims.has_mfa());
+
+    // A challenge can only be completed once
+    test_app.clock.advance(Duration::seconds(30));
+    let code = current_code(&test_app, &secret);
+    let response = post(
+        &test_app,
+        &client,
+        "/login/mfa",
+        None,
+        json!({ "challenge_token": challenge_token, "code": code }),
+    )
+    .await;
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+}
+
+#[tokio::test]
+async fn recovery_codes_can_only_be_used_once() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let (_, recovery_codes) = enable_mfa(&test_app, &client, &token).await;
+
+    for expected in [true, false] {
+        let challenge_token = login_challenge(&test_app, &client, "test1").await;
+        let response = post(
+            &test_app,
+            &client,
+            "/login/mfa",
+            None,
+            json!({
+                "challenge_token": challenge_token,
+                // Typed back by a user, without the separators
+                "recovery_code": recovery_codes[0].to_uppercase().replace('-', ""),
+            }),
+        )
+        .await;
+        assert_eq!(expected, response.status().is_success());
+    }
+}
+
+#[tokio::test]
+async fn challenges_expire_and_allow_few_attempts() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let (secret, _) = enable_mfa(&test_app, &client, &token).await;
+
+    let challenge_token = login_challenge(&test_app, &client, "test1").await;
+    test_app.clock.advance(Duration::seconds(301));
+    let code = current_code(&test_app, &secret);
+    let response = post(
+        &test_app,
+        &client,
+        "/login/mfa",
+        None,
+        json!({ "challenge_token": challenge_token, "code": code }),
+    )
+    .await;
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    assert!(response.text().await.unwrap().contains("challenge"));
+
+    let challenge_token = login_challenge(&test_app, &client, "test1").await;
+    for _ in 0..5 {
+        let response = post(
+            &test_app,
+            &client,
+            "/login/mfa",
+            None,
+            json!({ "challenge_token": challenge_token, "code": "000000" }),
+        )
+        .await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    }
+    let code = current_code(&test_app, &secret);
+    let response = post(
+        &test_app,
+        &client,
+        "/login/mfa",
+        None,
+        json!({ "challenge_token": challenge_token, "code": code }),
+    )
+    .await;
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    // An unknown challenge is refused as well
+    let response = post(
+        &test_app,
+        &client,
+        "/login/mfa",
+        None,
+        json!({ "challenge_token": Uuid::new_v4().to_string(), "code": code }),
+    )
+    .await;
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+}
+
+#[tokio::test]
+async fn wrong_codes_lock_the_account_out_across_challenges() {
+    let test_app = spawn_app_with(|config| {
+        config.login_account_free_attempts = 2;
+        config.login_backoff_base = 60;
+    })
+    .await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let (secret, _) = enable_mfa(&test_app, &client, &token).await;
+
+    // A new challenge for each guess does not reset the count
+    let mut challenge_token = String::new();
+    for _ in 0..2 {
+        challenge_token = login_challenge(&test_app, &client, "test1").await;
+        let response = post(
+            &test_app,
+            &client,
+            "/login/mfa",
+            None,
+            json!({ "challenge_token": challenge_token, "code": "000000" }),
+        )
+        .await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    }
+    // Even the right code is refused, and no new challenge is issued meanwhile
+    let code = current_code(&test_app, &secret);
+    let response = post(
+        &test_app,
+        &client,
+        "/login/mfa",
+        None,
+        json!({ "challenge_token": challenge_token, "code": code }),
+    )
+    .await;
+    assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+    let response = post(
+        &test_app,
+        &client,
+        "/login",
+        None,
+        json!({ "username": "test1", "password": "test1234" }),
+    )
+    .await;
+    assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+    let challenges = sqlx::query!("SELECT id FROM mfa_challenges WHERE user_id = $1", user_id)
+        .fetch_all(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!(2, challenges.len());
+}
+
+#[tokio::test]
+async fn disabling_requires_a_code() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let (secret, _) = enable_mfa(&test_app, &client, &token).await;
+
+    let disable = |code: &str| {
+        client
+            .delete(format!("{}/users/me/mfa", test_app.address))
+            .bearer_auth(&token)
+            .body(json!({ "code": code }).to_string())
+            .send()
+    };
+    let response = disable("000000").await.unwrap();
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    test_app.clock.advance(Duration::seconds(30));
+    let response = disable(&current_code(&test_app, &secret)).await.unwrap();
+    assert!(response.status().is_success());
+
+    let response = post(
+        &test_app,
+        &client,
+        "/login",
+        None,
+        json!({ "username": "test1", "password": "test1234" }),
+    )
+    .await;
+    assert!(response.json::<Value>().await.unwrap()["access_token"].is_string());
+}
+
+#[tokio::test]
+async fn admins_can_be_required_to_use_mfa() {
+    let test_app = spawn_app_with(|config| config.require_admin_mfa = true).await;
+    let admin_id = insert_user(&test_app, "admin", "admin").await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+    let admin_token = generate_token(&test_app, admin_id, "admin", "admin");
+    let user_token = generate_token(&test_app, user_id, "test1", "user");
+
+    let response = client
+        .get(format!("{}/users", test_app.address))
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    assert!(response.text().await.unwrap().contains("/users/me/mfa"));
+
+    // Users are not concerned
+    let response = client
+        .get(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth(&user_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+
+    // The admin can still enroll, then log in with the second factor
+    let (secret, _) = enable_mfa(&test_app, &client, &admin_token).await;
+    let challenge_token = login_challenge(&test_app, &client, "admin").await;
+    test_app.clock.advance(Duration::seconds(30));
+    let code = current_code(&test_app, &secret);
+    let response = post(
+        &test_app,
+        &client,
+        "/login/mfa",
+        None,
+        json!({ "challenge_token": challenge_token, "code": code }),
+    )
+    .await;
+    let tokens = response.json::<Value>().await.unwrap();
+
+    let response = client
+        .get(format!("{}/users", test_app.address))
+        .bearer_auth(tokens["access_token"].as_str().unwrap())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+}
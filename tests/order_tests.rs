@@ -0,0 +1,377 @@
+mod helper;
+use std::sync::Arc;
+
+use helper::{
+    generate_token, held_and_sold, insert_category, insert_concert, insert_seated_concert,
+    insert_user, spawn_app, TestApp,
+};
+use iomentum_backend_practice::clock::Clock;
+use iomentum_backend_practice::domain::{
+    errors::Error,
+    types::{
+        hold_types::NewHold,
+        order_types::{NewOrder, OrderItem},
+    },
+};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Place an order of `quantity` unnumbered tickets of the category, at 80.0
+async fn place_order(
+    test_app: &TestApp,
+    user_id: Uuid,
+    concert_id: Uuid,
+    category_id: Uuid,
+    quantity: i32,
+) -> Uuid {
+    let item = OrderItem::new(concert_id, category_id, Some(quantity), vec![], 80.0).unwrap();
+    let order = NewOrder::new(user_id, None, vec![item]).unwrap();
+    test_app
+        .app_state
+        .order_model
+        .create_order(order)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn orders_are_priced_by_the_server_and_issued_when_paid() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let other_id = insert_user(&test_app, "test2", "user").await;
+    let other_token = generate_token(&test_app, other_id, "test2", "user");
+    let admin_id = insert_user(&test_app, "admin", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin", "admin");
+    let client = reqwest::Client::new();
+    let (concert_id, seat_id) = insert_seated_concert(&test_app).await;
+    let pit_id = insert_category(&test_app, concert_id, "Pit", 80.0, 3).await;
+    let balcony_id = insert_category(&test_app, concert_id, "Balcony", 40.5, 10).await;
+
+    let order = |body: Value| {
+        client
+            .post(format!("{}/orders", test_app.address))
+            .bearer_auth(&token)
+            .body(body.to_string())
+            .send()
+    };
+    // The buyer cannot choose the price
+    let response = order(json!({
+        "items": [{ "category_id": pit_id, "quantity": 2, "price": 1.0 }],
+    }))
+    .await
+    .unwrap();
+    assert!(response.status().is_client_error());
+    let response = order(json!({ "items": [] })).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let response = order(json!({
+        "items": [
+            { "category_id": pit_id, "quantity": 2 },
+            { "category_id": balcony_id, "seat_ids": [seat_id] },
+        ],
+    }))
+    .await
+    .unwrap();
+    assert!(response.status().is_success());
+    let order_id: Uuid = response.json().await.unwrap();
+
+    let order_url = format!("{}/orders/{}", test_app.address, order_id);
+    let response = client
+        .get(&order_url)
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let saved: Value = client
+        .get(&order_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    assert_eq!("pending", saved["status"]);
+    assert_eq!(200.5, saved["total"]);
+    assert_eq!(80.0, saved["items"][0]["unit_price"]);
+    assert_eq!(160.0, saved["items"][0]["subtotal"]);
+    assert_eq!(json!([seat_id]), saved["items"][1]["seat_ids"]);
+    // Nothing is issued until the order is paid
+    assert_eq!(json!([]), saved["ticket_ids"]);
+    assert_eq!((0, 0), held_and_sold(&test_app, pit_id).await);
+
+    let post = |action: &str, token: &str| {
+        client
+            .post(format!("{order_url}/{action}"))
+            .bearer_auth(token)
+            .send()
+    };
+    // The buyer cannot issue their tickets without the payment being confirmed
+    let response = post("pay", &other_token).await.unwrap();
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let response = post("pay", &token).await.unwrap();
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    assert_eq!((0, 0), held_and_sold(&test_app, pit_id).await);
+    let response = post("pay", &admin_token).await.unwrap();
+    assert!(response.status().is_success());
+    let ticket_ids: Vec<Uuid> = response.json().await.unwrap();
+    assert_eq!(3, ticket_ids.len());
+    assert_eq!((0, 2), held_and_sold(&test_app, pit_id).await);
+    assert_eq!((0, 1), held_and_sold(&test_app, balcony_id).await);
+    let tickets = sqlx::query!(
+        "SELECT owner_id, seat_id, price FROM tickets WHERE order_id = $1 ORDER BY price",
+        order_id
+    )
+    .fetch_all(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch from db.");
+    assert!(tickets.iter().all(|ticket| ticket.owner_id == user_id));
+    assert_eq!(Some(seat_id), tickets[0].seat_id);
+    assert_eq!(40.5, tickets[0].price);
+    assert_eq!(80.0, tickets[2].price);
+
+    let saved: Value = client
+        .get(&order_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    assert_eq!("paid", saved["status"]);
+    assert_eq!(3, saved["ticket_ids"].as_array().unwrap().len());
+    assert!(saved["paid_at"].is_string());
+    // A paid order is not paid again nor cancelled, only refunded by an admin
+    let response = post("pay", &admin_token).await.unwrap();
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    let response = post("cancel", &token).await.unwrap();
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    let response = post("refund", &token).await.unwrap();
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let response = post("refund", &admin_token).await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!((0, 0), held_and_sold(&test_app, pit_id).await);
+    assert_eq!((0, 0), held_and_sold(&test_app, balcony_id).await);
+    let tickets = sqlx::query_scalar!(
+        "SELECT count(*) FROM tickets WHERE id = ANY($1)",
+        &ticket_ids
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch from db.");
+    assert_eq!(Some(0), tickets);
+    let response = post("refund", &admin_token).await.unwrap();
+    assert_eq!(StatusCode::CONFLICT, response.status());
+}
+
+#[tokio::test]
+async fn holds_are_checked_out_and_orders_listed() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let other_id = insert_user(&test_app, "test2", "user").await;
+    let other_token = generate_token(&test_app, other_id, "test2", "user");
+    let admin_id = insert_user(&test_app, "admin", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin", "admin");
+    let client = reqwest::Client::new();
+    let (concert_id, seat_id) = insert_seated_concert(&test_app).await;
+    let category_id = insert_category(&test_app, concert_id, "Pit", 80.0, 3).await;
+
+    let expires_at = test_app.clock.now() + test_app.app_state.hold_ttl;
+    let mut hold_ids = vec![];
+    for seat_ids in [vec![seat_id], vec![]] {
+        let hold = NewHold::new(
+            user_id,
+            concert_id,
+            category_id,
+            Some(1),
+            seat_ids,
+            expires_at,
+        )
+        .unwrap();
+        let hold_id = test_app
+            .app_state
+            .hold_model
+            .create_hold(hold, test_app.clock.now())
+            .await
+            .unwrap();
+        hold_ids.push(hold_id);
+    }
+
+    let order = |token: &str, hold_id: Uuid| {
+        client
+            .post(format!("{}/orders", test_app.address))
+            .bearer_auth(token)
+            .body(json!({ "hold_id": hold_id }).to_string())
+            .send()
+    };
+    let response = order(&other_token, hold_ids[0]).await.unwrap();
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let response = order(&token, hold_ids[0]).await.unwrap();
+    assert!(response.status().is_success());
+    let paid_id: Uuid = response.json().await.unwrap();
+    let response = order(&token, hold_ids[1]).await.unwrap();
+    assert!(response.status().is_success());
+    let cancelled_id: Uuid = response.json().await.unwrap();
+
+    let post = |order_id: Uuid, action: &str, token: &str| {
+        client
+            .post(format!(
+                "{}/orders/{}/{}",
+                test_app.address, order_id, action
+            ))
+            .bearer_auth(token)
+            .send()
+    };
+    let response = post(paid_id, "pay", &admin_token).await.unwrap();
+    assert!(response.status().is_success());
+    let ticket_ids: Vec<Uuid> = response.json().await.unwrap();
+    let ticket = sqlx::query!(
+        "SELECT seat_id, price FROM tickets WHERE id = $1",
+        ticket_ids[0]
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch from db.");
+    assert_eq!(Some(seat_id), ticket.seat_id);
+    assert_eq!(80.0, ticket.price);
+    // Cancelling the other order gives its held ticket back
+    assert_eq!((1, 1), held_and_sold(&test_app, category_id).await);
+    let response = post(cancelled_id, "cancel", &token).await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!((0, 1), held_and_sold(&test_app, category_id).await);
+    assert!(matches!(
+        test_app.app_state.hold_model.get_hold(hold_ids[1]).await,
+        Err(Error::HoldNotFound)
+    ));
+
+    let list = |url: String, query: Vec<(&'static str, String)>| {
+        client.get(url).bearer_auth(&token).query(&query).send()
+    };
+    let my_orders = format!("{}/users/me/orders", test_app.address);
+    let page: Value = list(my_orders.clone(), vec![("limit", "1".to_string())])
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(paid_id.to_string(), page["items"][0]["id"]);
+    let cursor = page["next_cursor"].as_str().unwrap().to_string();
+    let page: Value = list(
+        my_orders.clone(),
+        vec![("limit", "1".to_string()), ("cursor", cursor)],
+    )
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(cancelled_id.to_string(), page["items"][0]["id"]);
+    assert!(page["next_cursor"].is_null());
+
+    let page: Value = list(
+        format!("{}/users/{}/orders", test_app.address, user_id),
+        vec![
+            ("status", "cancelled".to_string()),
+            ("include_total", "true".to_string()),
+        ],
+    )
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(1, page["total"]);
+    assert_eq!("cancelled", page["items"][0]["status"]);
+    let response = list(my_orders, vec![("status", "shipped".to_string())])
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let response = client
+        .get(format!("{}/users/{}/orders", test_app.address, user_id))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+}
+
+#[tokio::test]
+async fn concurrent_payments_cannot_overbook() {
+    let test_app = Arc::new(spawn_app().await);
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let concert_id = insert_concert(&test_app, "Trivium").await;
+    let category_id = insert_category(&test_app, concert_id, "Pit", 80.0, 3).await;
+
+    // The same order paid many times at once is only issued once
+    let order_id = place_order(&test_app, user_id, concert_id, category_id, 2).await;
+    let mut payers = tokio::task::JoinSet::new();
+    for _ in 0..5 {
+        let test_app = test_app.clone();
+        payers.spawn(async move {
+            let barcodes = vec![Uuid::new_v4().to_string(), Uuid::new_v4().to_string()];
+            test_app
+                .app_state
+                .order_model
+                .pay_order(order_id, barcodes, test_app.clock.now())
+                .await
+        });
+    }
+    let mut paid = 0;
+    while let Some(result) = payers.join_next().await {
+        match result.unwrap() {
+            Ok(ticket_ids) => {
+                assert_eq!(2, ticket_ids.len());
+                paid += 1;
+            }
+            Err(Error::InvalidOrderTransition(_, _)) => {}
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+    assert_eq!(1, paid);
+    assert_eq!((0, 2), held_and_sold(&test_app, category_id).await);
+
+    // Of the orders racing for the last ticket, one gets it and the others issue nothing
+    let mut order_ids = vec![];
+    for _ in 0..5 {
+        order_ids.push(place_order(&test_app, user_id, concert_id, category_id, 1).await);
+    }
+    let mut payers = tokio::task::JoinSet::new();
+    for order_id in order_ids {
+        let test_app = test_app.clone();
+        payers.spawn(async move {
+            test_app
+                .app_state
+                .order_model
+                .pay_order(
+                    order_id,
+                    vec![Uuid::new_v4().to_string()],
+                    test_app.clock.now(),
+                )
+                .await
+        });
+    }
+    let mut paid = 0;
+    while let Some(result) = payers.join_next().await {
+        match result.unwrap() {
+            Ok(_) => paid += 1,
+            Err(Error::SoldOut) => {}
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+    assert_eq!(1, paid);
+    assert_eq!((0, 3), held_and_sold(&test_app, category_id).await);
+    let tickets = sqlx::query_scalar!("SELECT count(*) FROM tickets")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!(Some(3), tickets);
+    let pending = sqlx::query_scalar!("SELECT count(*) FROM orders WHERE status = 'pending'")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!(Some(4), pending);
+}
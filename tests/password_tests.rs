@@ -0,0 +1,238 @@
+mod helper;
+use std::path::Path;
+
+use helper::{
+    generate_token, insert_user, last_notification, spawn_app, spawn_app_with_spool, TestApp,
+};
+use reqwest::StatusCode;
+use serde_json::json;
+
+/// Read the reset token from the last notification of the spool
+fn reset_token_from_spool(spool_dir: &Path) -> String {
+    let notification = last_notification(spool_dir);
+    notification["body"]
+        .as_str()
+        .unwrap()
+        .split("password: ")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .unwrap()
+        .to_string()
+}
+
+async fn login(test_app: &TestApp, client: &reqwest::Client, password: &str) -> reqwest::Response {
+    client
+        .post(format!("{}/login", test_app.address))
+        .body(json!({ "username": "test1", "password": password }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn reset_password(
+    test_app: &TestApp,
+    client: &reqwest::Client,
+    token: &str,
+    new_password: &str,
+) -> reqwest::Response {
+    client
+        .post(format!("{}/password-reset", test_app.address))
+        .body(json!({ "token": token, "new_password": new_password }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn changing_the_password_requires_the_current_one() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let version = || async {
+        sqlx::query_scalar!("SELECT version FROM users WHERE id = $1", user_id)
+            .fetch_one(&test_app.db_pool)
+            .await
+            .expect("Failed to fetch from db.")
+    };
+    let original_version = version().await;
+
+    let response = client
+        .post(format!("{}/users/me/password", test_app.address))
+        .bearer_auth(&token)
+        .body(
+            json!({ "current_password": "wrongpassword", "new_password": "newpassword" })
+                .to_string(),
+        )
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+
+    // Make sure the token was issued before the revocation
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let response = client
+        .post(format!("{}/users/me/password", test_app.address))
+        .bearer_auth(&token)
+        .body(json!({ "current_password": "test1234", "new_password": "newpassword" }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let tokens = response.json::<serde_json::Value>().await.unwrap();
+    // The user changed, so do its ETags
+    assert_eq!(original_version + 1, version().await);
+
+    // The previous sessions are logged out, the new one is not
+    let response = client
+        .get(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    let response = client
+        .get(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth(tokens["access_token"].as_str().unwrap())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+
+    assert!(!login(&test_app, &client, "test1234")
+        .await
+        .status()
+        .is_success());
+    assert!(login(&test_app, &client, "newpassword")
+        .await
+        .status()
+        .is_success());
+}
+
+#[tokio::test]
+async fn password_reset_flow_works() {
+    let (test_app, spool_dir) = spawn_app_with_spool(|_| {}).await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let admin_id = insert_user(&test_app, "admin", "admin").await;
+    let client = reqwest::Client::new();
+    let user_token = generate_token(&test_app, user_id, "test1", "user");
+    let admin_token = generate_token(&test_app, admin_id, "admin", "admin");
+
+    // Only an admin can start a reset
+    let response = client
+        .post(format!(
+            "{}/users/{}/password-reset",
+            test_app.address, user_id
+        ))
+        .bearer_auth(&user_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let response = client
+        .post(format!(
+            "{}/users/{}/password-reset",
+            test_app.address, user_id
+        ))
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let reset_token = reset_token_from_spool(&spool_dir);
+
+    // Only the hash of the token is stored
+    let saved = sqlx::query!("SELECT token_hash FROM password_reset_tokens")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_ne!(reset_token, saved.token_hash);
+
+    // A rejected password does not use the token
+    let response = reset_password(&test_app, &client, &reset_token, "short").await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response = reset_password(&test_app, &client, &reset_token, "newpassword").await;
+    assert!(response.status().is_success());
+    assert!(login(&test_app, &client, "newpassword")
+        .await
+        .status()
+        .is_success());
+
+    // The token can only be used once
+    let response = reset_password(&test_app, &client, &reset_token, "otherpassword").await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response = client
+        .get(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth(&user_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    std::fs::remove_dir_all(spool_dir).unwrap();
+}
+
+#[tokio::test]
+async fn a_new_reset_token_replaces_the_previous_one() {
+    let (test_app, spool_dir) = spawn_app_with_spool(|_| {}).await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let admin_id = insert_user(&test_app, "admin", "admin").await;
+    let client = reqwest::Client::new();
+    let admin_token = generate_token(&test_app, admin_id, "admin", "admin");
+
+    let mut reset_tokens = Vec::new();
+    for _ in 0..2 {
+        let response = client
+            .post(format!(
+                "{}/users/{}/password-reset",
+                test_app.address, user_id
+            ))
+            .bearer_auth(&admin_token)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert!(response.status().is_success());
+        reset_tokens.push(reset_token_from_spool(&spool_dir));
+    }
+
+    let response = reset_password(&test_app, &client, &reset_tokens[0], "newpassword").await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let response = reset_password(&test_app, &client, &reset_tokens[1], "newpassword").await;
+    assert!(response.status().is_success());
+
+    std::fs::remove_dir_all(spool_dir).unwrap();
+}
+
+#[tokio::test]
+async fn expired_reset_token_is_rejected() {
+    let (test_app, spool_dir) = spawn_app_with_spool(|config| config.password_reset_ttl = 0).await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let admin_id = insert_user(&test_app, "admin", "admin").await;
+    let client = reqwest::Client::new();
+    let admin_token = generate_token(&test_app, admin_id, "admin", "admin");
+
+    let response = client
+        .post(format!(
+            "{}/users/{}/password-reset",
+            test_app.address, user_id
+        ))
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let reset_token = reset_token_from_spool(&spool_dir);
+
+    let response = reset_password(&test_app, &client, &reset_token, "newpassword").await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    assert!(login(&test_app, &client, "test1234")
+        .await
+        .status()
+        .is_success());
+
+    std::fs::remove_dir_all(spool_dir).unwrap();
+}
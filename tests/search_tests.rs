@@ -0,0 +1,137 @@
+mod helper;
+use helper::{generate_token, insert_concert, insert_user, spawn_app, TestApp};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+async fn insert_ticket(test_app: &TestApp, owner_id: Uuid, concert_id: Uuid) {
+    sqlx::query!(
+        "INSERT INTO tickets (owner_id, concert_id, barcode_data, price) VALUES ($1, $2, '12345-abcde-67890', 50)",
+        owner_id,
+        concert_id,
+    )
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to insert a ticket.");
+}
+
+async fn search(test_app: &TestApp, token: &str, q: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("{}/search", test_app.address))
+        .query(&[("q", q)])
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+fn concert_names(results: &Value) -> Vec<&str> {
+    results["tickets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["concert_name"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn tickets_are_found_despite_typos() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    for concert_name in ["Trivium Live in Paris", "Gojira", "Metallica"] {
+        let concert_id = insert_concert(&test_app, concert_name).await;
+        insert_ticket(&test_app, user_id, concert_id).await;
+    }
+
+    let response = search(&test_app, &token, "trivum").await;
+    assert_eq!(StatusCode::ACCEPTED, response.status());
+    let results: Value = response.json().await.unwrap();
+    assert_eq!(vec!["Trivium Live in Paris"], concert_names(&results));
+    assert_eq!(
+        json!([{ "start": 0, "end": 7 }]),
+        results["tickets"][0]["highlights"]
+    );
+    assert!(results["tickets"][0]["rank"].as_f64().unwrap() > 0.0);
+
+    // The start of a word is enough
+    let response = search(&test_app, &token, "goj").await;
+    let results: Value = response.json().await.unwrap();
+    assert_eq!(vec!["Gojira"], concert_names(&results));
+}
+
+#[tokio::test]
+async fn users_only_search_their_own_tickets() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let other_id = insert_user(&test_app, "test2", "user").await;
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let concert_id = insert_concert(&test_app, "Gojira").await;
+    insert_ticket(&test_app, user_id, concert_id).await;
+    insert_ticket(&test_app, other_id, concert_id).await;
+
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let results: Value = search(&test_app, &token, "gojira")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(1, results["tickets"].as_array().unwrap().len());
+    assert_eq!(user_id.to_string(), results["tickets"][0]["owner_id"]);
+    // A user only finds themself
+    let results: Value = search(&test_app, &token, "test")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(json!(["test1"]), usernames(&results));
+
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let results: Value = search(&test_app, &admin_token, "gojira")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(2, results["tickets"].as_array().unwrap().len());
+    let results: Value = search(&test_app, &admin_token, "tst2")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(json!(["test2"]), usernames(&results));
+}
+
+fn usernames(results: &Value) -> Value {
+    results["users"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|u| u["username"].clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn invalid_searches_are_rejected() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+
+    for q in ["", "  ", "&|!", &"a".repeat(101)] {
+        let response = search(&test_app, &token, q).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status(), "q = {q:?}");
+    }
+    let response = reqwest::Client::new()
+        .get(format!("{}/search?q=gojira&limit=0", test_app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let response = reqwest::Client::new()
+        .get(format!("{}/search", test_app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+}
@@ -1,37 +1,45 @@
 mod helper;
-use helper::{insert_user, spawn_app};
+use helper::{generate_token, insert_concert, insert_user, spawn_app};
+use reqwest::StatusCode;
 use serde_json::json;
 
 #[tokio::test]
 async fn crud_ticket_works() {
     let test_app = spawn_app().await;
     let test_user_id = insert_user(&test_app, "Test1", "user").await;
+    let token = generate_token(&test_app, test_user_id, "Test1", "user");
+    let admin_id = insert_user(&test_app, "Admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "Admin1", "admin");
+    let concert_id = insert_concert(&test_app, "Trivium").await;
+    let other_concert_id = insert_concert(&test_app, "Not Trivium").await;
     let client = reqwest::Client::new();
 
-    let response = client
-        .post(format!("{}/tickets", test_app.address))
-        .body(
-            json!({
-                "owner_id": test_user_id, // This doesn't exist
-                "concert_name": "Trivium",
-                "concert_date": "2021-08-01T00:00:00Z",
-                "barcode_data": "12345-abcde-67890",
-                "price": 50.0,
-            })
-            .to_string(),
-        )
-        .send()
-        .await
-        .expect("Failed to execute request.");
+    let create = |token: &str| {
+        client
+            .post(format!("{}/tickets", test_app.address))
+            .bearer_auth(token)
+            .body(
+                json!({
+                    "owner_id": test_user_id,
+                    "concert_id": concert_id,
+                    "barcode_data": "12345-abcde-67890",
+                    "price": 50.0,
+                })
+                .to_string(),
+            )
+            .send()
+    };
+    // Users buy their tickets through orders, only admins issue them directly
+    let response = create(&token).await.unwrap();
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let response = create(&admin_token).await.unwrap();
     assert!(response.status().is_success());
-    let saved = sqlx::query!(
-        "SELECT id, owner_id, concert_name, concert_date, barcode_data, price FROM Tickets"
-    )
-    .fetch_one(&test_app.db_pool)
-    .await
-    .expect("Failed to fetch from db.");
+    let saved = sqlx::query!("SELECT id, owner_id, concert_id, barcode_data, price FROM Tickets")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
     assert_eq!(test_user_id, saved.owner_id);
-    assert_eq!("Trivium", saved.concert_name);
+    assert_eq!(concert_id, saved.concert_id);
     assert_eq!("12345-abcde-67890", saved.barcode_data);
     assert_eq!(50.0, saved.price);
     let received_text = response.text().await.unwrap();
@@ -41,25 +49,30 @@ async fn crud_ticket_works() {
     // get ticket by id
     let response = client
         .get(format!("{}/tickets/{}", test_app.address, id))
+        .bearer_auth(&token)
         .send()
         .await
         .expect("Failed to execute request.");
     assert!(response.status().is_success());
+    let data = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(concert_id.to_string(), data["concert_id"]);
+    assert_eq!("Trivium", data["concert_name"]);
+    assert_eq!("2021-08-01T00:00:00Z", data["concert_date"]);
     assert_eq!(test_user_id, saved.owner_id);
-    assert_eq!("Trivium", saved.concert_name);
     assert_eq!("12345-abcde-67890", saved.barcode_data);
     assert_eq!(50.0, saved.price);
 
     // get all tickets
     let response = client
         .get(format!("{}/tickets", test_app.address))
+        .bearer_auth(&admin_token)
         .send()
         .await
         .expect("Failed to execute request.");
     assert!(response.status().is_success());
-    let data = response.json::<Vec<serde_json::Value>>().await.unwrap();
-    assert_eq!(id.to_string(), data[0]["id"]);
-    assert_eq!(data.len(), 1);
+    let data = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(id.to_string(), data["items"][0]["id"]);
+    assert_eq!(data["items"].as_array().unwrap().len(), 1);
 
     // get ticket by username
     let response = client
@@ -67,6 +80,7 @@ async fn crud_ticket_works() {
             "{}/tickets/by-user/{}",
             test_app.address, test_user_id
         ))
+        .bearer_auth(&token)
         .send()
         .await
         .expect("Failed to execute request.");
@@ -75,44 +89,466 @@ async fn crud_ticket_works() {
     assert_eq!(id.to_string(), data[0]["id"]);
     assert_eq!("Trivium", data[0]["concert_name"]);
 
+    // update ticket, only admins may move or reprice it
+    let update = |token: &str| {
+        client
+            .patch(format!("{}/tickets/{}", test_app.address, id))
+            .header("If-Match", "*")
+            .bearer_auth(token)
+            .body(
+                json!({
+                    "owner_id": test_user_id,
+                    "concert_id": other_concert_id,
+                    "barcode_data": "12345-abcde-67890",
+                    "price": 55.0,
+                })
+                .to_string(),
+            )
+            .send()
+    };
+    let response = update(&token).await.unwrap();
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let response = update(&admin_token).await.unwrap();
+    assert!(response.status().is_success());
+    let saved = sqlx::query!("SELECT id, owner_id, concert_id, barcode_data, price FROM Tickets")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!(test_user_id, saved.owner_id);
+    assert_eq!(other_concert_id, saved.concert_id);
+    assert_eq!("12345-abcde-67890", saved.barcode_data);
+    assert_eq!(55.0, saved.price);
+
     // update ticket
+    let response = client
+        .delete(format!("{}/tickets/{}", test_app.address, id))
+        .header("If-Match", "*")
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let saved = sqlx::query!("SELECT id FROM Tickets")
+        .fetch_optional(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert!(saved.is_none());
+}
+
+#[tokio::test]
+async fn users_cannot_manage_other_users_tickets() {
+    let test_app = spawn_app().await;
+    let owner_id = insert_user(&test_app, "Owner", "user").await;
+    let owner_token = generate_token(&test_app, owner_id, "Owner", "user");
+    let other_id = insert_user(&test_app, "Other", "user").await;
+    let other_token = generate_token(&test_app, other_id, "Other", "user");
+    let admin_id = insert_user(&test_app, "Admin", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "Admin", "admin");
+    let concert_id = insert_concert(&test_app, "Trivium").await;
+    let client = reqwest::Client::new();
+
+    let ticket = json!({
+        "owner_id": owner_id,
+        "concert_id": concert_id,
+        "barcode_data": "12345-abcde-67890",
+        "price": 50.0,
+    });
+
+    // a user cannot create a ticket, for someone else or for themselves
+    for token in [&other_token, &owner_token] {
+        let response = client
+            .post(format!("{}/tickets", test_app.address))
+            .bearer_auth(token)
+            .body(ticket.to_string())
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+    }
+
+    let response = client
+        .post(format!("{}/tickets", test_app.address))
+        .bearer_auth(&admin_token)
+        .body(ticket.to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let id = response.text().await.unwrap();
+    let id = id.trim_matches('"');
+
+    let response = client
+        .get(format!("{}/tickets/{}", test_app.address, id))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+
+    let response = client
+        .get(format!("{}/tickets", test_app.address))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+
+    let response = client
+        .delete(format!("{}/tickets/{}", test_app.address, id))
+        .header("If-Match", "*")
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let saved = sqlx::query!("SELECT id FROM Tickets")
+        .fetch_optional(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert!(saved.is_some());
+}
+
+#[tokio::test]
+async fn patch_only_changes_the_fields_sent_and_put_replaces_the_ticket() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let concert_id = insert_concert(&test_app, "Trivium").await;
+    let other_concert_id = insert_concert(&test_app, "Gojira").await;
+    let client = reqwest::Client::new();
+
+    let ticket = json!({
+        "owner_id": user_id,
+        "concert_id": concert_id,
+        "barcode_data": "12345-abcde-67890",
+        "price": 50.0,
+    });
+    let id: uuid::Uuid = client
+        .post(format!("{}/tickets", test_app.address))
+        .bearer_auth(&admin_token)
+        .body(ticket.to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+
+    let patch_as = |token: &str, body: serde_json::Value| {
+        client
+            .patch(format!("{}/tickets/{}", test_app.address, id))
+            .header("If-Match", "*")
+            .bearer_auth(token)
+            .header("Content-Type", "application/merge-patch+json")
+            .body(body.to_string())
+            .send()
+    };
+    let patch = |body: serde_json::Value| patch_as(&admin_token, body);
+    // The owner may change the barcode but not the price
+    let response = patch_as(&token, json!({ "price": 65.5 })).await.unwrap();
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let response = patch_as(&token, json!({ "barcode_data": "67890-abcde-12345" }))
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let response = patch(json!({ "price": 65.5 })).await.unwrap();
+    assert!(response.status().is_success());
+    let saved = sqlx::query!("SELECT concert_id, price FROM tickets WHERE id = $1", id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!(concert_id, saved.concert_id);
+    assert_eq!(65.5, saved.price);
+
+    // None of the fields can be removed, and unknown ones are refused
+    let response = patch(json!({ "concert_id": null })).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let response = patch(json!({ "venue": "Paris" })).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let response = patch(json!({ "concert_name": "Gojira" })).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    // The concert must exist
+    let response = patch(json!({ "concert_id": uuid::Uuid::new_v4() }))
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
     let response = client
         .patch(format!("{}/tickets/{}", test_app.address, id))
+        .header("If-Match", "*")
+        .bearer_auth(&token)
+        .header("Content-Type", "text/plain")
+        .body(json!({ "price": 10.0 }).to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::UNSUPPORTED_MEDIA_TYPE, response.status());
+
+    // A replacement needs every field
+    let put = |token: &str, body: serde_json::Value| {
+        client
+            .put(format!("{}/tickets/{}", test_app.address, id))
+            .header("If-Match", "*")
+            .bearer_auth(token)
+            .body(body.to_string())
+            .send()
+    };
+    let response = put(&admin_token, json!({ "price": 10.0 })).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let mut replacement = ticket.clone();
+    replacement["concert_id"] = json!(other_concert_id);
+    let response = put(&token, replacement.clone()).await.unwrap();
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let response = put(&admin_token, replacement).await.unwrap();
+    assert!(response.status().is_success());
+    let saved = sqlx::query!("SELECT concert_id, price FROM tickets WHERE id = $1", id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!(other_concert_id, saved.concert_id);
+    assert_eq!(50.0, saved.price);
+}
+
+#[tokio::test]
+async fn concurrent_edits_of_a_ticket_are_detected() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let concert_id = insert_concert(&test_app, "Trivium").await;
+    let client = reqwest::Client::new();
+
+    let id: uuid::Uuid = client
+        .post(format!("{}/tickets", test_app.address))
+        .bearer_auth(&admin_token)
         .body(
             json!({
-                "owner_id": test_user_id,
-                "concert_name": "Not Trivium",
-                "concert_date": "2021-08-03T00:00:00Z",
+                "owner_id": user_id,
+                "concert_id": concert_id,
                 "barcode_data": "12345-abcde-67890",
-                "price": 55.0,
+                "price": 50.0,
             })
             .to_string(),
         )
         .send()
         .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+
+    let response = client
+        .get(format!("{}/tickets/{}", test_app.address, id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    let response = client
+        .get(format!("{}/tickets/{}", test_app.address, id))
+        .bearer_auth(&token)
+        .header("If-None-Match", &etag)
+        .send()
+        .await
         .expect("Failed to execute request.");
+    assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+
+    let patch = |token: &str, if_match: Option<&str>, price: f64| {
+        let mut request = client
+            .patch(format!("{}/tickets/{}", test_app.address, id))
+            .bearer_auth(token)
+            .body(json!({ "price": price }).to_string());
+        if let Some(if_match) = if_match {
+            request = request.header("If-Match", if_match);
+        }
+        request.send()
+    };
+    let response = patch(&token, None, 60.0).await.unwrap();
+    assert_eq!(StatusCode::PRECONDITION_REQUIRED, response.status());
+
+    // Both agents fetched the same version, the second edit is refused
+    let response = patch(&admin_token, Some(&etag), 60.0).await.unwrap();
     assert!(response.status().is_success());
-    let saved = sqlx::query!(
-        "SELECT id, owner_id, concert_name, concert_date, barcode_data, price FROM Tickets"
-    )
-    .fetch_one(&test_app.db_pool)
-    .await
-    .expect("Failed to fetch from db.");
-    assert_eq!(test_user_id, saved.owner_id);
-    assert_eq!("Not Trivium", saved.concert_name);
-    assert_eq!("12345-abcde-67890", saved.barcode_data);
-    assert_eq!(55.0, saved.price);
+    let new_etag = response.headers()["etag"].to_str().unwrap().to_string();
+    assert_ne!(etag, new_etag);
+    let response = patch(&token, Some(&etag), 70.0).await.unwrap();
+    assert_eq!(StatusCode::PRECONDITION_FAILED, response.status());
+    let saved = sqlx::query!("SELECT price FROM tickets WHERE id = $1", id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!(60.0, saved.price);
 
-    // update ticket
     let response = client
-        .delete(format!("{}/tickets/{}", test_app.address, id))
+        .get(format!("{}/tickets/{}", test_app.address, id))
+        .bearer_auth(&token)
+        .header("If-None-Match", &etag)
         .send()
         .await
         .expect("Failed to execute request.");
     assert!(response.status().is_success());
-    let saved = sqlx::query!("SELECT id FROM Tickets")
-        .fetch_optional(&test_app.db_pool)
+
+    let delete = |if_match: &str| {
+        client
+            .delete(format!("{}/tickets/{}", test_app.address, id))
+            .bearer_auth(&admin_token)
+            .header("If-Match", if_match)
+            .send()
+    };
+    let response = delete(&etag).await.unwrap();
+    assert_eq!(StatusCode::PRECONDITION_FAILED, response.status());
+    let response = delete(&new_etag).await.unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn retried_ticket_creation_is_only_run_once() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let concert_id = insert_concert(&test_app, "Trivium").await;
+    let client = reqwest::Client::new();
+
+    let create = |key: &str, price: f64| {
+        client
+            .post(format!("{}/tickets", test_app.address))
+            .bearer_auth(&admin_token)
+            .header("Idempotency-Key", key)
+            .body(
+                json!({
+                    "owner_id": user_id,
+                    "concert_id": concert_id,
+                    "barcode_data": "12345-abcde-67890",
+                    "price": price,
+                })
+                .to_string(),
+            )
+            .send()
+    };
+    let count_tickets = || async {
+        sqlx::query!("SELECT count(*) as count FROM tickets")
+            .fetch_one(&test_app.db_pool)
+            .await
+            .expect("Failed to fetch from db.")
+            .count
+    };
+
+    let response = create("key-1", 50.0).await.unwrap();
+    assert!(response.status().is_success());
+    assert!(response.headers().get("idempotent-replayed").is_none());
+    let id: uuid::Uuid = response.json().await.unwrap();
+
+    // The retry gets the first response back
+    let response = create("key-1", 50.0).await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!("true", response.headers()["idempotent-replayed"]);
+    let replayed_id: uuid::Uuid = response.json().await.unwrap();
+    assert_eq!(id, replayed_id);
+    assert_eq!(Some(1), count_tickets().await);
+
+    let response = create("key-1", 60.0).await.unwrap();
+    assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, response.status());
+    let response = create("key with spaces", 50.0).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    // Concurrent requests with the same key create a single ticket
+    let (first, second) = tokio::join!(create("key-2", 50.0), create("key-2", 50.0));
+    for response in [first.unwrap(), second.unwrap()] {
+        assert!(response.status().is_success() || response.status() == StatusCode::CONFLICT);
+    }
+    assert_eq!(Some(2), count_tickets().await);
+}
+
+#[tokio::test]
+async fn tickets_are_listed_by_pages() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let other_id = insert_user(&test_app, "test2", "user").await;
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let trivium_id = insert_concert(&test_app, "Trivium").await;
+    let gojira_id = insert_concert(&test_app, "Gojira").await;
+    let lowercase_trivium_id = insert_concert(&test_app, "trivium").await;
+    let client = reqwest::Client::new();
+
+    for (owner_id, concert_id, price) in [
+        (user_id, trivium_id, 50.0),
+        (user_id, gojira_id, 40.0),
+        (user_id, lowercase_trivium_id, 30.0),
+        (other_id, trivium_id, 20.0),
+        (other_id, gojira_id, 40.0),
+    ] {
+        sqlx::query!(
+            "INSERT INTO tickets (owner_id, concert_id, barcode_data, price) VALUES ($1, $2, '12345-abcde-67890', $3)",
+            owner_id,
+            concert_id,
+            price
+        )
+        .execute(&test_app.db_pool)
         .await
-        .expect("Failed to fetch from db.");
-    assert!(saved.is_none());
+        .expect("Failed to insert a ticket.");
+    }
+
+    let list = |query: String| {
+        client
+            .get(format!("{}/tickets?{}", test_app.address, query))
+            .bearer_auth(&admin_token)
+            .send()
+    };
+
+    // Walk through every page
+    let mut prices = vec![];
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut query = "sort=-price&limit=2&include_total=true".to_string();
+        if let Some(cursor) = &cursor {
+            query.push_str(&format!("&cursor={cursor}"));
+        }
+        let response = list(query).await.unwrap();
+        assert!(response.status().is_success());
+        let page = response.json::<serde_json::Value>().await.unwrap();
+        assert_eq!(5, page["total"]);
+        for ticket in page["items"].as_array().unwrap() {
+            prices.push(ticket["price"].as_f64().unwrap());
+        }
+        match page["next_cursor"].as_str() {
+            Some(next) => cursor = Some(next.to_string()),
+            None => break,
+        }
+    }
+    assert_eq!(vec![50.0, 40.0, 40.0, 30.0, 20.0], prices);
+
+    let response = list(format!(
+        "owner_id={user_id}&concert_name=TRIVIUM&price_min=35"
+    ))
+    .await
+    .unwrap();
+    let page = response.json::<serde_json::Value>().await.unwrap();
+    let items = page["items"].as_array().unwrap();
+    assert_eq!(1, items.len());
+    assert_eq!(50.0, items[0]["price"]);
+    assert!(page["next_cursor"].is_null());
+    assert!(page.get("total").is_none());
+    let response = list(format!("concert_id={gojira_id}&include_total=true"))
+        .await
+        .unwrap();
+    let page = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(2, page["total"]);
+
+    // A cursor only works with the sort it was made for
+    let response = list("sort=price&limit=1".to_string()).await.unwrap();
+    let page = response.json::<serde_json::Value>().await.unwrap();
+    let cursor = page["next_cursor"].as_str().unwrap();
+    let response = list(format!("sort=concert_date&cursor={cursor}"))
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    for query in ["limit=0", "sort=barcode_data", "cursor=nope", "unknown=1"] {
+        let response = list(query.to_string()).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, response.status(), "{query}");
+    }
 }
@@ -0,0 +1,296 @@
+mod helper;
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use helper::{generate_token, insert_user, spawn_app, TestApp};
+use hmac::{Hmac, Mac};
+use iomentum_backend_practice::handlers::jwt_handler::LEGACY_TOKEN_TTL;
+use jwt::SignWithKey;
+use reqwest::StatusCode;
+use serde_json::json;
+use sha2::Sha256;
+
+async fn login(test_app: &TestApp, client: &reqwest::Client) -> serde_json::Value {
+    let response = client
+        .post(format!("{}/login", test_app.address))
+        .body(
+            json!({
+                "username": "test1",
+                "password": "test1234",
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    response.json::<serde_json::Value>().await.unwrap()
+}
+
+async fn refresh(
+    test_app: &TestApp,
+    client: &reqwest::Client,
+    refresh_token: &serde_json::Value,
+) -> reqwest::Response {
+    client
+        .post(format!("{}/token/refresh", test_app.address))
+        .body(json!({ "refresh_token": refresh_token }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn refresh_token_rotates() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    let tokens = login(&test_app, &client).await;
+
+    let response = refresh(&test_app, &client, &tokens["refresh_token"]).await;
+    assert!(response.status().is_success());
+    let new_tokens = response.json::<serde_json::Value>().await.unwrap();
+    assert_ne!(tokens["refresh_token"], new_tokens["refresh_token"]);
+    let claims = test_app
+        .app_state
+        .jwt_handler
+        .validate_token(new_tokens["access_token"].as_str().unwrap())
+        .expect("Failed to decode token");
+    assert_eq!(user_id, claims.user_id);
+
+    // The rotated token can be used in turn
+    let response = refresh(&test_app, &client, &new_tokens["refresh_token"]).await;
+    assert!(response.status().is_success());
+
+    let saved = sqlx::query!("SELECT token_hash FROM refresh_tokens")
+        .fetch_all(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!(3, saved.len());
+    for token in saved {
+        assert_ne!(tokens["refresh_token"], token.token_hash);
+    }
+}
+
+#[tokio::test]
+async fn refresh_token_reuse_revokes_the_family() {
+    let test_app = spawn_app().await;
+    insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    let tokens = login(&test_app, &client).await;
+    let response = refresh(&test_app, &client, &tokens["refresh_token"]).await;
+    assert!(response.status().is_success());
+    let new_tokens = response.json::<serde_json::Value>().await.unwrap();
+
+    // Replaying the first token is detected
+    let response = refresh(&test_app, &client, &tokens["refresh_token"]).await;
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    assert!(response.text().await.unwrap().contains("reuse detected"));
+
+    // And the legitimate descendant is revoked as well
+    let response = refresh(&test_app, &client, &new_tokens["refresh_token"]).await;
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    // Other sessions are not affected
+    let other_tokens = login(&test_app, &client).await;
+    let response = refresh(&test_app, &client, &other_tokens["refresh_token"]).await;
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn invalid_or_expired_refresh_token_is_rejected() {
+    let test_app = spawn_app().await;
+    insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    let response = refresh(&test_app, &client, &json!("not-a-refresh-token")).await;
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    let tokens = login(&test_app, &client).await;
+    sqlx::query!("UPDATE refresh_tokens SET expires_at = now() - interval '1 minute'")
+        .execute(&test_app.db_pool)
+        .await
+        .expect("Failed to update the db.");
+    let response = refresh(&test_app, &client, &tokens["refresh_token"]).await;
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+}
+
+#[tokio::test]
+async fn logout_revokes_the_session() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    let tokens = login(&test_app, &client).await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+    let other_tokens = login(&test_app, &client).await;
+
+    let response = client
+        .post(format!("{}/logout", test_app.address))
+        .bearer_auth(access_token)
+        .body(json!({ "refresh_token": tokens["refresh_token"] }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+
+    let response = client
+        .get(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    assert!(response.text().await.unwrap().contains("token revoked"));
+
+    let response = refresh(&test_app, &client, &tokens["refresh_token"]).await;
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    // The other session is still valid
+    let response = client
+        .get(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth(other_tokens["access_token"].as_str().unwrap())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+
+    // Logging out without a body only revokes the access token
+    let response = client
+        .post(format!("{}/logout", test_app.address))
+        .bearer_auth(other_tokens["access_token"].as_str().unwrap())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let response = refresh(&test_app, &client, &other_tokens["refresh_token"]).await;
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn admin_can_revoke_all_sessions_of_a_user() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let client = reqwest::Client::new();
+
+    let tokens = login(&test_app, &client).await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+
+    // A user cannot use the endpoint
+    let response = client
+        .delete(format!("{}/users/{}/sessions", test_app.address, admin_id))
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+
+    // Make sure the token was issued before the revocation
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let response = client
+        .delete(format!("{}/users/{}/sessions", test_app.address, user_id))
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+
+    let response = client
+        .get(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    let response = refresh(&test_app, &client, &tokens["refresh_token"]).await;
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+    // Logging in again works
+    let tokens = login(&test_app, &client).await;
+    let response = client
+        .get(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth(tokens["access_token"].as_str().unwrap())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn session_revocations_outlive_the_legacy_tokens() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let client = reqwest::Client::new();
+
+    // A token as signed before the claims were typed, valid for a day
+    let iat = Utc::now().timestamp() - 30 * 60;
+    let mut claims = BTreeMap::new();
+    claims.insert("sub", "test1".to_string());
+    claims.insert("user_id", user_id.to_string());
+    claims.insert("role", "user".to_string());
+    claims.insert("iat", iat.to_string());
+    claims.insert("exp", (iat + LEGACY_TOKEN_TTL).to_string());
+    let key: Hmac<Sha256> =
+        Hmac::new_from_slice(std::env::var("JWT_SECRET").unwrap().as_bytes()).unwrap();
+    let legacy_token = claims.sign_with_key(&key).unwrap();
+    let get_user = || {
+        client
+            .get(format!("{}/users/{}", test_app.address, user_id))
+            .bearer_auth(&legacy_token)
+            .send()
+    };
+    assert!(get_user().await.unwrap().status().is_success());
+
+    let response = client
+        .delete(format!("{}/users/{}/sessions", test_app.address, user_id))
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    assert_eq!(StatusCode::UNAUTHORIZED, get_user().await.unwrap().status());
+
+    // Long after the access tokens expired, the legacy token is still revoked
+    sqlx::query!(
+        "UPDATE revoked_sessions SET revoked_before = revoked_before - interval '20 minutes', expires_at = expires_at - interval '20 minutes'"
+    )
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to update the db.");
+    assert_eq!(0, test_app.app_state.revocation_list.sweep().await.unwrap());
+    assert_eq!(StatusCode::UNAUTHORIZED, get_user().await.unwrap().status());
+}
+
+#[tokio::test]
+async fn sweep_purges_expired_revocations() {
+    let test_app = spawn_app().await;
+    insert_user(&test_app, "test1", "user").await;
+    let client = reqwest::Client::new();
+
+    let tokens = login(&test_app, &client).await;
+    let response = client
+        .post(format!("{}/logout", test_app.address))
+        .bearer_auth(tokens["access_token"].as_str().unwrap())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+
+    assert_eq!(0, test_app.app_state.revocation_list.sweep().await.unwrap());
+    sqlx::query!("UPDATE revoked_tokens SET expires_at = now() - interval '1 minute'")
+        .execute(&test_app.db_pool)
+        .await
+        .expect("Failed to update the db.");
+    assert_eq!(1, test_app.app_state.revocation_list.sweep().await.unwrap());
+    let saved = sqlx::query!("SELECT jti FROM revoked_tokens")
+        .fetch_optional(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert!(saved.is_none());
+}
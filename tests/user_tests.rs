@@ -1,6 +1,6 @@
 mod helper;
-use helper::spawn_app;
-use iomentum_backend_practice::handlers::password_hasher;
+use helper::{generate_token, insert_concert, insert_user, spawn_app, spawn_app_with};
+use reqwest::StatusCode;
 use serde_json::json;
 
 #[tokio::test]
@@ -13,7 +13,8 @@ async fn crud_users_works() {
         .body(
             json!({
                 "username": "test1",
-                "password": "test1234",
+                "email": "test1@example.com",
+                "password": "secret1234",
                 "role": "user",
             })
             .to_string(),
@@ -29,12 +30,19 @@ async fn crud_users_works() {
         .expect("Failed to fetch from db.");
     assert_eq!("test1", saved.username);
     assert_eq!("user", saved.role);
-    assert!(password_hasher::verify("test1234", &saved.password_hash));
+    assert!(test_app
+        .app_state
+        .password_hasher
+        .verify("secret1234", &saved.password_hash));
     let id = saved.id;
+    let token = generate_token(&test_app, id, "test1", "user");
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
 
     // get user by id
     let response = client
         .get(format!("{}/users/{}", test_app.address, id))
+        .bearer_auth(&token)
         .send()
         .await
         .expect("Failed to execute request.");
@@ -49,6 +57,7 @@ async fn crud_users_works() {
         .body(
             json!({
                 "username": "test2",
+                "email": "test2@example.com",
                 "password": "2short",
                 "role": "user",
             })
@@ -63,18 +72,20 @@ async fn crud_users_works() {
     // get all users
     let response = client
         .get(format!("{}/users", test_app.address))
+        .bearer_auth(&admin_token)
         .send()
         .await
         .expect("Failed to execute request.");
     assert!(response.status().is_success());
     assert_eq!(
         id.to_string(),
-        response.json::<serde_json::Value>().await.unwrap()[0]["id"]
+        response.json::<serde_json::Value>().await.unwrap()["items"][0]["id"]
     );
 
     // get user by username
     let response = client
         .get(format!("{}/users/by-username/test1", test_app.address))
+        .bearer_auth(&token)
         .send()
         .await
         .expect("Failed to execute request.");
@@ -83,13 +94,33 @@ async fn crud_users_works() {
     assert_eq!(id.to_string(), data["id"]);
     assert_eq!("test1", data["username"]);
 
+    // a user cannot promote themselves
+    let response = client
+        .patch(format!("{}/users/{}", test_app.address, id))
+        .header("If-Match", "*")
+        .bearer_auth(&token)
+        .body(
+            json!({
+                "username": "test3",
+                "email": "test1@example.com",
+                "role": "admin",
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+
     // update user
     let response = client
         .patch(format!("{}/users/{}", test_app.address, id))
+        .header("If-Match", "*")
+        .bearer_auth(&admin_token)
         .body(
             json!({
                 "username": "test3",
-                "password": "newpassword",
+                "email": "test1@example.com",
                 "role": "admin",
             })
             .to_string(),
@@ -98,11 +129,421 @@ async fn crud_users_works() {
         .await
         .expect("Failed to execute request.");
     assert!(response.status().is_success());
-    let saved = sqlx::query!("SELECT id, username, password_hash, role FROM Users")
+    let saved = sqlx::query!("SELECT id, username, role FROM Users WHERE id = $1", id)
         .fetch_one(&test_app.db_pool)
         .await
         .expect("Failed to fetch from db.");
     assert_eq!("test3", saved.username);
     assert_eq!("admin", saved.role);
-    assert!(password_hasher::verify("newpassword", &saved.password_hash));
+}
+
+#[tokio::test]
+async fn registering_as_admin_is_forbidden() {
+    let test_app = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("{}/register", test_app.address))
+        .body(
+            json!({
+                "username": "test1",
+                "email": "test1@example.com",
+                "password": "secret1234",
+                "role": "admin",
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let saved = sqlx::query!("SELECT id FROM Users")
+        .fetch_optional(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert!(saved.is_none());
+}
+
+#[tokio::test]
+async fn registering_reports_every_password_violation() {
+    let blocklist = std::env::temp_dir().join(format!("blocklist-{}.txt", uuid::Uuid::new_v4()));
+    std::fs::write(&blocklist, "# common passwords\nPassword1!\n").unwrap();
+    let test_app = spawn_app_with(|config| {
+        config.password_require_uppercase = true;
+        config.password_require_symbol = true;
+        config.password_blocklist_file = Some(blocklist.to_string_lossy().into_owned());
+    })
+    .await;
+    std::fs::remove_file(&blocklist).unwrap();
+    let client = reqwest::Client::new();
+    let register = |password: &str| {
+        client
+            .post(format!("{}/register", test_app.address))
+            .body(json!({ "username": "test1", "email": "test1@example.com", "password": password, "role": "user" }).to_string())
+            .send()
+    };
+
+    let response = register("test1").await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let body = response.json::<serde_json::Value>().await.unwrap();
+    let rules: Vec<_> = body["violations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v["rule"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        vec![
+            "too_short",
+            "missing_uppercase",
+            "missing_symbol",
+            "contains_username"
+        ],
+        rules
+    );
+    assert_eq!(8, body["violations"][0]["min_length"]);
+
+    let response = register("PASSWORD1!").await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let body = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!("common", body["violations"][0]["rule"]);
+
+    let response = register("Correct-horse").await.unwrap();
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+async fn usernames_are_unique_without_case_or_unicode_variants() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "alice", "user").await;
+    let token = generate_token(&test_app, user_id, "alice", "user");
+    let client = reqwest::Client::new();
+    let register = |username: &str| {
+        client
+            .post(format!("{}/register", test_app.address))
+            .body(
+                json!({
+                    "username": username,
+                    "email": "other@example.com",
+                    "password": "secret1234",
+                    "role": "user",
+                })
+                .to_string(),
+            )
+            .send()
+    };
+
+    // Fullwidth letters are the same as their ASCII form once normalized
+    for username in ["ALICE", "ａｌｉｃｅ"] {
+        let response = register(username).await.unwrap();
+        assert_eq!(StatusCode::CONFLICT, response.status());
+    }
+    let response = register("Admin").await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response = client
+        .post(format!("{}/login", test_app.address))
+        .body(json!({ "username": " Alice ", "password": "test1234" }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+
+    let response = client
+        .get(format!("{}/users/by-username/ALICE", test_app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(
+        user_id.to_string(),
+        response.json::<serde_json::Value>().await.unwrap()["id"]
+    );
+}
+
+#[tokio::test]
+async fn usernames_cannot_be_probed() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "alice", "user").await;
+    insert_user(&test_app, "bob", "user").await;
+    let token = generate_token(&test_app, user_id, "alice", "user");
+    let client = reqwest::Client::new();
+
+    // Taken or not, other usernames get the same answer
+    for username in ["bob", "nobody"] {
+        let response = client
+            .get(format!(
+                "{}/users/by-username/{}",
+                test_app.address, username
+            ))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+    }
+}
+
+#[tokio::test]
+async fn me_endpoints_use_the_token_identity() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let concert_id = insert_concert(&test_app, "Trivium").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("{}/tickets", test_app.address))
+        .bearer_auth(&admin_token)
+        .body(
+            json!({
+                "owner_id": user_id,
+                "concert_id": concert_id,
+                "barcode_data": "12345-abcde-67890",
+                "price": 50.0,
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+
+    let response = client
+        .get(format!("{}/users/me", test_app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let profile = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(user_id.to_string(), profile["id"]);
+    assert_eq!("test1", profile["username"]);
+    assert_eq!("test1@example.com", profile["email"]);
+    assert_eq!(1, profile["ticket_count"]);
+    assert!(profile["created_at"].is_string());
+    assert!(profile["updated_at"].is_string());
+
+    let response = client
+        .get(format!("{}/users/me/tickets", test_app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let tickets = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(1, tickets.as_array().unwrap().len());
+    assert_eq!("Trivium", tickets[0]["concert_name"]);
+
+    let response = client
+        .patch(format!("{}/users/me", test_app.address))
+        .header("If-Match", "*")
+        .bearer_auth(&token)
+        .body(
+            json!({
+                "username": "test3",
+                "email": "test1@example.com",
+                "role": "user",
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let saved = sqlx::query!("SELECT username FROM users WHERE id = $1", user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!("test3", saved.username);
+
+    let other_id = insert_user(&test_app, "test2", "user").await;
+    let other_token = generate_token(&test_app, other_id, "test2", "user");
+    let response = client
+        .delete(format!("{}/users/me", test_app.address))
+        .header("If-Match", "*")
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let saved = sqlx::query!("SELECT id FROM users WHERE id = $1", other_id)
+        .fetch_optional(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert!(saved.is_none());
+}
+
+#[tokio::test]
+async fn user_writes_never_change_the_password() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let client = reqwest::Client::new();
+    let password_hash = || async {
+        sqlx::query!("SELECT password_hash FROM users WHERE id = $1", user_id)
+            .fetch_one(&test_app.db_pool)
+            .await
+            .expect("Failed to fetch from db.")
+            .password_hash
+    };
+    let original_hash = password_hash().await;
+
+    let patch = |body: serde_json::Value| {
+        client
+            .patch(format!("{}/users/{}", test_app.address, user_id))
+            .header("If-Match", "*")
+            .bearer_auth(&token)
+            .header("Content-Type", "application/merge-patch+json")
+            .body(body.to_string())
+            .send()
+    };
+    let response = patch(json!({ "username": "test3" })).await.unwrap();
+    assert!(response.status().is_success());
+    let saved = sqlx::query!(
+        "SELECT username, email, role FROM users WHERE id = $1",
+        user_id
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch from db.");
+    assert_eq!("test3", saved.username);
+    assert_eq!(Some("test1@example.com".to_string()), saved.email);
+    assert_eq!("user", saved.role);
+    assert_eq!(original_hash, password_hash().await);
+
+    // The password is only changed along with the current one
+    let response = patch(json!({ "password": "newpassword" })).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let response = client
+        .patch(format!("{}/users/me", test_app.address))
+        .header("If-Match", "*")
+        .bearer_auth(&token)
+        .body(json!({ "password": "newpassword" }).to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response = patch(json!({ "email": null })).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    // A replacement needs every field but the password
+    let put = |body: serde_json::Value| {
+        client
+            .put(format!("{}/users/{}", test_app.address, user_id))
+            .header("If-Match", "*")
+            .bearer_auth(&token)
+            .body(body.to_string())
+            .send()
+    };
+    let response = put(json!({ "username": "test4" })).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let user = json!({ "username": "test4", "email": "test1@example.com", "role": "user" });
+    let mut with_password = user.clone();
+    with_password["password"] = json!("otherpassword");
+    let response = put(with_password).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let response = put(user).await.unwrap();
+    assert!(response.status().is_success());
+    let saved = sqlx::query!("SELECT username FROM users WHERE id = $1", user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!("test4", saved.username);
+    assert_eq!(original_hash, password_hash().await);
+}
+
+#[tokio::test]
+async fn user_writes_require_the_current_etag() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("{}/users/{}", test_app.address, user_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+
+    let response = client
+        .delete(format!("{}/users/me", test_app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::PRECONDITION_REQUIRED, response.status());
+
+    let response = client
+        .patch(format!("{}/users/me", test_app.address))
+        .bearer_auth(&token)
+        .header("If-Match", &etag)
+        .body(json!({ "username": "test3" }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+
+    let response = client
+        .delete(format!("{}/users/me", test_app.address))
+        .bearer_auth(&token)
+        .header("If-Match", &etag)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::PRECONDITION_FAILED, response.status());
+    let saved = sqlx::query!("SELECT id FROM users WHERE id = $1", user_id)
+        .fetch_optional(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert!(saved.is_some());
+}
+
+#[tokio::test]
+async fn users_are_listed_by_pages() {
+    let test_app = spawn_app().await;
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    for username in ["Charlie", "alice", "bob"] {
+        insert_user(&test_app, username, "user").await;
+    }
+    let client = reqwest::Client::new();
+
+    let list = |query: String| {
+        client
+            .get(format!("{}/users?{}", test_app.address, query))
+            .bearer_auth(&admin_token)
+            .send()
+    };
+
+    let response = list("role=user&sort=username&limit=2".to_string())
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let page = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!("alice", page["items"][0]["username"]);
+    assert_eq!("bob", page["items"][1]["username"]);
+    let cursor = page["next_cursor"].as_str().unwrap();
+
+    let response = list(format!("role=user&sort=username&limit=2&cursor={cursor}"))
+        .await
+        .unwrap();
+    let page = response.json::<serde_json::Value>().await.unwrap();
+    let items = page["items"].as_array().unwrap();
+    assert_eq!(1, items.len());
+    assert_eq!("Charlie", items[0]["username"]);
+    assert!(page["next_cursor"].is_null());
+
+    let response = list("created_to=2000-01-01T00:00:00Z&include_total=true".to_string())
+        .await
+        .unwrap();
+    let page = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(0, page["total"]);
+
+    let response = list("role=superuser".to_string()).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
 }
@@ -0,0 +1,253 @@
+mod helper;
+use helper::{generate_token, insert_concert, insert_user, insert_venue, spawn_app};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+const SEATING_CHART: &str = include_str!("fixtures/seating_chart.json");
+
+#[tokio::test]
+async fn admins_import_seating_charts() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let client = reqwest::Client::new();
+
+    let venue = json!({ "name": " Zénith ", "address": "Paris" });
+    let create = |token: &str| {
+        client
+            .post(format!("{}/venues", test_app.address))
+            .bearer_auth(token)
+            .body(venue.to_string())
+            .send()
+    };
+    let response = create(&token).await.unwrap();
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    let response = create(&admin_token).await.unwrap();
+    assert!(response.status().is_success());
+    let venue_id: Uuid = response.json().await.unwrap();
+
+    let import = |body: String| {
+        client
+            .put(format!(
+                "{}/venues/{}/seating-chart",
+                test_app.address, venue_id
+            ))
+            .header("If-Match", "*")
+            .bearer_auth(&admin_token)
+            .body(body)
+            .send()
+    };
+    let response = import(SEATING_CHART.to_string()).await.unwrap();
+    assert!(response.status().is_success());
+    let duplicated = json!({
+        "sections": [{ "name": "Pit", "rows": [{ "name": "A", "seats": ["1", "1"] }] }]
+    });
+    let response = import(duplicated.to_string()).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let venue: Value = client
+        .get(format!("{}/venues/{}", test_app.address, venue_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    assert_eq!("Zénith", venue["name"]);
+    let sections = venue["sections"].as_array().unwrap();
+    assert_eq!(2, sections.len());
+    assert_eq!("Pit", sections[0]["name"]);
+    assert_eq!("B", sections[0]["rows"][1]["name"]);
+    assert_eq!("3", sections[0]["rows"][0]["seats"][2]["number"]);
+    assert_eq!("102", sections[1]["rows"][0]["seats"][1]["number"]);
+
+    // A new chart replaces the seats of the previous one
+    let response = import(json!({ "sections": [] }).to_string()).await.unwrap();
+    assert!(response.status().is_success());
+    let count = sqlx::query_scalar!("SELECT count(*) FROM seats")
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch from db.");
+    assert_eq!(Some(0), count);
+}
+
+#[tokio::test]
+async fn seats_are_sold_once_per_concert() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let admin_id = insert_user(&test_app, "admin1", "admin").await;
+    let admin_token = generate_token(&test_app, admin_id, "admin1", "admin");
+    let client = reqwest::Client::new();
+
+    let chart: Value = serde_json::from_str(SEATING_CHART).unwrap();
+    let venue = json!({ "name": "Zénith", "seating_chart": chart });
+    let venue_id: Uuid = client
+        .post(format!("{}/venues", test_app.address))
+        .bearer_auth(&admin_token)
+        .body(venue.to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    let concert_id = insert_concert(&test_app, "Trivium").await;
+    sqlx::query!(
+        "UPDATE concerts SET venue_id = $1 WHERE id = $2",
+        venue_id,
+        concert_id
+    )
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to update the concert.");
+    let other_concert_id = insert_concert(&test_app, "Gojira").await;
+
+    let seats = |concert_id: Uuid| {
+        client
+            .get(format!(
+                "{}/concerts/{}/seats",
+                test_app.address, concert_id
+            ))
+            .bearer_auth(&token)
+            .send()
+    };
+    let map: Value = seats(concert_id).await.unwrap().json().await.unwrap();
+    assert_eq!(7, map["total"]);
+    assert_eq!(7, map["available"]);
+    let seat = &map["sections"][0]["rows"][0]["seats"][0];
+    assert_eq!(true, seat["available"]);
+    let seat_id = seat["id"].as_str().unwrap().to_string();
+    // The concert without a venue has no map
+    let response = seats(other_concert_id).await.unwrap();
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+    let create_ticket = |concert_id: Uuid, seat_id: &str| {
+        client
+            .post(format!("{}/tickets", test_app.address))
+            .bearer_auth(&admin_token)
+            .body(
+                json!({
+                    "owner_id": user_id,
+                    "concert_id": concert_id,
+                    "seat_id": seat_id,
+                    "barcode_data": "12345-abcde-67890",
+                    "price": 50.0,
+                })
+                .to_string(),
+            )
+            .send()
+    };
+    let response = create_ticket(concert_id, &seat_id).await.unwrap();
+    assert!(response.status().is_success());
+    let ticket_id: Uuid = response.json().await.unwrap();
+    let response = create_ticket(concert_id, &seat_id).await.unwrap();
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    let response = create_ticket(other_concert_id, &seat_id).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let response = create_ticket(concert_id, &Uuid::new_v4().to_string())
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+    let ticket: Value = client
+        .get(format!("{}/tickets/{}", test_app.address, ticket_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    assert_eq!("Pit", ticket["seat_section"]);
+    assert_eq!("A", ticket["seat_row"]);
+    assert_eq!("1", ticket["seat_number"]);
+
+    let map: Value = seats(concert_id).await.unwrap().json().await.unwrap();
+    assert_eq!(6, map["available"]);
+    assert_eq!(
+        false,
+        map["sections"][0]["rows"][0]["seats"][0]["available"]
+    );
+
+    // The seats held by a ticket stay in the venue
+    let response = client
+        .put(format!(
+            "{}/venues/{}/seating-chart",
+            test_app.address, venue_id
+        ))
+        .header("If-Match", "*")
+        .bearer_auth(&admin_token)
+        .body(json!({ "sections": [] }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    let response = client
+        .patch(format!("{}/concerts/{}", test_app.address, concert_id))
+        .header("If-Match", "*")
+        .bearer_auth(&admin_token)
+        .header("Content-Type", "application/merge-patch+json")
+        .body(json!({ "venue_id": null }).to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    let response = client
+        .delete(format!("{}/venues/{}", test_app.address, venue_id))
+        .header("If-Match", "*")
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(StatusCode::CONFLICT, response.status());
+}
+
+#[tokio::test]
+async fn venues_are_listed_by_pages() {
+    let test_app = spawn_app().await;
+    let user_id = insert_user(&test_app, "test1", "user").await;
+    let token = generate_token(&test_app, user_id, "test1", "user");
+    let client = reqwest::Client::new();
+    for name in ["Zénith", "Olympia", "Bataclan"] {
+        insert_venue(&test_app, name).await;
+    }
+
+    let list = |query: &str| {
+        client
+            .get(format!("{}/venues?{}", test_app.address, query))
+            .bearer_auth(&token)
+            .send()
+    };
+    let names = |page: &Value| -> Vec<String> {
+        page["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let page: Value = list("limit=2&include_total=true")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(vec!["Bataclan", "Olympia"], names(&page));
+    assert_eq!(3, page["total"]);
+    let cursor = page["next_cursor"].as_str().unwrap();
+    let page: Value = list(&format!("limit=2&cursor={cursor}"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(vec!["Zénith"], names(&page));
+
+    let response = list("sort=address").await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+}